@@ -0,0 +1,4 @@
+-- Auto-generated tile decoders
+-- Expanders for the codecs this export's grids use and accessors for their extra layers
+
+local _, addon = ...
@@ -12,6 +12,8 @@ print(ADDON_NAME .. " loaded")
 -- -------------------------
 addon.tileGrids = addon.tileGrids or {}
 addon._tileCache = addon._tileCache or {}
+addon.heightMaps = addon.heightMaps or {}
+addon.outlines = addon.outlines or {}
 
 -- -------------------------
 -- u32 LE reader
@@ -60,11 +62,53 @@ local function base64_decode(s)
 end
 
 -- -------------------------
--- Tile decode: base64 -> raw bytes
+-- Tile decode: base64 -> raw bytes (256 u32 LE)
 -- -------------------------
-local function decode_tile_blob(blob)
+local function u32_le_bytes(v)
+  return string.char(v % 256, math.floor(v / 256) % 256, math.floor(v / 65536) % 256, math.floor(v / 16777216) % 256)
+end
+
+-- Codec expanders (palette, rle, delta), each turning a tile's decoded bytes
+-- into its 256 u32s. Data/Decoders.lua is generated with the export and
+-- registers only the codecs its grids use.
+addon.codecs = addon.codecs or {}
+
+-- compression "deflate": raw DEFLATE under the codec, inflated with LibDeflate
+local LibDeflate = LibStub and LibStub("LibDeflate", true)
+
+local function decode_tile_blob(blob, codec, compression)
   if not blob then return nil end
-  return base64_decode(blob)
+  local data = base64_decode(blob)
+  if compression == "deflate" then
+    if not LibDeflate then error(ADDON_NAME .. ": deflate-compressed tiles need LibDeflate") end
+    data = LibDeflate:DecompressDeflate(data)
+    if not data then return nil end
+  end
+  if not codec or codec == "raw" then return data end
+  local expand = addon.codecs[codec]
+  if not expand then
+    error(ADDON_NAME .. ": no decoder for codec '" .. codec .. "'; Data/Decoders.lua doesn't match the data")
+  end
+  return expand(data)
+end
+
+local function layer_codec(grid, layer)
+  return grid.codecs and grid.codecs[layer]
+end
+
+-- A tile's area IDs as raw u32s, with any hotfix overrides applied
+local function decode_area_tile(grid, key)
+  -- Tiles players visit most can use a codec of their own (tileCodecs)
+  local codec = grid.tileCodecs and grid.tileCodecs[key] or layer_codec(grid, "areaid")
+  local raw = decode_tile_blob(grid.tiles[key], codec, grid.compression)
+  local chunks = raw and grid.hotfix and grid.hotfix[key]
+  if not chunks then return raw end
+  local parts = {}
+  for i = 0, 255 do
+    local areaID = chunks[i]
+    parts[i + 1] = areaID and u32_le_bytes(areaID) or raw:sub(i * 4 + 1, i * 4 + 4)
+  end
+  return table.concat(parts)
 end
 
 local function tile_key(tileX, tileY)
@@ -110,13 +154,274 @@ end
 -- Public API: Register tile grids (called by data files)
 -- -------------------------
 function addon:RegisterTileGrid(name, grid)
+  -- Deduplicated exports give tiles as indices into a shared payload list;
+  -- resolving them here shares the strings, so memory stays deduplicated
+  if grid.payloads then
+    for key, index in pairs(grid.tiles) do
+      if type(index) == "number" then grid.tiles[key] = grid.payloads[index] end
+    end
+    grid.payloads = nil
+  end
   self.tileGrids[name] = grid
   addon._tileCache[name] = new_cache(64)
+  if grid.wdt then
+    grid._tileState = {}
+    for _, key in ipairs(grid.wdt.missing or {}) do grid._tileState[key] = "missing" end
+    for _, key in ipairs(grid.wdt.empty or {}) do grid._tileState[key] = "empty" end
+  end
+  if grid.shards then
+    -- A manifest: tiles arrive with RegisterTileShard as each shard file loads
+    grid._shardsLoaded = {}
+    local count = 0
+    for _ in pairs(grid.shards) do count = count + 1 end
+    print(ADDON_NAME .. ": Registered " .. name .. " (" .. count .. " shards)")
+    return
+  end
   local count = 0
   if grid.tiles then for _ in pairs(grid.tiles) do count = count + 1 end end
   print(ADDON_NAME .. ": Registered " .. name .. " (" .. count .. " tiles)")
 end
 
+-- -------------------------
+-- Public API: Add one shard's tiles to a sharded grid (called by the
+-- Data/<Map>_tiles_<x>_<y>.lua files its manifest lists)
+-- -------------------------
+function addon:RegisterTileShard(name, shard, data)
+  local grid = self.tileGrids[name]
+  if not grid or not grid.shards then
+    print(ADDON_NAME .. ": Shard " .. shard .. " of " .. name .. " loaded before its grid; check the .toc order")
+    return
+  end
+  for key, tile in pairs(data.tiles) do
+    grid.tiles[key] = type(tile) == "number" and data.payloads[tile] or tile
+  end
+  if data.tileCodecs then
+    grid.tileCodecs = grid.tileCodecs or {}
+    for key, codec in pairs(data.tileCodecs) do grid.tileCodecs[key] = codec end
+  end
+  if data.layers then
+    grid.layers = grid.layers or {}
+    for layer, tiles in pairs(data.layers) do
+      grid.layers[layer] = grid.layers[layer] or {}
+      for key, blob in pairs(tiles) do grid.layers[layer][key] = blob end
+    end
+  end
+  grid._shardsLoaded[shard] = true
+end
+
+-- -------------------------
+-- Register a height map (from Data/<Map>_heights.lua)
+-- -------------------------
+function addon:RegisterHeightMap(name, heights)
+  self.heightMaps[name] = heights
+end
+
+-- -------------------------
+-- Register a hotfix (from Data/Hotfix.lua): chunk area IDs overriding the
+-- grids of the data release it was made for
+-- -------------------------
+function addon:RegisterGridHotfix(base, grids)
+  local loaded = self.DataVersion and self.DataVersion.version
+  if base ~= loaded then
+    print(ADDON_NAME .. ": Ignoring hotfix for data " .. base .. " (loaded " .. tostring(loaded) .. ")")
+    return
+  end
+  local count = 0
+  for name, tiles in pairs(grids) do
+    local grid = self.tileGrids[name]
+    if grid then
+      grid.hotfix = tiles
+      for _, chunks in pairs(tiles) do
+        for _ in pairs(chunks) do count = count + 1 end
+      end
+    end
+  end
+  print(ADDON_NAME .. ": Applied hotfix (" .. count .. " chunks)")
+end
+
+-- -------------------------
+-- Register zone outlines (from Data/<Map>_outlines.lua)
+-- -------------------------
+function addon:RegisterOutlines(name, outlines)
+  self.outlines[name] = outlines
+end
+
+-- -------------------------
+-- Public API: An area's outlines on a grid: a list of polygons, each a list
+-- of rings (the outline, then its holes) as flat {x1, y1, x2, y2, ...} lists
+-- of chunk corners across the map (tileX * 16 + chunkX, tileY * 16 + chunkY).
+-- nil when the grid has no outlines or the area isn't on it.
+-- -------------------------
+function addon:GetAreaOutline(gridName, areaID)
+  local outlines = self.outlines[gridName]
+  return outlines and outlines[areaID]
+end
+
+-- -------------------------
+-- Public API: Terrain height in yards at one cell of a chunk, with
+-- `resolution` cells per chunk side (cellX/cellY default to 0). nil when the
+-- grid has no height map or the tile no heights.
+-- -------------------------
+function addon:GetChunkHeight(gridName, tileX, tileY, chunkX, chunkY, cellX, cellY)
+  local heights = self.heightMaps[gridName]
+  local tile = heights and heights.tiles[tile_key(tileX, tileY)]
+  if not tile then return nil end
+  local res = heights.resolution
+  local row = chunkY * res + (cellY or 0)
+  local col = chunkX * res + (cellX or 0)
+  return tile[row * 16 * res + col + 1]
+end
+
+-- -------------------------
+-- Public API: Whether a tile has data: "present", "missing" (the map's WDT
+-- lists it but the export has no data for it), "empty" (ocean/void with no
+-- terrain), "unloaded" (a sharded grid whose shard file for it isn't loaded),
+-- or nil when the export has no WDT to tell missing from empty.
+-- -------------------------
+function addon:GetTileState(gridName, tileX, tileY)
+  local grid = self.tileGrids[gridName]
+  if not grid then return nil end
+  local key = tile_key(tileX, tileY)
+  if grid.tiles[key] then return "present" end
+  if grid.shards then
+    local perSide = math.ceil(64 / grid.shardSize)
+    local shard = math.floor(tileY / grid.shardSize) * perSide + math.floor(tileX / grid.shardSize)
+    if grid.shards[shard] and not grid._shardsLoaded[shard] then return "unloaded" end
+  end
+  if not grid._tileState then return nil end
+  return grid._tileState[key] or "empty"
+end
+
+-- -------------------------
+-- Public API: Whether a grid was exported with an extra layer. The shipped
+-- Data/ has none; regenerate with e.g. --layers areaid,liquiddepth to add one.
+-- Accessors for the layers exported (GetLiquidDepth, IsChunkHole,
+-- GetChunkLiquid) are defined by Data/Decoders.lua.
+-- -------------------------
+function addon:HasLayer(gridName, layer)
+  local grid = self.tileGrids[gridName]
+  return grid ~= nil and grid.layers ~= nil and grid.layers[layer] ~= nil
+end
+
+-- -------------------------
+-- Public API: Extra layer value (holes, liquid, ...) for one chunk; nil when
+-- the grid wasn't exported with the layer (see HasLayer)
+-- -------------------------
+function addon:GetLayerValue(gridName, layer, tileX, tileY, chunkX, chunkY)
+  local grid = self.tileGrids[gridName]
+  local tiles = grid and grid.layers and grid.layers[layer]
+  if not tiles then return nil end
+  local raw = decode_tile_blob(tiles[tile_key(tileX, tileY)], layer_codec(grid, layer), grid.compression)
+  if not raw then return 0 end
+  return area_id_from_raw(raw, chunkX, chunkY)
+end
+
+-- Open cells in a 4x4 holes bitmap
+local function count_holes(holes)
+  local count = 0
+  for _ = 1, 16 do
+    count = count + holes % 2
+    holes = math.floor(holes / 2)
+  end
+  return count
+end
+
+-- -------------------------
+-- Public API: Exploration flag for an area: the explored-zones field index
+-- (0-based) and bit mask within it, or nil if the area has no exploration bit.
+-- -------------------------
+function addon:GetExploreFlag(areaID)
+  local info = addon.AreaInfo and addon.AreaInfo[areaID]
+  local bit = info and info.exploreBit
+  if not bit then return nil end
+  return math.floor(bit / 32), 2 ^ (bit % 32)
+end
+
+-- -------------------------
+-- Public API: Chunks of a tile whose area is not in `explored` (a set of
+-- areaIDs), as a list of {chunkX, chunkY, areaID}
+-- -------------------------
+function addon:GetUnexploredChunks(gridName, tileX, tileY, explored)
+  local out = {}
+  local grid = self.tileGrids[gridName]
+  local raw = grid and decode_area_tile(grid, tile_key(tileX, tileY))
+  if not raw then return out end
+  for chunkY = 0, 15 do
+    for chunkX = 0, 15 do
+      local areaID = area_id_from_raw(raw, chunkX, chunkY)
+      if areaID and areaID ~= 0 and not explored[areaID] and self:GetExploreFlag(areaID) then
+        out[#out + 1] = { chunkX, chunkY, areaID }
+      end
+    end
+  end
+  return out
+end
+
+-- -------------------------
+-- Public API: Weather fields for an area (from Data/AreaWeather.lua), or nil
+-- -------------------------
+function addon:GetAreaWeather(areaID)
+  return addon.AreaWeather and addon.AreaWeather[areaID]
+end
+
+-- -------------------------
+-- Public API: Graveyards for a zone (from Data/Graveyards.lua). With a
+-- faction ("alliance" or "horde"), graveyards reserved for the other one
+-- are left out.
+-- -------------------------
+function addon:GetZoneGraveyards(zoneAreaID, faction)
+  local out = {}
+  local graveyards = addon.Graveyards and addon.Graveyards[zoneAreaID]
+  for _, graveyard in ipairs(graveyards or {}) do
+    if not faction or not graveyard.faction or graveyard.faction == faction then
+      out[#out + 1] = graveyard
+    end
+  end
+  return out
+end
+
+-- -------------------------
+-- Public API: Dungeon entrances on a grid (from Data/DungeonEntrances.lua),
+-- optionally only those leading to one instance map
+-- -------------------------
+function addon:GetDungeonEntrances(gridName, instanceMapID)
+  local out = {}
+  local entrances = addon.DungeonEntrances and addon.DungeonEntrances[gridName]
+  for _, entrance in ipairs(entrances or {}) do
+    if not instanceMapID or entrance.map == instanceMapID then
+      out[#out + 1] = entrance
+    end
+  end
+  return out
+end
+
+-- -------------------------
+-- Public API: Area triggers (from Data/AreaTriggers.lua) whose footprint
+-- touches one chunk, as a list of trigger tables; empty when none do
+-- -------------------------
+function addon:GetChunkAreaTriggers(gridName, tileX, tileY, chunkX, chunkY)
+  local out = {}
+  local map = addon.AreaTriggers and addon.AreaTriggers[gridName]
+  local chunks = map and map.chunks[tile_key(tileX, tileY)]
+  local ids = chunks and chunks[chunkY * 16 + chunkX]
+  for _, id in ipairs(ids or {}) do
+    out[#out + 1] = map.triggers[id]
+  end
+  return out
+end
+
+-- -------------------------
+-- Public API: An area's name and root zone on one continent (from
+-- Data/<Map>_areas.lua): name, zoneID, zoneName; nil when the continent's
+-- table isn't loaded or the area isn't on it
+-- -------------------------
+function addon:GetContinentArea(gridName, areaID)
+  local areas = addon.ContinentAreas and addon.ContinentAreas[gridName]
+  local area = areas and areas[areaID]
+  if not area then return nil end
+  return area.name, area.zoneId, area.zoneName
+end
+
 -- -------------------------
 -- Public API: Get area name from ID
 -- -------------------------
@@ -373,6 +678,20 @@ end
 
 local colorWarned = {}
 
+-- Liquid types 3 (magma) and 4 (slime) keep their own colours; any other
+-- liquid is water
+local LIQUID_TINTS = {
+  [3] = { 0.9, 0.35, 0.1 },
+  [4] = { 0.4, 0.8, 0.2 },
+}
+local WATER_TINT = { 0.2, 0.45, 0.9 }
+
+-- Blend an area colour towards its chunk's liquid
+local function TintForLiquid(r, g, b, liquidType)
+  local tint = LIQUID_TINTS[liquidType] or WATER_TINT
+  return r * 0.4 + tint[1] * 0.6, g * 0.4 + tint[2] * 0.6, b * 0.4 + tint[3] * 0.6
+end
+
 local function GetAreaColor(areaID)
   if zoneColorCache[areaID] then
     return unpack(zoneColorCache[areaID])
@@ -542,8 +861,8 @@ local function UpdateFillOverlay(silent)
   
   -- First pass: count total chunks to draw
   local totalChunks = 0
-  for key, blob in pairs(grid.tiles) do
-    local raw = decode_tile_blob(blob)
+  for key in pairs(grid.tiles) do
+    local raw = decode_area_tile(grid, key)
     if raw then
       for chunkY = 0, 15 do
         for chunkX = 0, 15 do
@@ -568,10 +887,16 @@ local function UpdateFillOverlay(silent)
   local areaCentroids = {}
   
   -- Second pass: draw chunks
-  for key, blob in pairs(grid.tiles) do
-    local raw = decode_tile_blob(blob)
+  local liquidTiles = grid.layers and grid.layers.liquid
+  local liquidCodec = layer_codec(grid, "liquid")
+  local holeTiles = grid.layers and grid.layers.holes
+  local holesCodec = layer_codec(grid, "holes")
+  for key in pairs(grid.tiles) do
+    local raw = decode_area_tile(grid, key)
     if raw then
       tilesScanned = tilesScanned + 1
+      local liquidRaw = liquidTiles and decode_tile_blob(liquidTiles[key], liquidCodec, grid.compression)
+      local holesRaw = holeTiles and decode_tile_blob(holeTiles[key], holesCodec, grid.compression)
       
       local tileY = math.floor(key / 64)
       local tileX = key % 64
@@ -614,7 +939,13 @@ local function UpdateFillOverlay(silent)
             end
             
             local r, g, b = GetAreaColor(areaID)
-            tex:SetColorTexture(r, g, b, 0.4)
+            local liquidType = liquidRaw and area_id_from_raw(liquidRaw, chunkX, chunkY)
+            if liquidType and liquidType ~= 0 then
+              r, g, b = TintForLiquid(r, g, b, liquidType)
+            end
+            -- Chunks with holes fade by the share of their terrain that's open
+            local holes = holesRaw and area_id_from_raw(holesRaw, chunkX, chunkY) or 0
+            tex:SetColorTexture(r, g, b, 0.4 * (1 - count_holes(holes) / 16))
             tex:ClearAllPoints()
             tex:SetPoint("TOPLEFT", canvas, "TOPLEFT", pixelX - pixelW/2, -(pixelY - pixelH/2))
             tex:SetSize(pixelW, pixelH)
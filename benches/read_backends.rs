@@ -0,0 +1,129 @@
+//! Scan throughput with plain reads vs io_uring reads overlapped with parsing.
+//!
+//!     cargo bench --features uring --bench read_backends
+//!     ZONEMAP_BENCH_DIR=path/to/adts cargo bench --features uring --bench read_backends
+//!
+//! Without `ZONEMAP_BENCH_DIR`, a temporary directory of synthetic files is
+//! hashed instead of parsed. The page cache is dropped before each run when
+//! the bench may write `/proc/sys/vm/drop_caches` (run it as root); io_uring
+//! only pays off when reads actually wait on the disk.
+
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use zone_map::adt::parse_adt_bytes;
+use zone_map::uring::{read_in_order, DEFAULT_DEPTH};
+
+const SYNTHETIC_FILES: usize = 2000;
+const SYNTHETIC_SIZE: usize = 256 * 1024;
+
+/// Stand-in for parsing: real ADTs are parsed, synthetic files hashed
+fn work(data: &[u8], real: bool) -> usize {
+    if real {
+        parse_adt_bytes(data)
+            .ok()
+            .flatten()
+            .map_or(0, |tile| tile.chunks.len())
+    } else {
+        Sha256::digest(data)[0] as usize
+    }
+}
+
+fn synthetic_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("zonemap-bench-{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("create bench dir");
+    let mut seed = 0x2545_f491_4f6c_dd1du64;
+    for i in 0..SYNTHETIC_FILES {
+        let data: Vec<u8> = (0..SYNTHETIC_SIZE)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                seed as u8
+            })
+            .collect();
+        fs::write(dir.join(format!("Bench_{}_{}.adt", i % 64, i / 64)), data)
+            .expect("write bench file");
+    }
+    dir
+}
+
+fn files_in(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .expect("read bench dir")
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .collect();
+    files.sort();
+    files
+}
+
+/// Drop the page cache so the next run reads from disk; false if not allowed
+fn drop_caches() -> bool {
+    std::process::Command::new("sync").status().is_ok()
+        && fs::write("/proc/sys/vm/drop_caches", "3").is_ok()
+}
+
+fn report(name: &str, elapsed: Duration, files: usize, bytes: usize) {
+    let secs = elapsed.as_secs_f64();
+    println!(
+        "{:<10} {:>8.3}s {:>10.0} files/s {:>8.1} MiB/s",
+        name,
+        secs,
+        files as f64 / secs,
+        bytes as f64 / secs / (1024.0 * 1024.0)
+    );
+}
+
+fn main() {
+    let (dir, real, cleanup) = match std::env::var_os("ZONEMAP_BENCH_DIR") {
+        Some(dir) => (PathBuf::from(dir), true, false),
+        None => (synthetic_dir(), false, true),
+    };
+    let files = files_in(&dir);
+    let cold = drop_caches();
+    println!(
+        "{} files in {} ({} page cache)",
+        files.len(),
+        dir.display(),
+        if cold { "cold" } else { "warm" }
+    );
+
+    let start = Instant::now();
+    let mut bytes = 0;
+    let mut checksum = 0;
+    for path in &files {
+        let data = fs::read(path).expect("read");
+        bytes += data.len();
+        checksum += work(&data, real);
+    }
+    report("plain", start.elapsed(), files.len(), bytes);
+
+    for depth in [8, DEFAULT_DEPTH, 256] {
+        if cold {
+            drop_caches();
+        }
+        let start = Instant::now();
+        let mut bytes = 0;
+        let mut uring_checksum = 0;
+        read_in_order(&files, depth, None, &mut |_, data| {
+            let data = data?;
+            bytes += data.len();
+            uring_checksum += work(&data, real);
+            Ok(())
+        })
+        .expect("io_uring read");
+        report(
+            &format!("uring/{}", depth),
+            start.elapsed(),
+            files.len(),
+            bytes,
+        );
+        assert_eq!(checksum, uring_checksum, "backends read different data");
+    }
+
+    if cleanup {
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
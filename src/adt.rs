@@ -0,0 +1,509 @@
+use crate::grid::{TILES_PER_SIDE, TILE_SIZE};
+
+use wow_adt::split_adt::SplitAdtParser;
+use wow_adt::{Adt, AdtError, McnkChunk, ModfChunk, MwidChunk, MwmoChunk, WaterLevelData};
+
+use std::fmt;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+// ============================================================================
+// Parsed tile data
+// ============================================================================
+
+/// Number of MCNK chunks along one side of an ADT tile
+pub const CHUNKS_PER_SIDE: usize = 16;
+
+/// Number of MCNK chunks in one ADT tile
+pub const CHUNKS_PER_TILE: usize = CHUNKS_PER_SIDE * CHUNKS_PER_SIDE;
+
+/// Liquid present in a chunk, reduced to what the exporters need
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct LiquidSummary {
+    /// LiquidType ID (MH2O), or 1 water / 2 ocean / 3 magma / 4 slime (MCLQ)
+    pub liquid_type: u16,
+    pub min_height: f32,
+    pub max_height: f32,
+}
+
+/// MCNK header flags, with accessors for the bits the exporters know
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct McnkFlags(pub u32);
+
+impl McnkFlags {
+    /// Has an MCSH shadow map
+    pub const HAS_MCSH: u32 = 0x1;
+    /// Players can't walk onto the chunk
+    pub const IMPASSABLE: u32 = 0x2;
+    /// MCLQ river or lake water (pre-WotLK)
+    pub const LIQUID_RIVER: u32 = 0x4;
+    /// MCLQ ocean water (pre-WotLK)
+    pub const LIQUID_OCEAN: u32 = 0x8;
+    /// MCLQ magma (pre-WotLK)
+    pub const LIQUID_MAGMA: u32 = 0x10;
+    /// MCLQ slime (pre-WotLK)
+    pub const LIQUID_SLIME: u32 = 0x20;
+    /// Has MCCV vertex colors
+    pub const HAS_MCCV: u32 = 0x40;
+    /// Alpha maps are stored at their full 64x64 and not fixed up to 63x63
+    pub const DO_NOT_FIX_ALPHA_MAP: u32 = 0x8000;
+    /// The header holds an 8x8 holes bitmap where the MCVT and MCNR offsets
+    /// would be (Legion on)
+    pub const HIGH_RES_HOLES: u32 = 0x10000;
+
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Whether every bit of `mask` is set
+    pub fn contains(self, mask: u32) -> bool {
+        self.0 & mask == mask
+    }
+
+    pub fn has_mcsh(self) -> bool {
+        self.contains(Self::HAS_MCSH)
+    }
+
+    pub fn is_impassable(self) -> bool {
+        self.contains(Self::IMPASSABLE)
+    }
+
+    pub fn has_mccv(self) -> bool {
+        self.contains(Self::HAS_MCCV)
+    }
+
+    pub fn do_not_fix_alpha_map(self) -> bool {
+        self.contains(Self::DO_NOT_FIX_ALPHA_MAP)
+    }
+
+    pub fn has_high_res_holes(self) -> bool {
+        self.contains(Self::HIGH_RES_HOLES)
+    }
+
+    /// MCLQ liquid as 1 water / 2 ocean / 3 magma / 4 slime, the first set
+    /// in that order
+    pub fn mclq_liquid_type(self) -> Option<u16> {
+        [Self::LIQUID_RIVER, Self::LIQUID_OCEAN, Self::LIQUID_MAGMA, Self::LIQUID_SLIME]
+            .iter()
+            .position(|&bit| self.contains(bit))
+            .map(|i| i as u16 + 1)
+    }
+}
+
+impl From<u32> for McnkFlags {
+    fn from(bits: u32) -> Self {
+        McnkFlags(bits)
+    }
+}
+
+impl fmt::LowerHex for McnkFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+/// Typed record for a single MCNK chunk
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ChunkData {
+    pub area_id: u32,
+    /// Raw MCNK header flags; `mcnk_flags` has the named accessors
+    pub flags: u32,
+    /// 4x4 holes bitmap, bit `y * 4 + x`; high-res 8x8 holes are folded
+    /// into it, a cell being a hole when any of its 2x2 high-res cells is
+    pub holes: u32,
+    /// Chunk index within the tile (ix, iy), as stored in the MCNK header
+    pub index: (u32, u32),
+    /// World position of the chunk corner (x, y, z)
+    pub position: [f32; 3],
+    /// Lowest and highest terrain vertex (MCVT heights plus the chunk's base height)
+    pub terrain_height: (f32, f32),
+    /// Absolute heights of the 9x9 outer MCVT vertices, row-major; empty
+    /// without an MCVT
+    pub heights: Vec<f32>,
+    pub liquid: Option<LiquidSummary>,
+}
+
+impl ChunkData {
+    pub fn mcnk_flags(&self) -> McnkFlags {
+        McnkFlags(self.flags)
+    }
+}
+
+/// The file a WMO placement refers to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WmoFile {
+    /// Path from the tile's MWMO list, e.g. `World\wmo\...\Foo.wmo`
+    Name(String),
+    /// FileDataID, for placements flagged as naming one (8.1 on)
+    FileDataId(u32),
+}
+
+/// One MODF entry: a WMO placed on the map
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WmoPlacement {
+    pub file: WmoFile,
+    /// Same for every tile the WMO overlaps, so duplicates can be dropped
+    pub unique_id: u32,
+    /// World position (x, y, z), converted from the ADT's placement space
+    pub position: [f32; 3],
+}
+
+/// All 256 chunks of a root ADT, row-major (`iy * 16 + ix`), and the WMOs
+/// placed on it
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileData {
+    pub chunks: Vec<ChunkData>,
+    pub wmos: Vec<WmoPlacement>,
+}
+
+impl TileData {
+    /// Chunk at (x, y) within the tile
+    pub fn chunk(&self, x: usize, y: usize) -> &ChunkData {
+        &self.chunks[y * CHUNKS_PER_SIDE + x]
+    }
+
+    /// Area IDs of all chunks in row-major order
+    pub fn area_ids(&self) -> Vec<u32> {
+        self.chunks.iter().map(|c| c.area_id).collect()
+    }
+}
+
+// ============================================================================
+// Client layouts
+// ============================================================================
+//
+// Up to Wrath a tile is one file. From Cataclysm on it is split: the root
+// `<Map>_<x>_<y>.adt` keeps the MCNK headers (area IDs included), heights
+// and MH2O but loses MCIN, the WMO and doodad placements move to `_obj0`
+// and the textures to `_tex0`. Which layout a tile uses is told from its
+// root, by whether it has an MCIN.
+
+/// How a client stores one tile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdtLayout {
+    /// Everything in the root ADT (Classic to Wrath)
+    Monolithic,
+    /// Root ADT plus `_obj0`, `_tex0`, ... siblings (Cataclysm on)
+    Split,
+}
+
+impl AdtLayout {
+    /// Layout of the tile whose root ADT is `root`
+    pub fn detect(root: &[u8]) -> Self {
+        let mut at = 0;
+        while at + 8 <= root.len() {
+            if expect_magic(root, at, "MCIN").is_ok() {
+                return AdtLayout::Monolithic;
+            }
+            let Ok(size) = u32_at(root, at + 4) else {
+                break;
+            };
+            at += 8 + size as usize;
+        }
+        AdtLayout::Split
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AdtLayout::Monolithic => "monolithic",
+            AdtLayout::Split => "split",
+        }
+    }
+}
+
+/// The split file next to `root` with `suffix`, e.g. `Azeroth_32_48_obj0.adt`
+/// for `Azeroth_32_48.adt` and `"_obj0"`
+pub fn split_sibling(root: &Path, suffix: &str) -> PathBuf {
+    let stem = root.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+    let ext = root.extension().map(|e| e.to_string_lossy()).unwrap_or_default();
+    root.with_file_name(format!("{}{}.{}", stem, suffix, ext))
+}
+
+// ============================================================================
+// ADT parsing
+// ============================================================================
+
+/// Parse a root ADT into per-chunk records, reading the WMOs from its
+/// `_obj0` sibling when the tile is split.
+/// Returns `None` when the file has no MCNK chunks at all.
+pub fn parse_adt(path: &Path) -> Result<Option<TileData>, Box<dyn std::error::Error>> {
+    let root = fs::read(path)?;
+    let obj0_path = split_sibling(path, "_obj0");
+    let obj0 = match AdtLayout::detect(&root) {
+        AdtLayout::Split if obj0_path.is_file() => Some(fs::read(obj0_path)?),
+        _ => None,
+    };
+    parse_tile_bytes(&root, obj0.as_deref())
+}
+
+/// `parse_adt` for a root already in memory, e.g. read from an archive; a
+/// split tile's WMOs are left out
+pub fn parse_adt_bytes(data: &[u8]) -> Result<Option<TileData>, Box<dyn std::error::Error>> {
+    parse_tile_bytes(data, None)
+}
+
+/// A tile from its root ADT and, for a split tile, its `_obj0`
+pub fn parse_tile_bytes(root: &[u8], obj0: Option<&[u8]>) -> Result<Option<TileData>, Box<dyn std::error::Error>> {
+    let layout = AdtLayout::detect(root);
+    let adt = Adt::from_reader(Cursor::new(root))?;
+
+    if adt.mcnk_chunks.is_empty() {
+        return Ok(None);
+    }
+
+    let mut chunks: Vec<ChunkData> = adt
+        .mcnk_chunks
+        .iter()
+        .enumerate()
+        .map(|(i, mcnk)| ChunkData {
+            area_id: mcnk.area_id,
+            flags: mcnk.flags,
+            holes: chunk_holes(mcnk),
+            index: (mcnk.ix, mcnk.iy),
+            position: mcnk.position,
+            terrain_height: terrain_range(mcnk),
+            heights: outer_heights(mcnk),
+            liquid: mh2o_summary(&adt, i).or_else(|| mclq_summary(mcnk)),
+        })
+        .collect();
+
+    // Without MCIN, chunks that fail to decode are dropped rather than left
+    // as gaps, so each goes where its header says
+    if layout == AdtLayout::Split {
+        let mut placed = vec![ChunkData::default(); CHUNKS_PER_TILE];
+        for chunk in chunks {
+            let (x, y) = (chunk.index.0 as usize, chunk.index.1 as usize);
+            if x < CHUNKS_PER_SIDE && y < CHUNKS_PER_SIDE {
+                placed[y * CHUNKS_PER_SIDE + x] = chunk;
+            }
+        }
+        chunks = placed;
+    }
+    chunks.resize(CHUNKS_PER_TILE, ChunkData::default());
+
+    let wmos = match (layout, obj0) {
+        (AdtLayout::Split, Some(obj0)) => {
+            let obj = SplitAdtParser::parse_obj0(&mut Cursor::new(obj0))?;
+            wmo_placements(obj.modf.as_ref(), obj.mwmo.as_ref(), obj.mwid.as_ref())
+        }
+        _ => wmo_placements(adt.modf.as_ref(), adt.mwmo.as_ref(), adt.mwid.as_ref()),
+    };
+
+    Ok(Some(TileData { chunks, wmos }))
+}
+
+/// Parse a root ADT and return only its 256 area IDs
+pub fn parse_adt_areaids(path: &Path) -> Result<Option<Vec<u32>>, Box<dyn std::error::Error>> {
+    parse_adt_chunks(path, |chunk| chunk.area_id)
+}
+
+/// Parse a root ADT and map each of its 256 chunks, row-major, through `f`
+pub fn parse_adt_chunks<T>(path: &Path, f: impl FnMut(&ChunkData) -> T) -> Result<Option<Vec<T>>, Box<dyn std::error::Error>> {
+    Ok(parse_adt(path)?.map(|tile| tile.chunks.iter().map(f).collect()))
+}
+
+/// Holes cells along one side of a chunk
+pub const HOLE_CELLS: usize = 4;
+
+/// Whether 4x4 holes cell (x, y) of a chunk is open
+pub fn is_hole(holes: u32, x: usize, y: usize) -> bool {
+    holes & (1 << (y * HOLE_CELLS + x)) != 0
+}
+
+/// A chunk's holes as the 4x4 bitmap
+fn chunk_holes(mcnk: &McnkChunk) -> u32 {
+    if !McnkFlags(mcnk.flags).has_high_res_holes() {
+        return mcnk.holes & 0xFFFF;
+    }
+    // One byte per row of 8, low bit first
+    let high = mcnk.mcvt_offset as u64 | (mcnk.mcnr_offset as u64) << 32;
+    let mut holes = 0;
+    for y in 0..HOLE_CELLS {
+        for x in 0..HOLE_CELLS {
+            let block = 0b11 << (x * 2);
+            let rows = (high >> (y * 16)) & 0xFF | (high >> (y * 16 + 8)) & 0xFF;
+            if rows & block != 0 {
+                holes |= 1 << (y * HOLE_CELLS + x);
+            }
+        }
+    }
+    holes
+}
+
+/// MCVT rows alternate 9 outer and 8 inner vertices
+const MCVT_ROW: usize = 9 + 8;
+
+/// The 9x9 outer vertices of a chunk's MCVT as absolute heights
+fn outer_heights(mcnk: &McnkChunk) -> Vec<f32> {
+    if mcnk.height_map.len() < 8 * MCVT_ROW + 9 {
+        return Vec::new();
+    }
+    let base = mcnk.position[2];
+    (0..9).flat_map(|row| (0..9).map(move |col| row * MCVT_ROW + col)).map(|i| base + mcnk.height_map[i]).collect()
+}
+
+/// MODF flag: `name_id` is a FileDataID rather than an MWID index
+const MODF_FILE_DATA_ID: u16 = 0x8;
+
+/// WMOs placed on a tile, from the root or `_obj0`. MODF positions are in
+/// placement space, measured from the map's corner with y up; the world axes
+/// run the other way.
+fn wmo_placements(modf: Option<&ModfChunk>, mwmo: Option<&MwmoChunk>, mwid: Option<&MwidChunk>) -> Vec<WmoPlacement> {
+    let Some(modf) = modf else {
+        return Vec::new();
+    };
+
+    // MWID holds byte offsets into MWMO, which wow_adt hands over already split
+    let names = mwmo.map(|m| m.filenames.as_slice()).unwrap_or_default();
+    let mut offsets = Vec::with_capacity(names.len());
+    let mut offset = 0;
+    for name in names {
+        offsets.push(offset);
+        offset += name.len() as u32 + 1;
+    }
+    let name_at = |name_id: u32| {
+        let offset = mwid?.offsets.get(name_id as usize)?;
+        let index = offsets.binary_search(offset).ok()?;
+        Some(names[index].clone())
+    };
+
+    let half = TILE_SIZE * (TILES_PER_SIDE / 2) as f32;
+    modf.models
+        .iter()
+        .filter_map(|m| {
+            let file = if m.flags & MODF_FILE_DATA_ID != 0 {
+                WmoFile::FileDataId(m.name_id)
+            } else {
+                WmoFile::Name(name_at(m.name_id)?)
+            };
+            let position = [half - m.position[2], half - m.position[0], m.position[1]];
+            Some(WmoPlacement { file, unique_id: m.unique_id, position })
+        })
+        .collect()
+}
+
+/// Min/max absolute terrain height of a chunk; flat at the base height when
+/// the chunk has no MCVT
+fn terrain_range(mcnk: &McnkChunk) -> (f32, f32) {
+    let base = mcnk.position[2];
+    mcnk.height_map
+        .iter()
+        .filter(|h| h.is_finite())
+        .fold(None, |acc: Option<(f32, f32)>, &h| match acc {
+            Some((lo, hi)) => Some((lo.min(h), hi.max(h))),
+            None => Some((h, h)),
+        })
+        .map_or((base, base), |(lo, hi)| (base + lo, base + hi))
+}
+
+/// Liquid from the root MH2O chunk (WotLK+), using the first layer
+fn mh2o_summary(adt: &Adt, chunk_idx: usize) -> Option<LiquidSummary> {
+    let instance = adt.mh2o.as_ref()?.chunks.get(chunk_idx)?.instances.first()?;
+    let (min_height, max_height) = match instance.level_data {
+        WaterLevelData::Uniform { min_height, max_height } => (min_height, max_height),
+        WaterLevelData::Variable { min_height, max_height, .. } => (min_height, max_height),
+    };
+
+    Some(LiquidSummary { liquid_type: instance.liquid_type, min_height, max_height })
+}
+
+/// Liquid from the legacy per-chunk MCLQ subchunk (pre-WotLK)
+fn mclq_summary(mcnk: &McnkChunk) -> Option<LiquidSummary> {
+    let liquid_type = McnkFlags(mcnk.flags).mclq_liquid_type()?;
+
+    let mclq = mcnk.mclq.as_ref()?;
+    let (min_height, max_height) = mclq
+        .vertices
+        .iter()
+        .map(|v| v.depth)
+        .filter(|h| h.is_finite())
+        .fold(None, |acc: Option<(f32, f32)>, h| match acc {
+            Some((lo, hi)) => Some((lo.min(h), hi.max(h))),
+            None => Some((h, h)),
+        })
+        .unwrap_or((mclq.base_height, mclq.base_height));
+
+    Some(LiquidSummary { liquid_type, min_height, max_height })
+}
+
+// ============================================================================
+// Area-only fast path
+// ============================================================================
+//
+// For runs that only need the area grid. MHDR points at MCIN, MCIN gives the
+// file offset of every MCNK, and area_id sits at a fixed offset in the MCNK
+// header, so nothing else in the file is decoded. Root ADTs from Cataclysm
+// on have no MCIN; their top-level chunks are walked instead.
+
+/// Offset of `area_id` within the MCNK header (after the 8-byte chunk header)
+const MCNK_AREA_ID_OFFSET: usize = 0x34;
+
+/// Size of one MCIN entry: offset, size, flags, async ID
+const MCIN_ENTRY_SIZE: usize = 16;
+
+fn u32_at(data: &[u8], at: usize) -> Result<u32, AdtError> {
+    data.get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or(AdtError::UnexpectedEof)
+}
+
+fn expect_magic(data: &[u8], at: usize, magic: &str) -> Result<(), AdtError> {
+    let found = data.get(at..at + 4).ok_or(AdtError::UnexpectedEof)?;
+    // Stored reversed on disk
+    if found.iter().rev().eq(magic.as_bytes()) {
+        return Ok(());
+    }
+    Err(AdtError::InvalidMagic {
+        expected: magic.to_string(),
+        found: found.iter().rev().map(|&b| b as char).collect(),
+    })
+}
+
+/// The 256 area IDs of a root ADT, read straight from the MCNK headers.
+/// Same result as `parse_adt_bytes(..).area_ids()` without decoding anything
+/// else; `None` when the file has no MCNK chunks.
+pub fn read_area_ids_fast(data: &[u8]) -> Result<Option<Vec<u32>>, AdtError> {
+    expect_magic(data, 0, "MVER")?;
+    let mut area_ids = vec![0u32; CHUNKS_PER_TILE];
+    let mut found = 0;
+
+    // MHDR offsets are relative to the start of its data; MCIN's is the second
+    // field. Files whose offset doesn't land on MCIN are walked instead.
+    let mhdr = 8 + u32_at(data, 4)? as usize;
+    if expect_magic(data, mhdr, "MHDR").is_ok() {
+        let mcin = mhdr + 8 + u32_at(data, mhdr + 8 + 4)? as usize;
+        if expect_magic(data, mcin, "MCIN").is_ok() {
+            for (i, area_id) in area_ids.iter_mut().enumerate() {
+                let mcnk = u32_at(data, mcin + 8 + i * MCIN_ENTRY_SIZE)? as usize;
+                if mcnk == 0 {
+                    continue;
+                }
+                expect_magic(data, mcnk, "MCNK")?;
+                *area_id = u32_at(data, mcnk + 8 + MCNK_AREA_ID_OFFSET)?;
+                found += 1;
+            }
+            return Ok((found > 0).then_some(area_ids));
+        }
+    }
+
+    let mut at = 0;
+    while at + 8 <= data.len() {
+        let size = u32_at(data, at + 4)? as usize;
+        if expect_magic(data, at, "MCNK").is_ok() && found < CHUNKS_PER_TILE {
+            area_ids[found] = u32_at(data, at + 8 + MCNK_AREA_ID_OFFSET)?;
+            found += 1;
+        }
+        at += 8 + size;
+    }
+    Ok((found > 0).then_some(area_ids))
+}
@@ -0,0 +1,177 @@
+use crate::dbc::Dbc;
+use crate::grid::{chunk_at_units, chunk_world_rect, world_to_chunk_units, ChunkPos, WorldRect};
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+// ============================================================================
+// Area triggers
+// ============================================================================
+//
+// AreaTrigger.dbc places the client's invisible trigger volumes: dungeon
+// portals, inn rest areas, exploration and quest objectives. Each is a sphere
+// (radius) or a box (length, width, height, rotated by yaw around its centre).
+// Binning their ground footprints into chunks lines them up with the grids.
+
+/// AreaTrigger.dbc fields used here (1.12 through 3.3.5 layout)
+const DBC_FIELDS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TriggerShape {
+    Sphere { radius: f32 },
+    /// `length` runs along world X before the yaw (radians) is applied
+    Box { length: f32, width: f32, height: f32, yaw: f32 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AreaTrigger {
+    pub id: u32,
+    pub map_id: u32,
+    pub pos: [f32; 3],
+    pub shape: TriggerShape,
+}
+
+impl AreaTrigger {
+    fn new(id: u32, map_id: u32, pos: [f32; 3], radius: f32, [length, width, height, yaw]: [f32; 4]) -> Self {
+        // The client uses the radius when set and the box otherwise
+        let shape = if radius > 0.0 { TriggerShape::Sphere { radius } } else { TriggerShape::Box { length, width, height, yaw } };
+        Self { id, map_id, pos, shape }
+    }
+
+    /// Corners of a box footprint, in world (x, y)
+    fn box_corners(&self, length: f32, width: f32, yaw: f32) -> [(f32, f32); 4] {
+        let (sin, cos) = yaw.sin_cos();
+        let (hl, hw) = (length / 2.0, width / 2.0);
+        [(hl, hw), (hl, -hw), (-hl, -hw), (-hl, hw)]
+            .map(|(u, v)| (self.pos[0] + u * cos - v * sin, self.pos[1] + u * sin + v * cos))
+    }
+
+    /// World rectangle around the footprint
+    pub fn bounds(&self) -> WorldRect {
+        let [x, y, _] = self.pos;
+        match self.shape {
+            TriggerShape::Sphere { radius } => WorldRect { min_x: x - radius, max_x: x + radius, min_y: y - radius, max_y: y + radius },
+            TriggerShape::Box { length, width, yaw, .. } => {
+                let corners = self.box_corners(length, width, yaw);
+                let (xs, ys) = (corners.map(|c| c.0), corners.map(|c| c.1));
+                WorldRect {
+                    min_x: xs.iter().copied().fold(f32::INFINITY, f32::min),
+                    max_x: xs.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+                    min_y: ys.iter().copied().fold(f32::INFINITY, f32::min),
+                    max_y: ys.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+                }
+            }
+        }
+    }
+
+    /// Whether the footprint touches `rect`
+    pub fn overlaps(&self, rect: &WorldRect) -> bool {
+        let [x, y, _] = self.pos;
+        match self.shape {
+            TriggerShape::Sphere { radius } => {
+                let dx = x - x.clamp(rect.min_x, rect.max_x);
+                let dy = y - y.clamp(rect.min_y, rect.max_y);
+                dx * dx + dy * dy <= radius * radius
+            }
+            TriggerShape::Box { length, width, yaw, .. } => {
+                // Separating axes: the rectangle's two and the box's two
+                let corners = self.box_corners(length, width, yaw);
+                let rect_corners =
+                    [(rect.min_x, rect.min_y), (rect.min_x, rect.max_y), (rect.max_x, rect.min_y), (rect.max_x, rect.max_y)];
+                let (sin, cos) = yaw.sin_cos();
+                [(1.0, 0.0), (0.0, 1.0), (cos, sin), (-sin, cos)].into_iter().all(|(ax, ay)| {
+                    let project = |points: &[(f32, f32)]| {
+                        points.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &(px, py)| {
+                            let d = px * ax + py * ay;
+                            (lo.min(d), hi.max(d))
+                        })
+                    };
+                    let (a_lo, a_hi) = project(&corners);
+                    let (b_lo, b_hi) = project(&rect_corners);
+                    a_lo <= b_hi && b_lo <= a_hi
+                })
+            }
+        }
+    }
+
+    /// Chunks the footprint touches, dropping any off the map
+    pub fn chunks(&self) -> Vec<ChunkPos> {
+        let bounds = self.bounds();
+        // World axes run against chunk axes, so the max corner is the first chunk
+        let (cx0, cy0) = world_to_chunk_units(bounds.max_x, bounds.max_y);
+        let (cx1, cy1) = world_to_chunk_units(bounds.min_x, bounds.min_y);
+        let mut chunks = Vec::new();
+        for cy in cy0.floor() as i64..=cy1.floor() as i64 {
+            for cx in cx0.floor() as i64..=cx1.floor() as i64 {
+                if let Some(pos) = chunk_at_units(cx, cy).filter(|&pos| self.overlaps(&chunk_world_rect(pos))) {
+                    chunks.push(pos);
+                }
+            }
+        }
+        chunks
+    }
+}
+
+/// Triggers of a WDBC AreaTrigger.dbc
+pub fn parse_area_trigger_dbc(dbc: &Dbc) -> Result<Vec<AreaTrigger>, String> {
+    if dbc.field_count < DBC_FIELDS {
+        return Err(format!("AreaTrigger.dbc has {} fields, expected at least {}", dbc.field_count, DBC_FIELDS));
+    }
+    Ok(dbc
+        .records()
+        .map(|r| {
+            let pos = [r.f32(2), r.f32(3), r.f32(4)];
+            AreaTrigger::new(r.u32(0), r.u32(1), pos, r.f32(5), [r.f32(6), r.f32(7), r.f32(8), r.f32(9)])
+        })
+        .collect())
+}
+
+/// Parse an AreaTrigger CSV export (wago.tools column names)
+pub fn parse_area_trigger_csv(csv_path: &Path) -> Result<Vec<AreaTrigger>, Box<dyn std::error::Error>> {
+    let reader = BufReader::new(File::open(csv_path)?);
+    let mut lines = reader.lines();
+    let header = lines.next().ok_or("Empty CSV")??;
+
+    let columns: Vec<&str> = header.split(',').map(|c| c.trim()).collect();
+    let col = |name: &str| columns.iter().position(|&c| c == name).ok_or_else(|| format!("No {} column", name));
+    let id_idx = col("ID")?;
+    let map_idx = col("ContinentID")?;
+    let pos_idx = [col("Pos_0")?, col("Pos_1")?, col("Pos_2")?];
+    let radius_idx = col("Radius")?;
+    let box_idx = [col("Box_length")?, col("Box_width")?, col("Box_height")?, col("Box_yaw")?];
+    let max_idx = columns.len() - 1;
+
+    let mut triggers = Vec::new();
+    for line in lines {
+        let line = line?;
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+        if fields.len() <= max_idx {
+            continue;
+        }
+        let int = |i: usize| fields[i].parse::<u32>().unwrap_or(0);
+        let float = |i: usize| fields[i].parse::<f32>().unwrap_or(0.0);
+        triggers.push(AreaTrigger::new(
+            int(id_idx),
+            int(map_idx),
+            pos_idx.map(float),
+            float(radius_idx),
+            box_idx.map(float),
+        ));
+    }
+    Ok(triggers)
+}
+
+/// Trigger IDs per chunk of one map: tile key -> chunk index -> IDs
+pub fn bin_triggers<'a>(triggers: impl IntoIterator<Item = &'a AreaTrigger>) -> BTreeMap<u32, BTreeMap<usize, Vec<u32>>> {
+    let mut bins: BTreeMap<u32, BTreeMap<usize, Vec<u32>>> = BTreeMap::new();
+    for trigger in triggers {
+        for pos in trigger.chunks() {
+            bins.entry(pos.tile_key()).or_default().entry(pos.chunk_index()).or_default().push(trigger.id);
+        }
+    }
+    bins
+}
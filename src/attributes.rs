@@ -0,0 +1,149 @@
+use crate::json;
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+// ============================================================================
+// Per-area attribute tables
+// ============================================================================
+//
+// User-supplied CSVs keyed by area ID (fishing skill, recommended level, ...).
+// The first column naming an area (`zone`, `area_id`, `AreaID`) is the key
+// and every other column is carried through as a field.
+
+/// Column names accepted as the area key, in order of preference
+const AREA_COLUMNS: &[&str] = &["zone", "area_id", "AreaID", "AreaId"];
+
+/// A CSV cell: numbers are written to Lua unquoted
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum AttrValue {
+    Number(f64),
+    Text(String),
+}
+
+impl AttrValue {
+    fn parse(s: &str) -> Self {
+        match s.parse() {
+            Ok(n) => AttrValue::Number(n),
+            Err(_) => AttrValue::Text(s.to_string()),
+        }
+    }
+
+    /// Lua literal for this value
+    pub fn to_lua(&self) -> String {
+        match self {
+            AttrValue::Number(n) => n.to_string(),
+            AttrValue::Text(s) => format!("{:?}", s),
+        }
+    }
+
+    /// JSON literal for this value; numbers JSON can't hold are `null`
+    pub fn to_json(&self) -> String {
+        match self {
+            AttrValue::Number(n) if n.is_finite() => n.to_string(),
+            AttrValue::Number(_) => "null".to_string(),
+            AttrValue::Text(s) => json::string(s),
+        }
+    }
+}
+
+/// Field name -> value for one area
+pub type AttrFields = BTreeMap<String, AttrValue>;
+
+/// `name` as a Lua table key: bare when it's an identifier, `["..."]` otherwise
+pub fn lua_key(name: &str) -> String {
+    let is_ident = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !name.starts_with(|c: char| c.is_ascii_digit());
+    if is_ident && !name.is_empty() {
+        name.to_string()
+    } else {
+        format!("[{:?}]", name)
+    }
+}
+
+/// `{ a = 1, b = "x" }`
+pub fn fields_to_lua(fields: &AttrFields) -> String {
+    let parts: Vec<String> = fields
+        .iter()
+        .map(|(name, value)| format!("{} = {}", lua_key(name), value.to_lua()))
+        .collect();
+    format!("{{ {} }}", parts.join(", "))
+}
+
+/// `{"a":1,"b":"x"}`
+pub fn fields_to_json(fields: &AttrFields) -> String {
+    let parts: Vec<String> =
+        fields.iter().map(|(name, value)| format!("{}:{}", json::string(name), value.to_json())).collect();
+    format!("{{{}}}", parts.join(","))
+}
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AttributeTable {
+    /// Table name, from the file stem
+    pub name: String,
+    pub rows: BTreeMap<u32, AttrFields>,
+}
+
+impl AttributeTable {
+    pub fn from_csv(csv_path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let name = csv_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .ok_or("Bad file name")?
+            .to_string();
+        let reader = BufReader::new(File::open(csv_path)?);
+        let mut lines = reader.lines();
+        let header = lines.next().ok_or("Empty CSV")??;
+
+        let columns: Vec<&str> = header.split(',').map(|c| c.trim().trim_matches('"')).collect();
+        let key_idx = AREA_COLUMNS
+            .iter()
+            .find_map(|name| columns.iter().position(|c| c == name))
+            .ok_or_else(|| format!("No area column ({})", AREA_COLUMNS.join(", ")))?;
+
+        let mut rows = BTreeMap::new();
+        for (line_no, line) in lines.enumerate() {
+            let line = line?;
+            let fields: Vec<&str> = line.split(',').map(|f| f.trim().trim_matches('"')).collect();
+            let Some(Ok(area_id)) = fields.get(key_idx).map(|f| f.parse::<u32>()) else {
+                continue;
+            };
+
+            let row: AttrFields = columns
+                .iter()
+                .zip(&fields)
+                .enumerate()
+                .filter(|&(i, (_, value))| i != key_idx && !value.is_empty())
+                .map(|(_, (name, value))| (name.to_string(), AttrValue::parse(value)))
+                .collect();
+            if rows.insert(area_id, row).is_some() {
+                return Err(format!("line {}: area {} listed twice", line_no + 2, area_id).into());
+            }
+        }
+
+        Ok(Self { name, rows })
+    }
+
+    /// Every `*.csv` in `dir`, sorted by name
+    pub fn load_dir(dir: &Path) -> Result<Vec<Self>, Box<dyn std::error::Error>> {
+        let mut paths: Vec<_> = fs::read_dir(dir)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|e| e.eq_ignore_ascii_case("csv")))
+            .collect();
+        paths.sort();
+        paths
+            .iter()
+            .map(|p| Self::from_csv(p).map_err(|e| format!("{}: {}", p.display(), e).into()))
+            .collect()
+    }
+
+    /// Area IDs in the table that `is_known` rejects
+    pub fn unknown_areas(&self, is_known: impl Fn(u32) -> bool) -> Vec<u32> {
+        self.rows.keys().copied().filter(|&id| !is_known(id)).collect()
+    }
+}
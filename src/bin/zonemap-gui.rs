@@ -0,0 +1,537 @@
+//! Desktop frontend for running the generator without a terminal: pick the
+//! working folder, preview each continent's area grid and regenerate Data/.
+//! Dropping a single `.adt` on the window inspects it; dropping a folder
+//! makes it the working folder.
+
+use eframe::egui;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use zone_map::adt::{parse_adt, TileData, CHUNKS_PER_SIDE};
+use zone_map::codec::{Codec, LayerCodecs, ALL_CODECS};
+use zone_map::failure::{is_split_adt, FailureKind};
+use zone_map::layers::{AreaIdLayer, LayerExtractor};
+use zone_map::pipeline::{discover, CancelStage, CancellationToken, EncodeStage, ExtractStage, ParseStage};
+
+/// Continents the generator knows about, with their ADT directories
+const CONTINENTS: [(&str, &str); 2] = [("Kalimdor", "kalimdor_adts"), ("Azeroth", "azeroth_adts")];
+
+fn main() -> eframe::Result {
+    zone_map::crash::install();
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_inner_size([1100.0, 750.0]),
+        ..Default::default()
+    };
+    eframe::run_native("ZoneMap", options, Box::new(|_cc| Ok(Box::new(GuiApp::new()))))
+}
+
+// ============================================================================
+// Background jobs
+// ============================================================================
+
+enum PreviewMsg {
+    Tile { continent: String, key: u32, area_ids: Vec<u32>, encoded_len: usize },
+    Error(String),
+    Done,
+}
+
+/// Parsing the selected continents on a worker thread
+struct PreviewJob {
+    token: CancellationToken,
+    done: Arc<AtomicUsize>,
+    total: usize,
+    rx: mpsc::Receiver<PreviewMsg>,
+}
+
+/// The `zone-map` generator running in the working folder
+struct GenerateJob {
+    rx: mpsc::Receiver<String>,
+}
+
+/// Parsed tiles of one continent
+#[derive(Default)]
+struct ContinentPreview {
+    tiles: HashMap<u32, Vec<u32>>,
+    encoded_bytes: usize,
+}
+
+// ============================================================================
+// Single-ADT inspection
+// ============================================================================
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum InspectField {
+    AreaId,
+    Flags,
+    Holes,
+    Liquid,
+}
+
+impl InspectField {
+    const ALL: [InspectField; 4] = [InspectField::AreaId, InspectField::Flags, InspectField::Holes, InspectField::Liquid];
+
+    fn label(&self) -> &'static str {
+        match self {
+            InspectField::AreaId => "Area ID",
+            InspectField::Flags => "Flags",
+            InspectField::Holes => "Holes",
+            InspectField::Liquid => "Liquid",
+        }
+    }
+}
+
+/// A dropped ADT: its chunks if it parsed, and anything worth flagging
+struct Inspection {
+    path: PathBuf,
+    tile: Option<TileData>,
+    warnings: Vec<String>,
+    field: InspectField,
+    open: bool,
+}
+
+impl Inspection {
+    fn new(path: &Path) -> Self {
+        let mut warnings = Vec::new();
+        if is_split_adt(path) {
+            warnings.push("split file (_tex/_obj/_lod): area IDs live in the root ADT".to_string());
+        }
+
+        let tile = match parse_adt(path) {
+            Ok(Some(tile)) => Some(tile),
+            Ok(None) => {
+                warnings.push(format!("{}: {}", FailureKind::NoChunks.label(), FailureKind::NoChunks.hint()));
+                None
+            }
+            Err(e) => {
+                let kind = FailureKind::classify(path, e.as_ref());
+                warnings.push(format!("{}: {}", kind.label(), e));
+                warnings.push(format!("hint: {}", kind.hint()));
+                None
+            }
+        };
+
+        if let Some(tile) = &tile {
+            let unset = tile.chunks.iter().filter(|c| c.area_id == 0).count();
+            if unset > 0 {
+                warnings.push(format!("{} chunks have area ID 0", unset));
+            }
+            for (idx, chunk) in tile.chunks.iter().enumerate() {
+                let expected = ((idx % CHUNKS_PER_SIDE) as u32, (idx / CHUNKS_PER_SIDE) as u32);
+                if chunk.index != expected {
+                    warnings.push(format!(
+                        "chunk {} claims index {:?}, expected {:?}",
+                        idx, chunk.index, expected
+                    ));
+                }
+            }
+        }
+
+        Self { path: path.to_path_buf(), tile, warnings, field: InspectField::AreaId, open: true }
+    }
+
+    fn show(&mut self, ctx: &egui::Context) {
+        let title = self.path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let mut open = self.open;
+        egui::Window::new(format!("Inspect {}", title)).open(&mut open).show(ctx, |ui| {
+            ui.label(self.path.display().to_string());
+            for warning in &self.warnings {
+                ui.colored_label(egui::Color32::YELLOW, warning);
+            }
+
+            let Some(tile) = &self.tile else {
+                return;
+            };
+            ui.horizontal(|ui| {
+                for field in InspectField::ALL {
+                    ui.selectable_value(&mut self.field, field, field.label());
+                }
+            });
+
+            egui::Grid::new("inspect_grid").spacing([2.0, 2.0]).show(ui, |ui| {
+                for chunk_y in 0..CHUNKS_PER_SIDE {
+                    for chunk_x in 0..CHUNKS_PER_SIDE {
+                        let chunk = &tile.chunks[chunk_y * CHUNKS_PER_SIDE + chunk_x];
+                        let text = match self.field {
+                            InspectField::AreaId => chunk.area_id.to_string(),
+                            InspectField::Flags => format!("{:#x}", chunk.flags),
+                            InspectField::Holes => format!("{:#x}", chunk.holes),
+                            InspectField::Liquid => chunk.liquid.map(|l| l.liquid_type.to_string()).unwrap_or_default(),
+                        };
+                        let liquid = chunk
+                            .liquid
+                            .map(|l| format!("type {} ({:.1}..{:.1})", l.liquid_type, l.min_height, l.max_height))
+                            .unwrap_or_else(|| "none".to_string());
+                        ui.label(egui::RichText::new(text).monospace().color(area_color(chunk.area_id)))
+                            .on_hover_text(format!(
+                                "chunk {},{}\narea {}\nflags {:#x}\nholes {:#x}\nliquid {}",
+                                chunk_x, chunk_y, chunk.area_id, chunk.flags, chunk.holes, liquid
+                            ));
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+        self.open = open;
+    }
+}
+
+// ============================================================================
+// App
+// ============================================================================
+
+struct GuiApp {
+    work_dir: String,
+    enabled: [bool; CONTINENTS.len()],
+    area_codec: Codec,
+    previews: HashMap<String, ContinentPreview>,
+    shown: String,
+    preview_job: Option<PreviewJob>,
+    generate_job: Option<GenerateJob>,
+    inspections: Vec<Inspection>,
+    log: Vec<String>,
+}
+
+impl GuiApp {
+    fn new() -> Self {
+        let work_dir = std::env::current_dir().map(|d| d.display().to_string()).unwrap_or_default();
+        Self {
+            work_dir,
+            enabled: [true; CONTINENTS.len()],
+            area_codec: Codec::Raw,
+            previews: HashMap::new(),
+            shown: CONTINENTS[0].0.to_string(),
+            preview_job: None,
+            generate_job: None,
+            inspections: Vec::new(),
+            log: Vec::new(),
+        }
+    }
+
+    fn start_preview(&mut self) {
+        let work_dir = PathBuf::from(&self.work_dir);
+        let mut files = Vec::new();
+        for (i, (name, dir)) in CONTINENTS.iter().enumerate() {
+            if !self.enabled[i] {
+                continue;
+            }
+            match discover(&work_dir.join(dir)) {
+                Ok(found) => files.push((name.to_string(), found.collect::<Vec<_>>())),
+                Err(e) => self.log.push(format!("{}: {}", dir, e)),
+            }
+        }
+
+        self.previews.clear();
+        let token = CancellationToken::new();
+        let done = Arc::new(AtomicUsize::new(0));
+        let total = files.iter().map(|(_, f)| f.len()).sum();
+        let (tx, rx) = mpsc::channel();
+        let codecs = LayerCodecs::parse(&[&format!("areaid={}", self.area_codec)]).unwrap_or_default();
+
+        let worker_token = token.clone();
+        let worker_done = Arc::clone(&done);
+        thread::spawn(move || {
+            let area_layer = AreaIdLayer;
+            let layers: [&dyn LayerExtractor; 1] = [&area_layer];
+            for (continent, continent_files) in files {
+                let tiles = continent_files
+                    .into_iter()
+                    .cancel_on(&worker_token)
+                    .parse()
+                    .extract(&layers)
+                    .encode(&codecs);
+                for tile in tiles {
+                    worker_done.fetch_add(1, Ordering::Relaxed);
+                    let msg = match tile {
+                        Ok(tile) => {
+                            let key = tile.file.key();
+                            let (codec, bytes) = &tile.layers["areaid"];
+                            match codec.decode(bytes) {
+                                Ok(area_ids) => PreviewMsg::Tile {
+                                    continent: continent.clone(),
+                                    key,
+                                    area_ids,
+                                    encoded_len: bytes.len(),
+                                },
+                                Err(e) => PreviewMsg::Error(format!("{}: {}", tile.file.path.display(), e)),
+                            }
+                        }
+                        Err(e) => PreviewMsg::Error(e.to_string()),
+                    };
+                    if tx.send(msg).is_err() {
+                        return;
+                    }
+                }
+            }
+            let _ = tx.send(PreviewMsg::Done);
+        });
+
+        self.log.push(format!("Previewing {} tiles...", total));
+        self.preview_job = Some(PreviewJob { token, done, total, rx });
+    }
+
+    fn start_generate(&mut self) {
+        let exe = std::env::current_exe()
+            .ok()
+            .and_then(|p| p.parent().map(|dir| dir.join(format!("zone-map{}", std::env::consts::EXE_SUFFIX))));
+        let Some(exe) = exe.filter(|p| p.exists()) else {
+            self.log.push("zone-map generator not found next to zonemap-gui".to_string());
+            return;
+        };
+
+        let child = Command::new(&exe)
+            .current_dir(&self.work_dir)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut child = match child {
+            Ok(c) => c,
+            Err(e) => {
+                self.log.push(format!("Failed to start {}: {}", exe.display(), e));
+                return;
+            }
+        };
+
+        let (tx, rx) = mpsc::channel();
+        if let Some(stderr) = child.stderr.take() {
+            let tx = tx.clone();
+            thread::spawn(move || {
+                for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                    let _ = tx.send(line);
+                }
+            });
+        }
+        let stdout = child.stdout.take();
+        thread::spawn(move || {
+            if let Some(stdout) = stdout {
+                for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                    let _ = tx.send(line);
+                }
+            }
+            let status = match child.wait().map(|s| s.code()) {
+                Ok(Some(0)) => "Generator finished".to_string(),
+                Ok(Some(2)) => "Generator finished, but a quality gate failed".to_string(),
+                Ok(code) => format!("Generator failed (exit code {:?})", code),
+                Err(e) => format!("Generator failed: {}", e),
+            };
+            let _ = tx.send(status);
+        });
+
+        self.log.push(format!("Running {} in {}", exe.display(), self.work_dir));
+        self.generate_job = Some(GenerateJob { rx });
+    }
+
+    /// Drain messages from background jobs
+    fn poll_jobs(&mut self) {
+        if let Some(job) = &self.preview_job {
+            let mut finished = false;
+            for msg in job.rx.try_iter() {
+                match msg {
+                    PreviewMsg::Tile { continent, key, area_ids, encoded_len } => {
+                        let preview = self.previews.entry(continent).or_default();
+                        preview.tiles.insert(key, area_ids);
+                        preview.encoded_bytes += encoded_len;
+                    }
+                    PreviewMsg::Error(e) => self.log.push(e),
+                    PreviewMsg::Done => finished = true,
+                }
+            }
+            if finished || job.token.is_cancelled() {
+                let done = job.done.load(Ordering::Relaxed);
+                self.log.push(if finished {
+                    format!("Preview done ({} tiles)", done)
+                } else {
+                    format!("Preview cancelled after {} of {} tiles", done, job.total)
+                });
+                self.preview_job = None;
+            }
+        }
+
+        if let Some(job) = &self.generate_job {
+            let mut finished = false;
+            for line in job.rx.try_iter() {
+                finished |= line.starts_with("Generator ");
+                self.log.push(line);
+            }
+            if finished {
+                self.generate_job = None;
+            }
+        }
+    }
+
+    /// ADTs open an inspection window, folders become the working folder
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        let dropped: Vec<PathBuf> = ctx.input(|i| i.raw.dropped_files.iter().map(|f| f.path().to_path_buf()).collect());
+        for path in dropped {
+            if path.is_dir() {
+                self.work_dir = path.display().to_string();
+            } else if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("adt")) {
+                self.inspections.push(Inspection::new(&path));
+            } else {
+                self.log.push(format!("Not an ADT or folder: {}", path.display()));
+            }
+        }
+    }
+
+    fn controls(&mut self, ui: &mut egui::Ui) {
+        ui.heading("ZoneMap");
+        ui.add_space(8.0);
+
+        ui.label("Working folder (holds the *_adts folders and AreaTable CSV), or drop one here:");
+        ui.text_edit_singleline(&mut self.work_dir);
+        let work_dir = Path::new(&self.work_dir);
+        ui.add_space(8.0);
+
+        ui.label("Continents:");
+        for (i, (name, dir)) in CONTINENTS.iter().enumerate() {
+            let found = work_dir.join(dir).is_dir();
+            ui.add_enabled(found, egui::Checkbox::new(&mut self.enabled[i], *name))
+                .on_disabled_hover_text(format!("{} not found in the working folder", dir));
+        }
+        ui.add_space(8.0);
+
+        egui::ComboBox::from_label("Area codec")
+            .selected_text(self.area_codec.name())
+            .show_ui(ui, |ui| {
+                for codec in ALL_CODECS {
+                    ui.selectable_value(&mut self.area_codec, codec, codec.name());
+                }
+            });
+        ui.add_space(8.0);
+
+        let busy = self.preview_job.is_some() || self.generate_job.is_some();
+        ui.horizontal(|ui| {
+            if ui.add_enabled(!busy, egui::Button::new("Preview")).clicked() {
+                self.start_preview();
+            }
+            if ui.add_enabled(!busy, egui::Button::new("Generate Data/")).clicked() {
+                self.start_generate();
+            }
+        });
+
+        if let Some(job) = &self.preview_job {
+            let done = job.done.load(Ordering::Relaxed);
+            let fraction = if job.total == 0 { 1.0 } else { done as f32 / job.total as f32 };
+            ui.add(egui::ProgressBar::new(fraction).text(format!("{} / {} tiles", done, job.total)));
+            if ui.button("Cancel").clicked() {
+                job.token.cancel();
+            }
+        }
+        if self.generate_job.is_some() {
+            ui.add(egui::Spinner::new());
+        }
+        ui.add_space(8.0);
+
+        for (name, _) in CONTINENTS {
+            if let Some(preview) = self.previews.get(name) {
+                ui.label(format!(
+                    "{}: {} tiles, {:.1} KB as {}",
+                    name,
+                    preview.tiles.len(),
+                    preview.encoded_bytes as f32 / 1024.0,
+                    self.area_codec
+                ));
+            }
+        }
+    }
+
+    fn preview(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            for (name, _) in CONTINENTS {
+                ui.selectable_value(&mut self.shown, name.to_string(), name);
+            }
+        });
+
+        let Some(preview) = self.previews.get(&self.shown).filter(|p| !p.tiles.is_empty()) else {
+            ui.centered_and_justified(|ui| {
+                ui.label("Press Preview to parse the selected continents, or drop an .adt here to inspect it")
+            });
+            return;
+        };
+        draw_grid(ui, &preview.tiles);
+    }
+}
+
+impl eframe::App for GuiApp {
+    fn ui(&mut self, ui: &mut egui::Ui, _frame: &mut eframe::Frame) {
+        self.poll_jobs();
+        if self.preview_job.is_some() || self.generate_job.is_some() {
+            ui.ctx().request_repaint();
+        }
+
+        egui::Panel::left("controls").default_size(300.0).show(ui, |ui| self.controls(ui));
+        egui::Panel::bottom("log").default_size(160.0).show(ui, |ui| {
+            egui::ScrollArea::vertical().stick_to_bottom(true).show(ui, |ui| {
+                for line in &self.log {
+                    ui.monospace(line);
+                }
+            });
+        });
+        egui::CentralPanel::default_margins().show(ui, |ui| self.preview(ui));
+
+        let ctx = ui.ctx().clone();
+        self.handle_dropped_files(&ctx);
+        for inspection in &mut self.inspections {
+            inspection.show(&ctx);
+        }
+        self.inspections.retain(|i| i.open);
+    }
+}
+
+// ============================================================================
+// Preview rendering
+// ============================================================================
+
+/// Chunk grid of one continent, cropped to its tiles, one colour per area
+fn draw_grid(ui: &mut egui::Ui, tiles: &HashMap<u32, Vec<u32>>) {
+    let xs = tiles.keys().map(|k| k % 64);
+    let ys = tiles.keys().map(|k| k / 64);
+    let (min_x, max_x) = (xs.clone().min().unwrap_or(0), xs.max().unwrap_or(0));
+    let (min_y, max_y) = (ys.clone().min().unwrap_or(0), ys.max().unwrap_or(0));
+    let chunks_w = ((max_x - min_x + 1) * 16) as f32;
+    let chunks_h = ((max_y - min_y + 1) * 16) as f32;
+
+    let available = ui.available_size();
+    let cell = (available.x / chunks_w).min(available.y / chunks_h).max(0.5);
+    let (response, painter) = ui.allocate_painter(egui::vec2(chunks_w * cell, chunks_h * cell), egui::Sense::hover());
+    let origin = response.rect.min;
+
+    for (&key, area_ids) in tiles {
+        let (tx, ty) = ((key % 64 - min_x) as f32, (key / 64 - min_y) as f32);
+        for (idx, &area_id) in area_ids.iter().enumerate() {
+            if area_id == 0 {
+                continue;
+            }
+            // Screen x follows tile_x/chunk_x like the in-game map
+            let x = origin.x + (tx * 16.0 + (idx % 16) as f32) * cell;
+            let y = origin.y + (ty * 16.0 + (idx / 16) as f32) * cell;
+            let rect = egui::Rect::from_min_size(egui::pos2(x, y), egui::vec2(cell, cell));
+            painter.rect_filled(rect, 0.0, area_color(area_id));
+        }
+    }
+
+    if let Some(pos) = response.hover_pos() {
+        let cx = ((pos.x - origin.x) / cell) as u32;
+        let cy = ((pos.y - origin.y) / cell) as u32;
+        let key = (min_y + cy / 16) * 64 + min_x + cx / 16;
+        if let Some(area_id) = tiles.get(&key).map(|ids| ids[((cy % 16) * 16 + cx % 16) as usize]) {
+            response.on_hover_text_at_pointer(format!(
+                "tile {}_{}  chunk {},{}  area {}",
+                key % 64,
+                key / 64,
+                cx % 16,
+                cy % 16,
+                area_id
+            ));
+        }
+    }
+}
+
+/// Stable, well-spread colour for an area ID
+fn area_color(area_id: u32) -> egui::Color32 {
+    let h = area_id.wrapping_mul(2_654_435_761);
+    egui::Color32::from_rgb(64 + (h >> 24) as u8 % 192, 64 + (h >> 16) as u8 % 192, 64 + (h >> 8) as u8 % 192)
+}
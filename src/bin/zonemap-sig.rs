@@ -0,0 +1,93 @@
+//! Key management and signature checks for signed exports:
+//!
+//!     zonemap-sig keygen <key-file>
+//!     zonemap-sig sign <dir-or-file> <key-file>
+//!     zonemap-sig verify-signature <dir-or-file> [public-key]
+//!
+//! Without a public key, verify-signature checks against the key embedded at
+//! build time (`ZONEMAP_TRUSTED_KEY`) and otherwise only reports the signer.
+
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+use zone_map::signing::{
+    embedded_key, generate_key, load_signing_key, parse_public_key, public_key_b64, sign_dir, sign_file, verify_bytes,
+    verify_dir,
+};
+
+const USAGE: &str = "usage: zonemap-sig keygen <key-file>
+       zonemap-sig sign <dir-or-file> <key-file>
+       zonemap-sig verify-signature <dir-or-file> [public-key]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+
+    let result = match args.as_slice() {
+        ["keygen", key_file] => keygen(Path::new(key_file)),
+        ["sign", target, key_file] => sign(Path::new(target), Path::new(key_file)),
+        ["verify-signature", target] => verify(Path::new(target), None),
+        ["verify-signature", target, public_key] => verify(Path::new(target), Some(public_key)),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn keygen(key_file: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let public = generate_key(key_file)?;
+    println!("Wrote: {} (keep it private)", key_file.display());
+    println!("Public key: {}", public_key_b64(&public));
+    Ok(())
+}
+
+fn sign(target: &Path, key_file: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let key = load_signing_key(key_file)?;
+    if target.is_dir() {
+        let files = sign_dir(target, &key)?;
+        println!("Signed {} files in {}", files, target.display());
+    } else {
+        println!("Wrote: {}", sign_file(target, &key)?.display());
+    }
+    Ok(())
+}
+
+fn verify(target: &Path, public_key: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let trusted = match public_key {
+        Some(b64) => Some(parse_public_key(b64)?),
+        None => embedded_key(),
+    };
+    if trusted.is_none() {
+        println!("No public key given or embedded; checking integrity only");
+    }
+
+    if target.is_dir() {
+        let report = verify_dir(target, trusted.as_ref())?;
+        println!("Signed by {}", report.signer);
+        println!("  {} files verified", report.verified);
+        for (label, files) in [("modified", &report.modified), ("missing", &report.missing), ("unsigned", &report.unsigned)] {
+            for file in files {
+                println!("  {}: {}", label, file);
+            }
+        }
+        if !report.is_ok() {
+            return Err("bundle does not match its signature".into());
+        }
+    } else {
+        let mut sig_path = target.as_os_str().to_owned();
+        sig_path.push(".sig");
+        let signer = verify_bytes(&fs::read(target)?, &fs::read_to_string(sig_path)?, trusted.as_ref())?;
+        println!("Signed by {}", public_key_b64(&signer));
+    }
+    println!("OK");
+    Ok(())
+}
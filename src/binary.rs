@@ -0,0 +1,60 @@
+use crate::grid::{ZoneGrid, TILES_PER_SIDE};
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use zonemap_format::{Container, FormatError, CHUNKS_PER_TILE};
+
+// ============================================================================
+// Binary export
+// ============================================================================
+//
+// With `--binary`, each map's grid is also written to `<Map>_tiles.zmap`, the
+// container from the `zonemap-format` crate (layout in its docs):
+//
+//     header   magic "ZMAP", u16 version, u16 reserved, u32 map ID, u32 tile count
+//     tiles    u8 x, u8 y, u16 reserved, 256 u32 area IDs, sorted by tile key
+//
+// All little-endian, with no codecs or compression, so navmesh builders and
+// other non-Lua tools get the same area IDs the addon decodes from its
+// grids. The container has no name; `read_grid` takes it from the file.
+
+pub const BINARY_EXTENSION: &str = "zmap";
+
+/// Container bytes for a map's tiles, each of which must have 256 area IDs
+pub fn encode_grid(map_id: u32, tiles: &HashMap<u32, Vec<u32>>) -> Result<Vec<u8>, String> {
+    let mut keys: Vec<u32> = tiles.keys().copied().collect();
+    keys.sort_unstable();
+    let mut records = Vec::with_capacity(keys.len());
+    for key in keys {
+        let area_ids = &tiles[&key];
+        let area_ids: &[u32; CHUNKS_PER_TILE] = area_ids
+            .as_slice()
+            .try_into()
+            .map_err(|_| format!("tile {}: {} area IDs, expected {}", key, area_ids.len(), CHUNKS_PER_TILE))?;
+        records.push((key % TILES_PER_SIDE, key / TILES_PER_SIDE, area_ids));
+    }
+    zonemap_format::to_vec(map_id, &records).map_err(|e| e.to_string())
+}
+
+/// Write `grid` to `path` as a container. Returns the bytes written.
+pub fn write_grid(grid: &ZoneGrid, path: &Path) -> Result<usize, Box<dyn std::error::Error>> {
+    let data = encode_grid(grid.map_id, grid.tiles())?;
+    fs::write(path, &data)?;
+    Ok(data.len())
+}
+
+/// Grid `name` from container bytes
+pub fn decode_grid(name: &str, data: &[u8]) -> Result<ZoneGrid, FormatError> {
+    let container = Container::parse(data)?;
+    let tiles = container.tiles().map(|tile| (tile.key(), tile.area_ids().collect())).collect();
+    Ok(ZoneGrid::new(name, container.map_id(), tiles))
+}
+
+/// Read a `<Map>_tiles.zmap` file into a grid named `<Map>`
+pub fn read_grid(path: &Path) -> Result<ZoneGrid, Box<dyn std::error::Error>> {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let name = stem.strip_suffix("_tiles").unwrap_or(&stem);
+    let data = fs::read(path)?;
+    decode_grid(name, &data).map_err(|e| format!("{}: {}", path.display(), e).into())
+}
@@ -0,0 +1,140 @@
+use crate::codec::{Codec, Compression};
+use crate::export::Exporter;
+
+use std::fmt;
+use std::str::FromStr;
+
+// ============================================================================
+// Size budgets
+// ============================================================================
+//
+// `--budget lua=2MB` caps each map's grid file. Costlier codecs make smaller
+// Lua grids, so a budgeted grid is encoded along `ESCALATION`, cheapest to
+// decode in game first, and the first encoding that fits is written; the
+// configured codecs don't apply to it. When nothing fits, the smallest is
+// written and the run fails like a quality gate, listing what each encoding
+// came to. JSON grids are plain numbers with no codec, so a JSON budget is
+// only checked.
+
+/// Encodings in order of decode cost: the codecs, then the same codecs
+/// deflated, as inflating costs more than any codec
+pub const ESCALATION: [(Codec, Compression); 8] = [
+    (Codec::Raw, Compression::None),
+    (Codec::Palette, Compression::None),
+    (Codec::Rle, Compression::None),
+    (Codec::Delta, Compression::None),
+    (Codec::Raw, Compression::Deflate),
+    (Codec::Palette, Compression::Deflate),
+    (Codec::Rle, Compression::Deflate),
+    (Codec::Delta, Compression::Deflate),
+];
+
+/// Grid file a budget applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BudgetFormat {
+    Lua,
+    Json,
+}
+
+impl BudgetFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BudgetFormat::Lua => "lua",
+            BudgetFormat::Json => "json",
+        }
+    }
+}
+
+/// Largest size of one map's grid file in a format, as `lua=2MB`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Budget {
+    pub format: BudgetFormat,
+    pub bytes: u64,
+}
+
+/// Bytes in `500KB`, `2MB`, `1.5 MiB` or `123456`; K, M and G are powers of 1024
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number.parse().map_err(|_| format!("bad size `{}`", s))?;
+    let scale = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1u64,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        _ => return Err(format!("unknown size unit in `{}` (B, KB, MB, GB)", s)),
+    };
+    Ok((number * scale as f64).round() as u64)
+}
+
+/// `1.5 MB`, `320.0 KB` or `12 B`
+pub fn format_size(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1 << 20 => format!("{:.1} MB", b as f64 / (1u64 << 20) as f64),
+        b if b >= 1 << 10 => format!("{:.1} KB", b as f64 / (1u64 << 10) as f64),
+        b => format!("{} B", b),
+    }
+}
+
+impl FromStr for Budget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format, size) = s.split_once('=').ok_or_else(|| format!("expected format=size, got `{}`", s))?;
+        let format = match format.trim() {
+            "lua" => BudgetFormat::Lua,
+            "json" => BudgetFormat::Json,
+            other => return Err(format!("unknown format `{}` (lua, json)", other)),
+        };
+        Ok(Budget { format, bytes: parse_size(size)? })
+    }
+}
+
+impl fmt::Display for Budget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.format.name(), format_size(self.bytes))
+    }
+}
+
+/// A grid encoded to fit a budget
+pub struct Fit {
+    /// The first encoding that fits, or the smallest if none does
+    pub grid: Exporter,
+    pub fits: bool,
+    /// Size of the Lua file with each encoding tried, in order
+    pub attempts: Vec<(Codec, Compression, usize)>,
+}
+
+impl Fit {
+    /// Each encoding tried and its size, e.g. `raw/none 3.1 MB, palette/none 2.4 MB`
+    pub fn describe_attempts(&self) -> String {
+        let attempts: Vec<String> = self
+            .attempts
+            .iter()
+            .map(|(codec, compression, len)| format!("{}/{} {}", codec, compression, format_size(*len as u64)))
+            .collect();
+        attempts.join(", ")
+    }
+}
+
+/// Encode `grid` along `ESCALATION` until its Lua file is at most `budget` bytes
+pub fn fit_lua(grid: &Exporter, budget: u64) -> Result<Fit, String> {
+    let mut attempts = Vec::new();
+    let mut smallest: Option<(usize, Exporter)> = None;
+    for (codec, compression) in ESCALATION {
+        let encoded = grid.reencoded(codec, compression)?;
+        let len = encoded.lua_len();
+        attempts.push((codec, compression, len));
+        if len as u64 <= budget {
+            return Ok(Fit { grid: encoded, fits: true, attempts });
+        }
+        if smallest.as_ref().is_none_or(|(best, _)| len < *best) {
+            smallest = Some((len, encoded));
+        }
+    }
+    let (_, grid) = smallest.ok_or("no encodings to try")?;
+    Ok(Fit { grid, fits: false, attempts })
+}
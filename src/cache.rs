@@ -0,0 +1,172 @@
+use crate::checkpoint::Record;
+use crate::hash::{ContentHasher, HashAlgorithm};
+use crate::info;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+// ============================================================================
+// Tile cache
+// ============================================================================
+//
+// Most reruns change exporter options, not ADTs, so every file read is
+// cached in `<dir>/<Map>.cache` under the hash of its bytes (and its _obj0's
+// on split tiles). The next run still reads each file to hash it, but only
+// parses the ones whose hash isn't in the cache:
+//
+//     zonemap-cache  <hash algorithm>  <settings>
+//     <hash>  <checkpoint record>
+//
+// Records are the checkpoint journal's lines, so a hit is exactly what
+// parsing the file would have produced, parse failures included. They hold
+// area IDs and layers before encoding, so codecs, compression and output
+// formats can change without invalidating anything. Settings that change
+// what is read (layers, sub-chunks, heights, sentinels) do: a cache written
+// with other settings or another hash is ignored and rewritten. `--force`
+// ignores it too. Entries for files no longer listed are dropped on save.
+
+const HEADER: &str = "zonemap-cache";
+
+/// Where and how tiles are cached, for every map of a run
+#[derive(Debug, Clone)]
+pub struct TileCache {
+    dir: PathBuf,
+    algorithm: HashAlgorithm,
+    /// Parse everything again, but still save what was parsed
+    force: bool,
+}
+
+impl TileCache {
+    pub fn new(dir: &Path, algorithm: HashAlgorithm, force: bool) -> Self {
+        Self { dir: dir.to_path_buf(), algorithm, force }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Where `map`'s cache is kept
+    pub fn cache_path(&self, map: &str) -> PathBuf {
+        self.dir.join(format!("{}.cache", map))
+    }
+
+    /// `map`'s cache for files read with `settings`: its saved entries,
+    /// unless they were written differently or the run is forced
+    pub fn open(&self, map: &str, settings: &str) -> MapCache {
+        let path = self.cache_path(map);
+        let header = format!("{}\t{}\t{}", HEADER, self.algorithm.name(), settings);
+        let mut cache = MapCache {
+            path,
+            header,
+            algorithm: self.algorithm,
+            force: self.force,
+            entries: BTreeMap::new(),
+            by_hash: HashMap::new(),
+            hits: 0,
+        };
+        if self.force {
+            return cache;
+        }
+        let Ok(text) = fs::read_to_string(&cache.path) else {
+            return cache;
+        };
+        let mut lines = text.lines();
+        if lines.next() != Some(cache.header.as_str()) {
+            info!("  {} was written with other settings; reading every file", cache.path.display());
+            return cache;
+        }
+        for line in lines {
+            // A bad line costs that file a parse, nothing more
+            let Some((hash, record)) = line.split_once('\t').and_then(|(hash, rest)| Some((hash, Record::from_line(rest)?))) else {
+                continue;
+            };
+            cache.insert(hash.to_string(), record);
+        }
+        cache
+    }
+}
+
+/// One map's cached records, by file
+pub struct MapCache {
+    path: PathBuf,
+    header: String,
+    algorithm: HashAlgorithm,
+    /// Never hit, only fill
+    force: bool,
+    /// File -> (hash of its bytes, record)
+    entries: BTreeMap<PathBuf, (String, Record)>,
+    /// Hash -> a file with those bytes
+    by_hash: HashMap<String, PathBuf>,
+    hits: usize,
+}
+
+impl MapCache {
+    /// Hash of a root ADT and its `_obj0`, if any
+    pub fn hash(&self, root: &[u8], obj0: Option<&[u8]>) -> String {
+        let mut hasher = ContentHasher::new(self.algorithm);
+        hasher.update(root);
+        if let Some(obj0) = obj0 {
+            hasher.update(b"\0obj0\0");
+            hasher.update(obj0);
+        }
+        hasher.finish_hex()
+    }
+
+    /// The record of a file with the bytes hashing to `hash`, moved to
+    /// `path` and tile `key`. Files identical to another (open sea) match
+    /// too, including ones read earlier in the same run.
+    pub fn get(&mut self, hash: &str, path: &Path, key: u32) -> Option<Record> {
+        if self.force {
+            return None;
+        }
+        // The file may have changed since, leaving its old hash behind
+        let (_, record) = self.entries.get(self.by_hash.get(hash)?).filter(|(stored, _)| stored == hash)?;
+        let mut record = record.clone();
+        match &mut record {
+            Record::Tile(tile) => {
+                tile.path = path.to_path_buf();
+                tile.key = key;
+            }
+            Record::Failure { path: failed, .. } => *failed = path.to_path_buf(),
+        }
+        self.hits += 1;
+        Some(record)
+    }
+
+    /// Remember `record` for the file whose bytes hash to `hash`
+    pub fn insert(&mut self, hash: String, record: Record) {
+        let path = record.path().to_path_buf();
+        self.by_hash.insert(hash.clone(), path.clone());
+        self.entries.insert(path, (hash, record));
+    }
+
+    /// Files found in the cache this run
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write the cache back, keeping only files in `listed`. The new file
+    /// replaces the old one only once complete.
+    pub fn save(&self, listed: &HashSet<&Path>) -> std::io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let partial = self.path.with_extension("cache.partial");
+        let mut out = BufWriter::new(File::create(&partial)?);
+        writeln!(out, "{}", self.header)?;
+        for (path, (hash, record)) in &self.entries {
+            if listed.contains(path.as_path()) {
+                writeln!(out, "{}\t{}", hash, record.to_line())?;
+            }
+        }
+        out.flush()?;
+        drop(out);
+        fs::rename(partial, &self.path)
+    }
+}
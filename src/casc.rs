@@ -0,0 +1,285 @@
+use crate::adt::split_sibling;
+use crate::failure::{is_split_adt, MissingKey};
+use crate::info;
+use crate::listfile::Listfile;
+use crate::pipeline::TileFile;
+use crate::source::TileSource;
+
+use casc_storage::types::CascConfig;
+use casc_storage::{ArchiveLocation, CascError, CascStorage, EKey, ManifestConfig};
+use ngdp_crypto::KeyService;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use tact_parser::encoding::EncodingFile;
+
+// ============================================================================
+// CASC storage
+// ============================================================================
+//
+// A modern client install read in place:
+//
+//     .build.info          active build per product (Build Key column)
+//     Data/config/ab/cd/   build configs, named by key
+//     Data/data, indices   the storage itself
+//
+// The build config names the encoding file (by encoding key) and the root
+// file (by content key, resolved through encoding). Root maps FileDataIDs to
+// content, but carries no file names, so ADTs are found through a community
+// listfile (see `listfile`).
+//
+// PTR and beta builds encrypt some files, new zones' ADTs among them, with
+// TACT keys Blizzard hands out over time. The storage reader can't decrypt,
+// so a file it fails to decode is read again from its archive and decoded
+// with the keys from `--tact-keys`. Files whose key is still unknown fail
+// as encrypted, naming the key, and the rest of the map is generated.
+
+/// Active build key in `.build.info`, for `product` if given (`wow`,
+/// `wow_classic`, ...) or the first active row otherwise
+fn active_build_key(build_info: &str, product: Option<&str>) -> Result<String, String> {
+    let mut lines = build_info.lines().filter(|l| !l.trim().is_empty());
+    let header: Vec<&str> = lines
+        .next()
+        .ok_or(".build.info is empty")?
+        .split('|')
+        .map(|c| c.split('!').next().unwrap_or(c))
+        .collect();
+    let column = |name: &str| header.iter().position(|c| c.eq_ignore_ascii_case(name));
+    let build_key = column("Build Key").ok_or(".build.info has no Build Key column")?;
+    let active = column("Active");
+    let product_col = column("Product");
+
+    for line in lines {
+        let fields: Vec<&str> = line.split('|').collect();
+        if active.is_some_and(|i| fields.get(i) != Some(&"1")) {
+            continue;
+        }
+        if let (Some(want), Some(i)) = (product, product_col) {
+            if fields.get(i) != Some(&want) {
+                continue;
+            }
+        }
+        if let Some(key) = fields.get(build_key).filter(|k| !k.is_empty()) {
+            return Ok(key.to_string());
+        }
+    }
+    Err(match product {
+        Some(product) => format!("no active build for {} in .build.info", product),
+        None => "no active build in .build.info".to_string(),
+    })
+}
+
+fn hex_bytes(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
+
+/// `(root content key, encoding encoding key)` from a build config
+fn build_config_keys(text: &str) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let field = |name: &str| {
+        text.lines()
+            .find_map(|l| l.split_once(" = ").filter(|(k, _)| k.trim() == name).map(|(_, v)| v.trim()))
+            .ok_or_else(|| format!("build config has no {}", name))
+    };
+    let root = field("root")?;
+    let encoding = field("encoding")?;
+    // `encoding = <content key> <encoding key>`
+    let encoding = encoding.split_whitespace().nth(1).ok_or("build config has no encoding key for encoding")?;
+    let root = hex_bytes(root).ok_or_else(|| format!("bad root key `{}`", root))?;
+    let encoding = hex_bytes(encoding).ok_or_else(|| format!("bad encoding key `{}`", encoding))?;
+    Ok((root, encoding))
+}
+
+/// TACT keys in a key file, by key name. Takes the community lists
+/// (`<name> <key>` as on wow.tools, or `;`/`,` separated as on wago) and a
+/// CDN keyring config (`key-<name> = <key>`). Names are 16 hex digits and
+/// keys 32; other columns, headers and names listed without a key are
+/// passed over.
+pub fn parse_tact_keys(text: &str) -> Vec<(u64, [u8; 16])> {
+    let is_hex = |s: &str, len: usize| s.len() == len && s.bytes().all(|b| b.is_ascii_hexdigit());
+    text.lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line
+                .split(|c: char| c == ';' || c == ',' || c == '=' || c.is_whitespace())
+                .map(|f| f.trim_start_matches("key-"))
+                .filter(|f| !f.is_empty())
+                .collect();
+            let name = fields.iter().position(|f| is_hex(f, 16))?;
+            let key = fields.get(name + 1).filter(|f| is_hex(f, 32))?;
+            Some((u64::from_str_radix(fields[name], 16).ok()?, hex_bytes(key)?.try_into().ok()?))
+        })
+        .collect()
+}
+
+/// One build of a client install, with ADTs named by a listfile
+pub struct CascSource {
+    storage: CascStorage,
+    install: PathBuf,
+    build_key: String,
+    /// Names of the map files
+    listfile: Listfile,
+    keys: KeyService,
+    /// Archive entries by truncated encoding key, once a file needed decrypting
+    locations: Option<HashMap<[u8; 9], ArchiveLocation>>,
+}
+
+impl CascSource {
+    /// Whether `path` looks like a client install (or one of its `_retail_`
+    /// style flavour folders)
+    pub fn is_install(path: &Path) -> bool {
+        path.join(".build.info").is_file()
+            || (path.join(".flavor.info").is_file() && path.parent().is_some_and(|p| p.join(".build.info").is_file()))
+    }
+
+    /// Open the active build of the install at `path`. A flavour folder
+    /// (`_retail_`, `_classic_`, ...) selects that product's build. Files
+    /// encrypted with a key in `tact_keys` are decrypted.
+    pub fn open(path: &Path, listfile: &Path, tact_keys: Option<&Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let (install, product) = if path.join(".build.info").is_file() {
+            (path.to_path_buf(), None)
+        } else {
+            let flavor = fs::read_to_string(path.join(".flavor.info"))?;
+            let product = flavor.lines().map(str::trim).rfind(|l| !l.is_empty()).map(str::to_string);
+            (path.parent().ok_or("flavour folder has no parent")?.to_path_buf(), product)
+        };
+
+        let build_info = fs::read_to_string(install.join(".build.info"))?;
+        let build_key = active_build_key(&build_info, product.as_deref())?;
+        let config_path = install
+            .join("Data/config")
+            .join(build_key.get(0..2).ok_or("bad build key")?)
+            .join(build_key.get(2..4).ok_or("bad build key")?)
+            .join(&build_key);
+        let config = fs::read_to_string(&config_path).map_err(|e| format!("{}: {}", config_path.display(), e))?;
+        let (root_ckey, encoding_ekey) = build_config_keys(&config)?;
+
+        let mut storage = CascStorage::new(CascConfig {
+            data_path: install.join("Data"),
+            read_only: true,
+            ..Default::default()
+        })?;
+        storage.load_indices()?;
+        storage.load_archives()?;
+
+        let ekey = |bytes: &[u8]| {
+            EKey::from_slice(bytes).ok_or_else(|| format!("bad encoding key of {} bytes", bytes.len()))
+        };
+        let encoding = storage.read(&ekey(&encoding_ekey)?)?;
+        let root_ekey = EncodingFile::parse(&encoding)?
+            .get_ekey_for_ckey(&root_ckey)
+            .cloned()
+            .ok_or("root file is not in the encoding file")?;
+        let root = storage.read(&ekey(&root_ekey)?)?;
+
+        storage.init_tact_manifests(ManifestConfig { lazy_loading: false, ..Default::default() });
+        storage.load_encoding_manifest(encoding)?;
+        storage.load_root_manifest(root)?;
+
+        let names = Listfile::read(listfile)?;
+        if names.is_empty() {
+            return Err(format!("listfile {} names no ADTs; `listfile update` downloads the latest", listfile.display()).into());
+        }
+
+        let mut keys = KeyService::empty();
+        if let Some(tact_keys) = tact_keys {
+            let text = fs::read_to_string(tact_keys).map_err(|e| format!("TACT keys {}: {}", tact_keys.display(), e))?;
+            let parsed = parse_tact_keys(&text);
+            if parsed.is_empty() {
+                return Err(format!("TACT keys {} holds no keys", tact_keys.display()).into());
+            }
+            for (name, key) in parsed {
+                keys.add_key(name, key);
+            }
+            info!("  TACT keys: {} from {}", keys.key_count(), tact_keys.display());
+        }
+        Ok(Self { storage, install, build_key, listfile: names, keys, locations: None })
+    }
+
+    /// Key of the build being read
+    pub fn build_key(&self) -> &str {
+        &self.build_key
+    }
+
+    /// FileDataID of a listed file
+    pub fn file_data_id(&self, path: &Path) -> Option<u32> {
+        self.listfile.file_data_id(&path.to_string_lossy())
+    }
+
+    /// The listfile naming the build's map files
+    pub fn listfile(&self) -> &Listfile {
+        &self.listfile
+    }
+
+    /// Read and decode a file straight from its archive, decrypting with
+    /// the supplied keys. The storage keeps its index to itself, so the
+    /// first call collects the archive entries.
+    fn read_encrypted(&mut self, fdid: u32) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let ekey = self.storage.get_file_mapping(fdid)?.encoding_key.ok_or_else(|| format!("no encoding key for FileDataID {}", fdid))?;
+        let storage = &self.storage;
+        let locations = self.locations.get_or_insert_with(|| storage.enumerate_files().map(|(ekey, location)| (ekey.truncated(), location)).collect());
+        let location = locations.get(&ekey.truncated()).ok_or_else(|| format!("{} is not in the storage", ekey))?;
+
+        let archive = self.install.join("Data/data").join(format!("data.{:03}", location.archive_id));
+        let mut file = File::open(&archive).map_err(|e| format!("{}: {}", archive.display(), e))?;
+        file.seek(SeekFrom::Start(location.offset))?;
+        let mut entry = vec![0u8; location.size as usize];
+        file.read_exact(&mut entry)?;
+        // Each entry starts with a 30-byte header (key, size, flags, checksums)
+        let blte = entry.get(30..).ok_or("archive entry too small")?.to_vec();
+        blte::decompress_blte(blte, Some(&self.keys)).map_err(|e| match e {
+            blte::Error::KeyNotFound(key_name) => MissingKey { key_name }.into(),
+            e => format!("FileDataID {}: {}", fdid, e).into(),
+        })
+    }
+}
+
+impl TileSource for CascSource {
+    fn describe(&self) -> String {
+        format!("{} (build {})", self.install.display(), self.build_key)
+    }
+
+    /// Root ADTs of `map` in the listfile; the storage may not hold them all
+    /// (stale listfile entries fail on read)
+    fn adt_files(&mut self, map: &str) -> Result<Vec<TileFile>, Box<dyn std::error::Error>> {
+        let prefix = format!("world/maps/{}/", map.to_ascii_lowercase());
+        let mut files: Vec<TileFile> = self
+            .listfile
+            .paths()
+            .filter(|p| p.starts_with(&prefix))
+            .map(PathBuf::from)
+            .filter(|path| !is_split_adt(path))
+            .filter_map(|path| TileFile::from_path(&path))
+            .filter(|file| file.map.eq_ignore_ascii_case(map))
+            .collect();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(files)
+    }
+
+    fn read(&mut self, path: &Path) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let fdid = self.file_data_id(path).ok_or_else(|| format!("{} is not in the listfile", path.display()))?;
+        match self.storage.read_by_fdid(fdid) {
+            Ok(data) => Ok(data),
+            // Encrypted blocks fail to decode without keys
+            Err(CascError::DecompressionError(_)) => self.read_encrypted(fdid),
+            Err(e) => Err(format!("FileDataID {}: {}", fdid, e).into()),
+        }
+    }
+
+    fn split_sibling(&self, root: &Path, suffix: &str) -> Option<PathBuf> {
+        let sibling = split_sibling(root, suffix);
+        self.file_data_id(&sibling).map(|_| sibling)
+    }
+
+    fn wdt(&mut self, map: &str) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+        let map = map.to_ascii_lowercase();
+        let path = PathBuf::from(format!("world/maps/{}/{}.wdt", map, map));
+        if self.file_data_id(&path).is_none() {
+            return Ok(None);
+        }
+        self.read(&path).map(Some)
+    }
+}
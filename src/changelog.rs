@@ -0,0 +1,125 @@
+use crate::diff::{ChunkChange, ExportDiff, ExportSnapshot};
+
+use std::collections::BTreeMap;
+
+// ============================================================================
+// Release changelog
+// ============================================================================
+
+/// Tile keys listed before the rest are summarised as "and N more"
+const MAX_LISTED_TILES: usize = 10;
+
+fn area_name(id: u32, snapshot: &ExportSnapshot) -> String {
+    match snapshot.areas.get(&id) {
+        Some(entry) => format!("{} ({})", entry.name, id),
+        None if id == 0 => "no area (0)".to_string(),
+        None => format!("area {}", id),
+    }
+}
+
+fn tile_list(keys: &[u32]) -> String {
+    let mut listed: Vec<String> = keys
+        .iter()
+        .take(MAX_LISTED_TILES)
+        .map(|k| format!("{},{}", k % 64, k / 64))
+        .collect();
+    if keys.len() > MAX_LISTED_TILES {
+        listed.push(format!("and {} more", keys.len() - MAX_LISTED_TILES));
+    }
+    listed.join("; ")
+}
+
+/// Markdown release notes for going from `old` to `new`. `new` names areas
+/// that still exist; `old` names removed ones.
+pub fn render_changelog(title: &str, diff: &ExportDiff, old: &ExportSnapshot, new: &ExportSnapshot) -> String {
+    let mut out = format!("## {}\n\n", title);
+    if diff.is_empty() {
+        out.push_str("No changes to zone data.\n");
+        return out;
+    }
+
+    if !diff.areas_added.is_empty()
+        || !diff.areas_removed.is_empty()
+        || !diff.areas_renamed.is_empty()
+        || !diff.areas_reparented.is_empty()
+    {
+        out.push_str("### Zones\n\n");
+        for (id, entry) in &diff.areas_added {
+            out.push_str(&format!("- Added {} ({})\n", entry.name, id));
+        }
+        for (id, entry) in &diff.areas_removed {
+            out.push_str(&format!("- Removed {} ({})\n", entry.name, id));
+        }
+        for (id, was, now) in &diff.areas_renamed {
+            out.push_str(&format!("- Renamed {} to {} ({})\n", was, now, id));
+        }
+        for (id, was, now) in &diff.areas_reparented {
+            out.push_str(&format!(
+                "- Moved {} from {} to {}\n",
+                area_name(*id, new),
+                area_name(*was, old),
+                area_name(*now, new)
+            ));
+        }
+        out.push('\n');
+    }
+
+    if diff.tiles_changed() {
+        out.push_str("### Tiles\n\n");
+        for grid in &diff.grids_added {
+            out.push_str(&format!("- {}: new continent ({} tiles)\n", grid, new.grids[grid].len()));
+        }
+        for grid in &diff.grids_removed {
+            out.push_str(&format!("- {}: removed\n", grid));
+        }
+        for (grid, g) in diff.grids.iter().filter(|(_, g)| !g.is_empty()) {
+            out.push_str(&format!(
+                "- {}: {} tiles added, {} removed, {} changed ({} chunks)\n",
+                grid,
+                g.tiles_added.len(),
+                g.tiles_removed.len(),
+                g.tiles_changed.len(),
+                g.chunks_changed
+            ));
+            if !g.tiles_changed.is_empty() {
+                out.push_str(&format!("  - changed tiles (x,y): {}\n", tile_list(&g.tiles_changed)));
+            }
+            let mut shifts: Vec<(&u32, &(usize, usize))> = g.area_chunks.iter().collect();
+            shifts.sort_by_key(|(_, (gained, lost))| std::cmp::Reverse(gained.max(lost)));
+            for (id, (gained, lost)) in shifts.into_iter().take(MAX_LISTED_TILES) {
+                let name = if new.areas.contains_key(id) { area_name(*id, new) } else { area_name(*id, old) };
+                out.push_str(&format!("  - {}: +{} / -{} chunks\n", name, gained, lost));
+            }
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Markdown list of every chunk whose area ID changed, a line per tile and
+/// change of area, e.g. `- 32,48: Elwynn Forest (12) -> Westfall (40) in
+/// chunks 3,7 4,7`. Chunk coordinates are within the tile.
+pub fn render_chunk_changes(diff: &ExportDiff, old: &ExportSnapshot, new: &ExportSnapshot) -> String {
+    let mut out = String::new();
+    for (grid, g) in diff.grids.iter().filter(|(_, g)| !g.chunk_changes.is_empty()) {
+        out.push_str(&format!("### {} chunks\n\n", grid));
+        let mut by_change: BTreeMap<(u32, u32, u32), Vec<&ChunkChange>> = BTreeMap::new();
+        for change in &g.chunk_changes {
+            by_change.entry((change.tile, change.was, change.now)).or_default().push(change);
+        }
+        for ((tile, was, now), changes) in by_change {
+            let chunks: Vec<String> = changes.iter().map(|c| format!("{},{}", c.chunk % 16, c.chunk / 16)).collect();
+            out.push_str(&format!(
+                "- {},{}: {} -> {} in chunks {}\n",
+                tile % 64,
+                tile / 64,
+                area_name(was, old),
+                area_name(now, new),
+                chunks.join(" ")
+            ));
+        }
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,287 @@
+use crate::adt::{WmoFile, WmoPlacement};
+use crate::codec::{decode_tile, encode_tile, Codec, Compression};
+use crate::failure::FailureKind;
+use crate::info;
+use crate::sentinel::SentinelHits;
+
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+// ============================================================================
+// Checkpoints
+// ============================================================================
+//
+// A batch run journals every ADT it finishes to `<dir>/<Map>.journal`, so a
+// run that was interrupted can be started again with --resume and replay the
+// journal instead of re-reading those files:
+//
+//     zonemap-checkpoint  <settings>
+//     tile  <key>  <path>  <area IDs>  [hits=<id>:<n>,...]  [layer:<name>=<values>]...  [sub:<chunk>=<cells>]...
+//           [wmo:<unique ID>=<x>,<y>,<z>,<path | #FileDataID>]...  [heights=<heights>]
+//     fail  <kind>  <path>  <message>
+//
+// Fields are tab-separated; ID lists are base64 little-endian u32s. The first
+// line holds the settings the tiles were read with, and a journal written
+// with other settings is dropped rather than mixed into the new run. Lines
+// are flushed every `FLUSH_EVERY` files; a line cut short by a crash is
+// discarded on resume, so that file is simply read again.
+
+const HEADER: &str = "zonemap-checkpoint";
+
+/// Files journaled between flushes
+pub const FLUSH_EVERY: usize = 64;
+
+/// What one ADT contributed to its map's export
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileRecord {
+    pub path: PathBuf,
+    /// `tile_y * 64 + tile_x`
+    pub key: u32,
+    /// Area IDs, sentinels already replaced
+    pub area_ids: Vec<u32>,
+    pub sentinel_hits: SentinelHits,
+    /// Extra layers with any non-zero value
+    pub layers: BTreeMap<String, Vec<u32>>,
+    /// Refined sub-chunk areas: chunk index -> 64 IDs
+    pub subchunks: BTreeMap<usize, Vec<u32>>,
+    /// WMOs placed on the tile
+    pub wmos: Vec<WmoPlacement>,
+    /// Height grid in yards, when height maps are exported
+    pub heights: Vec<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Record {
+    Tile(TileRecord),
+    Failure { kind: FailureKind, path: PathBuf, message: String },
+}
+
+impl Record {
+    pub fn path(&self) -> &Path {
+        match self {
+            Record::Tile(tile) => &tile.path,
+            Record::Failure { path, .. } => path,
+        }
+    }
+
+    pub(crate) fn to_line(&self) -> String {
+        match self {
+            Record::Tile(tile) => {
+                let mut line = format!("tile\t{}\t{}\t{}", tile.key, tile.path.display(), encode_ids(&tile.area_ids));
+                if !tile.sentinel_hits.is_empty() {
+                    let hits: Vec<String> = tile.sentinel_hits.iter().map(|(id, n)| format!("{}:{}", id, n)).collect();
+                    line.push_str(&format!("\thits={}", hits.join(",")));
+                }
+                for (name, values) in &tile.layers {
+                    line.push_str(&format!("\tlayer:{}={}", name, encode_ids(values)));
+                }
+                for (chunk, cells) in &tile.subchunks {
+                    line.push_str(&format!("\tsub:{}={}", chunk, encode_ids(cells)));
+                }
+                for wmo in &tile.wmos {
+                    let [x, y, z] = wmo.position;
+                    let file = match &wmo.file {
+                        WmoFile::Name(name) => name.replace(['\t', '\n', '\r'], " "),
+                        WmoFile::FileDataId(id) => format!("#{}", id),
+                    };
+                    line.push_str(&format!("\twmo:{}={},{},{},{}", wmo.unique_id, x, y, z, file));
+                }
+                if !tile.heights.is_empty() {
+                    let heights: Vec<u32> = tile.heights.iter().map(|&h| h as u32).collect();
+                    line.push_str(&format!("\theights={}", encode_ids(&heights)));
+                }
+                line
+            }
+            Record::Failure { kind, path, message } => {
+                // Keep the message on one line and inside its field
+                let message = message.replace(['\t', '\n', '\r'], " ");
+                format!("fail\t{}\t{}\t{}", kind.label(), path.display(), message)
+            }
+        }
+    }
+
+    pub(crate) fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        match fields.next()? {
+            "tile" => {
+                let key = fields.next()?.parse().ok()?;
+                let path = PathBuf::from(fields.next()?);
+                let area_ids = decode_ids(fields.next()?)?;
+                let mut tile = TileRecord {
+                    path,
+                    key,
+                    area_ids,
+                    sentinel_hits: SentinelHits::new(),
+                    layers: BTreeMap::new(),
+                    subchunks: BTreeMap::new(),
+                    wmos: Vec::new(),
+                    heights: Vec::new(),
+                };
+                for field in fields {
+                    if let Some(hits) = field.strip_prefix("hits=") {
+                        for hit in hits.split(',') {
+                            let (id, n) = hit.split_once(':')?;
+                            tile.sentinel_hits.insert(id.parse().ok()?, n.parse().ok()?);
+                        }
+                    } else if let Some(layer) = field.strip_prefix("layer:") {
+                        let (name, values) = layer.split_once('=')?;
+                        tile.layers.insert(name.to_string(), decode_ids(values)?);
+                    } else if let Some(sub) = field.strip_prefix("sub:") {
+                        let (chunk, cells) = sub.split_once('=')?;
+                        tile.subchunks.insert(chunk.parse().ok()?, decode_ids(cells)?);
+                    } else if let Some(heights) = field.strip_prefix("heights=") {
+                        tile.heights = decode_ids(heights)?.into_iter().map(|h| h as i32).collect();
+                    } else if let Some(wmo) = field.strip_prefix("wmo:") {
+                        let (unique_id, placement) = wmo.split_once('=')?;
+                        let mut parts = placement.splitn(4, ',');
+                        let mut coord = || parts.next()?.parse::<f32>().ok();
+                        let position = [coord()?, coord()?, coord()?];
+                        let file = parts.next()?;
+                        let file = match file.strip_prefix('#') {
+                            Some(id) => WmoFile::FileDataId(id.parse().ok()?),
+                            None => WmoFile::Name(file.to_string()),
+                        };
+                        tile.wmos.push(WmoPlacement { file, unique_id: unique_id.parse().ok()?, position });
+                    } else {
+                        return None;
+                    }
+                }
+                Some(Record::Tile(tile))
+            }
+            "fail" => {
+                let label = fields.next()?;
+                let kind = FailureKind::ALL.into_iter().find(|k| k.label() == label)?;
+                let path = PathBuf::from(fields.next()?);
+                let message = fields.next()?.to_string();
+                Some(Record::Failure { kind, path, message })
+            }
+            _ => None,
+        }
+    }
+}
+
+fn encode_ids(ids: &[u32]) -> String {
+    encode_tile(Codec::Raw, Compression::None, ids)
+}
+
+fn decode_ids(b64: &str) -> Option<Vec<u32>> {
+    decode_tile(Codec::Raw, Compression::None, b64).ok()
+}
+
+/// `(settings, complete records, bytes up to the end of the last complete
+/// record)` of a journal, or `None` if it has no header
+fn parse_journal(text: &str) -> Option<(&str, Vec<Record>, usize)> {
+    let (header, body) = text.split_once('\n')?;
+    let settings = header.strip_prefix(HEADER)?.strip_prefix('\t')?;
+    let mut records = Vec::new();
+    let mut valid = header.len() + 1;
+    // Only complete lines count; the last one may have been cut short
+    for line in body.split_inclusive('\n') {
+        let Some(record) = line.strip_suffix('\n').and_then(Record::from_line) else {
+            break;
+        };
+        records.push(record);
+        valid += line.len();
+    }
+    Some((settings, records, valid))
+}
+
+/// Settings and records of a journal file
+pub fn read_journal(path: &Path) -> Result<(String, Vec<Record>), Box<dyn std::error::Error>> {
+    let text = fs::read_to_string(path)?;
+    let (settings, records, _) = parse_journal(&text).ok_or_else(|| format!("{} is not a checkpoint journal", path.display()))?;
+    Ok((settings.to_string(), records))
+}
+
+/// Journals under one directory, one per map
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    dir: PathBuf,
+    resume: bool,
+}
+
+impl Checkpoint {
+    /// Checkpoints in `dir`. Existing journals are only read back when `resume` is set.
+    pub fn new(dir: &Path, resume: bool) -> Self {
+        Self { dir: dir.to_path_buf(), resume }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Where `map`'s journal is kept
+    pub fn journal_path(&self, map: &str) -> PathBuf {
+        self.dir.join(format!("{}.journal", map))
+    }
+
+    /// Open `map`'s journal for files read with `settings`. When resuming, the
+    /// records already journaled under the same settings are returned and new
+    /// ones are appended after them; otherwise the journal starts empty.
+    pub fn open(&self, map: &str, settings: &str) -> Result<(Vec<Record>, Journal), Box<dyn std::error::Error>> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.journal_path(map);
+
+        if self.resume && path.exists() {
+            let text = fs::read_to_string(&path)?;
+            match parse_journal(&text) {
+                Some((found, records, valid)) if found == settings => {
+                    // Drop a last line cut short, so new records start on their own line
+                    let file = OpenOptions::new().write(true).open(&path)?;
+                    file.set_len(valid as u64)?;
+                    let file = OpenOptions::new().append(true).open(&path)?;
+                    return Ok((records, Journal { out: BufWriter::new(file), unflushed: 0 }));
+                }
+                _ => info!("  {} was written with other settings; starting {} over", path.display(), map),
+            }
+        }
+
+        let mut file = File::create(&path)?;
+        writeln!(file, "{}\t{}", HEADER, settings)?;
+        Ok((Vec::new(), Journal { out: BufWriter::new(file), unflushed: 0 }))
+    }
+
+    /// Write `map`'s journal outright, e.g. from records gathered elsewhere,
+    /// replacing any journal it had
+    pub fn write(&self, map: &str, settings: &str, records: &[Record]) -> std::io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let mut out = BufWriter::new(File::create(self.journal_path(map))?);
+        writeln!(out, "{}\t{}", HEADER, settings)?;
+        for record in records {
+            writeln!(out, "{}", record.to_line())?;
+        }
+        out.flush()
+    }
+
+    /// Remove every journal, once the run they belong to has finished
+    pub fn clear(&self) -> std::io::Result<()> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Appends one map's records, flushing every `FLUSH_EVERY`
+pub struct Journal {
+    out: BufWriter<File>,
+    unflushed: usize,
+}
+
+impl Journal {
+    pub fn append(&mut self, record: &Record) -> std::io::Result<()> {
+        writeln!(self.out, "{}", record.to_line())?;
+        self.unflushed += 1;
+        if self.unflushed >= FLUSH_EVERY {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.unflushed = 0;
+        self.out.flush()
+    }
+}
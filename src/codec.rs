@@ -0,0 +1,439 @@
+use base64::{engine::general_purpose, Engine as _};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+// ============================================================================
+// Tile codecs
+// ============================================================================
+//
+// Every codec turns one tile's 256 values into bytes, which are compressed
+// and base64'd into the tile's payload in the grid file. The addon decoders
+// undo each step to get back to the raw layout. Everything in this module is
+// a pure function of its input (no I/O, clocks or state), so each step's
+// round trip is checked as a property over random tiles in the tests below.
+
+/// Byte encoding of one tile's 256 values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[non_exhaustive]
+pub enum Codec {
+    /// 256 u32 little-endian
+    #[default]
+    Raw,
+    /// u8 palette size (0 means 256), u32 LE entries, then one u8 index per chunk
+    Palette,
+    /// (u8 run length - 1, u32 LE value) pairs
+    Rle,
+    /// u8 format version, then (varint run length, varint zigzag delta from
+    /// the previous run's value) pairs
+    Delta,
+}
+
+pub const ALL_CODECS: [Codec; 4] = [Codec::Raw, Codec::Palette, Codec::Rle, Codec::Delta];
+
+/// Leading byte of `Codec::Delta` tiles; bumped if the layout ever changes
+pub const DELTA_VERSION: u8 = 1;
+
+/// Values a delta tile may expand to, so a corrupt run length can't
+/// allocate gigabytes
+const MAX_DECODED: usize = 1 << 16;
+
+/// LEB128: 7 bits per byte, low bits first, high bit set on all but the last
+fn push_varint(out: &mut Vec<u8>, mut v: u32) {
+    while v >= 0x80 {
+        out.push((v as u8 & 0x7F) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+fn read_varint(data: &[u8], at: &mut usize) -> Result<u32, String> {
+    let mut v = 0u32;
+    for shift in (0..35).step_by(7) {
+        let byte = *data.get(*at).ok_or_else(|| format!("delta: truncated at byte {}", at))?;
+        *at += 1;
+        v |= ((byte & 0x7F) as u32) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(v);
+        }
+    }
+    Err(format!("delta: varint too long at byte {}", at))
+}
+
+impl Codec {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Codec::Raw => "raw",
+            Codec::Palette => "palette",
+            Codec::Rle => "rle",
+            Codec::Delta => "delta",
+        }
+    }
+
+    pub fn encode(&self, values: &[u32]) -> Vec<u8> {
+        match self {
+            Codec::Raw => values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            Codec::Palette => {
+                let mut palette: Vec<u32> = Vec::new();
+                let mut indices = Vec::with_capacity(values.len());
+                for &v in values {
+                    let idx = match palette.iter().position(|&p| p == v) {
+                        Some(idx) => idx,
+                        None => {
+                            palette.push(v);
+                            palette.len() - 1
+                        }
+                    };
+                    indices.push(idx as u8);
+                }
+                let mut out = vec![palette.len() as u8];
+                out.extend(palette.iter().flat_map(|p| p.to_le_bytes()));
+                out.extend(indices);
+                out
+            }
+            Codec::Rle => {
+                let mut out = Vec::new();
+                let mut i = 0;
+                while i < values.len() {
+                    let run = values[i..].iter().take(256).take_while(|&&v| v == values[i]).count();
+                    out.push((run - 1) as u8);
+                    out.extend(values[i].to_le_bytes());
+                    i += run;
+                }
+                out
+            }
+            Codec::Delta => {
+                let mut out = vec![DELTA_VERSION];
+                let mut previous = 0u32;
+                let mut i = 0;
+                while i < values.len() {
+                    let run = values[i..].iter().take_while(|&&v| v == values[i]).count();
+                    // Wrapping, so any pair of u32s is a delta that fits an i32
+                    let delta = values[i].wrapping_sub(previous) as i32;
+                    push_varint(&mut out, run as u32);
+                    push_varint(&mut out, ((delta << 1) ^ (delta >> 31)) as u32);
+                    previous = values[i];
+                    i += run;
+                }
+                out
+            }
+        }
+    }
+
+    pub fn decode(&self, data: &[u8]) -> Result<Vec<u32>, String> {
+        let u32_at = |at: usize| -> Result<u32, String> {
+            data.get(at..at + 4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .ok_or_else(|| format!("{}: truncated at byte {}", self.name(), at))
+        };
+
+        match self {
+            Codec::Raw => {
+                if !data.len().is_multiple_of(4) {
+                    return Err("raw: length not a multiple of 4".to_string());
+                }
+                (0..data.len() / 4).map(|i| u32_at(i * 4)).collect()
+            }
+            Codec::Palette => {
+                let count = match *data.first().ok_or("palette: empty")? {
+                    0 => 256,
+                    n => n as usize,
+                };
+                let palette = (0..count).map(|i| u32_at(1 + i * 4)).collect::<Result<Vec<u32>, String>>()?;
+                data[1 + count * 4..]
+                    .iter()
+                    .map(|&idx| palette.get(idx as usize).copied().ok_or_else(|| format!("palette: index {} out of range", idx)))
+                    .collect()
+            }
+            Codec::Rle => {
+                if !data.len().is_multiple_of(5) {
+                    return Err("rle: length not a multiple of 5".to_string());
+                }
+                let mut out = Vec::new();
+                for pair in 0..data.len() / 5 {
+                    let run = data[pair * 5] as usize + 1;
+                    let value = u32_at(pair * 5 + 1)?;
+                    out.extend(std::iter::repeat_n(value, run));
+                }
+                Ok(out)
+            }
+            Codec::Delta => {
+                match data.first() {
+                    Some(&DELTA_VERSION) => {}
+                    Some(version) => return Err(format!("delta: unknown format version {}", version)),
+                    None => return Err("delta: empty".to_string()),
+                }
+                let mut out = Vec::new();
+                let mut previous = 0u32;
+                let mut at = 1;
+                while at < data.len() {
+                    let run = read_varint(data, &mut at)? as usize;
+                    let zigzag = read_varint(data, &mut at)?;
+                    let delta = ((zigzag >> 1) as i32) ^ -((zigzag & 1) as i32);
+                    previous = previous.wrapping_add(delta as u32);
+                    if out.len() + run > MAX_DECODED {
+                        return Err(format!("delta: more than {} values", MAX_DECODED));
+                    }
+                    out.extend(std::iter::repeat_n(previous, run));
+                }
+                Ok(out)
+            }
+        }
+    }
+}
+
+impl FromStr for Codec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ALL_CODECS
+            .into_iter()
+            .find(|c| c.name() == s.trim())
+            .ok_or_else(|| format!("unknown codec `{}` (raw, palette, rle, delta)", s))
+    }
+}
+
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Codec per layer name; layers not listed use `Codec::Raw`
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayerCodecs {
+    codecs: BTreeMap<String, Codec>,
+}
+
+impl LayerCodecs {
+    /// Parse `layer=codec` specs such as `areaid=palette`
+    pub fn parse(specs: &[&str]) -> Result<Self, String> {
+        let mut codecs = BTreeMap::new();
+        for spec in specs {
+            let (layer, codec) = spec
+                .split_once('=')
+                .ok_or_else(|| format!("expected layer=codec, got `{}`", spec))?;
+            codecs.insert(layer.trim().to_string(), codec.parse()?);
+        }
+        Ok(Self { codecs })
+    }
+
+    pub fn get(&self, layer: &str) -> Codec {
+        self.codecs.get(layer).copied().unwrap_or_default()
+    }
+
+    pub fn set(&mut self, layer: &str, codec: Codec) {
+        self.codecs.insert(layer.to_string(), codec);
+    }
+}
+
+// ============================================================================
+// Tile payloads
+// ============================================================================
+//
+// A tile as it appears in a grid file: its codec bytes, compressed, then
+// base64 (standard alphabet, padded).
+
+/// Payload of codec bytes already encoded
+pub fn encode_payload(compression: Compression, encoded: &[u8]) -> String {
+    general_purpose::STANDARD.encode(compression.compress(encoded))
+}
+
+/// Payload of one tile's values
+pub fn encode_tile(codec: Codec, compression: Compression, values: &[u32]) -> String {
+    encode_payload(compression, &codec.encode(values))
+}
+
+/// Values of a tile payload
+pub fn decode_tile(codec: Codec, compression: Compression, payload: &str) -> Result<Vec<u32>, String> {
+    let bytes = general_purpose::STANDARD.decode(payload).map_err(|e| format!("base64: {}", e))?;
+    codec.decode(&compression.decompress(&bytes)?)
+}
+
+// ============================================================================
+// Compression
+// ============================================================================
+//
+// Applied to a tile's codec bytes before base64, for every tile in a file.
+// Deflate is raw DEFLATE (no zlib or gzip header), which the addon inflates
+// with LibDeflate's `DecompressDeflate`.
+
+/// Deflate level; tiles are small, so the slowest setting costs little
+const DEFLATE_LEVEL: u8 = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[non_exhaustive]
+pub enum Compression {
+    #[default]
+    None,
+    Deflate,
+}
+
+impl Compression {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Compression::None => "none",
+            Compression::Deflate => "deflate",
+        }
+    }
+
+    pub fn compress(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Compression::None => data.to_vec(),
+            Compression::Deflate => miniz_oxide::deflate::compress_to_vec(data, DEFLATE_LEVEL),
+        }
+    }
+
+    pub fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            Compression::Deflate => {
+                miniz_oxide::inflate::decompress_to_vec(data).map_err(|e| format!("deflate: {}", e))
+            }
+        }
+    }
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "none" => Ok(Compression::None),
+            "deflate" => Ok(Compression::Deflate),
+            _ => Err(format!("unknown compression `{}` (none, deflate)", s)),
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::ExportSnapshot;
+    use proptest::prelude::*;
+    use std::path::Path;
+
+    /// Every tile of the committed Azeroth and Kalimdor grids
+    fn real_tiles() -> Vec<Vec<u32>> {
+        let snapshot = ExportSnapshot::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("Data")).unwrap();
+        let tiles: Vec<Vec<u32>> = snapshot.grids.values().flat_map(|grid| grid.values().cloned()).collect();
+        assert!(tiles.len() > 100, "expected the committed grids, found {} tiles", tiles.len());
+        tiles
+    }
+
+    #[test]
+    fn delta_round_trips_real_tiles() {
+        for tile in real_tiles() {
+            let encoded = Codec::Delta.encode(&tile);
+            assert_eq!(encoded[0], DELTA_VERSION);
+            assert_eq!(Codec::Delta.decode(&encoded).unwrap(), tile);
+        }
+    }
+
+    #[test]
+    fn delta_is_smaller_than_raw_on_real_tiles() {
+        let tiles = real_tiles();
+        let raw: usize = tiles.iter().map(|t| Codec::Raw.encode(t).len()).sum();
+        let delta: usize = tiles.iter().map(|t| Codec::Delta.encode(t).len()).sum();
+        assert!(delta * 10 < raw, "delta {} bytes vs raw {}", delta, raw);
+    }
+
+    #[test]
+    fn delta_round_trips_extremes() {
+        let tiles = [
+            vec![0; 256],
+            vec![u32::MAX; 256],
+            (0..256).map(|i| if i % 2 == 0 { 0 } else { u32::MAX }).collect(),
+            (0..256u32).map(|i| i.wrapping_mul(0x9E37_79B9)).collect(),
+            Vec::new(),
+        ];
+        for tile in tiles {
+            assert_eq!(Codec::Delta.decode(&Codec::Delta.encode(&tile)).unwrap(), tile);
+        }
+    }
+
+    #[test]
+    fn delta_rejects_other_versions_and_truncation() {
+        let mut encoded = Codec::Delta.encode(&[7; 256]);
+        assert!(Codec::Delta.decode(&encoded[..encoded.len() - 1]).is_err());
+        encoded[0] = DELTA_VERSION + 1;
+        assert!(Codec::Delta.decode(&encoded).unwrap_err().contains("version"));
+        assert!(Codec::Delta.decode(&[]).is_err());
+    }
+
+    #[test]
+    fn every_codec_round_trips_real_tiles() {
+        let tiles = real_tiles();
+        for codec in ALL_CODECS {
+            for tile in &tiles {
+                assert_eq!(&codec.decode(&codec.encode(tile)).unwrap(), tile, "{}", codec);
+            }
+        }
+    }
+
+    const ALL_COMPRESSIONS: [Compression; 2] = [Compression::None, Compression::Deflate];
+
+    /// Tiles shaped like real ones (a few areas in long runs), plus uniform
+    /// noise and values near the top of the range
+    fn any_tile() -> impl Strategy<Value = Vec<u32>> {
+        prop_oneof![
+            prop::collection::vec(any::<u32>(), 256),
+            prop::collection::vec(prop::sample::select(vec![0u32, 1, 12, 40, 5000, u32::MAX]), 256),
+            prop::collection::vec((0u32..8, 1usize..64), 1..32).prop_map(|runs| {
+                let mut tile: Vec<u32> = runs.into_iter().flat_map(|(area, len)| std::iter::repeat_n(area, len)).collect();
+                tile.resize(256, 0);
+                tile
+            }),
+            prop::collection::vec(u32::MAX - 4..=u32::MAX, 256),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn varint_round_trips(v in any::<u32>()) {
+            let mut out = Vec::new();
+            push_varint(&mut out, v);
+            let mut at = 0;
+            prop_assert_eq!(read_varint(&out, &mut at), Ok(v));
+            prop_assert_eq!(at, out.len());
+        }
+
+        #[test]
+        fn every_codec_round_trips(tile in any_tile()) {
+            for codec in ALL_CODECS {
+                prop_assert_eq!(codec.decode(&codec.encode(&tile)), Ok(tile.clone()), "{}", codec);
+            }
+        }
+
+        #[test]
+        fn every_payload_round_trips(tile in any_tile()) {
+            for codec in ALL_CODECS {
+                for compression in ALL_COMPRESSIONS {
+                    let payload = encode_tile(codec, compression, &tile);
+                    prop_assert_eq!(decode_tile(codec, compression, &payload), Ok(tile.clone()), "{}/{}", codec, compression);
+                }
+            }
+        }
+
+        #[test]
+        fn decoders_reject_garbage_without_panicking(bytes in prop::collection::vec(any::<u8>(), 0..512), text in ".{0,64}") {
+            for codec in ALL_CODECS {
+                let _ = codec.decode(&bytes);
+                for compression in ALL_COMPRESSIONS {
+                    let _ = decode_tile(codec, compression, &text);
+                }
+            }
+        }
+    }
+}
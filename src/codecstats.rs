@@ -0,0 +1,85 @@
+use crate::codec::{Codec, ALL_CODECS};
+
+use std::time::{Duration, Instant};
+
+// ============================================================================
+// Codec comparison
+// ============================================================================
+//
+// `COMPARE_CODECS` encodes each continent's area grid with every codec and
+// prints what each would cost: bytes, base64, encode and decode time, and
+// the tiles' rough memory in game once loaded.
+
+/// Bytes a Lua 5.1 string costs beyond its characters (TString header + NUL)
+const LUA_STRING_OVERHEAD: usize = 25;
+
+/// Bytes per entry in the Lua table holding the tiles
+const LUA_TABLE_SLOT: usize = 40;
+
+/// How one codec did on a set of tiles
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CodecStats {
+    pub codec: Codec,
+    pub tiles: usize,
+    /// Encoded bytes before base64
+    pub encoded_bytes: usize,
+    /// Base64 characters, as they appear in the Lua file
+    pub base64_bytes: usize,
+    pub encode_time: Duration,
+    pub decode_time: Duration,
+    /// Rough in-game memory for the tiles table once the file is loaded
+    pub lua_memory: usize,
+}
+
+/// Encode and decode `tiles` with every codec, checking each round-trips
+pub fn compare_codecs<'a>(tiles: impl IntoIterator<Item = &'a Vec<u32>> + Clone) -> Result<Vec<CodecStats>, String> {
+    let mut stats = Vec::new();
+
+    for codec in ALL_CODECS {
+        let start = Instant::now();
+        let encoded: Vec<Vec<u8>> = tiles.clone().into_iter().map(|t| codec.encode(t)).collect();
+        let encode_time = start.elapsed();
+
+        let start = Instant::now();
+        for (bytes, original) in encoded.iter().zip(tiles.clone()) {
+            if &codec.decode(bytes)? != original {
+                return Err(format!("{} did not round-trip a tile", codec));
+            }
+        }
+        let decode_time = start.elapsed();
+
+        let base64_bytes: usize = encoded.iter().map(|e| e.len().div_ceil(3) * 4).sum();
+        stats.push(CodecStats {
+            codec,
+            tiles: encoded.len(),
+            encoded_bytes: encoded.iter().map(|e| e.len()).sum(),
+            base64_bytes,
+            encode_time,
+            decode_time,
+            lua_memory: base64_bytes + encoded.len() * (LUA_STRING_OVERHEAD + LUA_TABLE_SLOT),
+        });
+    }
+
+    Ok(stats)
+}
+
+/// Comparison as an aligned text table
+pub fn format_codec_table(stats: &[CodecStats]) -> String {
+    let mut out = format!(
+        "{:<8} {:>10} {:>10} {:>10} {:>10} {:>10}\n",
+        "codec", "bytes", "base64", "encode", "decode", "lua mem"
+    );
+    for s in stats {
+        out.push_str(&format!(
+            "{:<8} {:>10} {:>10} {:>8.2}ms {:>8.2}ms {:>8.1}KB\n",
+            s.codec.name(),
+            s.encoded_bytes,
+            s.base64_bytes,
+            s.encode_time.as_secs_f64() * 1000.0,
+            s.decode_time.as_secs_f64() * 1000.0,
+            s.lua_memory as f64 / 1024.0
+        ));
+    }
+    out
+}
@@ -0,0 +1,239 @@
+use crate::log;
+use crate::zip::write_zip;
+
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::panic::PanicHookInfo;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// ============================================================================
+// Crash reports
+// ============================================================================
+//
+// A panic used to leave nothing but a backtrace, which rarely says which of
+// thousands of tiles was being read. `install` replaces the panic hook with
+// one that writes `zonemap-crash-<unix time>.zip` to the working directory
+// (or the temp directory if that isn't writable) holding
+//
+//     panic.txt        the message, where it was raised, what the thread was
+//                      working on (see `set_context`) and the backtrace
+//     log.txt          the latest log lines
+//     environment.txt  version, features, platform, command line and the
+//                      ZONEMAP_* settings
+//
+// and says where it is. Values that look like credentials (webhook URLs,
+// mail addresses, keys) are redacted. If the bundle can't be written, the
+// previous hook runs as before. A write to a closed stdout, e.g. `inspect`
+// piped into `head`, exits quietly instead.
+
+/// Setting or flag names whose values are left out of reports
+const SECRET_WORDS: [&str; 6] = ["webhook", "email", "key", "token", "secret", "password"];
+
+const REDACTED: &str = "<redacted>";
+
+thread_local! {
+    static CONTEXT: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Note what this thread is working on, e.g. the file being parsed, for a
+/// crash report
+pub fn set_context(context: impl Into<String>) {
+    CONTEXT.with(|c| *c.borrow_mut() = Some(context.into()));
+}
+
+pub fn clear_context() {
+    CONTEXT.with(|c| *c.borrow_mut() = None);
+}
+
+fn context() -> Option<String> {
+    CONTEXT.with(|c| c.try_borrow().ok().and_then(|c| c.clone()))
+}
+
+/// Exit status for a closed stdout, as if killed by SIGPIPE
+const EXIT_BROKEN_PIPE: i32 = 128 + 13;
+
+/// Write a diagnostic bundle on panic
+pub fn install() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // `println!` panics when the reader goes away (`| head`); that's not a bug
+        if is_broken_pipe(info) {
+            std::process::exit(EXIT_BROKEN_PIPE);
+        }
+        report(info, &previous)
+    }));
+}
+
+fn report(info: &PanicHookInfo, previous: &(dyn Fn(&PanicHookInfo) + Sync + Send)) {
+    match write_bundle(info) {
+        Ok(path) => {
+            crate::error!("zone-map crashed: {}", panic_message(info));
+            crate::error!("A crash report was written to {}; please attach it when reporting the bug.", path.display());
+        }
+        Err(e) => {
+            crate::error!("Couldn't write a crash report: {}", e);
+            previous(info);
+        }
+    }
+}
+
+fn is_broken_pipe(info: &PanicHookInfo) -> bool {
+    let payload = info.payload();
+    let message = payload.downcast_ref::<&str>().copied().or_else(|| payload.downcast_ref::<String>().map(String::as_str));
+    message.is_some_and(|m| m.starts_with("failed printing to std") && m.contains("Broken pipe"))
+}
+
+fn panic_message(info: &PanicHookInfo) -> String {
+    let payload = info.payload();
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    match info.location() {
+        Some(at) => format!("{} at {}:{}:{}", message, at.file(), at.line(), at.column()),
+        None => message,
+    }
+}
+
+fn is_secret(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SECRET_WORDS.iter().any(|word| name.contains(word))
+}
+
+/// The command line with the values of secret-looking flags replaced
+pub fn redact_args(args: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut redacted = Vec::new();
+    let mut hide_next = false;
+    for arg in args {
+        if hide_next {
+            redacted.push(REDACTED.to_string());
+            hide_next = false;
+        } else if let Some((flag, _)) = arg.split_once('=').filter(|(flag, _)| flag.starts_with("--") && is_secret(flag)) {
+            redacted.push(format!("{}={}", flag, REDACTED));
+        } else {
+            hide_next = arg.starts_with("--") && is_secret(&arg);
+            redacted.push(arg);
+        }
+    }
+    redacted
+}
+
+fn environment() -> String {
+    let features: Vec<&str> = [
+        ("casc", cfg!(feature = "casc")),
+        ("cli", cfg!(feature = "cli")),
+        ("crash", cfg!(feature = "crash")),
+        ("dbc", cfg!(feature = "dbc")),
+        ("gui", cfg!(feature = "gui")),
+        ("mpq", cfg!(feature = "mpq")),
+        ("png", cfg!(feature = "png")),
+        ("scripting", cfg!(feature = "scripting")),
+        ("serde", cfg!(feature = "serde")),
+        ("signing", cfg!(feature = "signing")),
+        ("sqlite", cfg!(feature = "sqlite")),
+        ("store", cfg!(feature = "store")),
+        ("toml", cfg!(feature = "toml")),
+        ("trends", cfg!(feature = "trends")),
+        ("uring", cfg!(feature = "uring")),
+        ("wasm", cfg!(feature = "wasm")),
+    ]
+    .into_iter()
+    .filter_map(|(name, on)| on.then_some(name))
+    .collect();
+
+    let mut out = String::new();
+    out.push_str(&format!("version: {}\n", env!("CARGO_PKG_VERSION")));
+    out.push_str(&format!("features: {}\n", features.join(", ")));
+    out.push_str(&format!("platform: {} {}\n", std::env::consts::OS, std::env::consts::ARCH));
+    out.push_str(&format!("command line: {}\n", redact_args(std::env::args()).join(" ")));
+    if let Ok(dir) = std::env::current_dir() {
+        out.push_str(&format!("working directory: {}\n", dir.display()));
+    }
+    out.push_str("\nsettings:\n");
+    let mut vars: Vec<(String, String)> = std::env::vars().filter(|(name, _)| name.starts_with("ZONEMAP_")).collect();
+    vars.sort();
+    for (name, value) in vars {
+        let value = if is_secret(&name) { REDACTED.to_string() } else { value };
+        out.push_str(&format!("  {}={}\n", name, value));
+    }
+    out
+}
+
+/// panic.txt for a panic with `message` on this thread
+fn panic_text(message: &str) -> String {
+    let thread = std::thread::current();
+    let mut panic = String::new();
+    panic.push_str(&format!("panic: {}\n", message));
+    panic.push_str(&format!("thread: {}\n", thread.name().unwrap_or("unnamed")));
+    panic.push_str(&format!("working on: {}\n", context().as_deref().unwrap_or("nothing recorded")));
+    panic.push_str(&format!("\nbacktrace:\n{}\n", Backtrace::force_capture()));
+    panic
+}
+
+/// The zipped bundle: `panic` as panic.txt, the log and the environment
+fn bundle(panic: &str) -> Result<Vec<u8>, String> {
+    let mut log = log::recent_lines().join("\n");
+    log.push('\n');
+    write_zip(&[
+        ("panic.txt", panic.as_bytes()),
+        ("log.txt", log.as_bytes()),
+        ("environment.txt", environment().as_bytes()),
+    ])
+}
+
+fn write_bundle(info: &PanicHookInfo) -> Result<PathBuf, String> {
+    let bundle = bundle(&panic_text(&panic_message(info)))?;
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let name = format!("zonemap-crash-{}.zip", now);
+    let mut tried = Vec::new();
+    for dir in [std::env::current_dir().ok(), Some(std::env::temp_dir())].into_iter().flatten() {
+        let path = dir.join(&name);
+        match std::fs::write(&path, &bundle) {
+            Ok(()) => return Ok(path),
+            Err(e) => tried.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+    Err(tried.join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zip::tests::read_zip;
+
+    #[test]
+    fn bundle_holds_panic_log_and_environment() {
+        set_context("Azeroth tile 32,48 (azeroth_32_48.adt)");
+        crate::warn!("crash bundle test line");
+        let zip = bundle(&panic_text("boom at src/adt.rs:1:1")).unwrap();
+        clear_context();
+
+        let files = read_zip(&zip);
+        let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["panic.txt", "log.txt", "environment.txt"]);
+        let text = |i: usize| String::from_utf8(files[i].1.clone()).unwrap();
+        let panic = text(0);
+        assert!(panic.starts_with("panic: boom at src/adt.rs:1:1\n"), "{}", panic);
+        assert!(panic.contains("working on: Azeroth tile 32,48 (azeroth_32_48.adt)\n"));
+        assert!(panic.contains("\nbacktrace:\n"));
+        assert!(text(1).contains("warn  crash bundle test line"), "{}", text(1));
+        let environment = text(2);
+        assert!(environment.starts_with(&format!("version: {}\n", env!("CARGO_PKG_VERSION"))));
+        assert!(environment.lines().any(|l| l.starts_with("features: ") && l.contains("crash")));
+        assert!(environment.contains("\nsettings:\n"));
+        assert_eq!(context(), None);
+    }
+
+    #[test]
+    fn secret_flags_are_redacted() {
+        let args = ["zone-map", "generate", "--webhook", "https://hooks.example/abc", "--notify-email=ops@example.com", "--signing-key", "zonemap.key", "--out", "Data"];
+        let redacted = redact_args(args.iter().map(|a| a.to_string()));
+        assert_eq!(
+            redacted,
+            ["zone-map", "generate", "--webhook", REDACTED, "--notify-email=<redacted>", "--signing-key", REDACTED, "--out", "Data"]
+        );
+    }
+}
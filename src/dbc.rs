@@ -0,0 +1,204 @@
+use std::path::Path;
+
+// ============================================================================
+// DBC files
+// ============================================================================
+//
+// The WDBC client database format of pre-Cataclysm clients:
+//
+//     "WDBC"  record count  field count  record size  string block size
+//     records             record count * record size bytes
+//     string block        NUL-terminated strings, addressed by byte offset
+//
+// Header values are little-endian u32s. Every field is 4 bytes: an integer, a
+// float or a string offset, depending on the table.
+//
+// Cataclysm and Mists clients ship WDB2 instead: the same layout behind a
+// longer header (table hash, build, timestamp, min/max ID, locale, copy table
+// size), and from build 12880 on, an ID index and string length array between
+// the header and the records when max ID is set. Later formats (WDB5 onward,
+// WDC*) pack fields and aren't read; use a CSV export for those clients.
+
+const MAGIC: &[u8; 4] = b"WDBC";
+const HEADER_SIZE: usize = 20;
+const WDB2_MAGIC: &[u8; 4] = b"WDB2";
+const WDB2_HEADER_SIZE: usize = 48;
+/// First build whose WDB2 files carry the ID index
+const WDB2_INDEX_BUILD: usize = 12880;
+
+pub struct Dbc {
+    pub field_count: usize,
+    record_size: usize,
+    records: Vec<u8>,
+    strings: Vec<u8>,
+}
+
+impl Dbc {
+    /// Parse a WDBC or WDB2 file
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        let header = |i: usize| u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]) as usize;
+        let records_start = match data.get(..4) {
+            Some(magic) if magic == MAGIC && data.len() >= HEADER_SIZE => HEADER_SIZE,
+            Some(magic) if magic == WDB2_MAGIC && data.len() >= WDB2_HEADER_SIZE => {
+                let (build, min_id, max_id) = (header(24), header(32), header(36));
+                if build > WDB2_INDEX_BUILD && max_id != 0 {
+                    // u32 record index and u16 string length per ID
+                    WDB2_HEADER_SIZE + (max_id.saturating_sub(min_id) + 1) * 6
+                } else {
+                    WDB2_HEADER_SIZE
+                }
+            }
+            _ => return Err("not a WDBC or WDB2 file".to_string()),
+        };
+        let (record_count, field_count, record_size, string_size) = (header(4), header(8), header(12), header(16));
+        if record_size < field_count * 4 {
+            return Err(format!("{} fields don't fit in {}-byte records", field_count, record_size));
+        }
+        // Sizes from the header can be anything; don't let them overflow
+        let records_end = record_count.checked_mul(record_size).and_then(|size| size.checked_add(records_start));
+        let Some((records_end, needed)) = records_end.and_then(|end| Some((end, end.checked_add(string_size)?))) else {
+            return Err(format!("{} records of {} bytes don't fit in memory", record_count, record_size));
+        };
+        if data.len() < needed {
+            return Err(format!("truncated: {} bytes, header needs {}", data.len(), needed));
+        }
+        Ok(Self {
+            field_count,
+            record_size,
+            records: data[records_start..records_end].to_vec(),
+            strings: data[records_end..records_end + string_size].to_vec(),
+        })
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self::parse(&std::fs::read(path)?)?)
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len() / self.record_size.max(1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = DbcRecord<'_>> {
+        self.records.chunks_exact(self.record_size.max(1)).map(move |data| DbcRecord { data, strings: &self.strings })
+    }
+}
+
+/// One record; fields are read by index and panic past `field_count`
+pub struct DbcRecord<'a> {
+    data: &'a [u8],
+    strings: &'a [u8],
+}
+
+impl DbcRecord<'_> {
+    pub fn u32(&self, field: usize) -> u32 {
+        let b = &self.data[field * 4..field * 4 + 4];
+        u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+    }
+
+    pub fn f32(&self, field: usize) -> f32 {
+        f32::from_bits(self.u32(field))
+    }
+
+    /// The string a field points at; empty if the offset is out of range
+    pub fn string(&self, field: usize) -> &str {
+        let start = self.u32(field) as usize;
+        let rest = self.strings.get(start..).unwrap_or_default();
+        let end = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+        std::str::from_utf8(&rest[..end]).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A WDBC file of two 3-field records: (id, a float, a string offset)
+    fn wdbc() -> Vec<u8> {
+        let strings = b"\0Elwynn Forest\0Westfall\0";
+        let mut out = MAGIC.to_vec();
+        for v in [2, 3, 12, strings.len() as u32] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        for (id, x, name) in [(12u32, 1.5f32, 1u32), (40, -2.0, 15)] {
+            out.extend_from_slice(&id.to_le_bytes());
+            out.extend_from_slice(&x.to_le_bytes());
+            out.extend_from_slice(&name.to_le_bytes());
+        }
+        out.extend_from_slice(strings);
+        out
+    }
+
+    fn set_header(data: &mut [u8], at: usize, value: u32) {
+        data[at..at + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[test]
+    fn wdbc_records_are_read() {
+        let dbc = Dbc::parse(&wdbc()).unwrap();
+        assert_eq!((dbc.len(), dbc.field_count), (2, 3));
+        let rows: Vec<(u32, f32, String)> = dbc.records().map(|r| (r.u32(0), r.f32(1), r.string(2).to_string())).collect();
+        assert_eq!(rows, vec![(12, 1.5, "Elwynn Forest".to_string()), (40, -2.0, "Westfall".to_string())]);
+
+        // A string offset past the block reads as empty
+        let mut data = wdbc();
+        set_header(&mut data, HEADER_SIZE + 8, 500);
+        assert_eq!(Dbc::parse(&data).unwrap().records().next().unwrap().string(2), "");
+    }
+
+    #[test]
+    fn wdb2_skips_the_id_index() {
+        let body = &wdbc()[4..HEADER_SIZE];
+        let records = &wdbc()[HEADER_SIZE..];
+        let (min_id, max_id) = (12u32, 40u32);
+        // Before build 12880, without a max ID, and with the index
+        for (build, index) in [(12340u32, 0), (15595, 0), (15595, (max_id - min_id + 1) as usize * 6)] {
+            let mut data = WDB2_MAGIC.to_vec();
+            data.extend_from_slice(body);
+            data.resize(WDB2_HEADER_SIZE, 0);
+            set_header(&mut data, 24, build);
+            if index != 0 {
+                set_header(&mut data, 32, min_id);
+                set_header(&mut data, 36, max_id);
+            }
+            data.resize(WDB2_HEADER_SIZE + index, 0xAA);
+            data.extend_from_slice(records);
+            let dbc = Dbc::parse(&data).unwrap();
+            assert_eq!(dbc.records().map(|r| r.u32(0)).collect::<Vec<_>>(), vec![12, 40], "build {}", build);
+        }
+    }
+
+    #[test]
+    fn truncated_files_are_errors() {
+        let data = wdbc();
+        for len in [0, 3, 4, HEADER_SIZE - 1, HEADER_SIZE, HEADER_SIZE + 12, data.len() - 1] {
+            assert!(Dbc::parse(&data[..len]).is_err(), "{} bytes", len);
+        }
+        assert_eq!(Dbc::parse(b"WDB5 and more").err().as_deref(), Some("not a WDBC or WDB2 file"));
+        assert!(Dbc::parse(&[b"WDB2".as_slice(), &[0; 40]].concat()).is_err());
+    }
+
+    #[test]
+    fn oversize_counts_are_errors() {
+        // Record count, record size, string block size
+        for (at, value) in [(4, u32::MAX), (12, u32::MAX), (16, u32::MAX), (4, 1 << 20)] {
+            let mut data = wdbc();
+            set_header(&mut data, at, value);
+            assert!(Dbc::parse(&data).is_err(), "header {} = {}", at, value);
+        }
+        let mut data = wdbc();
+        set_header(&mut data, 8, 4);
+        assert_eq!(Dbc::parse(&data).err().as_deref(), Some("4 fields don't fit in 12-byte records"));
+
+        // An ID range too big for the file
+        let mut wdb2 = WDB2_MAGIC.to_vec();
+        wdb2.extend_from_slice(&wdbc()[4..HEADER_SIZE]);
+        wdb2.resize(WDB2_HEADER_SIZE, 0);
+        set_header(&mut wdb2, 24, 15595);
+        set_header(&mut wdb2, 36, u32::MAX);
+        assert!(Dbc::parse(&wdb2).is_err());
+    }
+}
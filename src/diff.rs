@@ -0,0 +1,388 @@
+use crate::codec::{decode_tile, Codec, Compression};
+use crate::pipeline::{discover, ParseStage, TileError};
+use crate::theme::Rgb;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+// ============================================================================
+// Export snapshots
+// ============================================================================
+//
+// Reads a generated Data/ directory back: every `*_tiles.lua` grid (decoded
+// through its shared payloads, compression and codec, and gathered from its
+// shard files when it is sharded) and the names,
+// parents and colours from AreaInfo.lua.
+// Only the parts the generator writes itself are understood, so hand-edited
+// files may not load.
+//
+// A snapshot can also be scanned from an ADT directory, to compare an export
+// with the files it would be generated from next: one grid per map named in
+// the files, holding each chunk's area ID as stored in the ADT.
+
+/// Area as written to AreaInfo.lua
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AreaEntry {
+    pub name: String,
+    pub parent_id: u32,
+}
+
+/// Tile key -> 256 area IDs
+pub type GridTiles = BTreeMap<u32, Vec<u32>>;
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExportSnapshot {
+    /// Grid name (continent) -> tiles
+    pub grids: BTreeMap<String, GridTiles>,
+    pub areas: BTreeMap<u32, AreaEntry>,
+    /// Area colours from AreaInfo.lua
+    pub colors: BTreeMap<u32, Rgb>,
+}
+
+impl ExportSnapshot {
+    pub fn load(dir: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut snapshot = Self::default();
+
+        let mut paths: Vec<_> = fs::read_dir(dir)?.filter_map(|e| e.ok().map(|e| e.path())).collect();
+        paths.sort();
+        for path in paths {
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if name == "AreaInfo.lua" {
+                let text = fs::read_to_string(&path)?;
+                snapshot.areas = parse_area_info(&text);
+                snapshot.colors = parse_area_colors(&text);
+            } else if name.ends_with(".lua") {
+                if let Some((grid, tiles)) = load_tile_grid(&path).map_err(|e| format!("{}: {}", name, e))? {
+                    if snapshot.grids.insert(grid.clone(), tiles).is_some() {
+                        return Err(format!("{}: grid {} is registered by more than one file", name, grid).into());
+                    }
+                }
+            }
+        }
+
+        if snapshot.grids.is_empty() {
+            return Err(format!("no tile grids in {}", dir.display()).into());
+        }
+        Ok(snapshot)
+    }
+
+    /// Area IDs of the root ADTs in `dir`, and the files that failed to parse.
+    /// There is no AreaInfo.lua to read areas from.
+    pub fn scan(dir: &Path) -> Result<(Self, Vec<TileError>), Box<dyn std::error::Error>> {
+        let mut snapshot = Self::default();
+        let mut failures = Vec::new();
+        for tile in discover(dir)?.parse() {
+            match tile {
+                Ok(tile) => {
+                    snapshot.grids.entry(tile.file.map.clone()).or_default().insert(tile.file.key(), tile.tile.area_ids());
+                }
+                Err(e) => failures.push(e),
+            }
+        }
+        if snapshot.grids.is_empty() {
+            return Err(format!("no readable ADTs in {}", dir.display()).into());
+        }
+        Ok((snapshot, failures))
+    }
+
+    /// Names `area_id` answers to in AreaInfo.lua, innermost first (the
+    /// area, then its parents up to the root zone)
+    pub fn area_names(&self, area_id: u32) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut current = area_id;
+        let mut visited = BTreeSet::new();
+        while let Some(area) = self.areas.get(&current) {
+            if !visited.insert(current) {
+                break;
+            }
+            names.push(area.name.clone());
+            current = area.parent_id;
+        }
+        names
+    }
+
+    /// Whether `dir` holds ADTs to scan rather than an export to load
+    pub fn is_adt_dir(dir: &Path) -> bool {
+        discover(dir).is_ok_and(|mut files| files.next().is_some())
+    }
+}
+
+/// Tiles of the `<Map>_tiles.lua` at `path`, decoded as the addon decodes them
+pub fn read_tile_grid(path: &Path) -> Result<GridTiles, String> {
+    let (_, tiles) = load_tile_grid(path)?.ok_or("no RegisterTileGrid call")?;
+    Ok(tiles)
+}
+
+/// `(grid name, tiles)` from a `<Map>_tiles.lua` and, for a sharded grid,
+/// the shard files it names next to it; `None` for other Lua files
+fn load_tile_grid(path: &Path) -> Result<Option<(String, GridTiles)>, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let Some(name) = text
+        .lines()
+        .find_map(|l| l.strip_prefix("addon:RegisterTileGrid(\""))
+        .and_then(|rest| rest.split('"').next())
+    else {
+        return Ok(None);
+    };
+
+    let codec = text
+        .lines()
+        .skip_while(|l| l.trim() != "codecs = {")
+        .take_while(|l| l.trim() != "},")
+        .find_map(|l| l.trim().strip_prefix("areaid = \""))
+        .map(|c| c.trim_end_matches("\",").parse::<Codec>())
+        .transpose()?
+        .unwrap_or_default();
+    let compression = text
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("compression = \""))
+        .map(|c| c.trim_end_matches("\",").parse::<Compression>())
+        .transpose()?
+        .unwrap_or_default();
+
+    let mut tiles = parse_tiles(&text, codec, compression)?;
+    let shards = text
+        .lines()
+        .skip_while(|l| l.trim() != "shards = {")
+        .skip(1)
+        .take_while(|l| l.trim() != "},")
+        .filter_map(|l| l.split('"').nth(1));
+    for shard in shards {
+        let shard_path = path.with_file_name(format!("{}.lua", shard));
+        let shard_text = fs::read_to_string(&shard_path).map_err(|e| format!("shard {}: {}", shard, e))?;
+        tiles.extend(parse_tiles(&shard_text, codec, compression).map_err(|e| format!("shard {}: {}", shard, e))?);
+    }
+
+    Ok(Some((name.to_string(), tiles)))
+}
+
+/// The `tiles` local of a grid or shard file, decoded with `codec` unless
+/// the file's `tileCodecs` names another for the tile
+fn parse_tiles(text: &str, codec: Codec, compression: Compression) -> Result<GridTiles, String> {
+    let mut tile_codecs: BTreeMap<u32, Codec> = BTreeMap::new();
+    for line in text.lines().skip_while(|l| l.trim() != "tileCodecs = {").skip(1).take_while(|l| l.trim() != "},") {
+        let (key, codec) = line
+            .trim()
+            .strip_prefix('[')
+            .and_then(|l| l.split_once("] = \""))
+            .ok_or_else(|| format!("unexpected tile codec line `{}`", line.trim()))?;
+        let key: u32 = key.parse().map_err(|_| format!("bad tile key `{}`", key))?;
+        tile_codecs.insert(key, codec.trim_end_matches("\",").parse()?);
+    }
+
+
+    // Deduplicated grids list each distinct tile once, and tiles refer to them by index
+    let payloads: Vec<&str> = text
+        .lines()
+        .skip_while(|l| *l != "local payloads = {")
+        .skip(1)
+        .take_while(|l| *l != "}")
+        .map(|l| l.trim().trim_start_matches("[[").trim_end_matches("]],"))
+        .collect();
+
+    let mut tiles = GridTiles::new();
+    for line in text.lines().skip_while(|l| *l != "local tiles = {").skip(1) {
+        if line == "}" {
+            break;
+        }
+        let (key, value) = line
+            .trim()
+            .strip_prefix('[')
+            .and_then(|l| l.split_once("] = "))
+            .ok_or_else(|| format!("unexpected tile line `{}`", line.trim()))?;
+        let key: u32 = key.parse().map_err(|_| format!("bad tile key `{}`", key))?;
+        let blob = match value.strip_prefix("[[") {
+            Some(blob) => blob.trim_end_matches("]],"),
+            None => {
+                let index: usize = value.trim_end_matches(',').parse().map_err(|_| format!("bad tile `{}`", value))?;
+                index
+                    .checked_sub(1)
+                    .and_then(|i| payloads.get(i))
+                    .ok_or_else(|| format!("tile {}: no payload {}", key, index))?
+            }
+        };
+        let codec = tile_codecs.get(&key).copied().unwrap_or(codec);
+        tiles.insert(key, decode_tile(codec, compression, blob).map_err(|e| format!("tile {}: {}", key, e))?);
+    }
+
+    Ok(tiles)
+}
+
+fn parse_area_info(text: &str) -> BTreeMap<u32, AreaEntry> {
+    let mut areas = BTreeMap::new();
+    let mut current: Option<(u32, AreaEntry)> = None;
+
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(id) = line.strip_prefix('[').and_then(|l| l.strip_suffix("] = {")) {
+            if let Ok(id) = id.parse() {
+                current = Some((id, AreaEntry { name: String::new(), parent_id: 0 }));
+            }
+        } else if let Some((_, entry)) = current.as_mut() {
+            if let Some(name) = line.strip_prefix("name = \"").and_then(|l| l.strip_suffix("\",")) {
+                entry.name = name.replace("\\\"", "\"");
+            } else if let Some(parent) = line.strip_prefix("parentId = ").and_then(|l| l.strip_suffix(',')) {
+                entry.parent_id = parent.parse().unwrap_or(0);
+            } else if line == "}," {
+                let (id, entry) = current.take().expect("checked above");
+                areas.insert(id, entry);
+            }
+        }
+    }
+    areas
+}
+
+/// `color = {r, g, b}` of each AreaInfo.lua entry
+fn parse_area_colors(text: &str) -> BTreeMap<u32, Rgb> {
+    let mut colors = BTreeMap::new();
+    let mut current = None;
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(id) = line.strip_prefix('[').and_then(|l| l.strip_suffix("] = {")) {
+            current = id.parse::<u32>().ok();
+        } else if let Some(rgb) = line.strip_prefix("color = {").and_then(|l| l.strip_suffix("},")) {
+            let parts: Vec<f32> = rgb.split(',').filter_map(|c| c.trim().parse().ok()).collect();
+            if let (Some(id), [r, g, b]) = (current, parts.as_slice()) {
+                colors.insert(id, (*r, *g, *b));
+            }
+        }
+    }
+    colors
+}
+
+// ============================================================================
+// Diff
+// ============================================================================
+
+/// Changes to one grid present in both exports
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct GridDiff {
+    pub tiles_added: Vec<u32>,
+    pub tiles_removed: Vec<u32>,
+    /// Tiles in both exports with at least one chunk changed
+    pub tiles_changed: Vec<u32>,
+    /// Chunks whose area ID changed, across changed tiles
+    pub chunks_changed: usize,
+    /// Area -> (chunks gained, chunks lost) in changed tiles
+    pub area_chunks: BTreeMap<u32, (usize, usize)>,
+    /// Every chunk whose area ID changed, by tile and chunk index
+    pub chunk_changes: Vec<ChunkChange>,
+}
+
+/// One chunk with a different area ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChunkChange {
+    /// Tile key, `y * 64 + x`
+    pub tile: u32,
+    /// Chunk within the tile, `y * 16 + x`
+    pub chunk: usize,
+    pub was: u32,
+    pub now: u32,
+}
+
+impl GridDiff {
+    pub fn is_empty(&self) -> bool {
+        self.tiles_added.is_empty() && self.tiles_removed.is_empty() && self.tiles_changed.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ExportDiff {
+    pub grids_added: Vec<String>,
+    pub grids_removed: Vec<String>,
+    /// Grids in both exports, changed or not
+    pub grids: BTreeMap<String, GridDiff>,
+    pub areas_added: Vec<(u32, AreaEntry)>,
+    pub areas_removed: Vec<(u32, AreaEntry)>,
+    /// (area, old name, new name)
+    pub areas_renamed: Vec<(u32, String, String)>,
+    /// (area, old parent, new parent)
+    pub areas_reparented: Vec<(u32, u32, u32)>,
+}
+
+impl ExportDiff {
+    pub fn is_empty(&self) -> bool {
+        self.grids_added.is_empty()
+            && self.grids_removed.is_empty()
+            && self.grids.values().all(|g| g.is_empty())
+            && self.areas_added.is_empty()
+            && self.areas_removed.is_empty()
+            && self.areas_renamed.is_empty()
+            && self.areas_reparented.is_empty()
+    }
+
+    /// Anything changed in the tile grids themselves
+    pub fn tiles_changed(&self) -> bool {
+        !self.grids_added.is_empty() || !self.grids_removed.is_empty() || self.grids.values().any(|g| !g.is_empty())
+    }
+}
+
+pub fn diff_exports(old: &ExportSnapshot, new: &ExportSnapshot) -> ExportDiff {
+    let mut diff = ExportDiff {
+        grids_added: new.grids.keys().filter(|g| !old.grids.contains_key(*g)).cloned().collect(),
+        grids_removed: old.grids.keys().filter(|g| !new.grids.contains_key(*g)).cloned().collect(),
+        ..Default::default()
+    };
+
+    for (name, old_tiles) in &old.grids {
+        if let Some(new_tiles) = new.grids.get(name) {
+            diff.grids.insert(name.clone(), diff_grid(old_tiles, new_tiles));
+        }
+    }
+
+    for (&id, entry) in &new.areas {
+        match old.areas.get(&id) {
+            None => diff.areas_added.push((id, entry.clone())),
+            Some(old_entry) => {
+                if old_entry.name != entry.name {
+                    diff.areas_renamed.push((id, old_entry.name.clone(), entry.name.clone()));
+                }
+                if old_entry.parent_id != entry.parent_id {
+                    diff.areas_reparented.push((id, old_entry.parent_id, entry.parent_id));
+                }
+            }
+        }
+    }
+    for (&id, entry) in &old.areas {
+        if !new.areas.contains_key(&id) {
+            diff.areas_removed.push((id, entry.clone()));
+        }
+    }
+
+    diff
+}
+
+fn diff_grid(old: &GridTiles, new: &GridTiles) -> GridDiff {
+    let keys: BTreeSet<u32> = old.keys().chain(new.keys()).copied().collect();
+    let mut diff = GridDiff::default();
+
+    for key in keys {
+        match (old.get(&key), new.get(&key)) {
+            (None, Some(_)) => diff.tiles_added.push(key),
+            (Some(_), None) => diff.tiles_removed.push(key),
+            (Some(a), Some(b)) if a != b => {
+                diff.tiles_changed.push(key);
+                for (chunk, (&was, &now)) in a.iter().zip(b).enumerate() {
+                    if was != now {
+                        diff.chunks_changed += 1;
+                        diff.chunk_changes.push(ChunkChange { tile: key, chunk, was, now });
+                        diff.area_chunks.entry(now).or_default().0 += 1;
+                        diff.area_chunks.entry(was).or_default().1 += 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    diff
+}
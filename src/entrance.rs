@@ -0,0 +1,105 @@
+use crate::adt::{WmoFile, WmoPlacement};
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+// ============================================================================
+// Dungeon entrances
+// ============================================================================
+//
+// Nothing in a continent's ADTs says which building leads into which
+// instance, but the entrance WMOs are placed there (MODF) like any other. An
+// `entrances.csv` names the WMOs that are entrances and the map each leads to:
+//
+//     wmo,map_id,name
+//     World\wmo\Azeroth\Buildings\Foo\Foo_Entrance.wmo,36,The Deadmines
+//     1234567,34,The Stockade
+//
+// `wmo` is a path, matched case-insensitively against the end of the placed
+// file's path (so a bare file name will do), or a FileDataID for clients
+// whose placements name one.
+
+/// One `entrances.csv` row
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EntranceRule {
+    pub wmo: WmoFile,
+    /// Instance map the entrance leads to
+    pub map_id: u32,
+    pub name: String,
+}
+
+impl EntranceRule {
+    pub fn matches(&self, file: &WmoFile) -> bool {
+        match (&self.wmo, file) {
+            (WmoFile::FileDataId(want), WmoFile::FileDataId(id)) => want == id,
+            (WmoFile::Name(want), WmoFile::Name(path)) => {
+                let (want, path) = (normalize(want), normalize(path));
+                path == want || path.ends_with(&format!("/{}", want))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A placed entrance WMO and where it leads
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entrance<'a> {
+    pub placement: &'a WmoPlacement,
+    pub rule: &'a EntranceRule,
+}
+
+/// Lower case with forward slashes, as paths differ in both between sources
+fn normalize(path: &str) -> String {
+    path.trim().replace('\\', "/").to_ascii_lowercase()
+}
+
+/// Parse an `entrances.csv` (`wmo,map_id,name`)
+pub fn parse_entrances_csv(csv_path: &Path) -> Result<Vec<EntranceRule>, Box<dyn std::error::Error>> {
+    let reader = BufReader::new(File::open(csv_path)?);
+    let mut lines = reader.lines();
+    let header = lines.next().ok_or("Empty CSV")??;
+
+    let columns: Vec<&str> = header.split(',').map(str::trim).collect();
+    let col = |name: &str| columns.iter().position(|c| c.eq_ignore_ascii_case(name)).ok_or_else(|| format!("No {} column", name));
+    let wmo_idx = col("wmo")?;
+    let map_idx = col("map_id")?;
+    let name_idx = col("name").ok();
+
+    let mut rules = Vec::new();
+    for (i, line) in lines.enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        // The name is last and may contain commas
+        let fields: Vec<&str> = line.splitn(columns.len(), ',').map(str::trim).collect();
+        let field = |idx: usize| fields.get(idx).copied().unwrap_or("");
+        let wmo = field(wmo_idx);
+        let map_id = field(map_idx).parse().map_err(|_| format!("line {}: bad map_id `{}`", i + 2, field(map_idx)))?;
+        let wmo = match wmo.parse::<u32>() {
+            Ok(id) => WmoFile::FileDataId(id),
+            Err(_) if !wmo.is_empty() => WmoFile::Name(wmo.to_string()),
+            Err(_) => return Err(format!("line {}: empty wmo", i + 2).into()),
+        };
+        let name = name_idx.map(|idx| field(idx).trim_matches('"').to_string()).unwrap_or_default();
+        rules.push(EntranceRule { wmo, map_id, name });
+    }
+    Ok(rules)
+}
+
+/// Placements that match a rule, each once (a WMO spanning several tiles is
+/// placed in each of them), ordered by unique ID
+pub fn find_entrances<'a>(rules: &'a [EntranceRule], placements: &'a [WmoPlacement]) -> Vec<Entrance<'a>> {
+    let mut entrances: Vec<Entrance> = placements
+        .iter()
+        .filter_map(|placement| {
+            let rule = rules.iter().find(|r| r.matches(&placement.file))?;
+            Some(Entrance { placement, rule })
+        })
+        .collect();
+    entrances.sort_by_key(|e| e.placement.unique_id);
+    entrances.dedup_by_key(|e| e.placement.unique_id);
+    entrances
+}
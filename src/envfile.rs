@@ -0,0 +1,47 @@
+use std::fs;
+use std::path::Path;
+
+// ============================================================================
+// Environment files
+// ============================================================================
+//
+// Settings for a container job, in the format of `docker run --env-file`:
+//
+//     # comments and blank lines are skipped
+//     ZONEMAP_OUT=/data/out
+//     ZONEMAP_MANIFEST="/config/zonemap.manifest"
+//
+// One `NAME=value` per line; quotes around the whole value are dropped. Only
+// `ZONEMAP_` names are accepted, so a typo'd or foreign setting is an error
+// instead of being silently ignored.
+
+/// Names every setting starts with
+pub const PREFIX: &str = "ZONEMAP_";
+
+/// `(name, value)` pairs of an environment file, in file order
+pub fn parse_env_file(text: &str) -> Result<Vec<(String, String)>, String> {
+    let mut vars = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (name, value) = line.split_once('=').ok_or_else(|| format!("line {}: expected NAME=value", i + 1))?;
+        let name = name.trim();
+        if !name.starts_with(PREFIX) {
+            return Err(format!("line {}: `{}` is not a {}* setting", i + 1, name, PREFIX));
+        }
+        let value = value.trim();
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|&q| value.strip_prefix(q)?.strip_suffix(q))
+            .unwrap_or(value);
+        vars.push((name.to_string(), value.to_string()));
+    }
+    Ok(vars)
+}
+
+pub fn read_env_file(path: &Path) -> Result<Vec<(String, String)>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    parse_env_file(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
@@ -0,0 +1,153 @@
+use crate::grid::{find_grid, ZoneGrid};
+use crate::info;
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+// ============================================================================
+// Ground-truth samples
+// ============================================================================
+
+/// One in-game position with the zone text the client reported there
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ZoneSample {
+    /// Grid name (e.g. "Kalimdor"); when empty, the first grid with a tile there is used
+    pub continent: String,
+    pub x: f32,
+    pub y: f32,
+    pub zone_name: String,
+}
+
+/// Load samples from a CSV with `x`, `y`, `zone` and an optional `continent` column
+pub fn parse_samples_csv(csv_path: &Path) -> Result<Vec<ZoneSample>, Box<dyn std::error::Error>> {
+    let reader = BufReader::new(File::open(csv_path)?);
+    let mut lines = reader.lines();
+    let header = lines.next().ok_or("Empty CSV")??;
+
+    let columns: Vec<&str> = header.split(',').map(|c| c.trim()).collect();
+    let x_idx = columns.iter().position(|&c| c == "x").ok_or("No x column")?;
+    let y_idx = columns.iter().position(|&c| c == "y").ok_or("No y column")?;
+    let zone_idx = columns.iter().position(|&c| c == "zone").ok_or("No zone column")?;
+    let continent_idx = columns.iter().position(|&c| c == "continent");
+
+    let mut samples = Vec::new();
+    for line in lines {
+        let line = line?;
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim().trim_matches('"')).collect();
+        if fields.len() <= x_idx.max(y_idx).max(zone_idx) {
+            continue;
+        }
+
+        let (Ok(x), Ok(y)) = (fields[x_idx].parse(), fields[y_idx].parse()) else {
+            continue;
+        };
+        let continent = continent_idx
+            .and_then(|i| fields.get(i))
+            .map(|c| c.to_string())
+            .unwrap_or_default();
+
+        samples.push(ZoneSample { continent, x, y, zone_name: fields[zone_idx].to_string() });
+    }
+
+    Ok(samples)
+}
+
+// ============================================================================
+// Scoring
+// ============================================================================
+
+/// Label used when a sample falls outside every grid or on an unset chunk
+pub const NO_AREA: &str = "<none>";
+
+/// Accuracy for a single expected zone name
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ZoneScore {
+    pub samples: usize,
+    pub correct: usize,
+    /// Predicted label -> count, for misses only
+    pub confusions: BTreeMap<String, usize>,
+}
+
+impl ZoneScore {
+    pub fn accuracy(&self) -> f64 {
+        if self.samples == 0 {
+            0.0
+        } else {
+            self.correct as f64 / self.samples as f64
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EvaluationReport {
+    pub zones: BTreeMap<String, ZoneScore>,
+}
+
+impl EvaluationReport {
+    pub fn total_samples(&self) -> usize {
+        self.zones.values().map(|z| z.samples).sum()
+    }
+
+    pub fn total_correct(&self) -> usize {
+        self.zones.values().map(|z| z.correct).sum()
+    }
+
+    pub fn print(&self) {
+        let total = self.total_samples();
+        let correct = self.total_correct();
+        info!(
+            "  {} / {} samples correct ({:.1}%)",
+            correct,
+            total,
+            if total == 0 { 0.0 } else { correct as f64 * 100.0 / total as f64 }
+        );
+
+        for (zone, score) in &self.zones {
+            info!("  {:<32} {:>5} / {:<5} {:>5.1}%", zone, score.correct, score.samples, score.accuracy() * 100.0);
+
+            let mut confusions: Vec<_> = score.confusions.iter().collect();
+            confusions.sort_by_key(|&(_, &n)| std::cmp::Reverse(n));
+            for (predicted, n) in confusions.into_iter().take(3) {
+                info!("      as {:<28} {:>5}", predicted, n);
+            }
+        }
+    }
+}
+
+/// Score grid lookups against ground-truth samples.
+///
+/// `area_names` returns the names an area ID answers to, innermost first
+/// (subzone, then its parents up to the root zone). A sample counts as
+/// correct when its zone name matches any of them, since the client reports
+/// either the zone or the subzone depending on the API used.
+pub fn evaluate(
+    samples: &[ZoneSample],
+    grids: &[ZoneGrid],
+    area_names: impl Fn(u32) -> Vec<String>,
+) -> EvaluationReport {
+    let mut report = EvaluationReport::default();
+
+    for sample in samples {
+        let names = find_grid(grids, &sample.continent, sample.x, sample.y)
+            .and_then(|g| g.area_at(sample.x, sample.y))
+            .map(&area_names)
+            .unwrap_or_default();
+
+        let score = report.zones.entry(sample.zone_name.clone()).or_default();
+        score.samples += 1;
+
+        if names.iter().any(|n| n == &sample.zone_name) {
+            score.correct += 1;
+        } else {
+            let predicted = names.last().cloned().unwrap_or_else(|| NO_AREA.to_string());
+            *score.confusions.entry(predicted).or_default() += 1;
+        }
+    }
+
+    report
+}
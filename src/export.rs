@@ -0,0 +1,630 @@
+use crate::codec::{decode_tile, encode_payload, Codec, Compression, LayerCodecs};
+use crate::grid::TILES_PER_SIDE;
+use crate::hash::DedupMap;
+use crate::json;
+use crate::pipeline::{EncodedTile, TileSink};
+use crate::wdt::Wdt;
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+// ============================================================================
+// Tile grid export
+// ============================================================================
+//
+// `<Map>_tiles.lua`, the file the addon registers a map's grid from: every
+// tile's area IDs, plus any extra layers, encoded with the layer's codec,
+// compressed and base64'd. It is a `TileSink`, so a pipeline can write into
+// it directly:
+//
+//     let mut exporter = Exporter::new("Kalimdor", &codecs, Compression::None);
+//     discover(dir)?.parse().extract(&layers).encode(&codecs).write(&mut exporter);
+//     exporter.write_lua(Path::new("Data/Kalimdor_tiles.lua"))?;
+
+/// Values in one tile: 16x16 chunks
+const TILE_VALUES: usize = 256;
+
+/// Chunks along one side of a tile
+const TILE_SIZE: usize = 16;
+
+/// Which grid files a run writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum OutputFormat {
+    /// `<Map>_tiles.lua` for the addon
+    #[default]
+    Lua,
+    /// `<Map>_tiles.json` for web maps and scripts
+    Json,
+    Both,
+}
+
+impl OutputFormat {
+    pub fn lua(self) -> bool {
+        self != OutputFormat::Json
+    }
+
+    pub fn json(self) -> bool {
+        self != OutputFormat::Lua
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lua" => Ok(OutputFormat::Lua),
+            "json" => Ok(OutputFormat::Json),
+            "both" => Ok(OutputFormat::Both),
+            _ => Err(format!("unknown output format `{}` (expected lua, json or both)", s)),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            OutputFormat::Lua => "lua",
+            OutputFormat::Json => "json",
+            OutputFormat::Both => "both",
+        })
+    }
+}
+
+/// One map's grid file, built up tile by tile
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Exporter {
+    name: String,
+    codecs: LayerCodecs,
+    compression: Compression,
+    /// Tile key -> base64 area IDs
+    tiles: BTreeMap<u32, String>,
+    /// Non-areaid layers: layer name -> tile key -> base64 tile (all-zero
+    /// tiles left out, but every exported layer is listed)
+    layers: BTreeMap<String, BTreeMap<u32, String>>,
+    /// Tiles whose area IDs use another codec than the grid's areaid one
+    tile_codecs: BTreeMap<u32, Codec>,
+    /// Player visits per tile, ordering shard files hottest first
+    heat: BTreeMap<u32, u64>,
+    wdt: Option<Wdt>,
+}
+
+impl Exporter {
+    pub fn new(name: &str, codecs: &LayerCodecs, compression: Compression) -> Self {
+        Self {
+            name: name.to_string(),
+            codecs: codecs.clone(),
+            compression,
+            tiles: BTreeMap::new(),
+            layers: BTreeMap::new(),
+            tile_codecs: BTreeMap::new(),
+            heat: BTreeMap::new(),
+            wdt: None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Tiles with area IDs so far
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    /// List an extra layer even if no tile has a non-zero value, so the
+    /// addon can tell "all zero" from "not exported"
+    pub fn add_layer(&mut self, layer: &str) {
+        self.layers.entry(layer.to_string()).or_default();
+    }
+
+    /// Add tile `key`'s 256 values of `layer`, replacing any it had. Extra
+    /// layers that are all zero are left out.
+    pub fn add(&mut self, key: u32, layer: &str, values: &[u32]) -> Result<(), Box<dyn std::error::Error>> {
+        if values.len() != TILE_VALUES {
+            return Err(format!("expected {} {} values, got {}", TILE_VALUES, layer, values.len()).into());
+        }
+        if layer != "areaid" && values.iter().all(|&v| v == 0) {
+            return Ok(());
+        }
+        self.insert(key, layer, &self.codecs.get(layer).encode(values));
+        Ok(())
+    }
+
+    fn insert(&mut self, key: u32, layer: &str, encoded: &[u8]) {
+        let b64 = encode_payload(self.compression, encoded);
+        match layer {
+            "areaid" => {
+                self.tile_codecs.remove(&key);
+                self.tiles.insert(key, b64)
+            }
+            _ => self.layers.entry(layer.to_string()).or_default().insert(key, b64),
+        };
+    }
+
+    /// Re-encode tile `key`'s area IDs with `codec` rather than the grid's
+    /// areaid codec. The file lists such tiles in `tileCodecs`.
+    pub fn set_tile_codec(&mut self, key: u32, codec: Codec) -> Result<(), String> {
+        let Some(b64) = self.tiles.get(&key) else {
+            return Ok(());
+        };
+        let values = decode_tile(self.tile_codec(key), self.compression, b64).map_err(|e| format!("tile {}: {}", key, e))?;
+        self.tiles.insert(key, encode_payload(self.compression, &codec.encode(&values)));
+        if codec == self.codecs.get("areaid") {
+            self.tile_codecs.remove(&key);
+        } else {
+            self.tile_codecs.insert(key, codec);
+        }
+        Ok(())
+    }
+
+    /// Codec tile `key`'s area IDs are encoded with
+    fn tile_codec(&self, key: u32) -> Codec {
+        self.tile_codecs.get(&key).copied().unwrap_or_else(|| self.codecs.get("areaid"))
+    }
+
+    /// Extra layers the grid lists, exported or not
+    pub fn layer_names(&self) -> impl Iterator<Item = &str> {
+        self.layers.keys().map(|k| k.as_str())
+    }
+
+    /// Codecs the addon needs to decode the grid: areaid's, each listed
+    /// layer's and any in `tileCodecs`, without raw
+    pub fn codecs_used(&self) -> Vec<Codec> {
+        let mut used: Vec<Codec> = Vec::new();
+        let layers = std::iter::once("areaid").chain(self.layer_names()).map(|layer| self.codecs.get(layer));
+        for codec in layers.chain(self.tile_codecs.values().copied()) {
+            if codec != Codec::Raw && !used.contains(&codec) {
+                used.push(codec);
+            }
+        }
+        used
+    }
+
+    /// Player visits per tile key; `write_lua_sharded` returns (and so
+    /// `--shard-toc` lists) the shards players visit most first
+    pub fn set_heat(&mut self, heat: BTreeMap<u32, u64>) {
+        self.heat = heat;
+    }
+
+    /// The map's WDT, so the addon can tell missing tiles from empty ones
+    pub fn set_wdt(&mut self, wdt: Wdt) {
+        self.wdt = Some(wdt);
+    }
+
+    pub fn wdt(&self) -> Option<&Wdt> {
+        self.wdt.as_ref()
+    }
+
+    /// The same grid with every layer encoded with `codec` and `compression`
+    pub fn reencoded(&self, codec: Codec, compression: Compression) -> Result<Exporter, String> {
+        let mut codecs = LayerCodecs::default();
+        codecs.set("areaid", codec);
+        for layer in self.layers.keys() {
+            codecs.set(layer, codec);
+        }
+        let mut grid = Exporter { codecs, compression, tiles: BTreeMap::new(), layers: BTreeMap::new(), tile_codecs: BTreeMap::new(), ..self.clone() };
+        let tiles = self.tiles.iter().map(|(k, v)| ("areaid", k, v));
+        let layers = self.layers.iter().flat_map(|(layer, tiles)| tiles.iter().map(move |(k, v)| (layer.as_str(), k, v)));
+        for (layer, &key, b64) in tiles.chain(layers) {
+            let codec = if layer == "areaid" { self.tile_codec(key) } else { self.codecs.get(layer) };
+            let values = decode_tile(codec, self.compression, b64).map_err(|e| format!("tile {}: {}", key, e))?;
+            grid.insert(key, layer, &grid.codecs.get(layer).encode(&values));
+        }
+        Ok(grid)
+    }
+
+    /// Size of the grid file `write_lua` writes
+    pub fn lua_len(&self) -> usize {
+        let mut out = Vec::new();
+        self.render_lua(&mut out).expect("writing to memory");
+        out.len()
+    }
+
+    /// Write the grid file for `addon:RegisterTileGrid`
+    pub fn write_lua(&self, out_path: &Path) -> std::io::Result<()> {
+        let mut f = BufWriter::new(File::create(out_path)?);
+        self.render_lua(&mut f)?;
+        f.flush()
+    }
+
+    /// Write the grid split into `size`x`size`-tile shards, one file each
+    /// next to `out_path`, which becomes a manifest naming them. Returns the
+    /// files written, manifest first, then the shards by heat (see `set_heat`).
+    pub fn write_lua_sharded(&self, out_path: &Path, size: u32) -> std::io::Result<Vec<PathBuf>> {
+        let stem = out_path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_else(|| self.name.clone());
+        let per_side = TILES_PER_SIDE.div_ceil(size);
+        let shard_of = |key: u32| (key / TILES_PER_SIDE / size) * per_side + key % TILES_PER_SIDE / size;
+        let mut shards: BTreeMap<u32, Exporter> = BTreeMap::new();
+        let empty = || Exporter::new(&self.name, &self.codecs, self.compression);
+        for (&key, b64) in &self.tiles {
+            let shard = shards.entry(shard_of(key)).or_insert_with(empty);
+            shard.tiles.insert(key, b64.clone());
+            if let Some(&codec) = self.tile_codecs.get(&key) {
+                shard.tile_codecs.insert(key, codec);
+            }
+        }
+        for (layer, tiles) in &self.layers {
+            for (&key, b64) in tiles {
+                shards.entry(shard_of(key)).or_insert_with(empty).layers.entry(layer.clone()).or_default().insert(key, b64.clone());
+            }
+        }
+        let names: BTreeMap<u32, String> = shards.keys().map(|&shard| (shard, format!("{}_{}_{}", stem, shard % per_side, shard / per_side))).collect();
+
+        let mut f = BufWriter::new(File::create(out_path)?);
+        self.render_manifest(&mut f, size, &names)?;
+        f.flush()?;
+        let mut shard_heat: BTreeMap<u32, u64> = BTreeMap::new();
+        for (&key, &visits) in &self.heat {
+            *shard_heat.entry(shard_of(key)).or_default() += visits;
+        }
+        let mut order: Vec<(&u32, &Exporter)> = shards.iter().collect();
+        order.sort_by_key(|&(shard, _)| (std::cmp::Reverse(shard_heat.get(shard).copied().unwrap_or(0)), *shard));
+
+        let mut written = vec![out_path.to_path_buf()];
+        for (shard, grid) in order {
+            let path = out_path.with_file_name(format!("{}.lua", names[shard]));
+            let mut f = BufWriter::new(File::create(&path)?);
+            grid.render_shard(&mut f, *shard, (shard % per_side, shard / per_side), size)?;
+            f.flush()?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+
+    /// Identical tiles (open sea, the inside of a zone) share one payload,
+    /// numbered from 1 in the order first seen
+    fn payloads(&self) -> (Vec<&str>, DedupMap<&str, usize>) {
+        let mut payloads: Vec<&str> = Vec::new();
+        let mut payload_index: DedupMap<&str, usize> = DedupMap::default();
+        for b64 in self.tiles.values() {
+            payload_index.entry(b64.as_str()).or_insert_with(|| {
+                payloads.push(b64);
+                payloads.len()
+            });
+        }
+        (payloads, payload_index)
+    }
+
+    fn render_lua(&self, f: &mut impl Write) -> std::io::Result<()> {
+        let (payloads, payload_index) = self.payloads();
+        let dedup = payloads.len() < self.tiles.len();
+
+        writeln!(f, "-- Auto-generated AreaID grid for {}", self.name)?;
+        self.render_tiles(f, &payloads, &payload_index)?;
+        writeln!(f, "addon:RegisterTileGrid(\"{}\", {{", self.name)?;
+        writeln!(f, "  name = \"{}\",", self.name)?;
+        writeln!(f, "  tileSize = 16,")?;
+        writeln!(f, "  tilesPerSide = 64,")?;
+        writeln!(f, "  tiles = tiles,")?;
+        if dedup {
+            writeln!(f, "  payloads = payloads,")?;
+        }
+        self.render_tile_codecs(f)?;
+        self.render_encoding(f)?;
+        self.render_layers(f)?;
+        writeln!(f, "}})")?;
+        Ok(())
+    }
+
+    /// The grid without its tiles, naming the shard files that hold them
+    fn render_manifest(&self, f: &mut impl Write, size: u32, shards: &BTreeMap<u32, String>) -> std::io::Result<()> {
+        writeln!(f, "-- Auto-generated AreaID grid manifest for {}", self.name)?;
+        writeln!(f, "-- Tiles are in {}x{}-tile shards, one file each, listed in `shards` by shardY * {} + shardX.", size, size, TILES_PER_SIDE.div_ceil(size))?;
+        writeln!(f)?;
+        writeln!(f, "local _, addon = ...")?;
+        writeln!(f)?;
+        writeln!(f, "addon:RegisterTileGrid(\"{}\", {{", self.name)?;
+        writeln!(f, "  name = \"{}\",", self.name)?;
+        writeln!(f, "  tileSize = 16,")?;
+        writeln!(f, "  tilesPerSide = 64,")?;
+        writeln!(f, "  tiles = {{}},")?;
+        writeln!(f, "  shardSize = {},", size)?;
+        writeln!(f, "  shards = {{")?;
+        for (shard, name) in shards {
+            writeln!(f, "    [{}] = \"{}\",", shard, name)?;
+        }
+        writeln!(f, "  }},")?;
+        self.render_encoding(f)?;
+        // Empty until the shards load, but the layers exported are known up front
+        if !self.layers.is_empty() {
+            writeln!(f, "  layers = {{")?;
+            for name in self.layers.keys() {
+                writeln!(f, "    {} = {{}},", name)?;
+            }
+            writeln!(f, "  }},")?;
+        }
+        writeln!(f, "}})")?;
+        Ok(())
+    }
+
+    /// One shard's tiles, for `addon:RegisterTileShard`
+    fn render_shard(&self, f: &mut impl Write, shard: u32, (x, y): (u32, u32), size: u32) -> std::io::Result<()> {
+        let (payloads, payload_index) = self.payloads();
+        let dedup = payloads.len() < self.tiles.len();
+
+        let last = |first: u32| (first + size).min(TILES_PER_SIDE) - 1;
+        writeln!(
+            f,
+            "-- Auto-generated AreaID grid shard {},{} of {} (tiles {}-{}, {}-{})",
+            x,
+            y,
+            self.name,
+            x * size,
+            last(x * size),
+            y * size,
+            last(y * size)
+        )?;
+        self.render_tiles(f, &payloads, &payload_index)?;
+        writeln!(f, "addon:RegisterTileShard(\"{}\", {}, {{", self.name, shard)?;
+        writeln!(f, "  tiles = tiles,")?;
+        if dedup {
+            writeln!(f, "  payloads = payloads,")?;
+        }
+        self.render_tile_codecs(f)?;
+        self.render_layers(f)?;
+        writeln!(f, "}})")?;
+        Ok(())
+    }
+
+    /// The rest of the header, then `payloads` (when tiles share them) and
+    /// `tiles` as locals
+    fn render_tiles(&self, f: &mut impl Write, payloads: &[&str], payload_index: &DedupMap<&str, usize>) -> std::io::Result<()> {
+        let dedup = payloads.len() < self.tiles.len();
+        writeln!(f, "-- Each tile is 16x16 chunks (256 u32 AreaIDs), base64 encoded.")?;
+        if dedup {
+            writeln!(f, "-- Identical tiles are stored once in `payloads`; `tiles` holds their indices.")?;
+        }
+        writeln!(f)?;
+        writeln!(f, "local _, addon = ...")?;
+        writeln!(f)?;
+        if dedup {
+            writeln!(f, "local payloads = {{")?;
+            for payload in payloads {
+                writeln!(f, "  [[{}]],", payload)?;
+            }
+            writeln!(f, "}}")?;
+            writeln!(f)?;
+        }
+        writeln!(f, "local tiles = {{")?;
+
+        for (k, v) in &self.tiles {
+            if dedup {
+                writeln!(f, "  [{}] = {},", k, payload_index[v.as_str()])?;
+            } else {
+                writeln!(f, "  [{}] = [[{}]],", k, v)?;
+            }
+        }
+
+        writeln!(f, "}}")?;
+        writeln!(f)?;
+        Ok(())
+    }
+
+    /// `compression`, `codecs` and `wdt` fields of the grid
+    fn render_encoding(&self, f: &mut impl Write) -> std::io::Result<()> {
+        // Only non-raw codecs are marked; the addon assumes raw otherwise
+        let marked: Vec<(&str, Codec)> = std::iter::once("areaid")
+            .chain(self.layers.keys().map(|k| k.as_str()))
+            .map(|layer| (layer, self.codecs.get(layer)))
+            .filter(|(_, codec)| *codec != Codec::Raw)
+            .collect();
+        if self.compression != Compression::None {
+            writeln!(f, "  compression = \"{}\",", self.compression)?;
+        }
+        if !marked.is_empty() {
+            writeln!(f, "  codecs = {{")?;
+            for (layer, codec) in marked {
+                writeln!(f, "    {} = \"{}\",", layer, codec)?;
+            }
+            writeln!(f, "  }},")?;
+        }
+        if let Some(wdt) = &self.wdt {
+            // Tiles the WDT says exist but have no data here, and tiles within
+            // the map's extent that have no terrain at all
+            let missing: Vec<String> =
+                wdt.tiles.iter().filter(|k| !self.tiles.contains_key(k)).map(|k| k.to_string()).collect();
+            let empty: Vec<String> = wdt.empty_tiles().iter().map(|k| k.to_string()).collect();
+            writeln!(f, "  wdt = {{")?;
+            writeln!(f, "    missing = {{ {} }},", missing.join(", "))?;
+            writeln!(f, "    empty = {{ {} }},", empty.join(", "))?;
+            writeln!(f, "  }},")?;
+        }
+        Ok(())
+    }
+
+    /// `tileCodecs`: the areaid codec of tiles encoded with another one
+    fn render_tile_codecs(&self, f: &mut impl Write) -> std::io::Result<()> {
+        if !self.tile_codecs.is_empty() {
+            writeln!(f, "  tileCodecs = {{")?;
+            for (key, codec) in &self.tile_codecs {
+                writeln!(f, "    [{}] = \"{}\",", key, codec)?;
+            }
+            writeln!(f, "  }},")?;
+        }
+        Ok(())
+    }
+
+    fn render_layers(&self, f: &mut impl Write) -> std::io::Result<()> {
+        if !self.layers.is_empty() {
+            writeln!(f, "  layers = {{")?;
+            for (name, tiles) in &self.layers {
+                writeln!(f, "    {} = {{", name)?;
+                for (k, v) in tiles {
+                    writeln!(f, "      [{}] = [[{}]],", k, v)?;
+                }
+                writeln!(f, "    }},")?;
+            }
+            writeln!(f, "  }},")?;
+        }
+        Ok(())
+    }
+}
+
+impl TileSink for Exporter {
+    /// Tiles must be encoded with the exporter's codecs, which the file names
+    fn write_tile(&mut self, tile: EncodedTile) -> Result<(), Box<dyn std::error::Error>> {
+        let key = tile.file.key();
+        for (layer, (codec, bytes)) in &tile.layers {
+            let expected = self.codecs.get(layer);
+            if *codec != expected {
+                return Err(format!("{} is encoded as {}, the export expects {}", layer, codec, expected).into());
+            }
+            if layer != "areaid" && codec.decode(bytes)?.iter().all(|&v| v == 0) {
+                continue;
+            }
+            self.insert(key, layer, bytes);
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// JSON grid export
+// ============================================================================
+//
+// `<Map>_tiles.json`, the same grid for tools outside the game. Values are
+// written as plain numbers, so there is no codec, compression or base64:
+//
+//     {"format":"zonemap-grid","version":1,"name":"Kalimdor","tileSize":16,"tilesPerSide":64,
+//      "tiles":[{"key":2080,"x":32,"y":32,"areaIds":[[16 IDs], ... 16 rows]}],
+//      "layers":{"holes":[{"key":2080,"x":32,"y":32,"values":[[...]]}]},
+//      "wdt":{"missing":[...],"empty":[...]}}
+//
+// `areaIds[y][x]` is chunk `y * 16 + x`, the row-major order of the ADT.
+
+/// Version of the JSON layout, bumped when a reader would misread it
+pub const JSON_GRID_VERSION: u32 = 1;
+
+/// One map's grid as JSON, built up tile by tile
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct JsonExporter {
+    name: String,
+    /// Tile key -> 256 area IDs
+    tiles: BTreeMap<u32, Vec<u32>>,
+    /// Non-areaid layers: layer name -> tile key -> 256 values (all-zero tiles left out)
+    layers: BTreeMap<String, BTreeMap<u32, Vec<u32>>>,
+    wdt: Option<Wdt>,
+}
+
+impl JsonExporter {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string(), ..Self::default() }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Tiles with area IDs so far
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    /// List an extra layer even if no tile has a non-zero value
+    pub fn add_layer(&mut self, layer: &str) {
+        self.layers.entry(layer.to_string()).or_default();
+    }
+
+    /// Add tile `key`'s 256 values of `layer`, replacing any it had. Extra
+    /// layers that are all zero are left out.
+    pub fn add(&mut self, key: u32, layer: &str, values: &[u32]) -> Result<(), Box<dyn std::error::Error>> {
+        if values.len() != TILE_VALUES {
+            return Err(format!("expected {} {} values, got {}", TILE_VALUES, layer, values.len()).into());
+        }
+        match layer {
+            "areaid" => {
+                self.tiles.insert(key, values.to_vec());
+            }
+            _ if values.iter().all(|&v| v == 0) => {}
+            _ => {
+                self.layers.entry(layer.to_string()).or_default().insert(key, values.to_vec());
+            }
+        }
+        Ok(())
+    }
+
+    /// The map's WDT, so readers can tell missing tiles from empty ones
+    pub fn set_wdt(&mut self, wdt: Wdt) {
+        self.wdt = Some(wdt);
+    }
+
+    /// Write the grid as one JSON document
+    pub fn write_json(&self, out_path: &Path) -> std::io::Result<()> {
+        let mut f = BufWriter::new(File::create(out_path)?);
+
+        writeln!(f, "{{")?;
+        writeln!(f, "  \"format\": \"zonemap-grid\",")?;
+        writeln!(f, "  \"version\": {},", JSON_GRID_VERSION)?;
+        writeln!(f, "  \"name\": {},", json::string(&self.name))?;
+        writeln!(f, "  \"tileSize\": {},", TILE_SIZE)?;
+        writeln!(f, "  \"tilesPerSide\": 64,")?;
+        if let Some(wdt) = &self.wdt {
+            let missing = wdt.tiles.iter().filter(|k| !self.tiles.contains_key(k));
+            writeln!(f, "  \"wdt\": {{")?;
+            writeln!(f, "    \"missing\": {},", json::numbers(missing))?;
+            writeln!(f, "    \"empty\": {}", json::numbers(wdt.empty_tiles()))?;
+            writeln!(f, "  }},")?;
+        }
+        writeln!(f, "  \"tiles\": [")?;
+        write_tiles(&mut f, &self.tiles, "areaIds", "    ")?;
+        writeln!(f, "  ],")?;
+        writeln!(f, "  \"layers\": {{")?;
+        for (i, (name, tiles)) in self.layers.iter().enumerate() {
+            writeln!(f, "    {}: [", json::string(name))?;
+            write_tiles(&mut f, tiles, "values", "      ")?;
+            let comma = if i + 1 < self.layers.len() { "," } else { "" };
+            writeln!(f, "    ]{}", comma)?;
+        }
+        writeln!(f, "  }}")?;
+        writeln!(f, "}}")?;
+        f.flush()
+    }
+}
+
+/// One `{"key","x","y",<field>:[[16] x 16]}` object per tile
+fn write_tiles(f: &mut impl Write, tiles: &BTreeMap<u32, Vec<u32>>, field: &str, indent: &str) -> std::io::Result<()> {
+    for (i, (key, values)) in tiles.iter().enumerate() {
+        let rows: Vec<String> = values.chunks(TILE_SIZE).map(json::numbers).collect();
+        let comma = if i + 1 < tiles.len() { "," } else { "" };
+        writeln!(
+            f,
+            "{}{{\"key\":{},\"x\":{},\"y\":{},{}:[{}]}}{}",
+            indent,
+            key,
+            key % 64,
+            key / 64,
+            json::string(field),
+            rows.join(","),
+            comma
+        )?;
+    }
+    Ok(())
+}
+
+impl TileSink for JsonExporter {
+    /// Tiles are decoded with whatever codec they were encoded with
+    fn write_tile(&mut self, tile: EncodedTile) -> Result<(), Box<dyn std::error::Error>> {
+        let key = tile.file.key();
+        for (layer, (codec, bytes)) in &tile.layers {
+            self.add(key, layer, &codec.decode(bytes)?)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use crate::info;
+
+use wow_adt::AdtError;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+// ============================================================================
+// Parse failure taxonomy
+// ============================================================================
+
+/// Split ADT suffixes used by Cataclysm+ clients
+const SPLIT_SUFFIXES: [&str; 5] = ["_tex0", "_tex1", "_obj0", "_obj1", "_lod"];
+
+/// Why a file did not produce a tile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum FailureKind {
+    BadMagic,
+    Truncated,
+    UnsupportedVersion,
+    MissingChunk,
+    /// Root ADT parsed but contained no MCNK chunks
+    NoChunks,
+    SplitFile,
+    /// Encrypted with a TACT key that wasn't supplied
+    Encrypted,
+    Io,
+    Other,
+}
+
+impl FailureKind {
+    pub const ALL: [FailureKind; 9] = [
+        FailureKind::BadMagic,
+        FailureKind::Truncated,
+        FailureKind::UnsupportedVersion,
+        FailureKind::MissingChunk,
+        FailureKind::NoChunks,
+        FailureKind::SplitFile,
+        FailureKind::Encrypted,
+        FailureKind::Io,
+        FailureKind::Other,
+    ];
+
+    /// Classify an error returned while parsing `path`
+    pub fn classify(path: &Path, err: &(dyn std::error::Error + 'static)) -> Self {
+        if is_split_adt(path) {
+            return FailureKind::SplitFile;
+        }
+        if let Some(adt_err) = err.downcast_ref::<AdtError>() {
+            return match adt_err {
+                AdtError::InvalidMagic { .. } => FailureKind::BadMagic,
+                AdtError::UnexpectedEof | AdtError::InvalidChunkSize { .. } | AdtError::InvalidFileSize(_) => {
+                    FailureKind::Truncated
+                }
+                AdtError::UnsupportedVersion(_)
+                | AdtError::InvalidVersion(_)
+                | AdtError::VersionConversionUnsupported { .. } => FailureKind::UnsupportedVersion,
+                AdtError::MissingChunk(_) => FailureKind::MissingChunk,
+                AdtError::Io(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => FailureKind::Truncated,
+                AdtError::Io(_) => FailureKind::Io,
+                _ => FailureKind::Other,
+            };
+        }
+        match err.downcast_ref::<std::io::Error>() {
+            Some(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => FailureKind::Truncated,
+            Some(_) => FailureKind::Io,
+            None => FailureKind::Other,
+        }
+    }
+
+    /// Classify an error returned while reading a file, before parsing
+    pub fn of_read_error(err: &(dyn std::error::Error + 'static)) -> Self {
+        if err.is::<MissingKey>() {
+            FailureKind::Encrypted
+        } else {
+            FailureKind::Io
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FailureKind::BadMagic => "bad magic",
+            FailureKind::Truncated => "truncated",
+            FailureKind::UnsupportedVersion => "unsupported version",
+            FailureKind::MissingChunk => "missing chunk",
+            FailureKind::NoChunks => "no MCNK chunks",
+            FailureKind::SplitFile => "split ADT",
+            FailureKind::Encrypted => "encrypted",
+            FailureKind::Io => "I/O error",
+            FailureKind::Other => "other",
+        }
+    }
+
+    /// Actionable advice shown next to the failures of this kind
+    pub fn hint(&self) -> &'static str {
+        match self {
+            FailureKind::BadMagic => "not an ADT (or a different byte order); check the extractor wrote the right files",
+            FailureKind::Truncated => "the file ends early; re-extract it, the dump was probably interrupted",
+            FailureKind::UnsupportedVersion => "unknown MVER; this client version's ADT layout isn't supported",
+            FailureKind::MissingChunk => "a required chunk (MVER/MHDR/MCIN) is absent; the file may be a partial dump",
+            FailureKind::NoChunks => {
+                "root ADT without terrain; split (Cataclysm+) roots still hold the MCNKs, so the file is probably a stub"
+            }
+            FailureKind::SplitFile => {
+                "a split file with nothing the grids need (textures, LOD, or _obj0 under --fast); safe to ignore"
+            }
+            FailureKind::Encrypted => "encrypted with a TACT key that isn't known yet; pass it with --tact-keys once it is published",
+            FailureKind::Io => "the file could not be read; check permissions and that the path still exists",
+            FailureKind::Other => "unexpected parse error; please report it with the file attached",
+        }
+    }
+}
+
+/// A file encrypted with a TACT key the reader doesn't have
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MissingKey {
+    /// Key name as TACT key lists write it
+    pub key_name: u64,
+}
+
+impl std::fmt::Display for MissingKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "encrypted with TACT key {:016X}, which wasn't supplied", self.key_name)
+    }
+}
+
+impl std::error::Error for MissingKey {}
+
+/// True for `<map>_<x>_<y>_tex0.adt` and friends
+pub fn is_split_adt(path: &Path) -> bool {
+    let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+        return false;
+    };
+    let stem = stem.to_ascii_lowercase();
+    SPLIT_SUFFIXES.iter().any(|suffix| stem.ends_with(suffix))
+}
+
+/// Failures collected while scanning one directory
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FailureReport {
+    pub by_kind: BTreeMap<FailureKind, Vec<(PathBuf, String)>>,
+}
+
+impl FailureReport {
+    pub fn record(&mut self, kind: FailureKind, path: &Path, message: String) {
+        self.by_kind.entry(kind).or_default().push((path.to_path_buf(), message));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_kind.is_empty()
+    }
+
+    pub fn count(&self, kind: FailureKind) -> usize {
+        self.by_kind.get(&kind).map(|v| v.len()).unwrap_or(0)
+    }
+
+    pub fn total(&self) -> usize {
+        self.by_kind.values().map(|v| v.len()).sum()
+    }
+
+    /// Print each kind with a few example files and its hint
+    pub fn print(&self) {
+        for (kind, files) in &self.by_kind {
+            info!("  {} ({}):", kind.label(), files.len());
+            for (path, message) in files.iter().take(5) {
+                info!("    {}: {}", path.display(), message);
+            }
+            if files.len() > 5 {
+                info!("    ... and {} more", files.len() - 5);
+            }
+            info!("    hint: {}", kind.hint());
+        }
+    }
+}
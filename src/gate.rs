@@ -0,0 +1,128 @@
+use std::fmt;
+use std::str::FromStr;
+
+// ============================================================================
+// Quality gates
+// ============================================================================
+
+/// Counts from a generation run that quality gates are checked against
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct RunMetrics {
+    pub tiles_parsed: usize,
+    /// Files that should have produced a tile but didn't (split files excluded)
+    pub parse_failures: usize,
+    pub areas_found: usize,
+    /// Areas seen in the grids but missing from the area table
+    pub unknown_areas: usize,
+    /// Chunks scanned across all grids
+    pub chunks_scanned: usize,
+    /// Chunks whose area ID was a sentinel and got replaced
+    pub sentinel_chunks: usize,
+    /// Tiles the WDTs list, ignored tiles excluded
+    pub wdt_tiles: usize,
+    /// Tiles the WDTs list that no ADT was parsed for
+    pub missing_tiles: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Metric {
+    ParseFailures,
+    UnknownAreas,
+    SentinelChunks,
+    MissingTiles,
+}
+
+impl Metric {
+    fn name(&self) -> &'static str {
+        match self {
+            Metric::ParseFailures => "parse-failures",
+            Metric::UnknownAreas => "unknown-areas",
+            Metric::SentinelChunks => "sentinel-chunks",
+            Metric::MissingTiles => "missing-tiles",
+        }
+    }
+
+    /// (value, base for percentages)
+    fn measure(&self, m: &RunMetrics) -> (usize, usize) {
+        match self {
+            Metric::ParseFailures => (m.parse_failures, m.tiles_parsed + m.parse_failures),
+            Metric::UnknownAreas => (m.unknown_areas, m.areas_found),
+            Metric::SentinelChunks => (m.sentinel_chunks, m.chunks_scanned),
+            Metric::MissingTiles => (m.missing_tiles, m.wdt_tiles),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Limit {
+    Count(usize),
+    Percent(f64),
+}
+
+/// A `metric=limit` assertion such as `parse-failures=5%` or `unknown-areas=0`.
+/// The run fails when the metric exceeds the limit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Threshold {
+    pub metric: Metric,
+    pub limit: Limit,
+}
+
+impl FromStr for Threshold {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, limit) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected metric=limit, got `{}`", s))?;
+        let metric = match name.trim() {
+            "parse-failures" => Metric::ParseFailures,
+            "unknown-areas" => Metric::UnknownAreas,
+            "sentinel-chunks" => Metric::SentinelChunks,
+            "missing-tiles" => Metric::MissingTiles,
+            other => {
+                return Err(format!(
+                    "unknown metric `{}` (parse-failures, unknown-areas, sentinel-chunks, missing-tiles)",
+                    other
+                ))
+            }
+        };
+        let limit = limit.trim();
+        let limit = match limit.strip_suffix('%') {
+            Some(pct) => Limit::Percent(pct.parse().map_err(|_| format!("bad percentage `{}`", limit))?),
+            None => Limit::Count(limit.parse().map_err(|_| format!("bad count `{}`", limit))?),
+        };
+        Ok(Threshold { metric, limit })
+    }
+}
+
+impl fmt::Display for Threshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.limit {
+            Limit::Count(n) => write!(f, "{}={}", self.metric.name(), n),
+            Limit::Percent(p) => write!(f, "{}={}%", self.metric.name(), p),
+        }
+    }
+}
+
+impl Threshold {
+    /// `Err` with a readable message when the metric exceeds the limit
+    pub fn check(&self, metrics: &RunMetrics) -> Result<(), String> {
+        let (value, base) = self.metric.measure(metrics);
+        let exceeded = match self.limit {
+            Limit::Count(n) => value > n,
+            Limit::Percent(p) => base > 0 && value as f64 * 100.0 / base as f64 > p,
+        };
+        if exceeded {
+            Err(format!("{} is {} of {} (limit {})", self.metric.name(), value, base, self))
+        } else {
+            Ok(())
+        }
+    }
+}
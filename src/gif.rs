@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+
+// ============================================================================
+// GIF encoding
+// ============================================================================
+//
+// Just enough GIF89a to loop a few RGB frames (render's blink comparison):
+// one global colour table of the colours used, a NETSCAPE2.0 block so it
+// loops for ever, and per frame a graphic control block with the delay, an
+// image descriptor and LZW-compressed colour indices. Frames with more than
+// 256 colours between them are rounded to a 6x6x6 colour cube first.
+
+/// LZW codes stop growing here; the encoder starts over with a clear code
+const MAX_CODE: u16 = 4096;
+
+/// Packs codes least significant bit first, as GIF wants
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    pending: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.pending |= (code as u32) << self.count;
+        self.count += size as u32;
+        while self.count >= 8 {
+            self.bytes.push(self.pending as u8);
+            self.pending >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.pending as u8);
+        }
+        self.bytes
+    }
+}
+
+/// LZW-compress colour `indices` with `min_size`-bit roots
+fn lzw(indices: &[u8], min_size: u8) -> Vec<u8> {
+    let clear = 1u16 << min_size;
+    let end = clear + 1;
+    let mut codes: HashMap<(u16, u8), u16> = HashMap::new();
+    let (mut size, mut next) = (min_size + 1, end + 1);
+    let mut out = BitWriter::default();
+    out.write(clear, size);
+
+    let mut prefix: Option<u16> = None;
+    for &index in indices {
+        let Some(current) = prefix else {
+            prefix = Some(index as u16);
+            continue;
+        };
+        if let Some(&code) = codes.get(&(current, index)) {
+            prefix = Some(code);
+            continue;
+        }
+        out.write(current, size);
+        if next < MAX_CODE {
+            codes.insert((current, index), next);
+            next += 1;
+            // The decoder adds each code a step later, so widen once the
+            // code just added needs the extra bit
+            if next > 1 << size && size < 12 {
+                size += 1;
+            }
+        } else {
+            out.write(clear, size);
+            codes.clear();
+            (size, next) = (min_size + 1, end + 1);
+        }
+        prefix = Some(index as u16);
+    }
+    if let Some(current) = prefix {
+        out.write(current, size);
+    }
+    out.write(end, size);
+    out.finish()
+}
+
+/// Round a colour to the nearest of a 6x6x6 cube
+fn to_cube(rgb: [u8; 3]) -> [u8; 3] {
+    rgb.map(|c| ((c as u32 * 5 + 127) / 255 * 51) as u8)
+}
+
+/// Encode `frames` of `width` x `height` RGB pixels (3 bytes each, row-major
+/// from the top) as a GIF that shows each for `delay` hundredths of a second
+/// and loops for ever
+pub fn encode_animation(width: u32, height: u32, frames: &[&[u8]], delay: u16) -> Result<Vec<u8>, String> {
+    let (Ok(w), Ok(h)) = (u16::try_from(width), u16::try_from(height)) else {
+        return Err(format!("{}x{} is too big for a GIF", width, height));
+    };
+    let size = (width * height * 3) as usize;
+    if let Some(frame) = frames.iter().find(|f| f.len() != size) {
+        return Err(format!("{}x{} frame needs {} bytes, got {}", width, height, size, frame.len()));
+    }
+
+    let pixels = |frame: &'_ [u8]| frame.chunks(3).map(|p| [p[0], p[1], p[2]]).collect::<Vec<[u8; 3]>>();
+    let mut frames: Vec<Vec<[u8; 3]>> = frames.iter().map(|f| pixels(f)).collect();
+    let mut table: Vec<[u8; 3]> = Vec::new();
+    let mut index: HashMap<[u8; 3], u8> = HashMap::new();
+    for rounded in [false, true] {
+        if rounded {
+            frames = frames.into_iter().map(|f| f.into_iter().map(to_cube).collect()).collect();
+            (table, index) = (Vec::new(), HashMap::new());
+        }
+        for &rgb in frames.iter().flatten() {
+            if !index.contains_key(&rgb) && table.len() <= 256 {
+                index.insert(rgb, table.len() as u8);
+                table.push(rgb);
+            }
+        }
+        if table.len() <= 256 {
+            break;
+        }
+    }
+
+    // The table holds 2^bits colours, at least two
+    let bits = (usize::BITS - (table.len().max(2) - 1).leading_zeros()) as u8;
+    let mut out = b"GIF89a".to_vec();
+    out.extend_from_slice(&w.to_le_bytes());
+    out.extend_from_slice(&h.to_le_bytes());
+    // Global table, 8 bits per primary, `bits` bits per index
+    out.extend_from_slice(&[0xF0 | (bits - 1), 0, 0]);
+    for i in 0..1usize << bits {
+        out.extend_from_slice(&table.get(i).copied().unwrap_or_default());
+    }
+    out.extend_from_slice(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+    let min_size = bits.max(2);
+    for frame in &frames {
+        // Graphic control: keep the frame up, no transparency
+        out.extend_from_slice(&[0x21, 0xF9, 0x04, 0x04]);
+        out.extend_from_slice(&delay.to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+        out.push(0x2C);
+        out.extend_from_slice(&[0, 0, 0, 0]);
+        out.extend_from_slice(&w.to_le_bytes());
+        out.extend_from_slice(&h.to_le_bytes());
+        out.push(0);
+        out.push(min_size);
+        let indices: Vec<u8> = frame.iter().map(|rgb| index[rgb]).collect();
+        for block in lzw(&indices, min_size).chunks(255) {
+            out.push(block.len() as u8);
+            out.extend_from_slice(block);
+        }
+        out.push(0);
+    }
+    out.push(0x3B);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// LZW-decode one frame's data, as a GIF reader would
+    fn unlzw(data: &[u8], min_size: u8) -> Vec<u8> {
+        let clear = 1u16 << min_size;
+        let end = clear + 1;
+        let roots = || (0..clear).map(|c| vec![c as u8]).chain([Vec::new(), Vec::new()]).collect::<Vec<Vec<u8>>>();
+        let (mut table, mut size) = (roots(), min_size + 1);
+        let (mut bit, mut previous, mut out): (usize, Option<u16>, Vec<u8>) = (0, None, Vec::new());
+        loop {
+            let mut code = 0u16;
+            for i in 0..size as usize {
+                code |= (((data[(bit + i) / 8] >> ((bit + i) % 8)) & 1) as u16) << i;
+            }
+            bit += size as usize;
+            if code == clear {
+                (table, size, previous) = (roots(), min_size + 1, None);
+                continue;
+            }
+            if code == end {
+                return out;
+            }
+            let entry = match (table.get(code as usize), previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(p)) => {
+                    let mut entry = table[p as usize].clone();
+                    entry.push(entry[0]);
+                    entry
+                }
+                (None, None) => panic!("code {} before any other", code),
+            };
+            if let Some(p) = previous.filter(|_| table.len() < MAX_CODE as usize) {
+                let mut added = table[p as usize].clone();
+                added.push(entry[0]);
+                table.push(added);
+                if table.len() == 1 << size && size < 12 {
+                    size += 1;
+                }
+            }
+            out.extend_from_slice(&entry);
+            previous = Some(code);
+        }
+    }
+
+    #[test]
+    fn lzw_round_trips_through_a_reader() {
+        // Runs, noise long enough to fill the code table several times over,
+        // and the single-colour case
+        let mut seed = 1u32;
+        let noise: Vec<u8> = (0..60_000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (seed >> 16) as u8 % 7
+            })
+            .collect();
+        let runs: Vec<u8> = (0..20_000u32).map(|i| (i / 37 % 4) as u8).collect();
+        for (indices, min_size) in [(noise, 3), (runs, 2), (vec![0; 5000], 2), (vec![1], 2)] {
+            assert_eq!(unlzw(&lzw(&indices, min_size), min_size), indices);
+        }
+    }
+
+    #[test]
+    fn frames_share_one_colour_table() {
+        let (red, blue) = ([255, 0, 0], [0, 0, 255]);
+        let first: Vec<u8> = [red, red, blue, blue].concat();
+        let second: Vec<u8> = [blue, blue, red, red].concat();
+        let gif = encode_animation(2, 2, &[&first, &second], 50).unwrap();
+        assert!(gif.starts_with(b"GIF89a\x02\x00\x02\x00"));
+        // A two-entry table: red then blue
+        assert_eq!(gif[10], 0xF0);
+        assert_eq!(&gif[13..19], &[255, 0, 0, 0, 0, 255]);
+        assert_eq!(gif.last(), Some(&0x3B));
+        assert!(encode_animation(2, 2, &[&first[..9]], 50).is_err());
+    }
+
+    #[test]
+    fn many_colours_round_to_a_cube() {
+        let pixels: Vec<u8> = (0..600u32).flat_map(|i| [i as u8, (i / 3) as u8, 7]).collect();
+        let gif = encode_animation(600, 1, &[&pixels], 0).unwrap();
+        // Six reds by five greens (up to 200) once rounded, in 32 entries
+        assert_eq!(gif[10], 0xF4);
+        assert!(gif[13..13 + 32 * 3].iter().all(|v| v % 51 == 0));
+    }
+}
@@ -0,0 +1,188 @@
+use crate::dbc::Dbc;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+// ============================================================================
+// Graveyards
+// ============================================================================
+//
+// WorldSafeLocs.dbc holds every place a ghost can be sent: graveyards with
+// their spirit healers, plus battleground and dungeon exits. Which zones use
+// which graveyard isn't in the client; servers keep it in `graveyard_zone`
+// (TrinityCore) or `game_graveyard_zone` (CMaNGOS): safe location, ghost zone
+// and the faction allowed to use it.
+
+/// WorldSafeLocs.dbc fields used here: ID, map, x, y, z, enUS name
+const DBC_FIELDS: usize = 6;
+
+/// One WorldSafeLocs row
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SafeLoc {
+    pub id: u32,
+    pub map_id: u32,
+    pub pos: [f32; 3],
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Faction {
+    Alliance,
+    Horde,
+}
+
+impl Faction {
+    /// Faction template IDs in `graveyard_zone.faction`; 0 (either) is `None`
+    pub fn from_team(team: u32) -> Option<Self> {
+        match team {
+            469 => Some(Faction::Alliance),
+            67 => Some(Faction::Horde),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Faction::Alliance => "alliance",
+            Faction::Horde => "horde",
+        }
+    }
+}
+
+/// A zone whose dead are sent to a safe location
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GraveyardLink {
+    pub safe_loc: u32,
+    pub zone: u32,
+    /// `None` when both factions may use it
+    pub faction: Option<Faction>,
+}
+
+/// Safe locations of a WDBC WorldSafeLocs.dbc
+pub fn parse_safe_locs_dbc(dbc: &Dbc) -> Result<Vec<SafeLoc>, String> {
+    if dbc.field_count < DBC_FIELDS {
+        return Err(format!("WorldSafeLocs.dbc has {} fields, expected at least {}", dbc.field_count, DBC_FIELDS));
+    }
+    Ok(dbc
+        .records()
+        .map(|r| SafeLoc { id: r.u32(0), map_id: r.u32(1), pos: [r.f32(2), r.f32(3), r.f32(4)], name: r.string(5).to_string() })
+        .collect())
+}
+
+/// Parse a WorldSafeLocs CSV export (wago.tools column names)
+pub fn parse_safe_locs_csv(csv_path: &Path) -> Result<Vec<SafeLoc>, Box<dyn std::error::Error>> {
+    let reader = BufReader::new(File::open(csv_path)?);
+    let mut lines = reader.lines();
+    let header = lines.next().ok_or("Empty CSV")??;
+
+    let columns = split_csv_line(&header);
+    let col = |name: &str| columns.iter().position(|c| c == name).ok_or_else(|| format!("No {} column", name));
+    let id_idx = col("ID")?;
+    let map_idx = col("MapID")?;
+    let pos_idx = [col("Loc_0")?, col("Loc_1")?, col("Loc_2")?];
+    let name_idx = col("AreaName_lang").ok();
+
+    let mut locs = Vec::new();
+    for line in lines {
+        let fields = split_csv_line(&line?);
+        if fields.len() < columns.len() {
+            continue;
+        }
+        let int = |i: usize| fields[i].parse::<u32>().unwrap_or(0);
+        let float = |i: usize| fields[i].parse::<f32>().unwrap_or(0.0);
+        locs.push(SafeLoc {
+            id: int(id_idx),
+            map_id: int(map_idx),
+            pos: pos_idx.map(float),
+            name: name_idx.map(|i| fields[i].clone()).unwrap_or_default(),
+        });
+    }
+    Ok(locs)
+}
+
+/// Parse a `graveyard_zone` export: TrinityCore (`ID`, `GhostZone`, `Faction`)
+/// or CMaNGOS (`id`, `ghost_zone`, `faction`) column names
+pub fn parse_graveyard_links_csv(csv_path: &Path) -> Result<Vec<GraveyardLink>, Box<dyn std::error::Error>> {
+    let reader = BufReader::new(File::open(csv_path)?);
+    let mut lines = reader.lines();
+    let header = lines.next().ok_or("Empty CSV")??;
+
+    let columns = split_csv_line(&header);
+    let col = |names: &[&str]| {
+        columns
+            .iter()
+            .position(|c| names.iter().any(|n| c.eq_ignore_ascii_case(n)))
+            .ok_or_else(|| format!("No {} column", names[0]))
+    };
+    let loc_idx = col(&["ID", "id"])?;
+    let zone_idx = col(&["GhostZone", "ghost_zone"])?;
+    let faction_idx = col(&["Faction", "faction"]).ok();
+
+    let mut links = Vec::new();
+    for line in lines {
+        let fields = split_csv_line(&line?);
+        let int = |i: usize| fields.get(i).and_then(|f| f.parse::<u32>().ok());
+        let (Some(safe_loc), Some(zone)) = (int(loc_idx), int(zone_idx)) else {
+            continue;
+        };
+        let faction = faction_idx.and_then(int).and_then(Faction::from_team);
+        links.push(GraveyardLink { safe_loc, zone, faction });
+    }
+    Ok(links)
+}
+
+/// Split a CSV line, honouring double-quoted fields (names contain commas)
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut current).trim().to_string()),
+            c => current.push(c),
+        }
+    }
+    fields.push(current.trim().to_string());
+    fields
+}
+
+/// Graveyards per zone: linked zones when `links` has any, otherwise the zone
+/// each graveyard stands in according to `zone_at(map, x, y)`. Only safe
+/// locations that are linked, or stand in a zone, are kept.
+pub fn graveyards_by_zone<'a>(
+    locs: &'a [SafeLoc],
+    links: &[GraveyardLink],
+    zone_at: impl Fn(u32, f32, f32) -> Option<u32>,
+) -> HashMap<u32, Vec<(&'a SafeLoc, Option<Faction>)>> {
+    let mut zones: HashMap<u32, Vec<(&SafeLoc, Option<Faction>)>> = HashMap::new();
+    if links.is_empty() {
+        for loc in locs {
+            if let Some(zone) = zone_at(loc.map_id, loc.pos[0], loc.pos[1]) {
+                zones.entry(zone).or_default().push((loc, None));
+            }
+        }
+    } else {
+        let by_id: HashMap<u32, &SafeLoc> = locs.iter().map(|l| (l.id, l)).collect();
+        for link in links {
+            if let Some(loc) = by_id.get(&link.safe_loc) {
+                zones.entry(link.zone).or_default().push((loc, link.faction));
+            }
+        }
+    }
+    for graveyards in zones.values_mut() {
+        graveyards.sort_by_key(|(loc, faction)| (loc.id, *faction));
+    }
+    zones
+}
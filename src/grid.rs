@@ -0,0 +1,460 @@
+use crate::adt::{CHUNKS_PER_SIDE, CHUNKS_PER_TILE};
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+// ============================================================================
+// World coordinates
+// ============================================================================
+
+/// Width of one ADT tile in yards
+pub const TILE_SIZE: f32 = 533.333_3;
+
+/// Number of ADT tiles along one side of a map
+pub const TILES_PER_SIDE: u32 = 64;
+
+/// Position of a chunk on a map: tile (x, y) and chunk (x, y) within that tile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChunkPos {
+    pub tile_x: u32,
+    pub tile_y: u32,
+    pub chunk_x: u32,
+    pub chunk_y: u32,
+}
+
+impl ChunkPos {
+    /// Key of the containing tile, as used in the exported `tiles` tables
+    pub fn tile_key(&self) -> u32 {
+        self.tile_y * TILES_PER_SIDE + self.tile_x
+    }
+
+    /// Row-major index of the chunk within its tile
+    pub fn chunk_index(&self) -> usize {
+        self.chunk_y as usize * CHUNKS_PER_SIDE + self.chunk_x as usize
+    }
+}
+
+/// Convert world (x, y) coordinates to the chunk containing them.
+/// ADT tile X runs along world Y and tile Y along world X, both decreasing.
+pub fn world_to_chunk(x: f32, y: f32) -> Option<ChunkPos> {
+    let half = TILE_SIZE * (TILES_PER_SIDE / 2) as f32;
+    let fx = (half - y) / TILE_SIZE;
+    let fy = (half - x) / TILE_SIZE;
+    if !(0.0..TILES_PER_SIDE as f32).contains(&fx) || !(0.0..TILES_PER_SIDE as f32).contains(&fy) {
+        return None;
+    }
+
+    let tile_x = fx.floor() as u32;
+    let tile_y = fy.floor() as u32;
+    let chunk_x = (((fx - tile_x as f32) * CHUNKS_PER_SIDE as f32) as u32).min(CHUNKS_PER_SIDE as u32 - 1);
+    let chunk_y = (((fy - tile_y as f32) * CHUNKS_PER_SIDE as f32) as u32).min(CHUNKS_PER_SIDE as u32 - 1);
+
+    Some(ChunkPos { tile_x, tile_y, chunk_x, chunk_y })
+}
+
+/// World (x, y) in map-wide chunk units: (0, 0) is the top-left corner of
+/// tile (0, 0) and each chunk is 1.0 wide. May fall outside the map.
+pub(crate) fn world_to_chunk_units(x: f32, y: f32) -> (f32, f32) {
+    let half = TILE_SIZE * (TILES_PER_SIDE / 2) as f32;
+    let chunk_size = TILE_SIZE / CHUNKS_PER_SIDE as f32;
+    ((half - y) / chunk_size, (half - x) / chunk_size)
+}
+
+/// Chunk at map-wide chunk coordinates, `None` off the map
+pub(crate) fn chunk_at_units(cx: i64, cy: i64) -> Option<ChunkPos> {
+    let side = TILES_PER_SIDE as i64 * CHUNKS_PER_SIDE as i64;
+    if !(0..side).contains(&cx) || !(0..side).contains(&cy) {
+        return None;
+    }
+    let per_tile = CHUNKS_PER_SIDE as i64;
+    Some(ChunkPos {
+        tile_x: (cx / per_tile) as u32,
+        tile_y: (cy / per_tile) as u32,
+        chunk_x: (cx % per_tile) as u32,
+        chunk_y: (cy % per_tile) as u32,
+    })
+}
+
+/// Axis-aligned rectangle in world coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WorldRect {
+    pub min_x: f32,
+    pub max_x: f32,
+    pub min_y: f32,
+    pub max_y: f32,
+}
+
+impl WorldRect {
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        (self.min_x..=self.max_x).contains(&x) && (self.min_y..=self.max_y).contains(&y)
+    }
+
+    /// Grow to cover another rectangle
+    pub fn union(&self, other: &WorldRect) -> WorldRect {
+        WorldRect {
+            min_x: self.min_x.min(other.min_x),
+            max_x: self.max_x.max(other.max_x),
+            min_y: self.min_y.min(other.min_y),
+            max_y: self.max_y.max(other.max_y),
+        }
+    }
+}
+
+/// World-space extent of a single chunk
+pub fn chunk_world_rect(pos: ChunkPos) -> WorldRect {
+    let half = TILE_SIZE * (TILES_PER_SIDE / 2) as f32;
+    let chunk_size = TILE_SIZE / CHUNKS_PER_SIDE as f32;
+    let max_y = half - pos.tile_x as f32 * TILE_SIZE - pos.chunk_x as f32 * chunk_size;
+    let max_x = half - pos.tile_y as f32 * TILE_SIZE - pos.chunk_y as f32 * chunk_size;
+
+    WorldRect { min_x: max_x - chunk_size, max_x, min_y: max_y - chunk_size, max_y }
+}
+
+/// World-space extent of a whole tile
+pub fn tile_world_rect(tile_x: u32, tile_y: u32) -> WorldRect {
+    let last = CHUNKS_PER_SIDE as u32 - 1;
+    chunk_world_rect(ChunkPos { tile_x, tile_y, chunk_x: 0, chunk_y: 0 })
+        .union(&chunk_world_rect(ChunkPos { tile_x, tile_y, chunk_x: last, chunk_y: last }))
+}
+
+// ============================================================================
+// Area lookup
+// ============================================================================
+
+/// What `ZoneGrid` lookups return for a position whose tile isn't in the grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[non_exhaustive]
+pub enum MissingTilePolicy {
+    /// No area
+    #[default]
+    None,
+    /// A fixed area, typically the map's ocean
+    Ocean(u32),
+    /// The nearest chunk of the nearest tile that exists
+    Nearest,
+}
+
+impl FromStr for MissingTilePolicy {
+    type Err = String;
+
+    /// `none`, `ocean:<area id>` or `nearest`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "nearest" => Ok(Self::Nearest),
+            _ => match s.strip_prefix("ocean:") {
+                Some(id) => id.parse().map(Self::Ocean).map_err(|_| format!("bad ocean area `{}`", id)),
+                None => Err(format!("unknown missing-tile policy `{}` (none, ocean:<id>, nearest)", s)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for MissingTilePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => f.write_str("none"),
+            Self::Ocean(id) => write!(f, "ocean:{}", id),
+            Self::Nearest => f.write_str("nearest"),
+        }
+    }
+}
+
+/// Result of `ZoneGrid::area_at_with_confidence`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct AreaSample {
+    /// Area of the chunk containing the point, as `area_at` returns
+    pub area_id: u32,
+    /// Bilinear weight of the four nearest chunks that agree with `area_id`:
+    /// 1.0 well inside an area, down to 0.25 at a corner where all differ
+    pub confidence: f32,
+    /// Yards to the nearest chunk edge with a different area (or no data)
+    /// on the other side, `None` when all four nearest chunks agree
+    pub edge_distance: Option<f32>,
+}
+
+/// Area ID grid for one map, keyed by tile key with 256 area IDs per tile
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ZoneGrid {
+    pub name: String,
+    /// Map.dbc ID of the continent (0 Eastern Kingdoms, 1 Kalimdor)
+    pub map_id: u32,
+    tiles: HashMap<u32, Vec<u32>>,
+    missing_tile: MissingTilePolicy,
+}
+
+impl ZoneGrid {
+    pub fn new(name: &str, map_id: u32, tiles: HashMap<u32, Vec<u32>>) -> Self {
+        Self { name: name.to_string(), map_id, tiles, missing_tile: MissingTilePolicy::default() }
+    }
+
+    pub fn missing_tile_policy(&self) -> MissingTilePolicy {
+        self.missing_tile
+    }
+
+    /// How `area_at` and `area_at_with_confidence` treat positions on tiles
+    /// that aren't in the grid
+    pub fn set_missing_tile_policy(&mut self, policy: MissingTilePolicy) {
+        self.missing_tile = policy;
+    }
+
+    /// Raw tiles, keyed by tile key with 256 area IDs each
+    pub fn tiles(&self) -> &HashMap<u32, Vec<u32>> {
+        &self.tiles
+    }
+
+    pub fn has_tile(&self, tile_key: u32) -> bool {
+        self.tiles.contains_key(&tile_key)
+    }
+
+    /// Area ID of a chunk, `None` when its tile is missing or the chunk is unset
+    pub fn area_at_chunk(&self, pos: ChunkPos) -> Option<u32> {
+        let tile = self.tiles.get(&pos.tile_key())?;
+        if tile.len() != CHUNKS_PER_TILE {
+            return None;
+        }
+        self.area_at_index(pos.tile_key(), pos.chunk_index())
+    }
+
+    /// Area ID by tile key and row-major chunk index
+    pub fn area_at_index(&self, tile_key: u32, chunk_idx: usize) -> Option<u32> {
+        match self.tiles.get(&tile_key)?.get(chunk_idx)? {
+            0 => None,
+            &id => Some(id),
+        }
+    }
+
+    /// World-space extent of every tile in the grid, `None` when it has none
+    pub fn bounds(&self) -> Option<WorldRect> {
+        self.tiles
+            .keys()
+            .map(|&key| tile_world_rect(key % TILES_PER_SIDE, key / TILES_PER_SIDE))
+            .reduce(|a, b| a.union(&b))
+    }
+
+    /// Every non-zero area ID present in the grid
+    pub fn area_ids(&self) -> BTreeSet<u32> {
+        self.tiles.values().flatten().copied().filter(|&id| id != 0).collect()
+    }
+
+    /// World-space bounding box of every area in the grid
+    pub fn area_bounds(&self) -> BTreeMap<u32, WorldRect> {
+        let mut bounds: BTreeMap<u32, WorldRect> = BTreeMap::new();
+        for (&key, tile) in &self.tiles {
+            for (idx, &area_id) in tile.iter().enumerate() {
+                if area_id == 0 {
+                    continue;
+                }
+                let rect = chunk_world_rect(ChunkPos {
+                    tile_x: key % TILES_PER_SIDE,
+                    tile_y: key / TILES_PER_SIDE,
+                    chunk_x: (idx % CHUNKS_PER_SIDE) as u32,
+                    chunk_y: (idx / CHUNKS_PER_SIDE) as u32,
+                });
+                bounds
+                    .entry(area_id)
+                    .and_modify(|b| *b = b.union(&rect))
+                    .or_insert(rect);
+            }
+        }
+        bounds
+    }
+
+    /// Area ID of a chunk, applying the missing-tile policy when its tile
+    /// isn't in the grid
+    fn resolve_chunk(&self, pos: ChunkPos) -> Option<u32> {
+        if self.has_tile(pos.tile_key()) {
+            return self.area_at_chunk(pos);
+        }
+        match self.missing_tile {
+            MissingTilePolicy::None => None,
+            MissingTilePolicy::Ocean(id) => Some(id),
+            MissingTilePolicy::Nearest => self.area_at_chunk(self.nearest_chunk(pos)?),
+        }
+    }
+
+    /// Chunk of an existing tile closest to `pos`, ties going to the lower tile key
+    fn nearest_chunk(&self, pos: ChunkPos) -> Option<ChunkPos> {
+        let per_tile = CHUNKS_PER_SIDE as i64;
+        let cx = (pos.tile_x * CHUNKS_PER_SIDE as u32 + pos.chunk_x) as i64;
+        let cy = (pos.tile_y * CHUNKS_PER_SIDE as u32 + pos.chunk_y) as i64;
+
+        self.tiles
+            .keys()
+            .map(|&key| {
+                let tile_x = (key % TILES_PER_SIDE) as i64;
+                let tile_y = (key / TILES_PER_SIDE) as i64;
+                let nx = cx.clamp(tile_x * per_tile, tile_x * per_tile + per_tile - 1);
+                let ny = cy.clamp(tile_y * per_tile, tile_y * per_tile + per_tile - 1);
+                ((nx - cx).pow(2) + (ny - cy).pow(2), key, nx, ny)
+            })
+            .min()
+            .and_then(|(_, _, nx, ny)| chunk_at_units(nx, ny))
+    }
+
+    /// Area ID at world (x, y)
+    pub fn area_at(&self, x: f32, y: f32) -> Option<u32> {
+        self.resolve_chunk(world_to_chunk(x, y)?)
+    }
+
+    /// Area ID at world (x, y) plus how close it is to a border. Looks at the
+    /// four chunks whose centres surround the point, so consumers can prefer
+    /// other signals (subzone text, quest data) when `confidence` is low.
+    pub fn area_at_with_confidence(&self, x: f32, y: f32) -> Option<AreaSample> {
+        let pos = world_to_chunk(x, y)?;
+        let area_id = self.resolve_chunk(pos)?;
+
+        let (ux, uy) = world_to_chunk_units(x, y);
+        let cx = (pos.tile_x * CHUNKS_PER_SIDE as u32 + pos.chunk_x) as i64;
+        let cy = (pos.tile_y * CHUNKS_PER_SIDE as u32 + pos.chunk_y) as i64;
+        // Position within the containing chunk, 0.0-1.0 on each axis
+        let px = (ux - cx as f32).clamp(0.0, 1.0);
+        let py = (uy - cy as f32).clamp(0.0, 1.0);
+
+        // The neighbours on the near side in x, in y, and diagonally
+        let step_x = if px < 0.5 { -1 } else { 1 };
+        let step_y = if py < 0.5 { -1 } else { 1 };
+        // Bilinear weight of the neighbouring column and row
+        let wx = (px - 0.5).abs();
+        let wy = (py - 0.5).abs();
+        let chunk_size = TILE_SIZE / CHUNKS_PER_SIDE as f32;
+        let dx = px.min(1.0 - px) * chunk_size;
+        let dy = py.min(1.0 - py) * chunk_size;
+
+        let neighbours = [
+            (step_x, 0, wx * (1.0 - wy), dx),
+            (0, step_y, (1.0 - wx) * wy, dy),
+            (step_x, step_y, wx * wy, dx.hypot(dy)),
+        ];
+
+        let mut confidence = (1.0 - wx) * (1.0 - wy);
+        let mut edge_distance: Option<f32> = None;
+        for (sx, sy, weight, distance) in neighbours {
+            let other = chunk_at_units(cx + sx, cy + sy).and_then(|p| self.resolve_chunk(p));
+            if other == Some(area_id) {
+                confidence += weight;
+            } else {
+                edge_distance = Some(edge_distance.map_or(distance, |d| d.min(distance)));
+            }
+        }
+
+        Some(AreaSample { area_id, confidence, edge_distance })
+    }
+}
+
+/// Grid for a position: the one named `continent`, or when that is empty,
+/// the first grid with a tile at (x, y)
+pub fn find_grid<'a>(grids: &'a [ZoneGrid], continent: &str, x: f32, y: f32) -> Option<&'a ZoneGrid> {
+    grids.iter().find(|g| {
+        if continent.is_empty() {
+            world_to_chunk(x, y).is_some_and(|pos| g.has_tile(pos.tile_key()))
+        } else {
+            g.name.eq_ignore_ascii_case(continent)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Grid with one tile at (32, 32): area 12 in its west half, 40 in its east half
+    fn grid(policy: MissingTilePolicy) -> ZoneGrid {
+        let tile = (0..CHUNKS_PER_TILE)
+            .map(|i| if i % CHUNKS_PER_SIDE < CHUNKS_PER_SIDE / 2 { 12 } else { 40 })
+            .collect();
+        let mut grid = ZoneGrid::new("Test", 0, HashMap::from([(32 * TILES_PER_SIDE + 32, tile)]));
+        grid.set_missing_tile_policy(policy);
+        grid
+    }
+
+    /// World position of the centre of a chunk
+    fn centre(tile_x: u32, tile_y: u32, chunk_x: u32, chunk_y: u32) -> (f32, f32) {
+        let r = chunk_world_rect(ChunkPos { tile_x, tile_y, chunk_x, chunk_y });
+        ((r.min_x + r.max_x) / 2.0, (r.min_y + r.max_y) / 2.0)
+    }
+
+    #[test]
+    fn existing_tiles_ignore_the_policy() {
+        for policy in [MissingTilePolicy::None, MissingTilePolicy::Ocean(1), MissingTilePolicy::Nearest] {
+            let grid = grid(policy);
+            let (x, y) = centre(32, 32, 2, 5);
+            assert_eq!(grid.area_at(x, y), Some(12));
+            let (x, y) = centre(32, 32, 13, 5);
+            assert_eq!(grid.area_at(x, y), Some(40));
+        }
+    }
+
+    #[test]
+    fn missing_tile_returns_none() {
+        let (x, y) = centre(10, 10, 0, 0);
+        assert_eq!(grid(MissingTilePolicy::None).area_at(x, y), None);
+    }
+
+    #[test]
+    fn missing_tile_returns_ocean_area() {
+        let (x, y) = centre(10, 10, 0, 0);
+        assert_eq!(grid(MissingTilePolicy::Ocean(5000)).area_at(x, y), Some(5000));
+    }
+
+    #[test]
+    fn missing_tile_extrapolates_from_nearest_tile() {
+        let grid = grid(MissingTilePolicy::Nearest);
+        // West of the tile the nearest chunks are area 12, east of it area 40
+        let (x, y) = centre(31, 32, 15, 8);
+        assert_eq!(grid.area_at(x, y), Some(12));
+        let (x, y) = centre(40, 20, 0, 0);
+        assert_eq!(grid.area_at(x, y), Some(40));
+        let (x, y) = centre(0, 63, 0, 15);
+        assert_eq!(grid.area_at(x, y), Some(12));
+    }
+
+    #[test]
+    fn nearest_policy_on_empty_grid_returns_none() {
+        let mut grid = ZoneGrid::new("Empty", 0, HashMap::new());
+        grid.set_missing_tile_policy(MissingTilePolicy::Nearest);
+        let (x, y) = centre(32, 32, 0, 0);
+        assert_eq!(grid.area_at(x, y), None);
+    }
+
+    #[test]
+    fn unset_chunks_are_not_missing_tiles() {
+        let mut grid = ZoneGrid::new("Holes", 0, HashMap::from([(32 * TILES_PER_SIDE + 32, vec![0; CHUNKS_PER_TILE])]));
+        grid.set_missing_tile_policy(MissingTilePolicy::Ocean(5000));
+        let (x, y) = centre(32, 32, 3, 3);
+        assert_eq!(grid.area_at(x, y), None);
+    }
+
+    #[test]
+    fn confidence_uses_the_policy_for_neighbours() {
+        // Chunk (0, 8) borders missing tile (31, 32) to the west
+        let (x, y) = centre(32, 32, 0, 8);
+        let near_west = (x, y + TILE_SIZE / CHUNKS_PER_SIDE as f32 * 0.4);
+
+        let sample = grid(MissingTilePolicy::None).area_at_with_confidence(near_west.0, near_west.1).unwrap();
+        assert_eq!(sample.area_id, 12);
+        assert!(sample.confidence < 1.0);
+        assert!(sample.edge_distance.is_some());
+
+        let sample = grid(MissingTilePolicy::Nearest).area_at_with_confidence(near_west.0, near_west.1).unwrap();
+        assert_eq!(sample.confidence, 1.0);
+        assert_eq!(sample.edge_distance, None);
+    }
+
+    #[test]
+    fn policy_round_trips_through_strings() {
+        for policy in [MissingTilePolicy::None, MissingTilePolicy::Ocean(5000), MissingTilePolicy::Nearest] {
+            assert_eq!(policy.to_string().parse::<MissingTilePolicy>(), Ok(policy));
+        }
+        assert!("ocean:sea".parse::<MissingTilePolicy>().is_err());
+        assert!("closest".parse::<MissingTilePolicy>().is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{BuildHasherDefault, Hasher};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use twox_hash::XxHash3_64;
+
+// ============================================================================
+// Content hashing
+// ============================================================================
+//
+// Every place that names or compares content by its hash goes through here,
+// so they agree on the algorithm and on how digests are written (lowercase
+// hex). SHA-256 is for hashes that leave the run and are checked by someone
+// else: signature manifests always use it. XXH3 (64-bit) is for telling
+// contents apart quickly: deduplicating tile payloads, and optionally naming
+// store snapshots and cache entries, which only need to be unique among our
+// own files. Which one names snapshots is `--hash`.
+
+/// Algorithm used to address content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Xxh3,
+}
+
+impl HashAlgorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Xxh3 => "xxh3",
+        }
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "sha256" | "sha-256" => Ok(HashAlgorithm::Sha256),
+            "xxh3" | "xxhash" | "xxhash3" => Ok(HashAlgorithm::Xxh3),
+            _ => Err(format!("unknown hash `{}` (expected sha256 or xxh3)", s)),
+        }
+    }
+}
+
+/// Incremental hash in either algorithm
+pub enum ContentHasher {
+    Sha256(Sha256),
+    Xxh3(Box<XxHash3_64>),
+}
+
+impl ContentHasher {
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Sha256 => ContentHasher::Sha256(Sha256::new()),
+            HashAlgorithm::Xxh3 => ContentHasher::Xxh3(Box::default()),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            ContentHasher::Sha256(h) => h.update(data),
+            ContentHasher::Xxh3(h) => h.write(data),
+        }
+    }
+
+    /// The digest in lowercase hex: 64 digits for SHA-256, 16 for XXH3
+    pub fn finish_hex(self) -> String {
+        match self {
+            ContentHasher::Sha256(h) => hex(&h.finalize()),
+            ContentHasher::Xxh3(h) => format!("{:016x}", h.finish()),
+        }
+    }
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Hex digest of `data`
+pub fn digest_hex(algorithm: HashAlgorithm, data: &[u8]) -> String {
+    match algorithm {
+        HashAlgorithm::Sha256 => sha256_hex(data),
+        HashAlgorithm::Xxh3 => format!("{:016x}", xxh3(data)),
+    }
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex(&Sha256::digest(data))
+}
+
+pub fn xxh3(data: &[u8]) -> u64 {
+    XxHash3_64::oneshot(data)
+}
+
+/// Hex digest of a set of files under `dir`: each relative path, a NUL and
+/// the file's bytes, in the order given (sort them for a stable hash)
+pub fn hash_files(algorithm: HashAlgorithm, dir: &Path, files: &[PathBuf]) -> std::io::Result<String> {
+    let mut hasher = ContentHasher::new(algorithm);
+    for rel in files {
+        hasher.update(rel.to_string_lossy().as_bytes());
+        hasher.update(&[0]);
+        hasher.update(&fs::read(dir.join(rel))?);
+    }
+    Ok(hasher.finish_hex())
+}
+
+/// Hash map keyed by XXH3, for deduplicating large keys such as payloads
+pub type DedupMap<K, V> = HashMap<K, V, BuildHasherDefault<XxHash3_64>>;
@@ -0,0 +1,141 @@
+use crate::grid::{world_to_chunk, ZoneGrid};
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+// ============================================================================
+// Player position heatmap
+// ============================================================================
+
+/// Aggregated player visits per grid and tile
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeatMap {
+    /// Grid name -> chunk position -> visit count
+    visits: HashMap<String, HashMap<(u32, usize), u64>>,
+}
+
+impl HeatMap {
+    /// Load a CSV with `continent`, `x`, `y` and an optional `count` column
+    /// (defaults to 1 per row, so raw position logs work too).
+    pub fn from_csv(csv_path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let reader = BufReader::new(File::open(csv_path)?);
+        let mut lines = reader.lines();
+        let header = lines.next().ok_or("Empty CSV")??;
+
+        let columns: Vec<&str> = header.split(',').map(|c| c.trim()).collect();
+        let continent_idx = columns.iter().position(|&c| c == "continent").ok_or("No continent column")?;
+        let x_idx = columns.iter().position(|&c| c == "x").ok_or("No x column")?;
+        let y_idx = columns.iter().position(|&c| c == "y").ok_or("No y column")?;
+        let count_idx = columns.iter().position(|&c| c == "count");
+
+        let mut heat = Self::default();
+        for line in lines {
+            let line = line?;
+            let fields: Vec<&str> = line.split(',').map(|f| f.trim().trim_matches('"')).collect();
+            if fields.len() <= continent_idx.max(x_idx).max(y_idx) {
+                continue;
+            }
+
+            let (Ok(x), Ok(y)) = (fields[x_idx].parse::<f32>(), fields[y_idx].parse::<f32>()) else {
+                continue;
+            };
+            let count = count_idx
+                .and_then(|i| fields.get(i))
+                .and_then(|c| c.parse().ok())
+                .unwrap_or(1);
+            let Some(pos) = world_to_chunk(x, y) else {
+                continue;
+            };
+
+            *heat
+                .visits
+                .entry(fields[continent_idx].to_ascii_lowercase())
+                .or_default()
+                .entry((pos.tile_key(), pos.chunk_index()))
+                .or_default() += count;
+        }
+
+        Ok(heat)
+    }
+
+    /// Total visits recorded on a tile
+    pub fn tile_heat(&self, grid_name: &str, tile_key: u32) -> u64 {
+        self.visits
+            .get(&grid_name.to_ascii_lowercase())
+            .map(|chunks| chunks.iter().filter(|((k, _), _)| *k == tile_key).map(|(_, n)| n).sum())
+            .unwrap_or(0)
+    }
+
+    /// Total visits per visited tile of a grid, in one pass
+    pub fn tile_heats(&self, grid_name: &str) -> BTreeMap<u32, u64> {
+        let mut heats = BTreeMap::new();
+        if let Some(chunks) = self.visits.get(&grid_name.to_ascii_lowercase()) {
+            for (&(tile_key, _), &n) in chunks {
+                *heats.entry(tile_key).or_default() += n;
+            }
+        }
+        heats
+    }
+
+    /// Tile keys ordered hottest first; ties and unvisited tiles keep key order
+    pub fn tiles_by_heat(&self, grid_name: &str, tile_keys: impl IntoIterator<Item = u32>) -> Vec<u32> {
+        let heats = self.tile_heats(grid_name);
+        let mut keys: Vec<u32> = tile_keys.into_iter().collect();
+        keys.sort_by_key(|&k| (std::cmp::Reverse(heats.get(&k).copied().unwrap_or(0)), k));
+        keys
+    }
+
+    /// The hottest tiles of a grid that between them hold `percent` of its
+    /// visits, hottest first
+    pub fn hot_tiles(&self, grid_name: &str, percent: f64) -> Vec<u32> {
+        let mut heats: Vec<(u32, u64)> = self.tile_heats(grid_name).into_iter().collect();
+        heats.sort_by_key(|&(k, n)| (std::cmp::Reverse(n), k));
+        let total: u64 = heats.iter().map(|&(_, n)| n).sum();
+        let mut hot = Vec::new();
+        let mut covered = 0;
+        for (key, n) in heats {
+            if covered as f64 >= total as f64 * percent / 100.0 {
+                break;
+            }
+            covered += n;
+            hot.push(key);
+        }
+        hot
+    }
+
+    /// How much of a grid's area set players actually visit
+    pub fn coverage(&self, grid: &ZoneGrid) -> Coverage {
+        let mut visits_per_area: BTreeMap<u32, u64> = BTreeMap::new();
+        if let Some(chunks) = self.visits.get(&grid.name.to_ascii_lowercase()) {
+            for (&(tile_key, chunk_idx), &n) in chunks {
+                if let Some(area_id) = grid.area_at_index(tile_key, chunk_idx) {
+                    *visits_per_area.entry(area_id).or_default() += n;
+                }
+            }
+        }
+
+        let visited: BTreeSet<u32> = visits_per_area.keys().copied().collect();
+        let unvisited = grid.area_ids().difference(&visited).copied().collect();
+        Coverage { visits_per_area, unvisited }
+    }
+}
+
+/// Visited-area coverage of one grid
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Coverage {
+    pub visits_per_area: BTreeMap<u32, u64>,
+    pub unvisited: BTreeSet<u32>,
+}
+
+impl Coverage {
+    /// Areas sorted by visits, busiest first
+    pub fn hottest(&self) -> Vec<(u32, u64)> {
+        let mut areas: Vec<(u32, u64)> = self.visits_per_area.iter().map(|(&a, &n)| (a, n)).collect();
+        areas.sort_by_key(|&(a, n)| (std::cmp::Reverse(n), a));
+        areas
+    }
+}
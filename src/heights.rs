@@ -0,0 +1,166 @@
+use crate::adt::{TileData, CHUNKS_PER_SIDE};
+use crate::json;
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+// ============================================================================
+// Height maps
+// ============================================================================
+//
+// Each chunk's MCVT holds a 9x9 grid of outer vertices (and 8x8 inner ones,
+// not used here). A height map splits every chunk into `resolution` x
+// `resolution` cells and averages the outer vertices in each, so a tile
+// becomes a (16 * resolution)^2 grid of heights in whole yards, row-major
+// across the tile: row `chunkY * resolution + cellY`, column
+// `chunkX * resolution + cellX`.
+
+/// Outer vertices along one side of a chunk
+pub const OUTER_VERTICES: usize = 9;
+
+/// Finest resolution: one cell per quad of outer vertices
+pub const MAX_RESOLUTION: usize = OUTER_VERTICES - 1;
+
+/// A tile's height grid, `resolution` cells per chunk side. Chunks without an
+/// MCVT take their base height.
+pub fn tile_heights(tile: &TileData, resolution: usize) -> Vec<i32> {
+    let side = CHUNKS_PER_SIDE * resolution;
+    let mut heights = vec![0; side * side];
+    for (i, chunk) in tile.chunks.iter().enumerate() {
+        let (chunk_x, chunk_y) = (i % CHUNKS_PER_SIDE, i / CHUNKS_PER_SIDE);
+        for (cell, height) in downsample(&chunk.heights, chunk.position[2], resolution).into_iter().enumerate() {
+            let (cell_x, cell_y) = (cell % resolution, cell / resolution);
+            heights[(chunk_y * resolution + cell_y) * side + chunk_x * resolution + cell_x] = height.round() as i32;
+        }
+    }
+    heights
+}
+
+/// Average the 9x9 `outer` heights (row-major, absolute) over
+/// `resolution` x `resolution` cells; `base` everywhere when there are none
+pub fn downsample(outer: &[f32], base: f32, resolution: usize) -> Vec<f32> {
+    if outer.len() != OUTER_VERTICES * OUTER_VERTICES {
+        return vec![base; resolution * resolution];
+    }
+    let span = |cell: usize| {
+        let first = cell * MAX_RESOLUTION / resolution;
+        let last = ((cell + 1) * MAX_RESOLUTION).div_ceil(resolution);
+        first..=last
+    };
+    let mut cells = Vec::with_capacity(resolution * resolution);
+    for cell_y in 0..resolution {
+        for cell_x in 0..resolution {
+            let (mut sum, mut n) = (0.0, 0);
+            for row in span(cell_y) {
+                for col in span(cell_x) {
+                    let h = outer[row * OUTER_VERTICES + col];
+                    if h.is_finite() {
+                        sum += h;
+                        n += 1;
+                    }
+                }
+            }
+            cells.push(if n > 0 { sum / n as f32 } else { base });
+        }
+    }
+    cells
+}
+
+// ============================================================================
+// Height map export
+// ============================================================================
+
+/// One map's height grids, written as `<Map>_heights.lua` or `.json`
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeightExport {
+    name: String,
+    resolution: usize,
+    /// Tile key -> (16 * resolution)^2 heights
+    tiles: BTreeMap<u32, Vec<i32>>,
+}
+
+impl HeightExport {
+    pub fn new(name: &str, resolution: usize) -> Self {
+        Self { name: name.to_string(), resolution, tiles: BTreeMap::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    /// Add tile `key`'s grid from `tile_heights`, replacing any it had
+    pub fn add(&mut self, key: u32, heights: Vec<i32>) -> Result<(), String> {
+        let side = CHUNKS_PER_SIDE * self.resolution;
+        if heights.len() != side * side {
+            return Err(format!("expected {} heights, got {}", side * side, heights.len()));
+        }
+        self.tiles.insert(key, heights);
+        Ok(())
+    }
+
+    /// Lowest and highest height over every tile
+    pub fn range(&self) -> Option<(i32, i32)> {
+        let all = self.tiles.values().flatten().copied();
+        Some((all.clone().min()?, all.max()?))
+    }
+
+    /// Write the grids for `addon:RegisterHeightMap`
+    pub fn write_lua(&self, out_path: &Path) -> std::io::Result<()> {
+        let mut f = BufWriter::new(File::create(out_path)?);
+        let side = CHUNKS_PER_SIDE * self.resolution;
+
+        writeln!(f, "-- Auto-generated height map for {}", self.name)?;
+        writeln!(f, "-- Each tile is {}x{} heights in yards ({} per chunk side), row-major.", side, side, self.resolution)?;
+        writeln!(f)?;
+        writeln!(f, "local _, addon = ...")?;
+        writeln!(f)?;
+        writeln!(f, "local tiles = {{")?;
+        for (key, heights) in &self.tiles {
+            let values: Vec<String> = heights.iter().map(|h| h.to_string()).collect();
+            writeln!(f, "  [{}] = {{{}}},", key, values.join(","))?;
+        }
+        writeln!(f, "}}")?;
+        writeln!(f)?;
+        writeln!(f, "addon:RegisterHeightMap(\"{}\", {{", self.name)?;
+        writeln!(f, "  resolution = {},", self.resolution)?;
+        if let Some((lo, hi)) = self.range() {
+            writeln!(f, "  minHeight = {},", lo)?;
+            writeln!(f, "  maxHeight = {},", hi)?;
+        }
+        writeln!(f, "  tiles = tiles,")?;
+        writeln!(f, "}})")?;
+        f.flush()
+    }
+
+    /// Write the grids as one JSON document, one array of rows per tile
+    pub fn write_json(&self, out_path: &Path) -> std::io::Result<()> {
+        let mut f = BufWriter::new(File::create(out_path)?);
+        let side = CHUNKS_PER_SIDE * self.resolution;
+
+        writeln!(f, "{{")?;
+        writeln!(f, "  \"format\": \"zonemap-heights\",")?;
+        writeln!(f, "  \"version\": 1,")?;
+        writeln!(f, "  \"name\": {},", json::string(&self.name))?;
+        writeln!(f, "  \"resolution\": {},", self.resolution)?;
+        if let Some((lo, hi)) = self.range() {
+            writeln!(f, "  \"minHeight\": {},", lo)?;
+            writeln!(f, "  \"maxHeight\": {},", hi)?;
+        }
+        writeln!(f, "  \"tiles\": [")?;
+        for (i, (key, heights)) in self.tiles.iter().enumerate() {
+            let rows: Vec<String> = heights.chunks(side).map(json::numbers).collect();
+            let comma = if i + 1 < self.tiles.len() { "," } else { "" };
+            writeln!(f, "    {{\"key\":{},\"x\":{},\"y\":{},\"heights\":[{}]}}{}", key, key % 64, key / 64, rows.join(","), comma)?;
+        }
+        writeln!(f, "  ]")?;
+        writeln!(f, "}}")?;
+        f.flush()
+    }
+}
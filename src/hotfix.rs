@@ -0,0 +1,195 @@
+use crate::adt::CHUNKS_PER_SIDE;
+use crate::diff::{ExportDiff, ExportSnapshot};
+use crate::grid::TILES_PER_SIDE;
+use crate::import::ChunkRow;
+use crate::version::DataVersion;
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+// ============================================================================
+// Hotfixes
+// ============================================================================
+//
+// A zone fix between releases shouldn't mean shipping every continent again.
+// `Hotfix.lua` holds only the chunks whose area ID differs from a shipped
+// release, taken from a diff against a newer export (or the ADTs) and from
+// `<Map>_edits.csv` files, and the addon applies them on top of the grids
+// it loaded:
+//
+//     addon:RegisterGridHotfix("1.2.0", {
+//       ["Azeroth"] = {
+//         [2080] = { [17] = 12, [18] = 12 },
+//       },
+//     })
+//
+// Keys are tile keys and chunk indices (`chunkY * 16 + chunkX`). The
+// version is the release the overrides are relative to; the addon ignores a
+// hotfix made for other data. Only chunks of shipped tiles can be
+// overridden: added or removed tiles and grids still need a release.
+
+pub const HOTFIX_FILE: &str = "Hotfix.lua";
+
+/// Tile key -> chunk index -> area ID
+pub type ChunkOverrides = BTreeMap<u32, BTreeMap<usize, u32>>;
+
+/// Chunk overrides on top of one release
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hotfix {
+    /// Release the overrides apply to
+    pub base: DataVersion,
+    /// Grid name -> overrides
+    pub grids: BTreeMap<String, ChunkOverrides>,
+}
+
+fn tile_coords(key: u32) -> String {
+    format!("{},{}", key % TILES_PER_SIDE, key / TILES_PER_SIDE)
+}
+
+impl Hotfix {
+    pub fn new(base: DataVersion) -> Self {
+        Self { base, grids: BTreeMap::new() }
+    }
+
+    /// Override every chunk `diff` changed. Returns what it changed that a
+    /// hotfix can't carry.
+    pub fn add_diff(&mut self, diff: &ExportDiff) -> Vec<String> {
+        let mut skipped: Vec<String> = Vec::new();
+        skipped.extend(diff.grids_added.iter().map(|grid| format!("{}: grid added", grid)));
+        skipped.extend(diff.grids_removed.iter().map(|grid| format!("{}: grid removed", grid)));
+        for (grid, changes) in &diff.grids {
+            skipped.extend(changes.tiles_added.iter().map(|&key| format!("{} {}: tile added", grid, tile_coords(key))));
+            skipped.extend(changes.tiles_removed.iter().map(|&key| format!("{} {}: tile removed", grid, tile_coords(key))));
+            for change in &changes.chunk_changes {
+                self.grids.entry(grid.clone()).or_default().entry(change.tile).or_default().insert(change.chunk, change.now);
+            }
+        }
+        skipped
+    }
+
+    /// Override the chunks listed in an edits file for grid `map`, after
+    /// anything added before. Edits that restore the shipped area ID drop
+    /// the chunk's override. Returns the edits a hotfix can't carry.
+    pub fn add_edits(&mut self, shipped: &ExportSnapshot, map: &str, rows: &[ChunkRow]) -> Vec<String> {
+        let Some(tiles) = shipped.grids.get(map) else {
+            return vec![format!("{}: no such grid in the shipped data", map)];
+        };
+        let mut skipped = Vec::new();
+        for &(tile_x, tile_y, chunk_x, chunk_y, area_id) in rows {
+            let key = tile_y * TILES_PER_SIDE + tile_x;
+            let chunk = chunk_y as usize * CHUNKS_PER_SIDE + chunk_x as usize;
+            let Some(shipped_id) = tiles.get(&key).and_then(|ids| ids.get(chunk)) else {
+                skipped.push(format!("{} {}: tile not shipped", map, tile_coords(key)));
+                continue;
+            };
+            let overrides = self.grids.entry(map.to_string()).or_default();
+            if *shipped_id == area_id {
+                if let Some(chunks) = overrides.get_mut(&key) {
+                    chunks.remove(&chunk);
+                    if chunks.is_empty() {
+                        overrides.remove(&key);
+                    }
+                }
+            } else {
+                overrides.entry(key).or_default().insert(chunk, area_id);
+            }
+        }
+        self.grids.retain(|_, overrides| !overrides.is_empty());
+        skipped
+    }
+
+    /// Chunks overridden, across grids
+    pub fn chunks(&self) -> usize {
+        self.grids.values().flat_map(|tiles| tiles.values()).map(|chunks| chunks.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks() == 0
+    }
+
+    /// Write `Hotfix.lua` for `addon:RegisterGridHotfix`
+    pub fn write_lua(&self, out_path: &Path) -> std::io::Result<()> {
+        let mut f = BufWriter::new(File::create(out_path)?);
+        writeln!(f, "-- Auto-generated area grid hotfix for ZoneMap data {}", self.base)?;
+        writeln!(f, "-- Chunk area IDs that differ from that release, by grid, tile key and chunk index (chunkY * 16 + chunkX).")?;
+        writeln!(f)?;
+        writeln!(f, "local _, addon = ...")?;
+        writeln!(f)?;
+        writeln!(f, "addon:RegisterGridHotfix(\"{}\", {{", self.base)?;
+        for (grid, tiles) in &self.grids {
+            writeln!(f, "  [\"{}\"] = {{", grid)?;
+            for (key, chunks) in tiles {
+                let chunks: Vec<String> = chunks.iter().map(|(chunk, area_id)| format!("[{}] = {}", chunk, area_id)).collect();
+                writeln!(f, "    [{}] = {{ {} }},", key, chunks.join(", "))?;
+            }
+            writeln!(f, "  }},")?;
+        }
+        writeln!(f, "}})")?;
+        f.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::{diff_exports, GridTiles};
+
+    /// A snapshot of one grid, `Test`, with every chunk of each tile in one area
+    fn snapshot(tiles: &[(u32, u32)]) -> ExportSnapshot {
+        let mut snapshot = ExportSnapshot::default();
+        let grid = snapshot.grids.entry("Test".to_string()).or_default();
+        for &(key, area_id) in tiles {
+            grid.insert(key, vec![area_id; CHUNKS_PER_SIDE * CHUNKS_PER_SIDE]);
+        }
+        snapshot
+    }
+
+    #[test]
+    fn diffs_override_changed_chunks_only() {
+        let shipped = snapshot(&[(2080, 12), (2081, 12)]);
+        let mut newer = snapshot(&[(2080, 12), (2082, 40)]);
+        newer.grids.get_mut("Test").unwrap().get_mut(&2080).unwrap()[17] = 40;
+        newer.grids.insert("Other".to_string(), GridTiles::new());
+
+        let mut hotfix = Hotfix::new(DataVersion::INITIAL);
+        let skipped = hotfix.add_diff(&diff_exports(&shipped, &newer));
+        assert_eq!(skipped, vec!["Other: grid added", "Test 34,32: tile added", "Test 33,32: tile removed"]);
+        assert_eq!(hotfix.chunks(), 1);
+        assert_eq!(hotfix.grids["Test"][&2080], BTreeMap::from([(17, 40)]));
+    }
+
+    #[test]
+    fn edits_override_and_restore_chunks() {
+        let shipped = snapshot(&[(2080, 12)]);
+        let mut hotfix = Hotfix::new(DataVersion::INITIAL);
+        // Tile (32, 32) is key 2080; chunk (1, 1) is index 17
+        let skipped = hotfix.add_edits(&shipped, "Test", &[(32, 32, 1, 1, 40), (32, 32, 2, 1, 41), (5, 5, 0, 0, 40)]);
+        assert_eq!(skipped, vec!["Test 5,5: tile not shipped"]);
+        assert_eq!(hotfix.grids["Test"][&2080], BTreeMap::from([(17, 40), (18, 41)]));
+
+        // Putting the shipped IDs back leaves nothing to override
+        hotfix.add_edits(&shipped, "Test", &[(32, 32, 1, 1, 12), (32, 32, 2, 1, 12)]);
+        assert!(hotfix.is_empty() && hotfix.grids.is_empty());
+
+        assert_eq!(hotfix.add_edits(&shipped, "Kalimdor", &[]), vec!["Kalimdor: no such grid in the shipped data"]);
+    }
+
+    #[test]
+    fn lua_registers_the_overrides_for_the_base_release() {
+        let dir = std::env::temp_dir().join(format!("zonemap-hotfix-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut hotfix = Hotfix::new("1.2.0".parse().unwrap());
+        hotfix.add_edits(&snapshot(&[(2080, 12)]), "Test", &[(32, 32, 1, 1, 40), (32, 32, 2, 1, 40)]);
+        let path = dir.join(HOTFIX_FILE);
+        hotfix.write_lua(&path).unwrap();
+        let lua = std::fs::read_to_string(&path).unwrap();
+        assert!(lua.contains("addon:RegisterGridHotfix(\"1.2.0\", {\n  [\"Test\"] = {\n    [2080] = { [17] = 40, [18] = 40 },\n  },\n})"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,188 @@
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+// ============================================================================
+// Ignore list
+// ============================================================================
+
+/// One ignore rule: a map name glob and optional tile ranges
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct IgnoreRule {
+    map: String,
+    /// `None` means the whole map is ignored
+    tiles: Option<(RangeInclusive<u32>, RangeInclusive<u32>)>,
+}
+
+/// Maps and tile ranges to leave out of discovery.
+///
+/// One rule per line, `#` starts a comment:
+/// ```text
+/// development*          # every map whose name starts with "development"
+/// Azeroth 30-35 40-45   # tiles x 30..=35, y 40..=45 of Azeroth
+/// Kalimdor * 12         # every x in row 12
+/// ```
+/// Map names match case-insensitively; `*` and `?` are wildcards.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IgnoreList {
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreList {
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut rules = Vec::new();
+
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let range = |s: &str| parse_range(s).map_err(|e| format!("line {}: bad tile range `{}`: {}", line_no + 1, s, e));
+            let tiles = match parts.len() {
+                1 => None,
+                3 => Some((range(parts[1])?, range(parts[2])?)),
+                _ => {
+                    return Err(format!("line {}: expected `<map>` or `<map> <x> <y>`, got `{}`", line_no + 1, line).into())
+                }
+            };
+            rules.push(IgnoreRule { map: parts[0].to_ascii_lowercase(), tiles });
+        }
+
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Also ignore tiles `xs` by `ys` of maps matching `map`
+    pub fn add_tiles(&mut self, map: &str, xs: RangeInclusive<u32>, ys: RangeInclusive<u32>) {
+        self.rules.push(IgnoreRule { map: map.to_ascii_lowercase(), tiles: Some((xs, ys)) });
+    }
+
+    /// True when a rule drops the whole map
+    pub fn ignores_map(&self, map: &str) -> bool {
+        let map = map.to_ascii_lowercase();
+        self.rules.iter().any(|r| r.tiles.is_none() && glob_match(&r.map, &map))
+    }
+
+    pub fn ignores_tile(&self, map: &str, x: u32, y: u32) -> bool {
+        let map = map.to_ascii_lowercase();
+        self.rules.iter().any(|r| {
+            glob_match(&r.map, &map)
+                && match &r.tiles {
+                    None => true,
+                    Some((xs, ys)) => xs.contains(&x) && ys.contains(&y),
+                }
+        })
+    }
+}
+
+/// `N`, `N-M` or `*`
+fn parse_range(s: &str) -> Result<RangeInclusive<u32>, Box<dyn std::error::Error>> {
+    if s == "*" {
+        return Ok(0..=u32::MAX);
+    }
+    match s.split_once('-') {
+        Some((a, b)) => Ok(a.parse()?..=b.parse()?),
+        None => {
+            let n = s.parse()?;
+            Ok(n..=n)
+        }
+    }
+}
+
+/// Glob match supporting `*` (any run) and `?` (any single character)
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_and_tile_ranges_are_ignored() {
+        let ignore = IgnoreList::parse(
+            "development*          # every map whose name starts with \"development\"\n\
+             \n\
+             Azeroth 30-35 40-45   # tiles x 30..=35, y 40..=45 of Azeroth\n\
+             Kalimdor * 12\n\
+             PVPZone0? 5 5\n",
+        )
+        .unwrap();
+        assert!(ignore.ignores_map("Development") && ignore.ignores_map("development_test"));
+        assert!(ignore.ignores_tile("DEVELOPMENT2", 0, 0));
+        assert!(!ignore.ignores_map("Azeroth"));
+
+        assert!(ignore.ignores_tile("azeroth", 30, 40) && ignore.ignores_tile("Azeroth", 35, 45));
+        assert!(!ignore.ignores_tile("Azeroth", 36, 40) && !ignore.ignores_tile("Azeroth", 30, 39));
+        assert!(ignore.ignores_tile("Kalimdor", 0, 12) && ignore.ignores_tile("Kalimdor", 63, 12));
+        assert!(!ignore.ignores_tile("Kalimdor", 0, 13));
+        assert!(ignore.ignores_tile("PVPZone03", 5, 5) && !ignore.ignores_tile("PVPZone10", 5, 5));
+        assert!(!ignore.ignores_tile("Northrend", 30, 40));
+    }
+
+    #[test]
+    fn added_tiles_join_the_rules() {
+        let mut ignore = IgnoreList::parse("# nothing yet\n").unwrap();
+        assert!(ignore.is_empty());
+        ignore.add_tiles("Expansion01", 10..=11, 20..=20);
+        assert!(!ignore.is_empty() && !ignore.ignores_map("Expansion01"));
+        assert!(ignore.ignores_tile("expansion01", 11, 20) && !ignore.ignores_tile("Expansion01", 11, 21));
+    }
+
+    #[test]
+    fn malformed_rules_are_errors() {
+        let error = |text: &str| IgnoreList::parse(text).unwrap_err().to_string();
+        assert_eq!(error("Azeroth 30\n"), "line 1: expected `<map>` or `<map> <x> <y>`, got `Azeroth 30`");
+        assert_eq!(error("\nAzeroth 1 2 3\n"), "line 2: expected `<map>` or `<map> <x> <y>`, got `Azeroth 1 2 3`");
+        assert!(error("Azeroth 30-x 1\n").starts_with("line 1: bad tile range `30-x`"));
+        assert!(error("Azeroth 1 -4\n").starts_with("line 1: bad tile range `-4`"));
+    }
+
+    #[test]
+    fn globs_match_whole_names() {
+        for (pattern, text, matches) in [
+            ("*", "", true),
+            ("a*c", "abbbc", true),
+            ("a*c", "abcd", false),
+            ("*b*", "abc", true),
+            ("a?c", "abc", true),
+            ("a?c", "ac", false),
+            ("azeroth", "azeroth", true),
+            ("azeroth", "azeroth2", false),
+            ("", "a", false),
+        ] {
+            assert_eq!(glob_match(pattern, text), matches, "{} vs {}", pattern, text);
+        }
+    }
+}
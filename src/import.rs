@@ -0,0 +1,295 @@
+use crate::adt::{CHUNKS_PER_SIDE, CHUNKS_PER_TILE};
+use crate::grid::TILES_PER_SIDE;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+// ============================================================================
+// Third-party area grid importers
+// ============================================================================
+//
+// Each importer returns tiles keyed by `tile_y * 64 + tile_x`, 256 row-major
+// area IDs per tile, the same shape the ADT path produces.
+
+/// Area IDs per tile keyed by tile key
+pub type ImportedTiles = HashMap<u32, Vec<u32>>;
+
+/// `MAP_AREA_NO_AREA`: the whole tile is one area, stored in the header
+const TC_AREA_NO_AREA: u16 = 0x0001;
+
+/// Read every `<map:03><y:02><x:02>.map` file for `map_id` from a
+/// TrinityCore/MaNGOS `maps/` directory.
+pub fn import_trinity_maps(dir: &Path, map_id: u32) -> Result<ImportedTiles, Box<dyn std::error::Error>> {
+    let prefix = format!("{:03}", map_id);
+    let mut tiles = HashMap::new();
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some((file_map, tile_x, tile_y)) = parse_trinity_map_filename(&path) else {
+            continue;
+        };
+        if file_map != prefix {
+            continue;
+        }
+        let data = fs::read(&path)?;
+        let area_ids = parse_trinity_map(&data).map_err(|e| format!("{}: {}", path.display(), e))?;
+        tiles.insert(tile_y * TILES_PER_SIDE + tile_x, area_ids);
+    }
+
+    Ok(tiles)
+}
+
+/// `0010203.map` -> ("001", 3, 2): the extractor writes y before x
+fn parse_trinity_map_filename(path: &Path) -> Option<(String, u32, u32)> {
+    if !path.extension()?.to_str()?.eq_ignore_ascii_case("map") {
+        return None;
+    }
+    let stem = path.file_stem()?.to_str()?;
+    if stem.len() != 7 || !stem.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let tile_y: u32 = stem[3..5].parse().ok()?;
+    let tile_x: u32 = stem[5..7].parse().ok()?;
+    if tile_x >= TILES_PER_SIDE || tile_y >= TILES_PER_SIDE {
+        return None;
+    }
+    Some((stem[..3].to_string(), tile_x, tile_y))
+}
+
+/// Area section of one `.map` file.
+///
+/// TrinityCore puts a build number after the version magic and older cores
+/// don't, so the area offset is tried at both positions.
+pub fn parse_trinity_map(data: &[u8]) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+    if data.get(0..4) != Some(b"MAPS".as_slice()) {
+        return Err("not a .map file (missing MAPS magic)".into());
+    }
+
+    let area_offset = [12usize, 8]
+        .into_iter()
+        .filter_map(|at| read_u32(data, at))
+        .map(|off| off as usize)
+        .find(|&off| data.get(off..off + 4) == Some(b"AREA".as_slice()))
+        .ok_or("no AREA section")?;
+
+    let flags = read_u16(data, area_offset + 4).ok_or("truncated AREA header")?;
+    let grid_area = read_u16(data, area_offset + 6).ok_or("truncated AREA header")?;
+
+    if flags & TC_AREA_NO_AREA != 0 {
+        return Ok(vec![grid_area as u32; CHUNKS_PER_TILE]);
+    }
+
+    let body = area_offset + 8;
+    (0..CHUNKS_PER_TILE)
+        .map(|i| read_u16(data, body + i * 2).map(|v| v as u32))
+        .collect::<Option<Vec<u32>>>()
+        .ok_or_else(|| "truncated area grid".into())
+}
+
+/// One row of a per-chunk CSV: tile x/y, chunk x/y, area ID
+pub type ChunkRow = (u32, u32, u32, u32, u32);
+
+/// Read a per-chunk CSV dump with `tile_x`, `tile_y`, `chunk_x`, `chunk_y`
+/// and `area_id` columns. Chunks that aren't listed stay 0.
+pub fn import_chunk_csv(path: &Path) -> Result<ImportedTiles, Box<dyn std::error::Error>> {
+    let mut tiles: ImportedTiles = HashMap::new();
+    for (tile_x, tile_y, chunk_x, chunk_y, area_id) in read_chunk_rows(path)? {
+        let tile = tiles
+            .entry(tile_y * TILES_PER_SIDE + tile_x)
+            .or_insert_with(|| vec![0; CHUNKS_PER_TILE]);
+        tile[chunk_y as usize * CHUNKS_PER_SIDE + chunk_x as usize] = area_id;
+    }
+    Ok(tiles)
+}
+
+/// Rows of a per-chunk CSV, range-checked
+pub fn read_chunk_rows(path: &Path) -> Result<Vec<ChunkRow>, Box<dyn std::error::Error>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut lines = reader.lines();
+
+    let header = lines.next().ok_or("empty CSV")??;
+    let columns: Vec<&str> = header.split(',').map(|c| c.trim()).collect();
+    let col = |name: &str| {
+        columns
+            .iter()
+            .position(|c| c.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("missing column `{}`", name))
+    };
+    let (tx_col, ty_col, cx_col, cy_col, area_col) =
+        (col("tile_x")?, col("tile_y")?, col("chunk_x")?, col("chunk_y")?, col("area_id")?);
+
+    let mut rows = Vec::new();
+    for (line_no, line) in lines.enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+        let field = |i: usize| -> Result<u32, String> {
+            fields
+                .get(i)
+                .and_then(|f| f.parse().ok())
+                .ok_or_else(|| format!("line {}: bad or missing value in column {}", line_no + 2, i + 1))
+        };
+        let (tile_x, tile_y, chunk_x, chunk_y) = (field(tx_col)?, field(ty_col)?, field(cx_col)?, field(cy_col)?);
+        if tile_x >= TILES_PER_SIDE
+            || tile_y >= TILES_PER_SIDE
+            || chunk_x as usize >= CHUNKS_PER_SIDE
+            || chunk_y as usize >= CHUNKS_PER_SIDE
+        {
+            return Err(format!("line {}: tile or chunk out of range", line_no + 2).into());
+        }
+        rows.push((tile_x, tile_y, chunk_x, chunk_y, field(area_col)?));
+    }
+
+    Ok(rows)
+}
+
+fn read_u16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// An empty scratch directory under the system temp dir
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("zonemap-import-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A `.map` file with an AREA section at `area_offset`, its offset
+    /// stored at `offset_at` (12 for TrinityCore, 8 for older cores)
+    fn map_file(offset_at: usize, flags: u16, grid_area: u16, area_ids: &[u16]) -> Vec<u8> {
+        let area_offset = 40;
+        let mut data = vec![0u8; area_offset];
+        data[0..4].copy_from_slice(b"MAPS");
+        data[4..8].copy_from_slice(b"v1.9");
+        data[offset_at..offset_at + 4].copy_from_slice(&(area_offset as u32).to_le_bytes());
+        data.extend_from_slice(b"AREA");
+        data.extend_from_slice(&flags.to_le_bytes());
+        data.extend_from_slice(&grid_area.to_le_bytes());
+        for id in area_ids {
+            data.extend_from_slice(&id.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn trinity_area_sections_are_read_at_either_offset() {
+        let ids: Vec<u16> = (0..256).collect();
+        let expected: Vec<u32> = (0..256).collect();
+        assert_eq!(parse_trinity_map(&map_file(12, 0, 0, &ids)).unwrap(), expected);
+        assert_eq!(parse_trinity_map(&map_file(8, 0, 0, &ids)).unwrap(), expected);
+        // One area for the whole tile, with no grid after the header
+        assert_eq!(parse_trinity_map(&map_file(12, TC_AREA_NO_AREA, 1519, &[])).unwrap(), vec![1519; 256]);
+    }
+
+    #[test]
+    fn malformed_map_files_are_errors() {
+        let ids = [12u16; 256];
+        let good = map_file(12, 0, 0, &ids);
+        let mut bad_magic = good.clone();
+        bad_magic[0] = b'X';
+        let mut bad_offset = good.clone();
+        bad_offset[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        for (data, expected) in [
+            (&b""[..], "not a .map file (missing MAPS magic)"),
+            (&bad_magic, "not a .map file (missing MAPS magic)"),
+            (&bad_offset, "no AREA section"),
+            (&good[..10], "no AREA section"),
+            (&good[..46], "truncated AREA header"),
+            (&good[..good.len() - 1], "truncated area grid"),
+        ] {
+            assert_eq!(parse_trinity_map(data).unwrap_err().to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn map_file_names_give_map_and_tile() {
+        let parse = |name: &str| parse_trinity_map_filename(Path::new(name));
+        assert_eq!(parse("0014832.map"), Some(("001".to_string(), 32, 48)));
+        assert_eq!(parse("5300102.MAP"), Some(("530".to_string(), 2, 1)));
+        for name in ["0016400.map", "0010064.map", "001483.map", "00148320.map", "001a832.map", "0014832.vmap", "0014832"] {
+            assert_eq!(parse(name), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn trinity_directories_import_one_map() {
+        let dir = scratch("trinity");
+        fs::write(dir.join("0004832.map"), map_file(12, TC_AREA_NO_AREA, 12, &[])).unwrap();
+        fs::write(dir.join("0004833.map"), map_file(8, TC_AREA_NO_AREA, 40, &[])).unwrap();
+        fs::write(dir.join("0014832.map"), map_file(12, TC_AREA_NO_AREA, 14, &[])).unwrap();
+        fs::write(dir.join("0004832.vmtile"), b"not a map").unwrap();
+
+        let tiles = import_trinity_maps(&dir, 0).unwrap();
+        assert_eq!(tiles.len(), 2);
+        assert_eq!(tiles[&(48 * 64 + 32)], vec![12; 256]);
+        assert_eq!(tiles[&(48 * 64 + 33)], vec![40; 256]);
+
+        // A broken file for the map fails the import and names the file
+        fs::write(dir.join("0004834.map"), b"MAPS").unwrap();
+        let err = import_trinity_maps(&dir, 0).unwrap_err().to_string();
+        assert!(err.contains("0004834.map") && err.ends_with("no AREA section"), "{}", err);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn chunk_csvs_round_trip() {
+        let dir = scratch("csv");
+        let path = dir.join("chunks.csv");
+        let mut tiles: ImportedTiles = HashMap::new();
+        tiles.insert(48 * 64 + 32, (0..256).map(|i| 1 + i % 3).collect());
+        tiles.insert(30 * 64 + 40, (0..256).map(|i| if i < 128 { 12 } else { 40 }).collect());
+
+        // Columns in another order, with spaces and a blank line
+        let mut csv = String::from("area_id, chunk_y, chunk_x, tile_y, tile_x\n");
+        for (key, ids) in &tiles {
+            for (i, id) in ids.iter().enumerate() {
+                csv += &format!("{}, {}, {}, {}, {}\n", id, i / 16, i % 16, key / 64, key % 64);
+            }
+        }
+        csv += "\n";
+        fs::write(&path, csv).unwrap();
+        assert_eq!(import_chunk_csv(&path).unwrap(), tiles);
+
+        // Chunks that aren't listed stay 0
+        fs::write(&path, "tile_x,tile_y,chunk_x,chunk_y,area_id\n1,2,3,4,5\n").unwrap();
+        let sparse = import_chunk_csv(&path).unwrap();
+        assert_eq!(sparse[&(2 * 64 + 1)].iter().filter(|&&id| id != 0).count(), 1);
+        assert_eq!(sparse[&(2 * 64 + 1)][4 * 16 + 3], 5);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn malformed_chunk_csvs_are_errors() {
+        let dir = scratch("bad-csv");
+        let path = dir.join("chunks.csv");
+        let header = "tile_x,tile_y,chunk_x,chunk_y,area_id\n";
+        for (csv, expected) in [
+            (String::new(), "empty CSV".to_string()),
+            ("tile_x,tile_y,chunk_x,area_id\n".to_string(), "missing column `chunk_y`".to_string()),
+            (format!("{}32,48,0,0,12\n32,48,x,0,12\n", header), "line 3: bad or missing value in column 3".to_string()),
+            (format!("{}32,48,0,0\n", header), "line 2: bad or missing value in column 5".to_string()),
+            (format!("{}32,48,0,0,-1\n", header), "line 2: bad or missing value in column 5".to_string()),
+            (format!("{}64,48,0,0,12\n", header), "line 2: tile or chunk out of range".to_string()),
+            (format!("{}32,48,16,0,12\n", header), "line 2: tile or chunk out of range".to_string()),
+        ] {
+            fs::write(&path, &csv).unwrap();
+            assert_eq!(read_chunk_rows(&path).unwrap_err().to_string(), expected, "{:?}", csv);
+        }
+        assert!(read_chunk_rows(&dir.join("missing.csv")).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
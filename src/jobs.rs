@@ -0,0 +1,117 @@
+use crate::envfile::PREFIX;
+use crate::toml::{self, Table, Value};
+
+use std::fs;
+use std::path::Path;
+
+// ============================================================================
+// Job files
+// ============================================================================
+//
+// A `zonemap.toml` describes a whole data build as generation jobs, so one
+// `generate --config zonemap.toml` reproduces a release:
+//
+//     # Settings for every job
+//     out = "Data"
+//     format = "both"
+//     compression = "deflate"
+//
+//     [[job]]
+//     name = "continents"
+//     maps = ["continents"]
+//     maps_root = "/games/wow/Data"
+//     heights = 2
+//
+//     [[job]]
+//     name = "outland"
+//     input = "adts/Expansion01"
+//     continent = "Expansion01"
+//     outlines = true
+//
+// Keys are `generate` options without the dashes (`maps_root` or
+// `maps-root`); a job's own settings win over the top-level ones. Each
+// setting becomes the option's `ZONEMAP_` variable for the job, so the
+// command line, then the environment, still override the file. Arrays are
+// joined with commas, for `maps`. Without any `[[job]]` the top level is the
+// one job.
+
+/// One `generate` run from a job file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Job {
+    pub name: String,
+    /// Environment variable and value for each setting, in file order
+    pub settings: Vec<(String, String)>,
+}
+
+/// The `ZONEMAP_` variable for setting `key`
+pub fn setting_var(key: &str) -> String {
+    format!("{}{}", PREFIX, key.replace('-', "_").to_ascii_uppercase())
+}
+
+fn setting_value(key: &str, value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Integer(n) => Ok(n.to_string()),
+        Value::Float(n) => Ok(n.to_string()),
+        Value::Boolean(b) => Ok(b.to_string()),
+        Value::Array(items) => {
+            let items: Result<Vec<String>, String> = items
+                .iter()
+                .map(|item| match item {
+                    Value::Array(_) | Value::Table(_) => Err(format!("`{}` can't hold a nested {}", key, item.type_name())),
+                    _ => setting_value(key, item),
+                })
+                .collect();
+            Ok(items?.join(","))
+        }
+        Value::Table(_) => Err(format!("`{}` is a table; only `[[job]]` tables are read", key)),
+    }
+}
+
+/// Settings of `table`, skipping the `job` array and a job's `name`
+fn settings(table: &Table) -> Result<Vec<(String, String)>, String> {
+    table
+        .iter()
+        .filter(|(key, _)| key != "job" && key != "name")
+        .map(|(key, value)| Ok((setting_var(key), setting_value(key, value)?)))
+        .collect()
+}
+
+/// The jobs a job file describes
+pub fn parse_jobs(text: &str) -> Result<Vec<Job>, String> {
+    let root = toml::parse(text)?;
+    if toml::get(&root, "name").is_some() {
+        return Err("`name` belongs in a `[[job]]`".to_string());
+    }
+    let defaults = settings(&root)?;
+    let tables = match toml::get(&root, "job") {
+        None => return Ok(vec![Job { name: "job 1".to_string(), settings: defaults }]),
+        Some(Value::Array(tables)) => tables,
+        Some(_) => return Err("`job` must be written as `[[job]]` tables".to_string()),
+    };
+
+    let mut jobs: Vec<Job> = Vec::new();
+    for (i, table) in tables.iter().enumerate() {
+        let Value::Table(table) = table else {
+            return Err("`job` must be written as `[[job]]` tables".to_string());
+        };
+        let name = match toml::get(table, "name") {
+            Some(Value::String(name)) => name.clone(),
+            Some(other) => return Err(format!("job {}: `name` must be a string, not a {}", i + 1, other.type_name())),
+            None => format!("job {}", i + 1),
+        };
+        if jobs.iter().any(|j| j.name == name) {
+            return Err(format!("two jobs are named `{}`", name));
+        }
+        let own = settings(table).map_err(|e| format!("job {}: {}", name, e))?;
+        let mut merged: Vec<(String, String)> = defaults.iter().filter(|(var, _)| !own.iter().any(|(v, _)| v == var)).cloned().collect();
+        merged.extend(own);
+        jobs.push(Job { name, settings: merged });
+    }
+    Ok(jobs)
+}
+
+pub fn read_jobs(path: &Path) -> Result<Vec<Job>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    parse_jobs(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
@@ -0,0 +1,198 @@
+use crate::adt::TileData;
+
+// ============================================================================
+// Layer extraction
+// ============================================================================
+
+/// Turns a parsed tile into one u32 value per chunk (256, row-major).
+/// Implement this to add a new dataset alongside the area ID grid.
+pub trait LayerExtractor {
+    /// Short identifier used to select the layer, e.g. "areaid"
+    fn name(&self) -> &str;
+
+    fn extract(&self, tile: &TileData) -> Vec<u32>;
+}
+
+/// MCNK area IDs, the grid the addon draws zones from
+pub struct AreaIdLayer;
+
+impl LayerExtractor for AreaIdLayer {
+    fn name(&self) -> &str {
+        "areaid"
+    }
+
+    fn extract(&self, tile: &TileData) -> Vec<u32> {
+        tile.area_ids()
+    }
+}
+
+/// MCNK holes bitmap: 4x4 cells in the lower 16 bits, bit `y * 4 + x`
+/// (see `adt::is_hole`), with high-res holes folded in
+pub struct HolesLayer;
+
+impl LayerExtractor for HolesLayer {
+    fn name(&self) -> &str {
+        "holes"
+    }
+
+    fn extract(&self, tile: &TileData) -> Vec<u32> {
+        tile.chunks.iter().map(|c| c.holes).collect()
+    }
+}
+
+/// Liquid type per chunk, 0 for dry land
+pub struct LiquidLayer;
+
+impl LayerExtractor for LiquidLayer {
+    fn name(&self) -> &str {
+        "liquid"
+    }
+
+    fn extract(&self, tile: &TileData) -> Vec<u32> {
+        tile.chunks
+            .iter()
+            .map(|c| c.liquid.map(|l| l.liquid_type as u32).unwrap_or(0))
+            .collect()
+    }
+}
+
+/// Liquid depth per chunk in tenths of a yard: shallowest in the low 16 bits,
+/// deepest in the high 16 bits, 0 for dry land. Depth is the liquid surface
+/// minus the terrain under it, so a puddle over a slope reads e.g. 0..8 and
+/// open ocean several hundred.
+pub struct LiquidDepthLayer;
+
+impl LiquidDepthLayer {
+    /// Pack (shallowest, deepest) depths in yards
+    pub fn pack(min_depth: f32, max_depth: f32) -> u32 {
+        let tenths = |d: f32| (d.max(0.0) * 10.0).round().min(u16::MAX as f32) as u32;
+        tenths(min_depth) | (tenths(max_depth) << 16)
+    }
+
+    /// Inverse of `pack`, in yards
+    pub fn unpack(value: u32) -> (f32, f32) {
+        ((value & 0xFFFF) as f32 / 10.0, (value >> 16) as f32 / 10.0)
+    }
+}
+
+impl LayerExtractor for LiquidDepthLayer {
+    fn name(&self) -> &str {
+        "liquiddepth"
+    }
+
+    fn extract(&self, tile: &TileData) -> Vec<u32> {
+        tile.chunks
+            .iter()
+            .map(|c| match c.liquid {
+                Some(l) => {
+                    let (ground_lo, ground_hi) = c.terrain_height;
+                    Self::pack(l.min_height - ground_hi, l.max_height - ground_lo)
+                }
+                None => 0,
+            })
+            .collect()
+    }
+}
+
+/// Liquid surface height per chunk in whole yards, offset by `LEVEL_OFFSET`
+/// so it stays positive; 0 for dry land. Sea level reads 32768.
+pub struct LiquidLevelLayer;
+
+impl LiquidLevelLayer {
+    /// Added to the level so heights below zero pack as well
+    pub const LEVEL_OFFSET: i32 = 0x8000;
+
+    /// Pack a surface height in yards
+    pub fn pack(level: f32) -> u32 {
+        (level.round() as i32 + Self::LEVEL_OFFSET).clamp(1, u16::MAX as i32) as u32
+    }
+
+    /// Inverse of `pack`: the surface height, or `None` for dry land
+    pub fn unpack(value: u32) -> Option<f32> {
+        (value != 0).then(|| (value as i32 - Self::LEVEL_OFFSET) as f32)
+    }
+}
+
+impl LayerExtractor for LiquidLevelLayer {
+    fn name(&self) -> &str {
+        "liquidlevel"
+    }
+
+    fn extract(&self, tile: &TileData) -> Vec<u32> {
+        tile.chunks.iter().map(|c| c.liquid.map_or(0, |l| Self::pack(l.max_height))).collect()
+    }
+}
+
+/// 1 for chunks flagged impassable in their MCNK header (`McnkFlags::is_impassable`),
+/// 0 elsewhere
+pub struct ImpassableLayer;
+
+impl LayerExtractor for ImpassableLayer {
+    fn name(&self) -> &str {
+        "impassable"
+    }
+
+    fn extract(&self, tile: &TileData) -> Vec<u32> {
+        tile.chunks.iter().map(|c| c.mcnk_flags().is_impassable() as u32).collect()
+    }
+}
+
+// ============================================================================
+// Registry
+// ============================================================================
+
+/// Named set of extractors the generator can pick from
+pub struct LayerRegistry {
+    extractors: Vec<Box<dyn LayerExtractor>>,
+}
+
+impl LayerRegistry {
+    /// Registry without any layers
+    pub fn empty() -> Self {
+        Self { extractors: Vec::new() }
+    }
+
+    /// Add an extractor; replaces an existing one with the same name
+    pub fn register(&mut self, extractor: Box<dyn LayerExtractor>) {
+        self.extractors.retain(|e| e.name() != extractor.name());
+        self.extractors.push(extractor);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn LayerExtractor> {
+        self.extractors
+            .iter()
+            .find(|e| e.name() == name)
+            .map(|e| e.as_ref())
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.extractors.iter().map(|e| e.name()).collect()
+    }
+
+    /// Look up several layers by name, failing on the first unknown one
+    pub fn select(&self, names: &[&str]) -> Result<Vec<&dyn LayerExtractor>, String> {
+        names
+            .iter()
+            .map(|&name| {
+                self.get(name).ok_or_else(|| {
+                    format!("unknown layer '{}' (available: {})", name, self.names().join(", "))
+                })
+            })
+            .collect()
+    }
+}
+
+impl Default for LayerRegistry {
+    /// Registry with the built-in areaid, holes, liquid, liquiddepth,
+    /// liquidlevel and impassable layers
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register(Box::new(AreaIdLayer));
+        registry.register(Box::new(HolesLayer));
+        registry.register(Box::new(LiquidLayer));
+        registry.register(Box::new(LiquidDepthLayer));
+        registry.register(Box::new(LiquidLevelLayer));
+        registry.register(Box::new(ImpassableLayer));
+        registry
+    }
+}
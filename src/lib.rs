@@ -0,0 +1,124 @@
+//! ZoneMap library: parsing helpers shared by the generator binary and
+//! other tools that want ADT area data without shelling out.
+//!
+//! By default only the parser, grids and lookups are built; the generator's
+//! reports, notifications and job queue, its DBC, PNG, TOML, crash-report and
+//! snapshot-store modules, archive readers, scripting and signing are behind
+//! cargo features.
+//!
+//! Writing a map's grid file, as the generator does for an ADT directory:
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use zone_map::codec::{Compression, LayerCodecs};
+//! use zone_map::export::Exporter;
+//! use zone_map::layers::LayerRegistry;
+//! use zone_map::pipeline::{discover, EncodeStage, ExtractStage, ParseStage, WriteStage};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let registry = LayerRegistry::default();
+//! let layers = registry.select(&["areaid"])?;
+//! let codecs = LayerCodecs::parse(&["areaid=delta"])?;
+//! let mut exporter = Exporter::new("Kalimdor", &codecs, Compression::None);
+//! let summary = discover(Path::new("adts/Kalimdor"))?.parse().extract(&layers).encode(&codecs).write(&mut exporter);
+//! for error in &summary.errors {
+//!     eprintln!("{}", error);
+//! }
+//! exporter.write_lua(Path::new("Data/Kalimdor_tiles.lua"))?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Single files can be read with `adt::parse_adt_areaids`, other sources
+//! through `source::TileSource`, and finished grids looked up with
+//! `grid::ZoneGrid`.
+
+pub mod adt;
+#[cfg(feature = "dbc")]
+pub mod areatrigger;
+pub mod attributes;
+pub mod binary;
+pub mod budget;
+pub mod cache;
+#[cfg(feature = "casc")]
+pub mod casc;
+pub mod changelog;
+#[cfg(feature = "dbc")]
+pub mod dbc;
+pub mod checkpoint;
+pub mod codec;
+pub mod codecstats;
+#[cfg(feature = "crash")]
+pub mod crash;
+pub mod diff;
+pub mod entrance;
+pub mod envfile;
+pub mod evaluate;
+pub mod export;
+pub mod failure;
+pub mod gate;
+#[cfg(feature = "cli")]
+pub mod gif;
+#[cfg(feature = "dbc")]
+pub mod graveyard;
+pub mod grid;
+pub mod hash;
+pub mod heatmap;
+pub mod heights;
+pub mod hotfix;
+pub mod ignore;
+pub mod import;
+#[cfg(feature = "toml")]
+pub mod jobs;
+pub mod json;
+pub mod layers;
+pub mod listfile;
+pub mod log;
+pub mod manifest;
+pub mod matrix;
+#[cfg(feature = "mpq")]
+pub mod mpq;
+pub mod normalize;
+#[cfg(feature = "cli")]
+pub mod notify;
+pub mod outline;
+pub mod patch;
+pub mod pipeline;
+#[cfg(feature = "png")]
+pub mod png;
+pub mod profile;
+#[cfg(feature = "cli")]
+pub mod queue;
+#[cfg(feature = "cli")]
+pub mod render;
+#[cfg(feature = "cli")]
+pub mod report;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod sentinel;
+#[cfg(feature = "signing")]
+pub mod signing;
+pub mod simulate;
+pub mod source;
+pub mod splat;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "store")]
+pub mod store;
+pub mod theme;
+#[cfg(feature = "toml")]
+pub mod toml;
+pub mod triggers;
+#[cfg(feature = "trends")]
+pub mod trends;
+pub mod uimap;
+#[cfg(all(feature = "uring", target_os = "linux"))]
+pub mod uring;
+pub mod version;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod wdt;
+pub mod weather;
+pub mod world;
+#[cfg(feature = "crash")]
+pub mod zip;
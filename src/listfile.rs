@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+// ============================================================================
+// Listfiles
+// ============================================================================
+//
+// CASC names files by FileDataID only. Names come from the community
+// listfile, `<fdid>;<path>` per line, as published by wowdev/wow-listfile
+// (formerly wow.tools). Only map files matter here, the ADTs and WDTs under
+// `world/maps/`, so the other couple of million lines are dropped on load.
+//
+// `listfile update` fetches the latest release through `curl`, as webhooks
+// are posted, and only replaces the local copy once the download has been
+// read back and names map files: a failed or truncated download leaves the
+// old listfile in place.
+
+/// Latest community listfile
+pub const LISTFILE_URL: &str = "https://github.com/wowdev/wow-listfile/releases/latest/download/community-listfile.csv";
+/// Seconds the download may take; the full listfile is well over 100 MB
+const DOWNLOAD_TIMEOUT: u32 = 900;
+
+/// Listfile paths are lowercase with forward slashes; so are lookups
+pub fn normalize(path: &str) -> String {
+    path.replace('\\', "/").to_ascii_lowercase()
+}
+
+/// Map files of a listfile, by FileDataID and by path
+#[derive(Debug, Clone, Default)]
+pub struct Listfile {
+    by_path: HashMap<String, u32>,
+    by_id: HashMap<u32, String>,
+}
+
+impl Listfile {
+    /// ADTs and WDTs under `world/maps/` in listfile text
+    pub fn parse(text: &str) -> Self {
+        let mut listfile = Self::default();
+        for line in text.lines() {
+            let Some((fdid, path)) = line.trim().split_once(';') else {
+                continue;
+            };
+            let path = normalize(path);
+            if !(path.starts_with("world/maps/") && (path.ends_with(".adt") || path.ends_with(".wdt"))) {
+                continue;
+            }
+            let Ok(fdid) = fdid.parse() else {
+                continue;
+            };
+            listfile.by_id.insert(fdid, path.clone());
+            listfile.by_path.insert(path, fdid);
+        }
+        listfile
+    }
+
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("listfile {}: {}", path.display(), e))?;
+        Ok(Self::parse(&text))
+    }
+
+    /// Map files listed
+    pub fn len(&self) -> usize {
+        self.by_id.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_id.is_empty()
+    }
+
+    /// FileDataID of a listed path, in any case or slash style
+    pub fn file_data_id(&self, path: &str) -> Option<u32> {
+        self.by_path.get(&normalize(path)).copied()
+    }
+
+    /// Path of a listed FileDataID
+    pub fn path(&self, fdid: u32) -> Option<&str> {
+        self.by_id.get(&fdid).map(String::as_str)
+    }
+
+    /// Every listed path, unordered
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.by_path.keys().map(String::as_str)
+    }
+
+    /// FileDataIDs listed here and not in `old`, and the reverse
+    pub fn changes_from(&self, old: &Listfile) -> (usize, usize) {
+        let added = self.by_id.keys().filter(|id| !old.by_id.contains_key(id)).count();
+        let removed = old.by_id.keys().filter(|id| !self.by_id.contains_key(id)).count();
+        (added, removed)
+    }
+}
+
+/// Download the listfile at `url` to `path` with curl, replacing what is
+/// there only if the download names map files
+pub fn download(url: &str, path: &Path) -> Result<Listfile, String> {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+    let output = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location", "--max-time", &DOWNLOAD_TIMEOUT.to_string()])
+        .arg("--output")
+        .arg(&partial)
+        .arg(url)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("can't run curl: {}", e))?;
+    if !output.status.success() {
+        let _ = fs::remove_file(&partial);
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    let listfile = Listfile::read(&partial)?;
+    if listfile.is_empty() {
+        let _ = fs::remove_file(&partial);
+        return Err(format!("{} names no map files; keeping {}", url, path.display()));
+    }
+    fs::rename(&partial, path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(listfile)
+}
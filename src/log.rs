@@ -0,0 +1,203 @@
+use crate::json;
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// ============================================================================
+// Logging
+// ============================================================================
+//
+// Progress, warnings and errors from a run go through `info!`, `warn!` and
+// `error!`. As text they print exactly as before: progress on stdout, the
+// rest on stderr. As JSON every line becomes one object for a log collector,
+//
+//     {"ts":"2026-05-01T12:00:00.000Z","level":"info","msg":"Wrote: Data/Kalimdor.lua"}
+//
+// with the blank lines and indentation that lay out the text dropped.
+//
+// The last few hundred lines are also kept in memory for crash reports.
+
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// Lines `recent_lines` keeps
+const RECENT_LINES: usize = 300;
+
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Runs the printing of a text line, e.g. inside `ProgressBar::suspend`
+pub type Printer = Box<dyn Fn(&mut dyn FnMut()) + Send>;
+
+static PRINTER: Mutex<Option<Printer>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("unknown log format `{}` (expected text or json)", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    pub fn label(self) -> &'static str {
+        match self {
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error",
+        }
+    }
+}
+
+/// Switch every later log line to `format`
+pub fn set_format(format: LogFormat) {
+    JSON.store(format == LogFormat::Json, Ordering::Relaxed);
+}
+
+pub fn format() -> LogFormat {
+    if JSON.load(Ordering::Relaxed) {
+        LogFormat::Json
+    } else {
+        LogFormat::Text
+    }
+}
+
+/// Print every later text line through `printer`, so a progress bar on the
+/// terminal is cleared and redrawn around it; `None` prints directly again
+pub fn set_printer(printer: Option<Printer>) {
+    if let Ok(mut current) = PRINTER.lock() {
+        *current = printer;
+    }
+}
+
+/// Print one message; use the macros instead
+pub fn write(level: Level, args: fmt::Arguments) {
+    remember(level, &args.to_string());
+    match (format(), level) {
+        (LogFormat::Text, _) => {
+            let mut print = || {
+                if level == Level::Info {
+                    println!("{}", args);
+                } else {
+                    eprintln!("{}", args);
+                }
+            };
+            match PRINTER.lock().as_deref() {
+                Ok(Some(printer)) => printer(&mut print),
+                _ => print(),
+            }
+        }
+        (LogFormat::Json, _) => {
+            let message = args.to_string();
+            for line in message.lines().map(str::trim).filter(|l| !l.is_empty()) {
+                let record = json_line(SystemTime::now(), level, line);
+                if level == Level::Info {
+                    println!("{}", record);
+                } else {
+                    eprintln!("{}", record);
+                }
+            }
+        }
+    }
+}
+
+fn remember(level: Level, message: &str) {
+    let Ok(mut recent) = RECENT.lock() else {
+        return;
+    };
+    let now = timestamp(SystemTime::now());
+    for line in message.lines().filter(|l| !l.trim().is_empty()) {
+        if recent.len() == RECENT_LINES {
+            recent.pop_front();
+        }
+        recent.push_back(format!("{} {:<5} {}", now, level.label(), line));
+    }
+}
+
+/// The latest log lines, oldest first, each with its time and level
+pub fn recent_lines() -> Vec<String> {
+    // A panic while a line was being added leaves the lock poisoned, not
+    // the lines
+    match RECENT.try_lock() {
+        Ok(recent) => recent.iter().cloned().collect(),
+        Err(std::sync::TryLockError::Poisoned(e)) => e.into_inner().iter().cloned().collect(),
+        Err(std::sync::TryLockError::WouldBlock) => Vec::new(),
+    }
+}
+
+fn json_line(time: SystemTime, level: Level, message: &str) -> String {
+    format!(r#"{{"ts":"{}","level":"{}","msg":{}}}"#, timestamp(time), level.label(), json::string(message))
+}
+
+/// RFC 3339 UTC time with milliseconds
+fn timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, rest) = ((secs / 86_400) as i64, secs % 86_400);
+
+    // Days since 1970-01-01 to a civil date (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        rest / 3600,
+        rest / 60 % 60,
+        rest % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Progress, on stdout
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)*) => {
+        $crate::log::write($crate::log::Level::Info, format_args!($($arg)*))
+    };
+}
+
+/// Something skipped or suspicious that doesn't stop the run, on stderr
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        $crate::log::write($crate::log::Level::Warn, format_args!($($arg)*))
+    };
+}
+
+/// A step that failed, on stderr
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)*) => {
+        $crate::log::write($crate::log::Level::Error, format_args!($($arg)*))
+    };
+}
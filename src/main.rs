@@ -1,730 +1,2492 @@
-use wow_adt::Adt;
+use zone_map::adt::{parse_adt, parse_tile_bytes, read_area_ids_fast, AdtLayout, WmoPlacement, CHUNKS_PER_SIDE};
+use zone_map::cache::TileCache;
+use zone_map::changelog::{render_chunk_changes, render_changelog};
+use zone_map::checkpoint::{read_journal, Checkpoint, Record, TileRecord};
+use zone_map::codec::{Codec, Compression, LayerCodecs};
+use zone_map::codecstats::{compare_codecs, format_codec_table};
+use zone_map::dbc::Dbc;
+use zone_map::diff::{diff_exports, ExportSnapshot};
+use zone_map::binary::BINARY_EXTENSION;
+use zone_map::budget::{parse_size, Budget, BudgetFormat};
+use zone_map::export::{Exporter, JsonExporter, OutputFormat};
+use zone_map::evaluate::{evaluate, parse_samples_csv};
+use zone_map::failure::{FailureKind, FailureReport};
+use zone_map::gate::{RunMetrics, Threshold};
+use zone_map::grid::{MissingTilePolicy, ZoneGrid};
+use zone_map::hash::HashAlgorithm;
+use zone_map::heights::{tile_heights, HeightExport, MAX_RESOLUTION};
+use zone_map::heatmap::HeatMap;
+use zone_map::ignore::IgnoreList;
+use zone_map::jobs::read_jobs;
+use zone_map::hotfix::{Hotfix, HOTFIX_FILE};
+use zone_map::import::{import_chunk_csv, import_trinity_maps, read_chunk_rows, ImportedTiles};
+use zone_map::layers::{AreaIdLayer, LayerExtractor, LayerRegistry};
+use zone_map::listfile::{self, Listfile, LISTFILE_URL};
+use zone_map::log::{self, LogFormat};
+use zone_map::manifest::{known_map_id, parse_map_csv, parse_map_dbc, select_maps, Manifest, ManifestEntry, MapRecord};
+use zone_map::matrix::{TileMatrix, TileState};
+#[cfg(feature = "casc")]
+use zone_map::casc::CascSource;
+#[cfg(feature = "mpq")]
+use zone_map::mpq::MpqSource;
+use zone_map::normalize::{apply_normalize, plan_normalize};
+use zone_map::outline::{HoleHandling, OutlineOptions};
+use zone_map::notify::{post_webhook, send_email, webhook_payload, RunStatus};
+use zone_map::pipeline::areas::{add_map_neighbors, area_colors, load_area_table, load_attributes, load_theme, write_area_hierarchy, write_area_info, write_continent_areas, AreaInfo, NeighborGraph};
+use zone_map::pipeline::decoders::{write_decoders, AddonDecoders};
+use zone_map::pipeline::maps::{apply_heat, print_map_summary, report_coverage, write_binary_grid, write_heights, write_json_grid, write_lua_grid, write_outlines, write_subchunks, LuaGridOptions, MapOutputs, MapReport};
+use zone_map::pipeline::overlays::{load_ui_map_assignments, write_area_triggers, write_area_weather, write_dungeon_entrances, write_graveyards, write_map_to_area, write_zone_triggers};
+#[cfg(feature = "signing")]
+use zone_map::pipeline::publish::sign_export;
+#[cfg(feature = "sqlite")]
+use zone_map::pipeline::publish::write_sqlite;
+#[cfg(feature = "trends")]
+use zone_map::pipeline::publish::record_run;
+use zone_map::pipeline::publish::{patch_edited_adts, store_export, version_export};
+use zone_map::pipeline::TileFile;
+use zone_map::profile::{render_slowest, TileTiming};
+use zone_map::queue::{Job, WorkQueue};
+use zone_map::render::{gallery_html, render_compare, render_png, render_svg, rgb_bytes, Annotations, ChunkRaster, CompareMode, GalleryEntry, Palette, NO_AREA, UNCOLORED};
+use zone_map::report::{MapSection, RunReport};
+#[cfg(feature = "scripting")]
+use zone_map::script::ScriptExtractor;
+use zone_map::sentinel::{format_hits, SentinelGuard, SentinelHits};
+use zone_map::simulate::{parse_path_csv, simulate};
+use zone_map::source::{DirSource, FileRead, ReadLimits, TileSource};
+use zone_map::splat::{parse_splats_bytes, refine_tile};
+use zone_map::store::Store;
+#[cfg(feature = "trends")]
+use zone_map::trends::RunsDb;
+use zone_map::version::{read_version, VERSION_FILE};
+#[cfg(feature = "wasm")]
+use zone_map::wasm::WasmExtractor;
+use zone_map::wdt::Wdt;
+use zone_map::{crash, envfile, error, info, warn};
 
-use base64::{engine::general_purpose, Engine as _};
+use clap::builder::BoolishValueParser;
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
 
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
-use std::fs::{self, File};
-use std::io::{BufRead, BufReader, Cursor, Write};
+use std::fs;
 use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+/// Codec per layer as `layer=codec` (raw, palette, rle, delta) unless --codec
+/// says otherwise; unlisted layers are raw
+const DEFAULT_LAYER_CODECS: &[&str] = &["areaid=delta", "holes=rle", "liquid=rle", "liquidlevel=rle"];
+
+/// Client build the input CSVs were exported from, unless --client-build says otherwise
+const DEFAULT_CLIENT_BUILD: &str = "1.15.8.64907";
+
+/// Exit status when a quality gate fails (1 is a fatal error)
+const EXIT_GATE_FAILED: u8 = 2;
+
+/// Progress journals for `generate --resume`
+const CHECKPOINT_DIR: &str = "zonemap.checkpoint";
+/// Where files read are cached for the next run
+const CACHE_DIR: &str = "zonemap.cache";
+
+/// Exit status when `--verify` finds a written tile that doesn't decode to what was read
+const EXIT_VERIFY_FAILED: u8 = 3;
+/// Exit status when SIGINT or SIGTERM stops a run
+const EXIT_INTERRUPTED: u8 = 130;
+
+/// Set by the first SIGINT/SIGTERM; scans stop before their next file
+static STOP: AtomicBool = AtomicBool::new(false);
 
 // ============================================================================
-// Area Table parsing
+// ADT / Tile parsing
 // ============================================================================
 
-#[derive(Debug, Clone)]
-struct AreaInfo {
-    id: u32,
-    name: String,
-    parent_id: u32,
-    exploration_level: i32,
+/// Settings shared by every continent's export
+struct ExportOptions<'a> {
+    area_layer: &'a dyn LayerExtractor,
+    extra_layers: &'a [&'a dyn LayerExtractor],
+    codecs: &'a LayerCodecs,
+    refine_subchunks: bool,
+    ignore: &'a IgnoreList,
+    sentinels: &'a SentinelGuard,
+    /// Community listfile naming ADTs in a CASC install
+    listfile: &'a Path,
+    /// TACT keys for files a CASC install holds encrypted
+    tact_keys: Option<&'a Path>,
+    /// Locale folder of a client's `Data/` to read MPQs from, instead of the detected one
+    locale: Option<&'a str>,
+    /// Read only area IDs (no extra layers or sub-chunks)
+    fast: bool,
+    /// Applied to every tile payload before base64
+    compression: Compression,
+    /// Journals finished files so an interrupted run can resume
+    checkpoint: &'a Checkpoint,
+    /// Height map cells per chunk side, or `None` for no height maps
+    heights: Option<usize>,
+    /// Reuses what files read in earlier runs produced, when their bytes haven't changed
+    cache: Option<&'a TileCache>,
+    /// Caps on the files read ahead
+    limits: ReadLimits,
 }
 
-fn parse_area_table(csv_path: &Path) -> Result<HashMap<u32, AreaInfo>, Box<dyn std::error::Error>> {
-    let file = File::open(csv_path)?;
-    let reader = BufReader::new(file);
-    let mut areas = HashMap::new();
-    
-    let mut lines = reader.lines();
-    let header = lines.next().ok_or("Empty CSV")??;
-    
-    let columns: Vec<&str> = header.split(',').collect();
-    let id_idx = columns.iter().position(|&c| c == "ID").ok_or("No ID column")?;
-    let name_idx = columns.iter().position(|&c| c == "AreaName_lang").ok_or("No AreaName_lang column")?;
-    let parent_idx = columns.iter().position(|&c| c == "ParentAreaID").ok_or("No ParentAreaID column")?;
-    let level_idx = columns.iter().position(|&c| c == "ExplorationLevel").ok_or("No ExplorationLevel column")?;
-    
-    for line in lines {
-        let line = line?;
-        let fields: Vec<&str> = parse_csv_line(&line);
-        
-        if fields.len() <= id_idx.max(name_idx).max(parent_idx).max(level_idx) {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct TileGridExport {
+    continent_name: String,
+    /// The `<Map>_tiles.lua` being built
+    grid: Exporter,
+    /// The `<Map>_tiles.json` being built
+    json: JsonExporter,
+    tiles_raw: HashMap<u32, Vec<u32>>,
+    found_areas: BTreeSet<u32>,
+    /// Heuristic 8x8 sub-cell areas for border chunks: tile key -> chunk index -> 64 IDs
+    subchunks: BTreeMap<u32, BTreeMap<usize, Vec<u32>>>,
+    failures: FailureReport,
+    /// Sentinel area IDs replaced while building the grid
+    sentinel_hits: SentinelHits,
+    /// WMOs placed on the map's tiles, once per tile they overlap
+    wmos: Vec<WmoPlacement>,
+    /// Height grids, when --heights is given
+    heights: Option<HeightExport>,
+    /// Time spent on each ADT read in this run
+    timings: Vec<TileTiming>,
+}
+
+impl TileGridExport {
+    fn new(continent_name: &str, codecs: &LayerCodecs, compression: Compression) -> Self {
+        Self {
+            continent_name: continent_name.to_string(),
+            grid: Exporter::new(continent_name, codecs, compression),
+            json: JsonExporter::new(continent_name),
+            tiles_raw: HashMap::new(),
+            found_areas: BTreeSet::new(),
+            subchunks: BTreeMap::new(),
+            failures: FailureReport::default(),
+            sentinel_hits: SentinelHits::new(),
+            wmos: Vec::new(),
+            timings: Vec::new(),
+            heights: None,
+        }
+    }
+}
+
+fn is_casc_install(path: &Path) -> bool {
+    #[cfg(feature = "casc")]
+    return CascSource::is_install(path);
+    #[cfg(not(feature = "casc"))]
+    return path.join(".build.info").is_file() || path.join(".flavor.info").is_file();
+}
+
+#[cfg(feature = "casc")]
+fn open_casc(install: &Path, listfile: &Path, tact_keys: Option<&Path>) -> Result<Box<dyn TileSource>, Box<dyn std::error::Error>> {
+    info!("Opening: {} (listfile {})", install.display(), listfile.display());
+    Ok(Box::new(CascSource::open(install, listfile, tact_keys)?))
+}
+
+#[cfg(not(feature = "casc"))]
+fn open_casc(install: &Path, _listfile: &Path, _tact_keys: Option<&Path>) -> Result<Box<dyn TileSource>, Box<dyn std::error::Error>> {
+    Err(format!("{} is a client install; build with --features casc to read it", install.display()).into())
+}
+
+#[cfg(feature = "mpq")]
+fn open_mpq(data_dir: &Path, locale: Option<&str>, limits: ReadLimits) -> Result<Box<dyn TileSource>, Box<dyn std::error::Error>> {
+    Ok(Box::new(MpqSource::open(data_dir, locale, limits)?))
+}
+
+#[cfg(not(feature = "mpq"))]
+fn open_mpq(data_dir: &Path, _locale: Option<&str>, _limits: ReadLimits) -> Result<Box<dyn TileSource>, Box<dyn std::error::Error>> {
+    Err(format!("{} holds MPQ archives; build with --features mpq to read them", data_dir.display()).into())
+}
+
+/// Tiles from `source`: a chunk CSV, a client install (CASC), a client's
+/// `Data/` folder (MPQ), a TrinityCore `maps/` directory or an ADT directory.
+/// A missing source falls back to `maps/` or `<Map>_chunks.csv` in the
+/// working directory.
+fn load_continent(
+    source: &Path,
+    continent_name: &str,
+    map_id: u32,
+    options: &ExportOptions,
+) -> Result<TileGridExport, Box<dyn std::error::Error>> {
+    let is_csv = |p: &Path| p.extension().is_some_and(|e| e.eq_ignore_ascii_case("csv"));
+    let has_map_files = |dir: &Path| {
+        fs::read_dir(dir)
+            .map(|mut entries| entries.any(|e| e.is_ok_and(|e| e.path().extension().is_some_and(|x| x == "map"))))
+            .unwrap_or(false)
+    };
+
+    let has_mpq_files = |dir: &Path| {
+        fs::read_dir(dir)
+            .map(|mut entries| {
+                entries.any(|e| e.is_ok_and(|e| e.path().extension().is_some_and(|x| x.eq_ignore_ascii_case("mpq"))))
+            })
+            .unwrap_or(false)
+    };
+
+    let tiles = if source.is_file() && is_csv(source) {
+        info!("Importing: {}", source.display());
+        import_chunk_csv(source)?
+    } else if source.is_dir() && is_casc_install(source) {
+        return build_source_export(open_casc(source, options.listfile, options.tact_keys)?.as_mut(), continent_name, options);
+    } else if source.is_dir() && has_mpq_files(source) {
+        return build_source_export(open_mpq(source, options.locale, options.limits)?.as_mut(), continent_name, options);
+    } else if source.is_dir() && has_map_files(source) {
+        info!("Importing: {} (map {})", source.display(), map_id);
+        import_trinity_maps(source, map_id)?
+    } else if source.is_dir() {
+        let renames = plan_normalize(source, continent_name)?;
+        if !renames.is_empty() {
+            info!(
+                "{} files in {} need normalizing (`zone-map normalize {} --map {} --apply`), e.g.:",
+                renames.len(),
+                source.display(),
+                source.display(),
+                continent_name
+            );
+            for rename in renames.iter().take(5) {
+                info!("  {} -> {}", rename.from.display(), rename.to.display());
+            }
+        }
+        return build_source_export(&mut DirSource::open(source)?.with_limits(options.limits), continent_name, options);
+    } else {
+        let maps_dir = Path::new("maps");
+        let chunks_csv = format!("{}_chunks.csv", continent_name);
+        if maps_dir.is_dir() {
+            info!("Importing: {} (map {})", maps_dir.display(), map_id);
+            import_trinity_maps(maps_dir, map_id)?
+        } else if Path::new(&chunks_csv).exists() {
+            info!("Importing: {}", chunks_csv);
+            import_chunk_csv(Path::new(&chunks_csv))?
+        } else {
+            return Err(format!("Directory not found: {}", source.display()).into());
+        }
+    };
+
+    import_tile_export(continent_name, tiles, options)
+}
+
+fn import_tile_export(
+    continent_name: &str,
+    tiles: ImportedTiles,
+    options: &ExportOptions,
+) -> Result<TileGridExport, Box<dyn std::error::Error>> {
+    let mut export = TileGridExport::new(continent_name, options.codecs, options.compression);
+    let mut ignored = 0usize;
+
+    for (key, mut area_ids) in tiles {
+        if options.ignore.ignores_tile(continent_name, key % 64, key / 64) {
+            ignored += 1;
             continue;
         }
-        
-        let id: u32 = match fields[id_idx].parse() {
-            Ok(v) => v,
-            Err(_) => continue,
-        };
-        
-        let name = fields[name_idx].trim_matches('"').to_string();
-        let parent_id: u32 = fields[parent_idx].parse().unwrap_or(0);
-        let exploration_level: i32 = fields[level_idx].parse().unwrap_or(0);
-        
-        areas.insert(id, AreaInfo { id, name, parent_id, exploration_level });
+        options.sentinels.scrub(&mut area_ids, &mut export.sentinel_hits);
+        export.found_areas.extend(area_ids.iter().copied().filter(|&aid| aid != 0));
+        export.grid.add(key, "areaid", &area_ids)?;
+        export.json.add(key, "areaid", &area_ids)?;
+        export.tiles_raw.insert(key, area_ids);
     }
-    
-    Ok(areas)
+
+    if ignored > 0 {
+        info!("  Ignored {} tiles", ignored);
+    }
+    info!("  Imported {} tiles, found {} unique areas", export.tiles_raw.len(), export.found_areas.len());
+    print_sentinel_hits(&export);
+    Ok(export)
 }
 
-fn parse_csv_line(line: &str) -> Vec<&str> {
-    let mut fields = Vec::new();
-    let mut start = 0;
-    let mut in_quotes = false;
-    let bytes = line.as_bytes();
-    
-    for i in 0..bytes.len() {
-        match bytes[i] {
-            b'"' => in_quotes = !in_quotes,
-            b',' if !in_quotes => {
-                fields.push(&line[start..i]);
-                start = i + 1;
+fn print_sentinel_hits(export: &TileGridExport) {
+    if !export.sentinel_hits.is_empty() {
+        let total: usize = export.sentinel_hits.values().sum();
+        info!("  Replaced {} sentinel area IDs ({})", total, format_hits(&export.sentinel_hits));
+    }
+}
+
+/// Present/failed tiles of an export as a 64x64 matrix
+fn print_coverage_matrix(export: &TileGridExport) -> TileMatrix {
+    let mut matrix = TileMatrix::new();
+    for &key in export.tiles_raw.keys() {
+        matrix.mark(key % 64, key / 64, TileState::Present);
+    }
+    for (kind, files) in &export.failures.by_kind {
+        if *kind == FailureKind::SplitFile {
+            continue;
+        }
+        for (path, _) in files {
+            if let Some(file) = TileFile::from_path(path) {
+                matrix.mark(file.tile_x, file.tile_y, TileState::Failed);
             }
-            _ => {}
         }
     }
-    fields.push(&line[start..]);
-    fields
+
+    if let Some(wdt) = export.grid.wdt() {
+        matrix.expect(wdt.tiles.iter().copied());
+    }
+
+    info!(
+        "  Tile coverage ({} present, {} failed, {} missing):",
+        matrix.count(TileState::Present),
+        matrix.count(TileState::Failed),
+        matrix.count(TileState::Missing)
+    );
+    for line in matrix.render_text().lines() {
+        info!("  {}", line);
+    }
+    matrix
 }
 
-fn find_root_parent(area_id: u32, areas: &HashMap<u32, AreaInfo>) -> u32 {
-    let mut current = area_id;
-    let mut visited = BTreeSet::new();
-    
-    while let Some(area) = areas.get(&current) {
-        if area.parent_id == 0 || visited.contains(&current) {
-            return current;
+/// What one root ADT contributes to its map's export, or why it contributes
+/// nothing. `obj0` is the tile's `_obj0` on split layouts.
+fn read_adt_tile(path: &Path, tile_x: u32, tile_y: u32, data: &[u8], obj0: Option<&[u8]>, options: &ExportOptions) -> Record {
+    let parsed = if options.fast {
+        read_area_ids_fast(data).map(|ids| ids.map(|ids| (ids, None))).map_err(|e| e.into())
+    } else {
+        parse_tile_bytes(data, obj0).map(|tile| tile.map(|tile| (options.area_layer.extract(&tile), Some(tile))))
+    };
+    let (mut area_ids, tile) = match parsed {
+        Ok(Some(parsed)) => parsed,
+        Ok(None) => {
+            return Record::Failure {
+                kind: FailureKind::NoChunks,
+                path: path.to_path_buf(),
+                message: "no MCNK chunks".to_string(),
+            };
+        }
+        Err(e) => {
+            let kind = FailureKind::classify(path, e.as_ref());
+            return Record::Failure { kind, path: path.to_path_buf(), message: e.to_string() };
+        }
+    };
+
+    let mut sentinel_hits = SentinelHits::new();
+    options.sentinels.scrub(&mut area_ids, &mut sentinel_hits);
+    let mut record = TileRecord {
+        path: path.to_path_buf(),
+        key: tile_y * 64 + tile_x,
+        area_ids,
+        sentinel_hits,
+        layers: BTreeMap::new(),
+        subchunks: BTreeMap::new(),
+        wmos: Vec::new(),
+        heights: Vec::new(),
+    };
+
+    // The fast path decodes nothing but area IDs
+    let Some(tile) = tile else {
+        return Record::Tile(record);
+    };
+
+    for layer in options.extra_layers {
+        let values = layer.extract(&tile);
+        if values.iter().any(|&v| v != 0) {
+            record.layers.insert(layer.name().to_string(), values);
+        }
+    }
+
+    // Split tiles keep their textures in _tex0, which isn't read
+    if options.refine_subchunks && AdtLayout::detect(data) == AdtLayout::Monolithic {
+        match parse_splats_bytes(data) {
+            Ok(splats) => record.subchunks = refine_tile(&record.area_ids, &splats),
+            Err(e) => error!("  ERROR reading splats from {}: {}", path.display(), e),
+        }
+    }
+
+    if let Some(resolution) = options.heights {
+        record.heights = tile_heights(&tile, resolution);
+    }
+    record.wmos = tile.wmos;
+    Record::Tile(record)
+}
+
+/// Add a file's record, just read or replayed from a checkpoint, to
+/// `export`. Returns whether it produced a tile.
+fn add_record(export: &mut TileGridExport, record: &Record) -> Result<bool, Box<dyn std::error::Error>> {
+    let tile = match record {
+        Record::Tile(tile) => tile,
+        Record::Failure { kind, path, message } => {
+            export.failures.record(*kind, path, message.clone());
+            return Ok(false);
+        }
+    };
+
+    for (&sentinel, &hits) in &tile.sentinel_hits {
+        *export.sentinel_hits.entry(sentinel).or_default() += hits;
+    }
+    export.found_areas.extend(tile.area_ids.iter().copied().filter(|&aid| aid != 0));
+    export.grid.add(tile.key, "areaid", &tile.area_ids)?;
+    export.json.add(tile.key, "areaid", &tile.area_ids)?;
+    for (name, values) in &tile.layers {
+        export.grid.add(tile.key, name, values)?;
+        export.json.add(tile.key, name, values)?;
+    }
+    if !tile.subchunks.is_empty() {
+        export.subchunks.insert(tile.key, tile.subchunks.clone());
+    }
+    export.wmos.extend(tile.wmos.iter().cloned());
+    if let Some(heights) = &mut export.heights {
+        if !tile.heights.is_empty() {
+            heights.add(tile.key, tile.heights.clone())?;
+        }
+    }
+
+    export.tiles_raw.insert(tile.key, tile.area_ids.clone());
+    Ok(true)
+}
+
+/// Bar of a map's files handled out of `total`, on stderr. Hidden when
+/// stderr isn't a terminal or the log is JSON, so neither gets bar frames.
+/// Log lines print above it until `log::set_printer(None)`.
+fn tile_progress(continent_name: &str, total: usize, done: usize) -> ProgressBar {
+    if log::format() == LogFormat::Json {
+        return ProgressBar::hidden();
+    }
+    let bar = ProgressBar::with_draw_target(Some(total as u64), ProgressDrawTarget::stderr());
+    if let Ok(style) = ProgressStyle::with_template("  {prefix} [{bar:40}] {pos}/{len} tiles, {elapsed} ({eta} left)") {
+        bar.set_style(style.progress_chars("=> "));
+    }
+    bar.set_prefix(continent_name.to_string());
+    bar.set_position(done as u64);
+    let shown = bar.clone();
+    log::set_printer(Some(Box::new(move |print| shown.suspend(print))));
+    bar
+}
+
+fn print_scan_summary(export: &TileGridExport, parsed: usize, ignored: usize) {
+    if ignored > 0 {
+        info!("  Ignored {} tiles", ignored);
+    }
+    info!("  Parsed {} tiles, found {} unique areas", parsed, export.found_areas.len());
+    print_sentinel_hits(export);
+    if !export.failures.is_empty() {
+        info!("  {} files not parsed:", export.failures.total());
+        export.failures.print();
+    }
+}
+
+/// Root ADTs of one map from any tile source
+fn build_source_export(
+    source: &mut dyn TileSource,
+    continent_name: &str,
+    options: &ExportOptions,
+) -> Result<TileGridExport, Box<dyn std::error::Error>> {
+    let mut export = TileGridExport::new(continent_name, options.codecs, options.compression);
+    export.heights = options.heights.map(|resolution| HeightExport::new(continent_name, resolution));
+    if !options.fast {
+        for layer in options.extra_layers {
+            export.grid.add_layer(layer.name());
+            export.json.add_layer(layer.name());
+        }
+    }
+    info!("Scanning: {}", source.describe());
+
+    let mut parsed = 0usize;
+    let mut ignored = 0usize;
+
+    let files = source.adt_files(continent_name)?;
+
+    match source.wdt(continent_name) {
+        Ok(Some(data)) => match Wdt::parse(&data) {
+            Ok(wdt) => {
+                export.json.set_wdt(wdt.clone());
+                export.grid.set_wdt(wdt);
+            }
+            Err(e) => warn!("  WARNING: {}.wdt: {}", continent_name, e),
+        },
+        Ok(None) => info!("  No {}.wdt; taking the ADTs present as the map's tiles", continent_name),
+        Err(e) => warn!("  WARNING: {}.wdt: {}", continent_name, e),
+    }
+    if let Some(wdt) = export.grid.wdt() {
+        let listed: BTreeSet<u32> = files.iter().map(|f| f.key()).collect();
+        let absent: Vec<String> = wdt
+            .tiles
+            .iter()
+            .filter(|k| !listed.contains(k))
+            .map(|k| format!("{},{}", k % 64, k / 64))
+            .collect();
+        if !absent.is_empty() {
+            let shown = absent.iter().take(10).cloned().collect::<Vec<_>>().join("; ");
+            let more = if absent.len() > 10 { format!("; and {} more", absent.len() - 10) } else { String::new() };
+            info!("  WARNING: {} tiles in {}.wdt have no ADT: {}{}", absent.len(), continent_name, shown, more);
+        }
+        let unlisted = listed.iter().filter(|k| !wdt.tiles.contains(k)).count();
+        if unlisted > 0 {
+            info!("  WARNING: {} ADTs are not in {}.wdt", unlisted, continent_name);
+        }
+    }
+
+    let files: Vec<TileFile> = files
+        .into_iter()
+        .filter(|file| {
+            let keep = !options.ignore.ignores_tile(&file.map, file.tile_x, file.tile_y);
+            ignored += usize::from(!keep);
+            keep
+        })
+        .collect();
+
+    let obj0s: HashMap<PathBuf, PathBuf> = files
+        .iter()
+        .filter(|_| !options.fast)
+        .filter_map(|f| Some((f.path.clone(), source.split_sibling(&f.path, "_obj0")?)))
+        .collect();
+    let consumed: HashSet<&PathBuf> = obj0s.values().collect();
+    for path in source.split_files().iter().filter(|p| !consumed.contains(p)) {
+        export.failures.record(FailureKind::SplitFile, path, "skipped".to_string());
+    }
+
+    // Files finished before an interrupted run are replayed, not read again
+    let layer_names: Vec<&str> = options.extra_layers.iter().map(|l| l.name()).collect();
+    let settings = format!(
+        "fast={};subchunks={};layers={};wmos={};heights={:?};{:?}",
+        options.fast,
+        options.refine_subchunks,
+        layer_names.join(","),
+        !options.fast,
+        options.heights,
+        options.sentinels
+    );
+    let (done, mut journal) = options.checkpoint.open(continent_name, &settings)?;
+    let mut cache = options.cache.map(|c| c.open(continent_name, &settings));
+    let listed: HashSet<&Path> = files.iter().map(|f| f.path.as_path()).collect();
+    let mut replayed = HashSet::new();
+    for record in done.iter().filter(|r| listed.contains(r.path())) {
+        if add_record(&mut export, record)? {
+            parsed += 1;
+        }
+        replayed.insert(record.path().to_path_buf());
+    }
+    if !replayed.is_empty() {
+        info!("  Resumed {} files from {}", replayed.len(), options.checkpoint.dir().display());
+    }
+
+    let files: Vec<TileFile> = files.into_iter().filter(|f| !replayed.contains(&f.path)).collect();
+
+    // The WDT says how many tiles the map has; ADTs it doesn't list still count
+    let expected = export.grid.wdt().map_or(0, |wdt| {
+        wdt.tiles.iter().filter(|&&k| !options.ignore.ignores_tile(continent_name, k % 64, k / 64)).count()
+    });
+    let progress = tile_progress(continent_name, expected.max(files.len() + replayed.len()), replayed.len());
+
+    // Split tiles keep their WMOs in _obj0, read straight after the root so
+    // the two reach `read_adt_tile` together. The fast path needs neither.
+    let mut paths: Vec<PathBuf> = Vec::new();
+    let mut reads: Vec<(usize, bool)> = Vec::new();
+    for (index, file) in files.iter().enumerate() {
+        paths.push(file.path.clone());
+        reads.push((index, false));
+        if let Some(obj0) = obj0s.get(&file.path) {
+            paths.push(obj0.clone());
+            reads.push((index, true));
         }
-        visited.insert(current);
-        current = area.parent_id;
     }
-    area_id
+
+    let mut pending_root: Option<FileRead> = None;
+    let result = source.read_each(&paths, &mut |index, data| {
+        if stopping() {
+            return Err("interrupted".into());
+        }
+        let (file_index, is_obj0) = reads[index];
+        if !is_obj0 && reads.get(index + 1).is_some_and(|&(next, obj0)| next == file_index && obj0) {
+            pending_root = Some(data);
+            return Ok(());
+        }
+        let (data, obj0) = match (is_obj0, pending_root.take()) {
+            (true, Some(root)) => (root, Some(data)),
+            (true, None) => return Ok(()),
+            (false, _) => (data, None),
+        };
+        let obj0 = match obj0 {
+            Some(Ok(obj0)) => Some(obj0),
+            Some(Err(e)) => {
+                warn!("  WARNING: {}: {}", paths[index].display(), e);
+                None
+            }
+            None => None,
+        };
+        let file = &files[file_index];
+        progress.inc(1);
+        crash::set_context(format!("{} tile {},{} ({})", continent_name, file.tile_x, file.tile_y, file.path.display()));
+        let started = Instant::now();
+        let (record, bytes) = match data {
+            Ok(data) => {
+                let bytes = data.len() + obj0.as_ref().map_or(0, Vec::len);
+                let hash = cache.as_ref().map(|c| c.hash(&data, obj0.as_deref()));
+                let cached = cache.as_mut().zip(hash.as_deref()).and_then(|(c, hash)| c.get(hash, &file.path, file.tile_y * 64 + file.tile_x));
+                let record = cached.unwrap_or_else(|| read_adt_tile(&file.path, file.tile_x, file.tile_y, &data, obj0.as_deref(), options));
+                if let (Some(cache), Some(hash)) = (cache.as_mut(), hash) {
+                    cache.insert(hash, record.clone());
+                }
+                (record, bytes)
+            }
+            // Not journaled: the file may be readable next time
+            Err(e) => {
+                export.failures.record(FailureKind::of_read_error(e.as_ref()), &file.path, e.to_string());
+                return Ok(());
+            }
+        };
+        let parse = started.elapsed();
+        journal.append(&record)?;
+        let started = Instant::now();
+        if add_record(&mut export, &record)? {
+            parsed += 1;
+        }
+        let encode = started.elapsed();
+        export.timings.push(TileTiming { map: continent_name.to_string(), path: file.path.clone(), bytes, parse, encode });
+        Ok(())
+    });
+    crash::clear_context();
+    progress.finish_and_clear();
+    log::set_printer(None);
+    // Whatever was read before a stop or failure stays journaled, and cached
+    journal.flush()?;
+    if let Some(cache) = &cache {
+        let listed: HashSet<&Path> = files.iter().map(|f| f.path.as_path()).chain(replayed.iter().map(PathBuf::as_path)).collect();
+        if let Err(e) = cache.save(&listed) {
+            warn!("  WARNING: Failed to write {}: {}", cache.path().display(), e);
+        }
+        if cache.hits() > 0 {
+            info!("  Reused {} files with contents already parsed ({})", cache.hits(), cache.path().display());
+        }
+    }
+    result?;
+
+    print_scan_summary(&export, parsed, ignored);
+    Ok(export)
 }
 
 // ============================================================================
-// Map ID to Area ID mapping
+// Command line
 // ============================================================================
 
-#[derive(Debug)]
-struct MapToAreaEntry {
-    zone_name: String,
-    map_id: u32,
-    area_id: u32,
+#[derive(Parser)]
+#[command(version, about = "Generates ZoneMap's area grids from ADT files")]
+#[command(args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Progress output: text, or one JSON object per line for log collectors
+    #[arg(long, global = true, env = "ZONEMAP_LOG_FORMAT", default_value = "text")]
+    log_format: LogFormat,
+    /// Without a subcommand, arguments are those of `generate`
+    #[command(flatten)]
+    generate: GenerateArgs,
 }
 
-fn parse_map_to_area_csv(csv_path: &Path) -> Result<Vec<MapToAreaEntry>, Box<dyn std::error::Error>> {
-    let file = File::open(csv_path)?;
-    let reader = BufReader::new(file);
-    let mut entries = Vec::new();
-    
-    let mut lines = reader.lines();
-    let header = lines.next().ok_or("Empty CSV")??;
-    
-    // Parse header to find column indices
-    let columns: Vec<&str> = header.split(',').collect();
-    let zone_idx = columns.iter().position(|&c| c.trim() == "Zone").ok_or("No Zone column")?;
-    let map_id_idx = columns.iter().position(|&c| c.trim() == "mapId").ok_or("No mapId column")?;
-    let area_id_idx = columns.iter().position(|&c| c.trim() == "AreaId").ok_or("No AreaId column")?;
-    
-    for line in lines {
-        let line = line?;
-        let fields: Vec<&str> = parse_csv_line(&line);
-        
-        if fields.len() <= zone_idx.max(map_id_idx).max(area_id_idx) {
-            continue;
+#[derive(Subcommand)]
+enum Command {
+    /// Generate the addon's data files (the default)
+    Generate(Box<GenerateArgs>),
+    /// Summarise an ADT file or a generated data directory, and print a
+    /// tile's 16x16 area IDs
+    Inspect(InspectArgs),
+    /// Compare two generated data directories, or one with an ADT directory
+    Diff {
+        /// Earlier export, or ADT directory
+        old: PathBuf,
+        /// Later export, or ADT directory
+        new: PathBuf,
+        /// Leave out the list of changed chunks
+        #[arg(long)]
+        summary: bool,
+    },
+    /// Write a Hotfix.lua of the chunks that changed since a shipped export
+    Hotfix(HotfixArgs),
+    /// Draw a grid from a generated data directory as a PNG or SVG
+    Render(RenderArgs),
+    /// Print which tiles of each map are present, failed or missing against
+    /// its WDT, as a 64x64 matrix
+    Coverage(CoverageArgs),
+    /// List the moves that clean up an extracted ADT directory, or make them
+    Normalize {
+        /// ADT directory
+        dir: PathBuf,
+        /// Map the files belong to, whose case their names take
+        #[arg(long)]
+        map: String,
+        /// Move the files, instead of only listing the moves
+        #[arg(long)]
+        apply: bool,
+    },
+    /// Score a generated data directory against zone names captured in-game
+    Evaluate {
+        /// Generated data directory
+        dir: PathBuf,
+        /// Samples CSV: `x`, `y` and `zone` columns, and optionally `continent`
+        #[arg(long)]
+        samples: PathBuf,
+        /// What lookups return on tiles a grid doesn't have: none, ocean:<area id> or nearest
+        #[arg(long, default_value = "none")]
+        missing: MissingTilePolicy,
+    },
+    /// Replay a recorded movement path against a generated data directory and
+    /// print every area transition
+    Simulate {
+        /// Generated data directory
+        dir: PathBuf,
+        /// Path CSV: `time`, `x` and `y` columns, and optionally `continent`
+        #[arg(long)]
+        path: PathBuf,
+        /// Transitions that bounce back within this many seconds count as flicker
+        #[arg(long, default_value_t = 5.0)]
+        flicker_window: f64,
+        /// What lookups return on tiles a grid doesn't have: none, ocean:<area id> or nearest
+        #[arg(long, default_value = "none")]
+        missing: MissingTilePolicy,
+    },
+    /// Encode the grids of a generated data directory with every codec and
+    /// compare sizes, encode/decode times and Lua memory
+    CompareCodecs {
+        /// Generated data directory
+        dir: PathBuf,
+        /// Grid to compare [default: all of them]
+        #[arg(long)]
+        map: Option<String>,
+    },
+    /// Download the community listfile, or look map files up in it
+    Listfile(ListfileArgs),
+    /// Split the maps into jobs in a work queue shared with `work`ers
+    Queue(QueueArgs),
+    /// Scan jobs from a work queue until none are left
+    Work(WorkArgs),
+    /// Merge a work queue's finished jobs into zonemap.checkpoint/ for `generate --resume`
+    Collect {
+        /// Work queue directory
+        #[arg(env = "ZONEMAP_QUEUE")]
+        dir: PathBuf,
+        /// Put jobs claimed longer ago than this many seconds back in the queue
+        #[arg(long, env = "ZONEMAP_REQUEUE_AFTER")]
+        requeue_after: Option<u64>,
+    },
+    /// Print a completion script for a shell
+    Completions {
+        /// Shell to complete in
+        shell: Shell,
+    },
+    /// Write man pages for zone-map and each of its commands
+    Man {
+        /// Directory to write `zone-map.1` and `zone-map-<command>.1` to, created if needed
+        #[arg(long, short, default_value = "man")]
+        out: PathBuf,
+    },
+    /// Print the metrics of recent runs from a `--runs-db` and flag regressions
+    #[cfg(feature = "trends")]
+    Trends {
+        /// Runs database written by `generate --runs-db`
+        #[arg(env = "ZONEMAP_RUNS_DB", default_value = "zonemap-runs.db")]
+        db: PathBuf,
+        /// Runs to show, most recent last
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+}
+
+#[derive(Args)]
+struct InspectArgs {
+    /// `.adt` file or data directory
+    path: PathBuf,
+    /// Tile of a data directory to print, as `X,Y`
+    #[arg(long, value_parser = parse_tile_coords)]
+    tile: Option<(u32, u32)>,
+    /// Grid of the data directory --tile is in; needed when it has more than one
+    #[arg(long, requires = "tile")]
+    map: Option<String>,
+    /// Print area names instead of IDs in the matrix
+    #[arg(long)]
+    names: bool,
+    /// Client build of the AreaTable CSV that names areas
+    #[arg(long, env = "ZONEMAP_CLIENT_BUILD", default_value = DEFAULT_CLIENT_BUILD)]
+    client_build: String,
+}
+
+#[derive(Args)]
+struct HotfixArgs {
+    /// Shipped export the hotfix applies on top of
+    shipped: PathBuf,
+    /// Newer export, or ADT directory, to take changed chunks from
+    #[arg(required_unless_present = "edits")]
+    new: Option<PathBuf>,
+    /// `<Map>_edits.csv` of chunk area IDs to override, applied after the diff
+    #[arg(long)]
+    edits: Vec<PathBuf>,
+    /// Where to write the hotfix [default: Hotfix.lua in the shipped export]
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+impl ScanArgs {
+    /// The built-in layers with the plugins and scripts registered, and the
+    /// names of the layers to export: --layers, then each plugin's and script's
+    fn layers(&self) -> Result<(LayerRegistry, Vec<String>), String> {
+        #[allow(unused_mut)]
+        let mut registry = LayerRegistry::default();
+        #[allow(unused_mut)]
+        let mut names: Vec<String> = self.layers.iter().map(|n| n.trim().to_string()).collect();
+        #[cfg(feature = "wasm")]
+        for path in &self.plugins {
+            let plugin = WasmExtractor::from_file(path).map_err(|e| format!("Failed to load plugin {}: {}", path.display(), e))?;
+            if !names.iter().any(|n| n == plugin.name()) {
+                names.push(plugin.name().to_string());
+            }
+            registry.register(Box::new(plugin));
         }
-        
-        let zone_name = fields[zone_idx].trim_matches('"').to_string();
-        let map_id: u32 = match fields[map_id_idx].trim().parse() {
-            Ok(v) => v,
-            Err(_) => continue,
+        #[cfg(feature = "scripting")]
+        for path in &self.scripts {
+            let script = ScriptExtractor::from_file(path).map_err(|e| format!("Failed to load script {}: {}", path.display(), e))?;
+            if !names.iter().any(|n| n == script.name()) {
+                names.push(script.name().to_string());
+            }
+            registry.register(Box::new(script));
+        }
+        Ok((registry, names))
+    }
+
+    /// The default codecs with each --codec applied; `layers` are the names
+    /// being exported
+    fn codecs(&self, layers: &[String]) -> Result<LayerCodecs, String> {
+        let mut codecs = LayerCodecs::parse(DEFAULT_LAYER_CODECS)?;
+        for (layer, codec) in &self.codecs {
+            if !layers.iter().any(|l| l == layer) {
+                return Err(format!("--codec {}={}: {} is not one of the --layers exported", layer, codec, layer));
+            }
+            codecs.set(layer, *codec);
+        }
+        Ok(codecs)
+    }
+}
+
+/// `layer=codec`, as --codec takes it
+fn parse_layer_codec(s: &str) -> Result<(String, Codec), String> {
+    let (layer, codec) = s.split_once('=').ok_or_else(|| format!("expected LAYER=CODEC, got `{}`", s))?;
+    Ok((layer.trim().to_string(), codec.parse()?))
+}
+
+/// A u32, in decimal or as `0x` hex
+fn parse_u32(s: &str) -> Result<u32, String> {
+    let s = s.trim();
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+    .map_err(|e| format!("`{}`: {}", s, e))
+}
+
+fn parse_tile_coords(s: &str) -> Result<(u32, u32), String> {
+    let (x, y) = s.split_once(',').ok_or_else(|| format!("expected X,Y, got `{}`", s))?;
+    let coord = |c: &str| c.trim().parse::<u32>().ok().filter(|&c| c < 64).ok_or_else(|| format!("`{}` is not a tile coordinate (0-63)", c));
+    Ok((coord(x)?, coord(y)?))
+}
+
+#[derive(Args)]
+struct ListfileArgs {
+    /// Community listfile (`<fdid>;<path>` lines)
+    #[arg(long, env = "ZONEMAP_LISTFILE", default_value = "listfile.csv")]
+    listfile: PathBuf,
+    #[command(subcommand)]
+    action: ListfileAction,
+}
+
+#[derive(Subcommand)]
+enum ListfileAction {
+    /// Replace the listfile with the latest community release
+    Update {
+        /// Where to download it from
+        #[arg(long, default_value = LISTFILE_URL)]
+        url: String,
+    },
+    /// Print the path of each FileDataID and the FileDataID of each path
+    Lookup {
+        /// FileDataIDs or paths of map files (ADTs and WDTs)
+        #[arg(required = true)]
+        files: Vec<String>,
+    },
+}
+
+#[derive(Args)]
+struct RenderArgs {
+    /// Generated data directory
+    dir: PathBuf,
+    /// Grid to draw; needed when the directory has more than one
+    #[arg(long)]
+    map: Option<String>,
+    /// Draw every grid, all the same size, into the --out directory with an index.html
+    #[arg(long, conflicts_with_all = ["map", "compare", "legend"])]
+    all: bool,
+    /// Image to write; `.svg` writes SVG, anything else PNG. A directory with --all
+    #[arg(long, short)]
+    out: PathBuf,
+    /// Pixels per chunk side
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=64))]
+    scale: u32,
+    /// Add a legend of the areas drawn (IDs in PNG, names and IDs in SVG)
+    #[arg(long)]
+    legend: bool,
+    /// Colour areas as the addon does, from AreaInfo.lua, rather than one hue per area
+    #[arg(long, conflicts_with = "palette")]
+    addon_colors: bool,
+    /// Area colours: distinct, okabe-ito, viridis[:<bins>] or colors:<#rrggbb,...>
+    #[arg(long, default_value_t = Palette::Distinct)]
+    palette: Palette,
+    /// Label each area inside it (names in SVG, IDs in PNG)
+    #[arg(long)]
+    labels: bool,
+    /// Draw lines along tile borders
+    #[arg(long)]
+    tile_grid: bool,
+    /// Write each tile's ADT coordinates in its corner
+    #[arg(long)]
+    coords: bool,
+    /// An earlier data directory to compare the grid with
+    #[arg(long, conflicts_with = "legend")]
+    compare: Option<PathBuf>,
+    /// With --compare: side-by-side (PNG), diff (PNG of the changes) or blink (GIF)
+    #[arg(long, default_value_t = CompareMode::SideBySide, requires = "compare")]
+    compare_mode: CompareMode,
+}
+
+#[derive(Args)]
+struct CoverageArgs {
+    #[command(flatten)]
+    maps: MapArgs,
+    /// Community listfile (`<fdid>;<path>` lines) for reading a CASC install
+    #[arg(long, env = "ZONEMAP_LISTFILE", default_value = "listfile.csv")]
+    listfile: PathBuf,
+    /// TACT key file for encrypted files in a CASC install, as `generate --tact-keys`
+    #[arg(long, env = "ZONEMAP_TACT_KEYS")]
+    tact_keys: Option<PathBuf>,
+    /// Locale folder to read MPQs from, as `generate --locale`
+    #[arg(long, env = "ZONEMAP_LOCALE")]
+    locale: Option<String>,
+    /// Maps and tiles to leave out, as `generate --ignore`
+    #[arg(long, env = "ZONEMAP_IGNORE", default_value = "zonemap.ignore")]
+    ignore: PathBuf,
+    /// Client build of the Map CSV that --maps reads
+    #[arg(long, env = "ZONEMAP_CLIENT_BUILD", default_value = DEFAULT_CLIENT_BUILD)]
+    client_build: String,
+    /// Also draw each map's matrix as `<Map>_coverage.png` in this directory
+    #[arg(long, value_name = "DIR")]
+    png: Option<PathBuf>,
+    /// Pixels per tile side in the PNGs
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..=64))]
+    scale: u32,
+}
+
+#[derive(Args)]
+struct QueueArgs {
+    /// Work queue directory, created if needed
+    #[arg(env = "ZONEMAP_QUEUE")]
+    dir: PathBuf,
+    #[command(flatten)]
+    maps: MapArgs,
+    /// Jobs per map, each a band of tile rows
+    #[arg(long, env = "ZONEMAP_SPLIT", default_value_t = 1)]
+    split: u32,
+    /// Maps and tiles to leave out, as `generate --ignore`
+    #[arg(long, env = "ZONEMAP_IGNORE", default_value = "zonemap.ignore")]
+    ignore: PathBuf,
+    /// Client build of the Map CSV that --maps reads
+    #[arg(long, env = "ZONEMAP_CLIENT_BUILD", default_value = DEFAULT_CLIENT_BUILD)]
+    client_build: String,
+}
+
+#[derive(Args)]
+struct WorkArgs {
+    /// Work queue directory
+    #[arg(env = "ZONEMAP_QUEUE")]
+    dir: PathBuf,
+    /// Name recorded on claimed jobs [default: $HOSTNAME-<pid>]
+    #[arg(long, env = "ZONEMAP_WORKER")]
+    worker: Option<String>,
+    /// Community listfile (`<fdid>;<path>` lines) for reading a CASC install
+    #[arg(long, env = "ZONEMAP_LISTFILE", default_value = "listfile.csv")]
+    listfile: PathBuf,
+    /// TACT key file for encrypted files in a CASC install, as `generate --tact-keys`
+    #[arg(long, env = "ZONEMAP_TACT_KEYS")]
+    tact_keys: Option<PathBuf>,
+    /// Locale folder to read MPQs from, as `generate --locale`
+    #[arg(long, env = "ZONEMAP_LOCALE")]
+    locale: Option<String>,
+    /// Read only each chunk's area ID, as `generate --fast`
+    #[arg(long, env = "ZONEMAP_FAST", value_parser = BoolishValueParser::new())]
+    fast: bool,
+    /// Height map cells per chunk side, as `generate --heights`
+    #[arg(long, env = "ZONEMAP_HEIGHTS", value_parser = clap::value_parser!(u8).range(1..=MAX_RESOLUTION as i64))]
+    heights: Option<u8>,
+    #[command(flatten)]
+    scan: ScanArgs,
+}
+
+/// How each tile is read. `work` takes the same options as `generate`, as
+/// jobs scanned with other settings don't resume
+#[derive(Args)]
+struct ScanArgs {
+    /// Layers to export, comma-separated: areaid (the grid itself, required),
+    /// holes, liquid, liquiddepth, liquidlevel, impassable
+    #[arg(long, env = "ZONEMAP_LAYERS", value_delimiter = ',', default_value = "areaid,holes,liquid,liquidlevel")]
+    layers: Vec<String>,
+    /// Codec of a layer's tiles (raw, palette, rle or delta), replacing its
+    /// default; areaid is delta and holes, liquid and liquidlevel rle by default
+    #[arg(long = "codec", env = "ZONEMAP_CODECS", value_name = "LAYER=CODEC", value_delimiter = ',', value_parser = parse_layer_codec)]
+    codecs: Vec<(String, Codec)>,
+    /// WASM layer plugin to load and export, as the layer named after its file
+    /// stem (see `WasmExtractor`)
+    #[cfg(feature = "wasm")]
+    #[arg(long = "plugin", env = "ZONEMAP_PLUGINS", value_name = "WASM", value_delimiter = ',')]
+    plugins: Vec<PathBuf>,
+    /// Rhai script to export as a layer, named after its file stem (see
+    /// `ScriptExtractor`)
+    #[cfg(feature = "scripting")]
+    #[arg(long = "script", env = "ZONEMAP_SCRIPTS", value_name = "RHAI", value_delimiter = ',')]
+    scripts: Vec<PathBuf>,
+    /// Maps and tiles to leave out of discovery, read when present
+    #[arg(long, env = "ZONEMAP_IGNORE", default_value = "zonemap.ignore")]
+    ignore: PathBuf,
+    /// MCNK area IDs meaning "unset", comma-separated; chunks carrying one
+    /// are exported as --sentinel-area-id and counted in the run report
+    #[arg(long = "sentinel", env = "ZONEMAP_SENTINELS", value_name = "AREA_ID", value_delimiter = ',', value_parser = parse_u32, default_value = "0xFFFFFFFF")]
+    sentinels: Vec<u32>,
+    /// Area ID written in place of a sentinel (0 = no area)
+    #[arg(long, env = "ZONEMAP_SENTINEL_AREA_ID", value_parser = parse_u32, default_value = "0")]
+    sentinel_area_id: u32,
+    /// Experimental: estimate 8x8 sub-chunk areas on zone borders from texture
+    /// splats, into `<Map>_subchunks.lua`; manifest entries can override it
+    #[arg(long, env = "ZONEMAP_SUBCHUNKS", value_parser = BoolishValueParser::new())]
+    subchunks: bool,
+    /// Most file data to hold while reading, as `512MB`; fewer files are read ahead to stay under it
+    #[arg(long, env = "ZONEMAP_MAX_MEMORY", value_parser = parse_size)]
+    max_memory: Option<u64>,
+    /// Most files to have open at once; fewer files are read ahead, and an MPQ client with more archives is refused
+    #[arg(long, env = "ZONEMAP_MAX_OPEN_FILES", value_parser = clap::value_parser!(usize))]
+    max_open_files: Option<usize>,
+}
+
+impl ScanArgs {
+    fn read_limits(&self) -> ReadLimits {
+        ReadLimits { max_memory: self.max_memory, max_open_files: self.max_open_files }
+    }
+}
+
+/// Which maps a run covers
+#[derive(Args)]
+struct MapArgs {
+    /// ADT directory, `maps/` directory or chunk CSV to generate from, instead
+    /// of the manifest
+    #[arg(long, env = "ZONEMAP_INPUT", requires = "continent")]
+    input: Option<PathBuf>,
+    /// Map name as used in ADT file names. Without --input, only this
+    /// manifest entry is generated
+    #[arg(long, env = "ZONEMAP_CONTINENT")]
+    continent: Option<String>,
+    /// Map.dbc ID for --input; known for the stock continents
+    #[arg(long, env = "ZONEMAP_MAP_ID", requires = "input")]
+    map_id: Option<u32>,
+    /// Batch manifest [default: zonemap.manifest when present]
+    #[arg(long, env = "ZONEMAP_MANIFEST", conflicts_with = "input")]
+    manifest: Option<PathBuf>,
+    /// Maps from Map.dbc instead of a manifest: directory names, IDs,
+    /// `continents`, `dungeons`, `raids`, `battlegrounds`, `arenas` or `all`,
+    /// comma-separated
+    #[arg(long, env = "ZONEMAP_MAPS", conflicts_with_all = ["input", "manifest"])]
+    maps: Option<String>,
+    /// Where --maps reads from: a directory holding one directory per map, a
+    /// client's Data folder or a CASC install [default: .]
+    #[arg(long, env = "ZONEMAP_MAPS_ROOT", requires = "maps")]
+    maps_root: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct GenerateArgs {
+    #[command(flatten)]
+    maps: MapArgs,
+    #[command(flatten)]
+    scan: ScanArgs,
+    /// Directory the data files are written to
+    #[arg(long, env = "ZONEMAP_OUT", default_value = "Data")]
+    out: PathBuf,
+    /// Community listfile (`<fdid>;<path>` lines) for reading a CASC install
+    #[arg(long, env = "ZONEMAP_LISTFILE", default_value = "listfile.csv")]
+    listfile: PathBuf,
+    /// TACT keys (`<name> <key>` lines, or a keyring config) for files a CASC install holds encrypted
+    #[arg(long, env = "ZONEMAP_TACT_KEYS")]
+    tact_keys: Option<PathBuf>,
+    /// Locale folder of a client's Data/ to read MPQs from (enUS, deDE, ...) [default: detected]
+    #[arg(long, env = "ZONEMAP_LOCALE")]
+    locale: Option<String>,
+    /// Read only each chunk's area ID, skipping extra layers and sub-chunks
+    #[arg(long, env = "ZONEMAP_FAST", value_parser = BoolishValueParser::new())]
+    fast: bool,
+    /// Compression of tile payloads before base64: none or deflate (LibDeflate-compatible)
+    #[arg(long, env = "ZONEMAP_COMPRESSION", default_value = "none")]
+    compression: Compression,
+    /// Largest grid file per map, as `lua=2MB` or `json=500KB`; Lua grids escalate codecs to fit
+    #[arg(long, env = "ZONEMAP_BUDGET", value_delimiter = ',')]
+    budget: Vec<Budget>,
+    /// Pick up an interrupted run from zonemap.checkpoint/ instead of re-reading finished files
+    #[arg(long, env = "ZONEMAP_RESUME", value_parser = BoolishValueParser::new())]
+    resume: bool,
+    /// Parse every ADT again instead of reusing unchanged ones from zonemap.cache/
+    #[arg(long, env = "ZONEMAP_FORCE", value_parser = BoolishValueParser::new())]
+    force: bool,
+    /// Grid and area info files to write: lua (the addon's), json, or both
+    #[arg(long, env = "ZONEMAP_FORMAT", default_value = "lua")]
+    format: OutputFormat,
+    /// Split each Lua grid into files of this many tiles per side, with `<Map>_tiles.lua` naming them
+    #[arg(long, env = "ZONEMAP_SHARD", value_name = "TILES", value_parser = clap::value_parser!(u32).range(1..=64))]
+    shard: Option<u32>,
+    /// With --shard, also write `<Map>_tiles.toc`: the grid's files as .toc lines, manifest first
+    #[arg(long, env = "ZONEMAP_SHARD_TOC", value_parser = BoolishValueParser::new(), requires = "shard")]
+    shard_toc: bool,
+    /// Also write each map's grid as `<Map>_tiles.zmap`, the binary container for non-Lua tools
+    #[arg(long, env = "ZONEMAP_BINARY", value_parser = BoolishValueParser::new())]
+    binary: bool,
+    /// Read each Lua grid back after writing it (with the Rust reader, not the addon) and check every tile decodes to the area IDs read
+    #[arg(long, env = "ZONEMAP_VERIFY", value_parser = BoolishValueParser::new())]
+    verify: bool,
+    /// Also export `<Map>_heights` from the MCVT heights, this many cells per chunk side (1-8)
+    #[arg(long, env = "ZONEMAP_HEIGHTS", value_parser = clap::value_parser!(u8).range(1..=MAX_RESOLUTION as i64))]
+    heights: Option<u8>,
+    /// Also export `<Map>_outlines`: polygon outlines of each area, as Lua and/or GeoJSON per --format
+    #[arg(long, env = "ZONEMAP_OUTLINES", value_parser = BoolishValueParser::new())]
+    outlines: bool,
+    /// Simplify outlines to within this many chunks (0 keeps them exact)
+    #[arg(long, env = "ZONEMAP_OUTLINE_TOLERANCE", value_name = "CHUNKS", default_value_t = 0.0)]
+    outline_tolerance: f64,
+    /// Merge outline regions smaller than this many chunks into the neighbour they share the longest border with
+    #[arg(long, env = "ZONEMAP_OUTLINE_MIN_AREA", value_name = "CHUNKS", default_value_t = 0)]
+    outline_min_area: usize,
+    /// Holes in outlines: keep, fill, or fill:<chunks> to fill those smaller than that
+    #[arg(long, env = "ZONEMAP_OUTLINE_HOLES", default_value = "keep")]
+    outline_holes: HoleHandling,
+    /// Hash naming snapshots in store/: sha256, or xxh3 for speed (signatures always use sha256)
+    #[arg(long, env = "ZONEMAP_HASH", default_value = "sha256")]
+    hash: HashAlgorithm,
+    /// Write a self-contained HTML report of the run here
+    #[arg(long, env = "ZONEMAP_REPORT")]
+    report: Option<PathBuf>,
+    /// Print the N files that took longest to parse and encode, and each map's timings
+    #[arg(long, env = "ZONEMAP_PROFILE_SLOWEST", value_name = "N")]
+    profile_slowest: Option<usize>,
+    /// Post the run summary and changelog here when the run ends (Discord, Slack or any JSON webhook; sent with curl)
+    #[arg(long, env = "ZONEMAP_WEBHOOK")]
+    webhook: Option<String>,
+    /// Mail the run summary and changelog here when the run ends (sent with sendmail)
+    #[arg(long, env = "ZONEMAP_NOTIFY_EMAIL")]
+    notify_email: Option<String>,
+    /// Client build the AreaTable, Map and other CSV exports are named after
+    #[arg(long, env = "ZONEMAP_CLIENT_BUILD", default_value = DEFAULT_CLIENT_BUILD)]
+    client_build: String,
+    /// Built-in colour theme for zones (e.g. classic), or `auto` to pick one by --client-build
+    #[arg(long, env = "ZONEMAP_THEME")]
+    theme: Option<String>,
+    /// Theme CSV (`area_id,r,g,b,name`) overriding --theme, read when present
+    #[arg(long, env = "ZONEMAP_THEME_FILE", default_value = "theme.csv")]
+    theme_file: PathBuf,
+    /// Directory of attribute CSVs joined into AreaInfo, read when present
+    #[arg(long, env = "ZONEMAP_ATTRIBUTES", default_value = "attributes")]
+    attributes: PathBuf,
+    /// Instance entrance WMOs (`wmo,map_id,name`) for DungeonEntrances.lua, read when present
+    #[arg(long, env = "ZONEMAP_ENTRANCES", default_value = "entrances.csv")]
+    entrances: PathBuf,
+    /// Weather per zone for AreaWeather.lua, read when present
+    #[arg(long, env = "ZONEMAP_WEATHER", default_value = "weather.csv")]
+    weather: PathBuf,
+    /// Earlier export to version and write DATA_CHANGES.md against [default: the newest in --store]
+    #[arg(long, env = "ZONEMAP_PREVIOUS", default_value = "previous_data")]
+    previous: PathBuf,
+    /// Snapshot store for this build's exports, used when the directory exists
+    #[arg(long, env = "ZONEMAP_STORE", default_value = "store")]
+    store: PathBuf,
+    /// Quality gate as `metric=limit`, e.g. `parse-failures=5%` or `missing-tiles=0`;
+    /// exceeding any exits with status 2
+    #[arg(long, env = "ZONEMAP_FAIL_ON", value_name = "METRIC=LIMIT", value_delimiter = ',', value_parser = str::parse::<Threshold>)]
+    fail_on: Vec<Threshold>,
+    /// Player positions (`continent`, `x`, `y`, optional `count`), read when
+    /// present: hot tiles get --hot-codec, shards are listed hottest first and
+    /// the areas players visit are reported
+    #[arg(long, env = "ZONEMAP_HEATMAP", default_value = "heatmap.csv")]
+    heatmap: PathBuf,
+    /// Areaid codec of a map's hot tiles, those holding --hot-share of its visits
+    #[arg(long, env = "ZONEMAP_HOT_CODEC", default_value = "raw")]
+    hot_codec: Codec,
+    /// Percentage of a map's visits whose tiles count as hot
+    #[arg(long, env = "ZONEMAP_HOT_SHARE", default_value_t = 50.0)]
+    hot_share: f64,
+    /// ed25519 key the export is signed with, when the file exists
+    #[cfg(feature = "signing")]
+    #[arg(long, env = "ZONEMAP_SIGNING_KEY", default_value = "zonemap.key")]
+    signing_key: PathBuf,
+    /// Also write the grids and areas to this SQLite database, replacing the continents written
+    #[cfg(feature = "sqlite")]
+    #[arg(long, env = "ZONEMAP_SQLITE")]
+    sqlite: Option<PathBuf>,
+    /// Append this run's metrics to a SQLite database for `trends`
+    #[cfg(feature = "trends")]
+    #[arg(long, env = "ZONEMAP_RUNS_DB")]
+    runs_db: Option<PathBuf>,
+    /// Run the jobs in this TOML job file, each as its own `generate`
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+    /// Only run the job with this name from --config
+    #[arg(long, requires = "config")]
+    job: Option<String>,
+}
+
+impl GenerateArgs {
+    fn outline_options(&self) -> OutlineOptions {
+        OutlineOptions { tolerance: self.outline_tolerance, min_area: self.outline_min_area, holes: self.outline_holes }
+    }
+}
+
+impl MapArgs {
+    /// Maps to generate: --input as a single entry, or the manifest
+    /// (narrowed to --continent)
+    fn manifest(&self, client_build: &str) -> Result<Manifest, Box<dyn std::error::Error>> {
+        if let Some(input) = &self.input {
+            let map = self.continent.as_deref().expect("clap requires --continent with --input");
+            let map_id = self
+                .map_id
+                .or_else(|| known_map_id(map))
+                .ok_or_else(|| format!("unknown map `{}`; pass --map-id", map))?;
+            let source = input.to_str().ok_or("input path is not valid UTF-8")?;
+            return Ok(Manifest { entries: vec![ManifestEntry::new(map, map_id, source)] });
+        }
+
+        let default_path = Path::new("zonemap.manifest");
+        let mut manifest = match (&self.maps, &self.manifest) {
+            (Some(selection), _) => {
+                let maps = load_map_table(client_build)?;
+                let selected = select_maps(&maps, selection)?;
+                Manifest::from_maps(&selected, self.maps_root.as_deref().unwrap_or(Path::new(".")))?
+            }
+            (None, Some(path)) => Manifest::from_file(path).map_err(|e| format!("{}: {}", path.display(), e))?,
+            (None, None) if default_path.exists() => {
+                Manifest::from_file(default_path).map_err(|e| format!("zonemap.manifest: {}", e))?
+            }
+            (None, None) => Manifest::default(),
         };
-        let area_id: u32 = match fields[area_id_idx].trim().parse() {
-            Ok(v) => v,
-            Err(_) => continue,
+        if let Some(continent) = &self.continent {
+            manifest.entries.retain(|e| e.map.eq_ignore_ascii_case(continent));
+            if manifest.entries.is_empty() {
+                return Err(format!("{} is not in the manifest; pass --input", continent).into());
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+fn main() -> ExitCode {
+    crash::install();
+
+    // Settings from $ZONEMAP_CONFIG fill in what the environment doesn't set
+    if let Some(path) = std::env::var_os("ZONEMAP_CONFIG") {
+        match envfile::read_env_file(Path::new(&path)) {
+            Ok(vars) => {
+                for (name, value) in vars {
+                    if std::env::var_os(&name).is_none() {
+                        std::env::set_var(name, value);
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Failed to read ZONEMAP_CONFIG: {}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let cli = Cli::parse();
+    log::set_format(cli.log_format);
+    match cli.command.unwrap_or(Command::Generate(Box::new(cli.generate))) {
+        Command::Generate(args) => {
+            stop_on_signal();
+            match &args.config {
+                Some(path) => generate_jobs(path, args.job.as_deref()),
+                None => generate(&args),
+            }
+        }
+        Command::Inspect(args) => inspect(&args),
+        Command::Diff { old, new, summary } => diff(&old, &new, summary),
+        Command::Hotfix(args) => exit_status("Failed to write the hotfix", hotfix(&args)),
+        Command::Render(args) => exit_status("Failed to render", render(&args)),
+        Command::Coverage(args) => exit_status("Failed to check coverage", coverage(&args)),
+        Command::Normalize { dir, map, apply } => exit_status("Failed to normalize", normalize(&dir, &map, apply)),
+        Command::Evaluate { dir, samples, missing } => exit_status("Failed to evaluate", evaluate_command(&dir, &samples, missing)),
+        Command::Simulate { dir, path, flicker_window, missing } => {
+            exit_status("Failed to simulate", simulate_command(&dir, &path, flicker_window, missing))
+        }
+        Command::CompareCodecs { dir, map } => exit_status("Failed to compare codecs", compare_codecs_command(&dir, map.as_deref())),
+        Command::Listfile(args) => {
+            let context = match args.action {
+                ListfileAction::Update { .. } => "Failed to update the listfile",
+                ListfileAction::Lookup { .. } => "Failed to look up files",
+            };
+            exit_status(context, listfile_command(&args))
+        }
+        Command::Queue(args) => exit_status("Failed to queue jobs", queue(&args)),
+        Command::Work(args) => {
+            stop_on_signal();
+            exit_status("Worker stopped", work(&args))
+        }
+        Command::Collect { dir, requeue_after } => exit_status("Failed to collect jobs", collect(&dir, requeue_after)),
+        #[cfg(feature = "trends")]
+        Command::Trends { db, limit } => exit_status("Failed to read runs", trends(&db, limit)),
+        Command::Completions { shell } => {
+            let mut command = Cli::command();
+            let name = command.get_name().to_string();
+            // Through print!, so a closed pipe exits like every other command's output
+            let mut script = Vec::new();
+            clap_complete::generate(shell, &mut command, name, &mut script);
+            print!("{}", String::from_utf8_lossy(&script));
+            ExitCode::SUCCESS
+        }
+        Command::Man { out } => exit_status("Failed to write man pages", write_man_pages(&out)),
+    }
+}
+
+/// Render the man pages of the command and its subcommands into `out_dir`
+fn write_man_pages(out_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    fs::create_dir_all(out_dir)?;
+    clap_mangen::generate_to(Cli::command(), out_dir)?;
+    info!("Wrote man pages to {}", out_dir.display());
+    Ok(())
+}
+
+/// Map.dbc/.db2, or else the Map CSV for `client_build`, from the working
+/// directory
+fn load_map_table(client_build: &str) -> Result<Vec<MapRecord>, Box<dyn std::error::Error>> {
+    let csv_name = format!("Map.{}.csv", client_build);
+    let maps = if let Some(dbc_path) = ["Map.dbc", "Map.db2"].iter().map(Path::new).find(|p| p.exists()) {
+        parse_map_dbc(&Dbc::from_file(dbc_path)?).map_err(|e| format!("{}: {}", dbc_path.display(), e))?
+    } else if Path::new(&csv_name).exists() {
+        parse_map_csv(Path::new(&csv_name)).map_err(|e| format!("{}: {}", csv_name, e))?
+    } else {
+        return Err(format!("--maps needs Map.dbc, Map.db2 or {} in the working directory", csv_name).into());
+    };
+    info!("Loaded {} maps", maps.len());
+    Ok(maps)
+}
+
+/// Longest name `print_area_matrix` prints in a cell
+const MATRIX_NAME_WIDTH: usize = 12;
+
+/// Print a tile's 256 area IDs as a 16x16 table, rows being chunk y. With
+/// `name`, cells show the area's name (cut short) where it has one.
+fn print_area_matrix(area_ids: &[u32], name: Option<&dyn Fn(u32) -> Option<String>>) {
+    let cells: Vec<String> = area_ids
+        .iter()
+        .map(|&id| match name.and_then(|name| name(id)) {
+            Some(name) => name.chars().take(MATRIX_NAME_WIDTH).collect(),
+            None => id.to_string(),
+        })
+        .collect();
+    let width = cells.iter().map(|c| c.chars().count()).max().unwrap_or(1).max(2);
+    let header: Vec<String> = (0..CHUNKS_PER_SIDE).map(|x| format!("{:>width$}", x)).collect();
+    println!("  y\\x {}", header.join(" "));
+    for (y, row) in cells.chunks(CHUNKS_PER_SIDE).enumerate() {
+        let row: Vec<String> = row.iter().map(|c| format!("{:>width$}", c)).collect();
+        println!("  {:>3} {}", y, row.join(" "));
+    }
+}
+
+/// Print one tile of a generated data directory
+fn inspect_tile(dir: &Path, snapshot: &ExportSnapshot, map: Option<&str>, (x, y): (u32, u32), names: bool) -> ExitCode {
+    let grids: Vec<&str> = snapshot.grids.keys().map(String::as_str).collect();
+    let name = match map {
+        Some(map) => map,
+        None if grids.len() == 1 => grids[0],
+        None => {
+            eprintln!("{} has grids {}; pick one with --map", dir.display(), grids.join(", "));
+            return ExitCode::FAILURE;
+        }
+    };
+    let Some(tiles) = snapshot.grids.get(name) else {
+        eprintln!("No grid {} in {} (it has {})", name, dir.display(), grids.join(", "));
+        return ExitCode::FAILURE;
+    };
+    let Some(area_ids) = tiles.get(&(y * 64 + x)) else {
+        eprintln!("{} has no tile {},{}", name, x, y);
+        return ExitCode::FAILURE;
+    };
+    println!("{}: {} tile {},{}", dir.display(), name, x, y);
+    let area_name = |id: u32| snapshot.areas.get(&id).map(|a| a.name.clone());
+    print_area_matrix(area_ids, names.then_some(&area_name as &dyn Fn(u32) -> Option<String>));
+    let mut chunks_per_area: BTreeMap<u32, usize> = BTreeMap::new();
+    for &id in area_ids {
+        *chunks_per_area.entry(id).or_default() += 1;
+    }
+    for (area_id, chunks) in &chunks_per_area {
+        println!("  {:>6} {:<28} {:>3} chunks", area_id, area_name(*area_id).as_deref().unwrap_or("?"), chunks);
+    }
+    ExitCode::SUCCESS
+}
+
+/// Print a summary of an ADT file or a generated data directory
+fn inspect(args: &InspectArgs) -> ExitCode {
+    let path = args.path.as_path();
+    if path.is_dir() {
+        let snapshot = match ExportSnapshot::load(path) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Failed to load {}: {}", path.display(), e);
+                return ExitCode::FAILURE;
+            }
         };
-        
-        entries.push(MapToAreaEntry { zone_name, map_id, area_id });
+        if let Some(tile) = args.tile {
+            return inspect_tile(path, &snapshot, args.map.as_deref(), tile, args.names);
+        }
+        println!("{}: {} areas in AreaInfo.lua", path.display(), snapshot.areas.len());
+        if let Ok(Some((version, schema))) = read_version(path) {
+            println!("  Data version {} (schema {})", version, schema);
+        }
+        for (name, tiles) in &snapshot.grids {
+            let areas: BTreeSet<u32> = tiles.values().flatten().copied().filter(|&id| id != 0).collect();
+            println!("  {:<20} {:>5} tiles {:>5} areas", name, tiles.len(), areas.len());
+        }
+        return ExitCode::SUCCESS;
     }
-    
-    Ok(entries)
+    if args.tile.is_some() {
+        eprintln!("--tile picks a tile of a data directory; {} is a single ADT", path.display());
+        return ExitCode::FAILURE;
+    }
+
+    let tile = match parse_adt(path) {
+        Ok(Some(tile)) => tile,
+        Ok(None) => {
+            eprintln!("{}: no MCNK chunks", path.display());
+            return ExitCode::FAILURE;
+        }
+        Err(e) => {
+            eprintln!("Failed to parse {}: {}", path.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let areas = load_area_table(&args.client_build);
+    match TileFile::from_path(path) {
+        Some(file) => println!("{}: {} tile {},{}", path.display(), file.map, file.tile_x, file.tile_y),
+        None => println!("{}", path.display()),
+    }
+    if let Ok(data) = fs::read(path) {
+        println!("  {} layout, {} WMOs", AdtLayout::detect(&data).label(), tile.wmos.len());
+    }
+    let area_name = |id: u32| areas.get(&id).map(|a| a.name.clone());
+    print_area_matrix(&tile.area_ids(), args.names.then_some(&area_name as &dyn Fn(u32) -> Option<String>));
+    let mut chunks_per_area: BTreeMap<u32, usize> = BTreeMap::new();
+    for chunk in &tile.chunks {
+        *chunks_per_area.entry(chunk.area_id).or_default() += 1;
+    }
+    for (area_id, chunks) in &chunks_per_area {
+        let name = areas.get(area_id).map(|a| a.name.as_str()).unwrap_or("?");
+        println!("  {:>6} {:<28} {:>3} chunks", area_id, name, chunks);
+    }
+    ExitCode::SUCCESS
 }
 
-fn export_map_to_area(entries: &[MapToAreaEntry], out_path: &Path) -> std::io::Result<()> {
-    let mut f = File::create(out_path)?;
-    
-    writeln!(f, "-- Auto-generated Map ID to Area ID mapping")?;
-    writeln!(f, "-- Maps WoW UI map IDs to parent area IDs")?;
-    writeln!(f)?;
-    writeln!(f, "local _, addon = ...")?;
-    writeln!(f)?;
-    writeln!(f, "addon.MapToArea = {{")?;
-    
-    for entry in entries {
-        let escaped_name = entry.zone_name.replace("\"", "\\\"");
-        writeln!(f, "  [{}] = {{ areaId = {}, name = \"{}\" }},", 
-            entry.map_id, entry.area_id, escaped_name)?;
+/// Draw one grid of a generated data directory
+fn render(args: &RenderArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let snapshot = ExportSnapshot::load(&args.dir)?;
+    let color = |id: u32| match id {
+        0 => NO_AREA,
+        _ if args.addon_colors => snapshot.colors.get(&id).copied().map_or(UNCOLORED, rgb_bytes),
+        _ => args.palette.color(id),
+    };
+    let annotations = Annotations { labels: args.labels, tile_grid: args.tile_grid, coords: args.coords };
+
+    if args.all {
+        let rasters: Vec<(&String, ChunkRaster)> = snapshot.grids.iter().filter_map(|(name, tiles)| Some((name, ChunkRaster::from_tiles(tiles)?))).collect();
+        if rasters.is_empty() {
+            return Err(format!("{} has no grids to draw", args.dir.display()).into());
+        }
+        // Pad every grid to the largest, so they share a size as well as colours
+        let width = rasters.iter().map(|(_, r)| r.width).max().unwrap_or(0);
+        let height = rasters.iter().map(|(_, r)| r.height).max().unwrap_or(0);
+        fs::create_dir_all(&args.out)?;
+        let mut entries = Vec::new();
+        for (name, raster) in &rasters {
+            let file = format!("{}.png", name);
+            fs::write(args.out.join(&file), render_png(&raster.padded(width, height), color, args.scale, &[], annotations)?)?;
+            let areas = raster.cells.iter().flatten().collect::<BTreeSet<_>>().len();
+            entries.push(GalleryEntry { name: name.to_string(), file, tiles: raster.tiles().len(), areas });
+        }
+        let index = args.out.join("index.html");
+        fs::write(&index, gallery_html(&args.dir.display().to_string(), &entries))?;
+        info!("Wrote: {} ({} grids at {}x{} chunks)", index.display(), entries.len(), width, height);
+        return Ok(());
+    }
+
+    let names: Vec<&str> = snapshot.grids.keys().map(String::as_str).collect();
+    let name = match &args.map {
+        Some(map) => map.as_str(),
+        None if names.len() == 1 => names[0],
+        None => return Err(format!("{} has grids {}; pick one with --map", args.dir.display(), names.join(", ")).into()),
+    };
+    let tiles = snapshot
+        .grids
+        .get(name)
+        .ok_or_else(|| format!("no grid {} in {} (it has {})", name, args.dir.display(), names.join(", ")))?;
+    let svg = args.out.extension().is_some_and(|e| e.eq_ignore_ascii_case("svg"));
+
+    if let Some(compare) = &args.compare {
+        if svg {
+            return Err("--compare writes PNG or GIF, not SVG".into());
+        }
+        let earlier = ExportSnapshot::load(compare)?;
+        // A grid new since then is compared with nothing
+        let no_tiles = BTreeMap::new();
+        let old_tiles = earlier.grids.get(name).unwrap_or(&no_tiles);
+        let (old, new) = ChunkRaster::aligned(old_tiles, tiles).ok_or_else(|| format!("grid {} has no tiles", name))?;
+        let changed = old.cells.iter().zip(&new.cells).filter(|(a, b)| a != b).count();
+        fs::write(&args.out, render_compare(&old, &new, color, args.scale, annotations, args.compare_mode)?)?;
+        info!("Wrote: {} ({} of {} against {}, {} chunks differ)", args.out.display(), args.compare_mode, name, compare.display(), changed);
+        return Ok(());
+    }
+
+    let raster = ChunkRaster::from_tiles(tiles).ok_or_else(|| format!("grid {} has no tiles", name))?;
+    let area_name = |id: u32| match snapshot.areas.get(&id) {
+        Some(area) => area.name.clone(),
+        None if id == 0 => "no area".to_string(),
+        None => "unknown".to_string(),
+    };
+    let legend = raster.legend(color, area_name);
+
+    if svg {
+        fs::write(&args.out, render_svg(&raster, args.scale, &legend, args.legend, annotations))?;
+    } else {
+        fs::write(&args.out, render_png(&raster, color, args.scale, if args.legend { &legend } else { &[] }, annotations)?)?;
+    }
+    info!("Wrote: {} ({}x{} chunks, {} areas)", args.out.display(), raster.width, raster.height, legend.len());
+    Ok(())
+}
+
+/// Scan each map's area IDs and print its tile coverage matrix, drawing it
+/// as a PNG with --png
+fn coverage(args: &CoverageArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let ignore = load_ignore_list(&args.ignore)?;
+    let codecs = LayerCodecs::parse(DEFAULT_LAYER_CODECS)?;
+    let sentinels = SentinelGuard::new(&[], 0);
+    // Scans journal what they read; keep that out of zonemap.checkpoint/
+    let checkpoint = Checkpoint::new(&std::env::temp_dir().join(format!("zonemap-coverage-{}", std::process::id())), false);
+    let options = ExportOptions {
+        area_layer: &AreaIdLayer,
+        extra_layers: &[],
+        codecs: &codecs,
+        refine_subchunks: false,
+        ignore: &ignore,
+        sentinels: &sentinels,
+        listfile: &args.listfile,
+        tact_keys: args.tact_keys.as_deref(),
+        locale: args.locale.as_deref(),
+        fast: true,
+        compression: Compression::None,
+        checkpoint: &checkpoint,
+        heights: None,
+        cache: None,
+        limits: ReadLimits::default(),
+    };
+    let manifest = args.maps.manifest(&args.client_build)?;
+
+    for entry in &manifest.entries {
+        if stopping() {
+            break;
+        }
+        if ignore.ignores_map(&entry.map) {
+            continue;
+        }
+        let export = match load_continent(&entry.source, &entry.map, entry.map_id, &options) {
+            Ok(export) => export,
+            Err(_) if stopping() => break,
+            Err(e) => {
+                warn!("Skipping {}: {}", entry.map, e);
+                continue;
+            }
+        };
+        let matrix = print_coverage_matrix(&export);
+        if let Some(dir) = &args.png {
+            let out_path = dir.join(format!("{}_coverage.png", entry.map));
+            let written = fs::create_dir_all(dir).map_err(|e| e.to_string()).and_then(|_| matrix.render_png(args.scale)).and_then(|png| fs::write(&out_path, png).map_err(|e| e.to_string()));
+            match written {
+                Ok(()) => info!("  Wrote: {}", out_path.display()),
+                Err(e) => error!("Failed to write {}: {}", out_path.display(), e),
+            }
+        }
+    }
+    checkpoint.clear()?;
+    Ok(())
+}
+
+/// List or make the moves that flatten an extracted ADT directory
+fn normalize(dir: &Path, map: &str, apply: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if !dir.is_dir() {
+        return Err(format!("Directory not found: {}", dir.display()).into());
     }
-    
-    writeln!(f, "}}")?;
-    
-    // Also create reverse lookup (areaId -> mapId)
-    writeln!(f)?;
-    writeln!(f, "addon.AreaToMap = {{")?;
-    
-    for entry in entries {
-        writeln!(f, "  [{}] = {},", entry.area_id, entry.map_id)?;
+    let renames = plan_normalize(dir, map)?;
+    if renames.is_empty() {
+        info!("{} needs no normalizing", dir.display());
+        return Ok(());
+    }
+    for rename in &renames {
+        println!("{} -> {}", rename.from.display(), rename.to.display());
+    }
+    if apply {
+        apply_normalize(dir, &renames)?;
+        info!("Normalized {} files in {}", renames.len(), dir.display());
+    } else {
+        info!("{} files to move; run again with --apply to move them", renames.len());
     }
-    
-    writeln!(f, "}}")?;
-    
     Ok(())
 }
 
-// ============================================================================
-// Neighbor detection and graph coloring
-// ============================================================================
+/// The grids of a generated data directory for position lookups, answering
+/// `missing` on tiles they don't have
+fn snapshot_zone_grids(snapshot: &ExportSnapshot, missing: MissingTilePolicy) -> Vec<ZoneGrid> {
+    snapshot
+        .grids
+        .iter()
+        .map(|(name, tiles)| {
+            let mut grid = ZoneGrid::new(name, known_map_id(name).unwrap_or(0), tiles.clone().into_iter().collect());
+            grid.set_missing_tile_policy(missing);
+            grid
+        })
+        .collect()
+}
 
-type NeighborGraph = HashMap<u32, HashSet<u32>>;
+/// Score the grids of a generated data directory against in-game samples
+fn evaluate_command(dir: &Path, samples_path: &Path, missing: MissingTilePolicy) -> Result<(), Box<dyn std::error::Error>> {
+    let snapshot = ExportSnapshot::load(dir)?;
+    let samples = parse_samples_csv(samples_path).map_err(|e| format!("{}: {}", samples_path.display(), e))?;
+    info!("Evaluating {} against {} samples...", dir.display(), samples.len());
+    let grids = snapshot_zone_grids(&snapshot, missing);
+    evaluate(&samples, &grids, |area_id| snapshot.area_names(area_id)).print();
+    Ok(())
+}
 
-/// Add a neighbor relationship (bidirectional)
-fn add_neighbor(graph: &mut NeighborGraph, a: u32, b: u32) {
-    if a != 0 && b != 0 && a != b {
-        graph.entry(a).or_default().insert(b);
-        graph.entry(b).or_default().insert(a);
+/// Replay a movement path against the grids of a generated data directory,
+/// flagging transitions that bounce back within `flicker_window` seconds
+fn simulate_command(dir: &Path, path_csv: &Path, flicker_window: f64, missing: MissingTilePolicy) -> Result<(), Box<dyn std::error::Error>> {
+    let snapshot = ExportSnapshot::load(dir)?;
+    let path = parse_path_csv(path_csv).map_err(|e| format!("{}: {}", path_csv.display(), e))?;
+    let grids = snapshot_zone_grids(&snapshot, missing);
+    let transitions = simulate(&path, &grids, flicker_window);
+    let area_label = |id: Option<u32>| match id {
+        Some(id) => format!("{} ({})", id, snapshot.areas.get(&id).map(|a| a.name.as_str()).unwrap_or("?")),
+        None => "<none>".to_string(),
+    };
+    for t in &transitions {
+        println!(
+            "{:>8.1}s ({:.1}, {:.1}): {} -> {}{}",
+            t.time,
+            t.x,
+            t.y,
+            area_label(t.from),
+            area_label(t.to),
+            if t.flicker { "  [flicker]" } else { "" }
+        );
     }
+    let flickers = transitions.iter().filter(|t| t.flicker).count();
+    info!("{} waypoints, {} transitions, {} flickering", path.len(), transitions.len(), flickers);
+    Ok(())
 }
 
-/// Find neighbors within a single tile (adjacent chunks with different area IDs)
-fn find_tile_neighbors(area_ids: &[u32], graph: &mut NeighborGraph) {
-    // area_ids is 256 elements, 16x16 grid
-    // Check horizontal neighbors (left-right)
-    for y in 0..16 {
-        for x in 0..15 {
-            let idx1 = y * 16 + x;
-            let idx2 = y * 16 + x + 1;
-            add_neighbor(graph, area_ids[idx1], area_ids[idx2]);
-        }
+/// Print each codec's sizes and timings on the grids of a generated data directory
+fn compare_codecs_command(dir: &Path, map: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let snapshot = ExportSnapshot::load(dir)?;
+    if let Some(map) = map.filter(|m| !snapshot.grids.contains_key(*m)) {
+        let names: Vec<&str> = snapshot.grids.keys().map(String::as_str).collect();
+        return Err(format!("no grid {} in {} (it has {})", map, dir.display(), names.join(", ")).into());
     }
-    
-    // Check vertical neighbors (up-down)
-    for y in 0..15 {
-        for x in 0..16 {
-            let idx1 = y * 16 + x;
-            let idx2 = (y + 1) * 16 + x;
-            add_neighbor(graph, area_ids[idx1], area_ids[idx2]);
+    for (name, tiles) in &snapshot.grids {
+        if map.is_some_and(|m| m != name) {
+            continue;
+        }
+        println!("{} ({} tiles)", name, tiles.len());
+        let stats = compare_codecs(tiles.values())?;
+        for line in format_codec_table(&stats).lines() {
+            println!("  {}", line);
         }
     }
+    Ok(())
 }
 
-/// Find neighbors between adjacent tiles
-fn find_inter_tile_neighbors(
-    tiles: &HashMap<u32, Vec<u32>>,
-    graph: &mut NeighborGraph,
-) {
-    for (&key, area_ids) in tiles {
-        let tile_x = key % 64;
-        let tile_y = key / 64;
-        
-        // Check right neighbor tile
-        if tile_x < 63 {
-            let right_key = tile_y * 64 + tile_x + 1;
-            if let Some(right_ids) = tiles.get(&right_key) {
-                // Compare rightmost column of current tile with leftmost column of right tile
-                for y in 0..16 {
-                    let idx_current = y * 16 + 15;  // Rightmost column
-                    let idx_right = y * 16 + 0;     // Leftmost column
-                    add_neighbor(graph, area_ids[idx_current], right_ids[idx_right]);
-                }
+/// Update the listfile, or resolve FileDataIDs and paths with it
+fn listfile_command(args: &ListfileArgs) -> Result<(), Box<dyn std::error::Error>> {
+    match &args.action {
+        ListfileAction::Update { url } => {
+            let old = Listfile::read(&args.listfile).ok();
+            info!("Downloading: {}", url);
+            let new = listfile::download(url, &args.listfile)?;
+            info!("Wrote: {} ({} map files)", args.listfile.display(), new.len());
+            if let Some(old) = old {
+                let (added, removed) = new.changes_from(&old);
+                info!("  {} added, {} removed since the last update", added, removed);
             }
         }
-        
-        // Check bottom neighbor tile
-        if tile_y < 63 {
-            let bottom_key = (tile_y + 1) * 64 + tile_x;
-            if let Some(bottom_ids) = tiles.get(&bottom_key) {
-                // Compare bottom row of current tile with top row of bottom tile
-                for x in 0..16 {
-                    let idx_current = 15 * 16 + x;  // Bottom row
-                    let idx_bottom = 0 * 16 + x;    // Top row
-                    add_neighbor(graph, area_ids[idx_current], bottom_ids[idx_bottom]);
+        ListfileAction::Lookup { files } => {
+            let names = Listfile::read(&args.listfile)?;
+            let mut missing = Vec::new();
+            for file in files {
+                let found = match file.parse::<u32>() {
+                    Ok(fdid) => names.path(fdid).map(|path| (fdid, path.to_string())),
+                    Err(_) => names.file_data_id(file).map(|fdid| (fdid, listfile::normalize(file))),
+                };
+                match found {
+                    Some((fdid, path)) => println!("{};{}", fdid, path),
+                    None => missing.push(file.as_str()),
                 }
             }
-        }
-    }
-}
-
-/// Generate distinct colors using graph coloring
-/// Returns a map of area_id -> (r, g, b)
-fn generate_colors_with_graph(
-    found_areas: &BTreeSet<u32>,
-    neighbors: &NeighborGraph,
-    areas: &HashMap<u32, AreaInfo>,
-) -> HashMap<u32, (f32, f32, f32)> {
-    let mut colors: HashMap<u32, (f32, f32, f32)> = HashMap::new();
-    
-    // Predefined palette of visually distinct colors
-    let palette: Vec<(f32, f32, f32)> = vec![
-        (0.90, 0.30, 0.30),  // Red
-        (0.30, 0.70, 0.30),  // Green
-        (0.30, 0.50, 0.90),  // Blue
-        (0.90, 0.80, 0.20),  // Yellow
-        (0.80, 0.40, 0.80),  // Purple
-        (0.20, 0.80, 0.80),  // Cyan
-        (0.95, 0.60, 0.30),  // Orange
-        (0.60, 0.80, 0.40),  // Lime
-        (0.80, 0.50, 0.60),  // Pink
-        (0.50, 0.70, 0.80),  // Sky blue
-        (0.70, 0.60, 0.40),  // Tan
-        (0.60, 0.40, 0.70),  // Violet
-        (0.40, 0.60, 0.50),  // Teal
-        (0.85, 0.70, 0.70),  // Light pink
-        (0.70, 0.85, 0.70),  // Light green
-        (0.70, 0.70, 0.85),  // Light blue
-    ];
-    
-    // Sort areas by number of neighbors (descending) for better coloring
-    let mut area_list: Vec<u32> = found_areas.iter().copied().filter(|&a| a != 0).collect();
-    area_list.sort_by_key(|&a| std::cmp::Reverse(neighbors.get(&a).map(|n| n.len()).unwrap_or(0)));
-    
-    for area_id in area_list {
-        // Find colors used by neighbors
-        let neighbor_colors: HashSet<usize> = neighbors
-            .get(&area_id)
-            .map(|ns| {
-                ns.iter()
-                    .filter_map(|&n| {
-                        colors.get(&n).and_then(|c| {
-                            palette.iter().position(|p| {
-                                (p.0 - c.0).abs() < 0.01 && 
-                                (p.1 - c.1).abs() < 0.01 && 
-                                (p.2 - c.2).abs() < 0.01
-                            })
-                        })
-                    })
-                    .collect()
-            })
-            .unwrap_or_default();
-        
-        // Also avoid parent color
-        let parent_id = areas.get(&area_id).map(|a| a.parent_id).unwrap_or(0);
-        let parent_color_idx: Option<usize> = colors.get(&parent_id).and_then(|c| {
-            palette.iter().position(|p| {
-                (p.0 - c.0).abs() < 0.01 && 
-                (p.1 - c.1).abs() < 0.01 && 
-                (p.2 - c.2).abs() < 0.01
-            })
-        });
-        
-        // Find first available color
-        let mut chosen_idx = 0;
-        for i in 0..palette.len() {
-            if !neighbor_colors.contains(&i) && parent_color_idx != Some(i) {
-                chosen_idx = i;
-                break;
+            if !missing.is_empty() {
+                return Err(format!("not in {}: {}", args.listfile.display(), missing.join(", ")).into());
             }
         }
-        
-        // If all colors used, generate a unique one based on area_id
-        let color = if chosen_idx < palette.len() && !neighbor_colors.contains(&chosen_idx) {
-            palette[chosen_idx]
-        } else {
-            // Fallback: generate unique color
-            let golden_ratio = 0.618033988749895_f64;
-            let hue = ((area_id as f64) * golden_ratio) % 1.0;
-            let s = 0.7_f64;
-            let v = 0.9_f64;
-            let c = v * s;
-            let x = c * (1.0 - ((hue * 6.0) % 2.0 - 1.0).abs());
-            let m = v - c;
-            
-            let (r, g, b) = match (hue * 6.0) as i32 {
-                0 => (c, x, 0.0),
-                1 => (x, c, 0.0),
-                2 => (0.0, c, x),
-                3 => (0.0, x, c),
-                4 => (x, 0.0, c),
-                _ => (c, 0.0, x),
-            };
-            ((r + m) as f32, (g + m) as f32, (b + m) as f32)
-        };
-        
-        colors.insert(area_id, color);
     }
-    
-    colors
+    Ok(())
 }
 
-/// Export area info to Lua
-fn export_area_info(
-    found_areas: &BTreeSet<u32>,
-    areas: &HashMap<u32, AreaInfo>,
-    colors: &HashMap<u32, (f32, f32, f32)>,
-    neighbors: &NeighborGraph,
-    out_path: &Path,
-) -> std::io::Result<()> {
-    let mut f = File::create(out_path)?;
-    
-    writeln!(f, "-- Auto-generated Area Info")?;
-    writeln!(f, "-- Contains name, parent, level, color, and neighbors for each area")?;
-    writeln!(f)?;
-    writeln!(f, "local _, addon = ...")?;
-    writeln!(f)?;
-    writeln!(f, "addon.AreaInfo = {{")?;
+/// Write the chunks that differ from a shipped export, by a diff and edits
+/// files, as a Hotfix.lua
+fn hotfix(args: &HotfixArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let (base, _) = read_version(&args.shipped)?.ok_or_else(|| format!("{} has no {}; hotfixes apply to a versioned export", args.shipped.display(), VERSION_FILE))?;
+    let shipped = ExportSnapshot::load(&args.shipped)?;
+    let mut fix = Hotfix::new(base);
+    let mut skipped = Vec::new();
+    if let Some(new_dir) = &args.new {
+        let (new, _) = load_snapshot(new_dir)?;
+        skipped.extend(fix.add_diff(&diff_exports(&shipped, &new)));
+    }
+    for path in &args.edits {
+        let map = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.strip_suffix("_edits.csv"))
+            .ok_or_else(|| format!("{}: edits files are named <Map>_edits.csv", path.display()))?;
+        let rows = read_chunk_rows(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        skipped.extend(fix.add_edits(&shipped, map, &rows));
+    }
     
-    for &area_id in found_areas {
-        if area_id == 0 {
-            continue;
+    if !skipped.is_empty() {
+        warn!("{} changes need a release, not a hotfix:", skipped.len());
+        for msg in &skipped {
+            warn!("  {}", msg);
         }
-        
-        let (name, parent_id, root_parent, level) = if let Some(area) = areas.get(&area_id) {
-            let root = find_root_parent(area_id, areas);
-            (area.name.clone(), area.parent_id, root, area.exploration_level)
-        } else {
-            (format!("Unknown_{}", area_id), 0, area_id, 0)
-        };
-        
-        let (r, g, b) = colors.get(&area_id).copied().unwrap_or((0.5, 0.5, 0.5));
-        let escaped_name = name.replace("\"", "\\\"");
-        
-        // Get neighbor count for info
-        let neighbor_count = neighbors.get(&area_id).map(|n| n.len()).unwrap_or(0);
-        
-        writeln!(f, "  [{}] = {{", area_id)?;
-        writeln!(f, "    name = \"{}\",", escaped_name)?;
-        writeln!(f, "    parentId = {},", parent_id)?;
-        writeln!(f, "    rootParentId = {},", root_parent)?;
-        writeln!(f, "    explorationLevel = {},", level)?;
-        writeln!(f, "    color = {{{:.3}, {:.3}, {:.3}}},", r, g, b)?;
-        writeln!(f, "    neighborCount = {},", neighbor_count)?;
-        writeln!(f, "  }},")?;
     }
-    
-    writeln!(f, "}}")?;
+    if fix.is_empty() {
+        info!("No chunks differ from data {}; nothing to write", base);
+        return Ok(());
+    }
+    let out_path = args.out.clone().unwrap_or_else(|| args.shipped.join(HOTFIX_FILE));
+    fix.write_lua(&out_path)?;
+    info!("Wrote: {} ({} chunks in {} grids, for data {})", out_path.display(), fix.chunks(), fix.grids.len(), base);
     Ok(())
 }
 
-fn export_area_hierarchy(
-    found_areas: &BTreeSet<u32>,
-    areas: &HashMap<u32, AreaInfo>,
-    out_path: &Path,
-) -> std::io::Result<()> {
-    // Group areas by root parent
-    let mut hierarchy: BTreeMap<u32, BTreeMap<u32, String>> = BTreeMap::new();
-    
-    for &area_id in found_areas {
-        if area_id == 0 {
-            continue;
-        }
-        
-        let root_parent = find_root_parent(area_id, areas);
-        let name = if let Some(area) = areas.get(&area_id) {
-            area.name.clone()
-        } else {
-            format!("Unknown_{}", area_id)
-        };
-        
-        hierarchy
-            .entry(root_parent)
-            .or_insert_with(BTreeMap::new)
-            .insert(area_id, name);
+/// An export to compare, or the area IDs of an ADT directory
+fn load_snapshot(dir: &Path) -> Result<(ExportSnapshot, bool), Box<dyn std::error::Error>> {
+    if !ExportSnapshot::is_adt_dir(dir) {
+        return Ok((ExportSnapshot::load(dir)?, false));
     }
-    
-    let mut f = File::create(out_path)?;
-    
-    writeln!(f, "-- Auto-generated Area Hierarchy")?;
-    writeln!(f, "-- Groups areas by their root parent zone")?;
-    writeln!(f)?;
-    writeln!(f, "local _, addon = ...")?;
-    writeln!(f)?;
-    writeln!(f, "addon.AreaHierarchy = {{")?;
-    
-    for (root_id, children) in &hierarchy {
-        let root_name = if let Some(area) = areas.get(root_id) {
-            area.name.replace("\"", "\\\"")
-        } else {
-            format!("Unknown_{}", root_id)
-        };
-        
-        writeln!(f, "  [{}] = {{  -- {}", root_id, root_name)?;
-        writeln!(f, "    name = \"{}\",", root_name)?;
-        writeln!(f, "    children = {{")?;
-        
-        for (child_id, child_name) in children {
-            let escaped = child_name.replace("\"", "\\\"");
-            writeln!(f, "      [{}] = \"{}\",", child_id, escaped)?;
+    let (snapshot, failures) = ExportSnapshot::scan(dir)?;
+    if !failures.is_empty() {
+        eprintln!("{} files in {} failed to parse; their tiles count as removed:", failures.len(), dir.display());
+        for failure in failures.iter().take(5) {
+            eprintln!("  {}", failure);
         }
-        
-        writeln!(f, "    }},")?;
-        writeln!(f, "  }},")?;
     }
-    
-    writeln!(f, "}}")?;
-    
-    println!("  {} root zones, {} total areas", hierarchy.len(), found_areas.len());
-    Ok(())
+    Ok((snapshot, true))
 }
 
-// ============================================================================
-// ADT / Tile parsing
-// ============================================================================
-
-fn parse_root_adt_filename(path: &Path) -> Option<(String, u32, u32)> {
-    if path.extension()?.to_str()?.to_ascii_lowercase() != "adt" {
-        return None;
+/// Print the changes between two generated data directories (or an ADT
+/// directory in place of either) as Markdown
+fn diff(old_dir: &Path, new_dir: &Path, summary: bool) -> ExitCode {
+    let loaded = load_snapshot(old_dir).and_then(|old| Ok((old, load_snapshot(new_dir)?)));
+    match loaded {
+        Ok(((mut old, old_scanned), (mut new, new_scanned))) => {
+            // A scan has no AreaInfo.lua; take the export's areas so they don't all show as removed
+            if old_scanned {
+                old.areas = new.areas.clone();
+            } else if new_scanned {
+                new.areas = old.areas.clone();
+            }
+            let title = format!("{} -> {}", old_dir.display(), new_dir.display());
+            let changes = diff_exports(&old, &new);
+            print!("{}", render_changelog(&title, &changes, &old, &new));
+            if !summary {
+                print!("{}", render_chunk_changes(&changes, &old, &new));
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Failed to load exports: {}", e);
+            ExitCode::FAILURE
+        }
     }
-    let stem = path.file_stem()?.to_str()?.to_string();
-    let parts: Vec<&str> = stem.split('_').collect();
-    if parts.len() != 3 {
-        return None;
+}
+
+/// Stop scans cleanly on SIGINT/SIGTERM: the file being read is finished, the
+/// checkpoint flushed and claimed jobs handed back. A second signal exits at once.
+fn stop_on_signal() {
+    let installed = ctrlc::set_handler(|| {
+        if STOP.swap(true, Ordering::SeqCst) {
+            std::process::exit(EXIT_INTERRUPTED.into());
+        }
+        warn!("Stopping after the current file; signal again to quit now");
+    });
+    if let Err(e) = installed {
+        warn!("Warning: no signal handler ({}); an interrupted run keeps less progress", e);
     }
-    let map = parts[0].to_string();
-    let x: u32 = parts[1].parse().ok()?;
-    let y: u32 = parts[2].parse().ok()?;
-    Some((map, x, y))
 }
 
-fn tile_key(tile_x: u32, tile_y: u32) -> u32 {
-    tile_y * 64 + tile_x
+fn stopping() -> bool {
+    STOP.load(Ordering::SeqCst)
 }
 
-fn encode_tile_b64(area_ids_256: &[u32]) -> Result<String, Box<dyn std::error::Error>> {
-    if area_ids_256.len() != 256 {
-        return Err(format!("expected 256 area IDs, got {}", area_ids_256.len()).into());
+fn exit_status(context: &str, result: Result<(), Box<dyn std::error::Error>>) -> ExitCode {
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(_) if stopping() => ExitCode::from(EXIT_INTERRUPTED),
+        Err(e) => {
+            error!("{}: {}", context, e);
+            ExitCode::FAILURE
+        }
     }
+}
 
-    let mut raw = Vec::with_capacity(256 * 4);
-    for &v in area_ids_256 {
-        raw.extend_from_slice(&v.to_le_bytes());
-    }
+// ============================================================================
+// Distributed generation
+// ============================================================================
+//
+// `queue` splits the maps into jobs in a shared directory, any number of
+// `work` processes scan them into journals, and `collect` merges those into
+// zonemap.checkpoint/ so `generate --resume` writes the export without
+// reading the ADTs again.
 
-    Ok(general_purpose::STANDARD.encode(&raw))
+fn queue(args: &QueueArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let manifest = args.maps.manifest(&args.client_build)?;
+    let ignore = load_ignore_list(&args.ignore)?;
+    let queue = WorkQueue::create(&args.dir)?;
+    let (mut added, mut known) = (0, 0);
+    for entry in manifest.entries.iter().filter(|e| !ignore.ignores_map(&e.map)) {
+        for job in Job::split(entry, args.split) {
+            if queue.add(&job)? {
+                added += 1;
+            } else {
+                known += 1;
+            }
+        }
+    }
+    info!("Queued {} jobs in {}", added, args.dir.display());
+    if known > 0 {
+        info!("  {} jobs were already queued", known);
+    }
+    Ok(())
 }
 
-fn parse_adt_areaids(path: &Path) -> Result<Option<Vec<u32>>, Box<dyn std::error::Error>> {
-    let data = fs::read(path)?;
-    let adt = Adt::from_reader(Cursor::new(data))?;
+fn work(args: &WorkArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let queue = WorkQueue::open(&args.dir)?;
+    let worker = args.worker.clone().unwrap_or_else(|| {
+        format!("{}-{}", std::env::var("HOSTNAME").unwrap_or_else(|_| "worker".to_string()), std::process::id())
+    });
 
-    let mut area_ids: Vec<u32> = adt
-        .mcnk_chunks
-        .iter()
-        .map(|chunk| chunk.area_id)
-        .collect();
+    // The same settings as `generate`, so collected journals resume cleanly
+    let (layers, layer_names) = args.scan.layers()?;
+    let (area_layer, extra_layers) = select_layers(&layers, &layer_names)?;
+    let ignore = load_ignore_list(&args.scan.ignore)?;
+    let codecs = args.scan.codecs(&layer_names)?;
+    let sentinels = SentinelGuard::new(&args.scan.sentinels, args.scan.sentinel_area_id);
+
+    let mut finished = 0;
+    while !stopping() {
+        let Some(job) = queue.claim(&worker)? else {
+            break;
+        };
+        info!("\nJob {} ({}, rows {}-{})", job.id, job.source.display(), job.rows.start(), job.rows.end());
+        let checkpoint = Checkpoint::new(&queue.work_dir(&job), false);
+        let mut job_ignore = ignore.clone();
+        if *job.rows.start() > 0 {
+            job_ignore.add_tiles(&job.map, 0..=u32::MAX, 0..=job.rows.start() - 1);
+        }
+        job_ignore.add_tiles(&job.map, 0..=u32::MAX, job.rows.end() + 1..=u32::MAX);
+        let options = ExportOptions {
+            area_layer,
+            extra_layers: &extra_layers,
+            codecs: &codecs,
+            refine_subchunks: job.subchunks.unwrap_or(args.scan.subchunks),
+            ignore: &job_ignore,
+            sentinels: &sentinels,
+            listfile: &args.listfile,
+            tact_keys: args.tact_keys.as_deref(),
+            locale: args.locale.as_deref(),
+            fast: args.fast,
+            compression: Compression::None,
+            checkpoint: &checkpoint,
+            heights: args.heights.map(usize::from),
+            cache: None,
+            limits: args.scan.read_limits(),
+        };
 
-    if area_ids.is_empty() {
-        return Ok(None);
+        let journal = checkpoint.journal_path(&job.map);
+        let result = load_continent(&job.source, &job.map, job.map_id, &options).and_then(|_| {
+            if journal.exists() {
+                Ok(())
+            } else {
+                Err("only ADT directories, MPQ archives and CASC installs can be split into jobs".into())
+            }
+        });
+        match result {
+            Ok(()) => {
+                queue.finish(&job, &journal)?;
+                finished += 1;
+            }
+            // Another worker can scan it from the start
+            Err(_) if stopping() => {
+                queue.release(&job)?;
+                info!("  Put {} back in the queue", job.id);
+            }
+            Err(e) => {
+                error!("  Job {} failed: {}", job.id, e);
+                queue.fail(&job, &e.to_string())?;
+            }
+        }
     }
-    
-    if area_ids.len() != 256 {
-        area_ids.resize(256, 0);
+    if stopping() {
+        return Err(format!("interrupted after {} jobs", finished).into());
     }
-
-    Ok(Some(area_ids))
+    info!("\n{} finished {} jobs; the queue is empty", worker, finished);
+    Ok(())
 }
 
-struct TileGridExport {
-    continent_name: String,
-    tiles_b64: BTreeMap<u32, String>,
-    tiles_raw: HashMap<u32, Vec<u32>>,
-    found_areas: BTreeSet<u32>,
+/// Print the last `limit` runs recorded in `db_path`
+#[cfg(feature = "trends")]
+fn trends(db_path: &Path, limit: usize) -> Result<(), Box<dyn std::error::Error>> {
+    if !db_path.exists() {
+        return Err(format!("{} not found; record runs with `generate --runs-db`", db_path.display()).into());
+    }
+    let db = RunsDb::open(db_path)?;
+    print!("{}", db.render_trends(&db.recent(limit)?));
+    Ok(())
 }
 
-impl TileGridExport {
-    fn new(continent_name: &str) -> Self {
-        Self {
-            continent_name: continent_name.to_string(),
-            tiles_b64: BTreeMap::new(),
-            tiles_raw: HashMap::new(),
-            found_areas: BTreeSet::new(),
+fn collect(dir: &Path, requeue_after: Option<u64>) -> Result<(), Box<dyn std::error::Error>> {
+    let queue = WorkQueue::open(dir)?;
+    if let Some(secs) = requeue_after {
+        for id in queue.requeue_stale(std::time::Duration::from_secs(secs))? {
+            info!("Requeued {} (claimed more than {}s ago)", id, secs);
         }
     }
 
-    fn export_lua(&self, out_path: &Path) -> std::io::Result<()> {
-        let mut f = File::create(out_path)?;
-
-        writeln!(f, "-- Auto-generated AreaID grid for {}", self.continent_name)?;
-        writeln!(f, "-- Each tile is 16x16 chunks (256 u32 AreaIDs), base64 encoded.")?;
-        writeln!(f)?;
-        writeln!(f, "local _, addon = ...")?;
-        writeln!(f)?;
-        writeln!(f, "local tiles = {{")?;
+    let status = queue.status()?;
+    info!(
+        "{}: {} pending, {} claimed, {} done, {} failed",
+        dir.display(),
+        status.pending,
+        status.claimed,
+        status.done,
+        status.failed
+    );
+    for (id, error) in queue.failed()? {
+        error!("  {} failed: {}", id, error);
+    }
 
-        for (k, v) in &self.tiles_b64 {
-            writeln!(f, "  [{}] = [[{}]],", k, v)?;
+    // Every job of a map must have been scanned with the same settings
+    let mut maps: BTreeMap<String, (String, Vec<Record>, usize)> = BTreeMap::new();
+    for (job, journal) in queue.done()? {
+        let (settings, records) = read_journal(&journal)?;
+        let (map_settings, map_records, jobs) =
+            maps.entry(job.map.clone()).or_insert_with(|| (settings.clone(), Vec::new(), 0));
+        if *map_settings != settings {
+            return Err(format!("jobs of {} were scanned with different settings ({})", job.map, job.id).into());
         }
+        map_records.extend(records);
+        *jobs += 1;
+    }
 
-        writeln!(f, "}}")?;
-        writeln!(f)?;
-        writeln!(f, "addon:RegisterTileGrid(\"{}\", {{", self.continent_name)?;
-        writeln!(f, "  name = \"{}\",", self.continent_name)?;
-        writeln!(f, "  tileSize = 16,")?;
-        writeln!(f, "  tilesPerSide = 64,")?;
-        writeln!(f, "  tiles = tiles,")?;
-        writeln!(f, "}})")?;
-        Ok(())
+    let checkpoint = Checkpoint::new(Path::new(CHECKPOINT_DIR), true);
+    for (map, (settings, records, jobs)) in &maps {
+        checkpoint.write(map, settings, records)?;
+        info!("  {}: {} files from {} jobs", map, records.len(), jobs);
+    }
+    if !maps.is_empty() {
+        info!("Wrote {}; run `generate --resume` with the workers' settings to build the export", CHECKPOINT_DIR);
     }
+    if status.pending + status.claimed > 0 {
+        info!("Jobs are still outstanding; their tiles will be read by `generate --resume` itself");
+    }
+    Ok(())
 }
 
-fn build_tile_export(adt_dir: &Path, continent_name: &str) -> Result<TileGridExport, Box<dyn std::error::Error>> {
-    let mut export = TileGridExport::new(continent_name);
+/// The areaid layer, and the other layers of `names`
+fn select_layers<'a>(layers: &'a LayerRegistry, names: &[String]) -> Result<(&'a dyn LayerExtractor, Vec<&'a dyn LayerExtractor>), String> {
+    let names: Vec<&str> = names.iter().map(|n| n.trim()).collect();
+    let selected = layers.select(&names).map_err(|e| format!("Invalid layer selection: {}", e))?;
+    let area_layer = selected.iter().copied().find(|l| l.name() == "areaid").ok_or("The areaid layer must be selected")?;
+    let extra_layers = selected.iter().copied().filter(|l| l.name() != "areaid").collect();
+    Ok((area_layer, extra_layers))
+}
 
-    if !adt_dir.exists() {
-        return Err(format!("Directory not found: {}", adt_dir.display()).into());
+/// The ignore list at `ignore_path`, or an empty list without one
+fn load_ignore_list(ignore_path: &Path) -> Result<IgnoreList, String> {
+    if !ignore_path.exists() {
+        return Ok(IgnoreList::default());
     }
+    IgnoreList::from_file(ignore_path).map_err(|e| format!("Failed to parse {}: {}", ignore_path.display(), e))
+}
 
-    println!("Scanning: {}", adt_dir.display());
-
-    let mut parsed = 0usize;
-
-    for entry in fs::read_dir(adt_dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        if !path.is_file() {
-            continue;
+/// Generate, then send the outcome to the webhook and mail address, if any
+fn generate(args: &GenerateArgs) -> ExitCode {
+    let mut run_report = RunReport { title: format!("ZoneMap run ({})", args.client_build), ..RunReport::default() };
+    let status = generate_into(args, &mut run_report);
+    if args.webhook.is_none() && args.notify_email.is_none() {
+        return status;
+    }
+    
+    let run_status = if status == ExitCode::SUCCESS {
+        RunStatus::Succeeded
+    } else if status == ExitCode::from(EXIT_GATE_FAILED) {
+        RunStatus::GatesFailed
+    } else if status == ExitCode::from(EXIT_INTERRUPTED) {
+        RunStatus::Interrupted
+    } else {
+        RunStatus::Failed
+    };
+    info!("\nSending notifications...");
+    if let Some(url) = &args.webhook {
+        match post_webhook(url, &webhook_payload(url, &run_report, run_status)) {
+            Ok(()) => info!("  Posted to webhook"),
+            Err(e) => error!("Failed to post to webhook: {}", e),
         }
+    }
+    if let Some(to) = &args.notify_email {
+        match send_email(to, &run_report, run_status) {
+            Ok(()) => info!("  Mailed {}", to),
+            Err(e) => error!("Failed to mail {}: {}", to, e),
+        }
+    }
+    status
+}
 
-        let Some((_, tx, ty)) = parse_root_adt_filename(&path) else {
-            continue;
-        };
+/// Write the --report HTML file, if one was asked for
+fn write_run_report(args: &GenerateArgs, run_report: &RunReport, grids: &[ZoneGrid], colors: &HashMap<u32, (f32, f32, f32)>, areas: &HashMap<u32, AreaInfo>) {
+    let Some(report_path) = &args.report else {
+        return;
+    };
+    let area_name = |id: u32| areas.get(&id).map(|a| a.name.clone()).unwrap_or_else(|| if id == 0 { "(none)".to_string() } else { format!("Unknown_{}", id) });
+    if let Err(e) = run_report.write_html(report_path, grids, colors, area_name) {
+        error!("Failed to write report: {}", e);
+    } else {
+        info!("\nWrote report: {}", report_path.display());
+    }
+}
 
-        match parse_adt_areaids(&path) {
-            Ok(Some(area_ids)) => {
-                for &aid in &area_ids {
-                    if aid != 0 {
-                        export.found_areas.insert(aid);
-                    }
-                }
-                
-                let b64 = encode_tile_b64(&area_ids)?;
-                let key = tile_key(tx, ty);
-                export.tiles_b64.insert(key, b64);
-                export.tiles_raw.insert(key, area_ids);
-                parsed += 1;
-            }
-            Ok(None) => {}
+/// Run the jobs of a job file one after another, each with its settings
+/// as `ZONEMAP_` variables under the real environment. Stops when one is
+/// interrupted; otherwise the status is the first failing job's.
+fn generate_jobs(path: &Path, only: Option<&str>) -> ExitCode {
+    let mut jobs = match read_jobs(path) {
+        Ok(jobs) => jobs,
+        Err(e) => {
+            error!("Failed to read job file: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(name) = only {
+        jobs.retain(|j| j.name == name);
+        if jobs.is_empty() {
+            error!("{} has no job named `{}`", path.display(), name);
+            return ExitCode::FAILURE;
+        }
+    }
+    
+    // Every setting has to be some option's variable, or a typo would be ignored
+    let known: HashSet<String> = Cli::command()
+        .find_subcommand("generate")
+        .map(|c| c.get_arguments().filter_map(|a| a.get_env()).map(|v| v.to_string_lossy().into_owned()).collect())
+        .unwrap_or_default();
+    for job in &jobs {
+        if let Some((var, _)) = job.settings.iter().find(|(var, _)| !known.contains(var)) {
+            error!("{}: {}: no generate option reads {}", path.display(), job.name, var);
+            return ExitCode::FAILURE;
+        }
+    }
+    
+    let preset: HashSet<String> = std::env::vars_os().filter_map(|(name, _)| name.into_string().ok()).collect();
+    let mut status = ExitCode::SUCCESS;
+    for (i, job) in jobs.iter().enumerate() {
+        info!("Job {} of {}: {}\n", i + 1, jobs.len(), job.name);
+        let settings: Vec<&(String, String)> = job.settings.iter().filter(|(var, _)| !preset.contains(var)).collect();
+        for (var, value) in &settings {
+            std::env::set_var(var, value);
+        }
+        let parsed = Cli::try_parse();
+        for (var, _) in &settings {
+            std::env::remove_var(var);
+        }
+        let args = match parsed {
+            Ok(Cli { command: Some(Command::Generate(args)), .. }) => *args,
+            Ok(cli) => cli.generate,
             Err(e) => {
-                eprintln!("  ERROR parsing {}: {}", path.display(), e);
+                error!("Job {}: {}", job.name, e);
+                status = ExitCode::FAILURE;
+                continue;
             }
+        };
+        
+        let job_status = generate(&args);
+        if job_status == ExitCode::from(EXIT_INTERRUPTED) {
+            return job_status;
+        }
+        if status == ExitCode::SUCCESS {
+            status = job_status;
         }
     }
-
-    println!("  Parsed {} tiles, found {} unique areas", parsed, export.found_areas.len());
-    Ok(export)
+    status
 }
 
-fn main() {
-    println!("ZoneMap Tile Generator\n");
+fn generate_into(args: &GenerateArgs, run_report: &mut RunReport) -> ExitCode {
+    info!("ZoneMap Tile Generator\n");
+    let started = Instant::now();
+    let client_build = args.client_build.as_str();
     
-    // Load area table
-    let csv_path = Path::new("AreaTable.1.15.8.64907.csv");
-    let areas = if csv_path.exists() {
-        match parse_area_table(csv_path) {
-            Ok(a) => {
-                println!("Loaded {} areas from CSV\n", a.len());
-                a
-            }
-            Err(e) => {
-                eprintln!("Warning: Failed to parse area table: {}", e);
-                HashMap::new()
-            }
+    let areas = load_area_table(client_build);
+    
+    // Create the output directory
+    let out_dir = args.out.as_path();
+    if !out_dir.exists() {
+        if let Err(e) = fs::create_dir_all(out_dir) {
+            error!("Failed to create {}: {}", out_dir.display(), e);
+            return ExitCode::FAILURE;
+        }
+        info!("Created {}/ directory", out_dir.display());
+    }
+    
+    // Area IDs come from the layer registry so custom extractors can stand in
+    let (layers, layer_names) = match args.scan.layers() {
+        Ok(layers) => layers,
+        Err(e) => {
+            error!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let (area_layer, extra_layers) = match select_layers(&layers, &layer_names) {
+        Ok(selected) => selected,
+        Err(e) => {
+            error!("{}", e);
+            return ExitCode::FAILURE;
         }
-    } else {
-        eprintln!("Warning: AreaTable CSV not found\n");
-        HashMap::new()
     };
     
-    // Create Data directory
-    let out_dir = Path::new("Data");
-    if !out_dir.exists() {
-        if let Err(e) = fs::create_dir(out_dir) {
-            eprintln!("Failed to create Data directory: {}", e);
-            return;
+    // Maps and tiles to leave out of discovery
+    let ignore = match load_ignore_list(&args.scan.ignore) {
+        Ok(list) => list,
+        Err(e) => {
+            error!("{}", e);
+            return ExitCode::FAILURE;
         }
-        println!("Created Data/ directory");
+    };
+    
+    let mut metrics = RunMetrics::default();
+    
+    let codecs = match args.scan.codecs(&layer_names) {
+        Ok(c) => c,
+        Err(e) => {
+            error!("Invalid layer codec: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let sentinels = SentinelGuard::new(&args.scan.sentinels, args.scan.sentinel_area_id);
+    let checkpoint = Checkpoint::new(Path::new(CHECKPOINT_DIR), args.resume);
+    let cache = TileCache::new(Path::new(CACHE_DIR), args.hash, args.force);
+    let options = ExportOptions {
+        area_layer,
+        extra_layers: &extra_layers,
+        codecs: &codecs,
+        refine_subchunks: args.scan.subchunks,
+        ignore: &ignore,
+        sentinels: &sentinels,
+        listfile: &args.listfile,
+        tact_keys: args.tact_keys.as_deref(),
+        locale: args.locale.as_deref(),
+        fast: args.fast,
+        compression: args.compression,
+        checkpoint: &checkpoint,
+        heights: args.heights.map(usize::from),
+        cache: Some(&cache),
+        limits: args.scan.read_limits(),
+    };
+    if args.fast && (!extra_layers.is_empty() || args.scan.subchunks || args.heights.is_some()) {
+        info!("Fast mode: reading area IDs only; extra layers, sub-chunks and height maps are skipped");
     }
     
+    // Maps to process: --input, zonemap.manifest, or the two continents
+    let manifest = match args.maps.manifest(&args.client_build) {
+        Ok(m) => m,
+        Err(e) => {
+            error!("Failed to read manifest: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    
+    // Player visits, to favour the tiles players are actually on
+    let heat = if args.heatmap.exists() {
+        match HeatMap::from_csv(&args.heatmap) {
+            Ok(heat) => Some(heat),
+            Err(e) => {
+                error!("Failed to parse {}: {}", args.heatmap.display(), e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    
     // Track all data across continents
     let mut all_found_areas = BTreeSet::new();
-    let mut all_tiles_raw: HashMap<u32, Vec<u32>> = HashMap::new();
+    let mut report: Vec<(String, Option<MapReport>)> = Vec::new();
+    let mut grids: Vec<ZoneGrid> = Vec::new();
     let mut neighbor_graph: NeighborGraph = HashMap::new();
+    let mut wmos: HashMap<u32, Vec<WmoPlacement>> = HashMap::new();
+    let mut timings: Vec<TileTiming> = Vec::new();
+    let mut budget_failures: Vec<String> = Vec::new();
+    let mut verify_failures: Vec<String> = Vec::new();
+    let mut decoders = AddonDecoders::default();
     
-    // Process Kalimdor
-    if let Ok(export) = build_tile_export(Path::new("kalimdor_adts"), "Kalimdor") {
-        all_found_areas.extend(&export.found_areas);
-        
-        // Find neighbors within tiles
-        for area_ids in export.tiles_raw.values() {
-            find_tile_neighbors(area_ids, &mut neighbor_graph);
+    for entry in &manifest.entries {
+        if stopping() {
+            break;
+        }
+        if ignore.ignores_map(&entry.map) {
+            info!("Skipping {} (ignored)", entry.map);
+            continue;
+        }
+        let entry_options = ExportOptions {
+            refine_subchunks: entry.subchunks.unwrap_or(args.scan.subchunks),
+            ..options
+        };
+        let map_started = Instant::now();
+        let mut export = match load_continent(&entry.source, &entry.map, entry.map_id, &entry_options) {
+            Ok(export) => export,
+            Err(_) if stopping() => break,
+            Err(e) => {
+                warn!("Skipping {}: {}", entry.map, e);
+                report.push((entry.map.clone(), None));
+                run_report.skipped.push((entry.map.clone(), e.to_string()));
+                continue;
+            }
+        };
+        // Maps found through Map.dbc include instances that are all WMO
+        if args.maps.maps.is_some() && export.tiles_raw.is_empty() {
+            info!("Skipping {} (no tiles)", entry.map);
+            continue;
         }
+        all_found_areas.extend(&export.found_areas);
+        timings.extend_from_slice(&export.timings);
         
-        // Find neighbors between tiles
-        find_inter_tile_neighbors(&export.tiles_raw, &mut neighbor_graph);
+        // Area borders, within and between tiles
+        add_map_neighbors(&export.tiles_raw, &mut neighbor_graph);
         
         // Export before moving tiles_raw
-        let out_path = out_dir.join("Kalimdor_tiles.lua");
-        if let Err(e) = export.export_lua(&out_path) {
-            eprintln!("Failed to write: {}", e);
-        } else {
-            println!("  Wrote: {}", out_path.display());
+        let matrix = print_coverage_matrix(&export);
+        
+        if let Some(heat) = &heat {
+            apply_heat(&entry.map, &mut export.grid, heat, args.hot_share, args.hot_codec);
+        }
+        
+        let out_path = out_dir.join(entry.output_name());
+        let mut outputs = MapOutputs::default();
+        if args.format.lua() {
+            let options = LuaGridOptions {
+                shard: args.shard,
+                shard_toc: args.shard_toc,
+                budget: args.budget.iter().rev().find(|b| b.format == BudgetFormat::Lua),
+                verify: args.verify,
+            };
+            write_lua_grid(&entry.map, &export.grid, &export.tiles_raw, &out_path, options, &mut outputs);
+        }
+        if args.format.json() {
+            let budget = args.budget.iter().rev().find(|b| b.format == BudgetFormat::Json);
+            write_json_grid(&entry.map, &export.json, &out_path.with_extension("json"), budget, &mut outputs);
+        }
+        if args.binary {
+            write_binary_grid(entry.map_id, &export.tiles_raw, &out_path.with_extension(BINARY_EXTENSION), &mut outputs);
+        }
+        if let Some(heights) = export.heights.as_ref().filter(|h| !h.is_empty()) {
+            write_heights(&entry.map, heights, args.format, out_dir, &mut outputs);
+        }
+        if args.format.lua() && !areas.is_empty() {
+            outputs.files.extend(write_continent_areas(&entry.map, &export.found_areas, &areas, out_dir));
+        }
+        if args.outlines && !export.tiles_raw.is_empty() {
+            write_outlines(&entry.map, &export.tiles_raw, &args.outline_options(), &areas, args.format, out_dir, &mut outputs);
+        }
+        if entry_options.refine_subchunks {
+            write_subchunks(&entry.map, &export.subchunks, out_dir, &mut outputs);
         }
         
-        all_tiles_raw.extend(export.tiles_raw);
+        let failures = export.failures.total() - export.failures.count(FailureKind::SplitFile);
+        metrics.tiles_parsed += export.tiles_raw.len();
+        metrics.parse_failures += failures;
+        metrics.chunks_scanned += export.tiles_raw.values().map(|t| t.len()).sum::<usize>();
+        metrics.sentinel_chunks += export.sentinel_hits.values().sum::<usize>();
+        if let Some(wdt) = export.grid.wdt() {
+            let listed: Vec<u32> = wdt.tiles.iter().copied().filter(|&k| !ignore.ignores_tile(&entry.map, k % 64, k / 64)).collect();
+            metrics.wdt_tiles += listed.len();
+            metrics.missing_tiles += listed.iter().filter(|k| !export.tiles_raw.contains_key(k)).count();
+        }
+        report.push((
+            entry.map.clone(),
+            Some(MapReport {
+                tiles: export.tiles_raw.len(),
+                areas: export.found_areas.len(),
+                failures,
+                sentinels: export.sentinel_hits.values().sum(),
+                output_bytes: outputs.size(),
+                duration: map_started.elapsed(),
+            }),
+        ));
+        run_report.maps.push(MapSection {
+            name: export.continent_name.clone(),
+            tiles: export.tiles_raw.len(),
+            areas: export.found_areas.len(),
+            failures,
+            sentinels: export.sentinel_hits.values().sum(),
+            matrix,
+            errors: export
+                .failures
+                .by_kind
+                .iter()
+                .filter(|(kind, _)| **kind != FailureKind::SplitFile)
+                .flat_map(|(kind, files)| files.iter().map(move |(path, msg)| format!("{}: {}: {}", kind.label(), path.display(), msg)))
+                .collect(),
+        });
+        budget_failures.extend(outputs.budget_failures);
+        verify_failures.extend(outputs.verify_failures);
+        decoders.merge(outputs.decoders);
+        wmos.entry(entry.map_id).or_default().extend(export.wmos);
+        let mut grid = ZoneGrid::new(&export.continent_name, entry.map_id, export.tiles_raw);
+        grid.set_missing_tile_policy(entry.missing_tiles);
+        grids.push(grid);
     }
     
-    // Process Azeroth
-    if let Ok(export) = build_tile_export(Path::new("azeroth_adts"), "Azeroth") {
-        all_found_areas.extend(&export.found_areas);
-        
-        for area_ids in export.tiles_raw.values() {
-            find_tile_neighbors(area_ids, &mut neighbor_graph);
+    // Nothing is written past the maps already done; the rest waits for --resume
+    if stopping() {
+        error!("\nInterrupted; {} keeps the files read so far for --resume", checkpoint.dir().display());
+        return ExitCode::from(EXIT_INTERRUPTED);
+    }
+
+    // Every map is scanned; a run that skipped one keeps its progress for --resume
+    if report.iter().all(|(_, r)| r.is_some()) {
+        if let Err(e) = checkpoint.clear() {
+            error!("Failed to remove {}: {}", checkpoint.dir().display(), e);
         }
-        find_inter_tile_neighbors(&export.tiles_raw, &mut neighbor_graph);
-        
-        // Export before moving tiles_raw
-        let out_path = out_dir.join("Azeroth_tiles.lua");
-        if let Err(e) = export.export_lua(&out_path) {
-            eprintln!("Failed to write: {}", e);
-        } else {
-            println!("  Wrote: {}", out_path.display());
+    } else if checkpoint.dir().is_dir() {
+        info!("\nKeeping {} for --resume", checkpoint.dir().display());
+    }
+    
+    // One line per map so batch runs are easy to check at a glance
+    print_map_summary(&report, all_found_areas.len());
+    
+    // Expanders and accessors for what the grids just written use
+    if args.format.lua() {
+        write_decoders(&decoders, out_dir);
+    }
+    
+    let theme = load_theme(&args.theme_file, args.theme.as_deref(), client_build);
+    let colors = area_colors(&all_found_areas, &neighbor_graph, &areas, theme.as_ref());
+    
+    metrics.areas_found = all_found_areas.len();
+    metrics.unknown_areas = all_found_areas.iter().filter(|id| !areas.contains_key(id)).count();
+    
+    // Gates and --verify only need the maps. A run that fails them stops
+    // here, before anything is versioned, signed, stored or recorded.
+    let mut failed_gates: Vec<String> = args.fail_on.iter().filter_map(|t| t.check(&metrics).err()).collect();
+    failed_gates.extend(budget_failures);
+    run_report.gate_failures = failed_gates.clone();
+    
+    if !verify_failures.is_empty() {
+        error!("\nVerification failed:");
+        for msg in &verify_failures {
+            error!("  {}", msg);
         }
-        
-        all_tiles_raw.extend(export.tiles_raw);
+        write_run_report(args, run_report, &grids, &colors, &areas);
+        return ExitCode::from(EXIT_VERIFY_FAILED);
+    }
+    
+    if !failed_gates.is_empty() {
+        error!("\nQuality gates failed:");
+        for msg in &failed_gates {
+            error!("  {}", msg);
+        }
+        write_run_report(args, run_report, &grids, &colors, &areas);
+        return ExitCode::from(EXIT_GATE_FAILED);
     }
     
-    // Generate colors using neighbor graph
-    println!("\nBuilding neighbor graph...");
-    println!("  Found {} areas with neighbor relationships", neighbor_graph.len());
+    // Patch edited area IDs back into copies of the source ADTs
+    patch_edited_adts(&manifest.entries);
+    
+    // User attribute tables joined into AreaInfo, one sub-table per CSV
+    let attributes = match load_attributes(&args.attributes, &areas) {
+        Ok(tables) => tables,
+        Err(e) => {
+            error!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    
+    write_area_info(&all_found_areas, &areas, &colors, &neighbor_graph, &attributes, args.format, out_dir);
+    write_area_hierarchy(&all_found_areas, &areas, out_dir);
     
-    let colors = generate_colors_with_graph(&all_found_areas, &neighbor_graph, &areas);
+    // UI map placement, for normalized zone-map coordinates next to world ones
+    let assignments = load_ui_map_assignments(client_build);
+    write_zone_triggers(&grids, &areas, &assignments, out_dir);
+    write_area_triggers(&grids, client_build, out_dir);
+    write_graveyards(&grids, &areas, client_build, out_dir);
+    write_dungeon_entrances(&grids, &wmos, &args.entrances, args.fast, out_dir);
+    write_area_weather(&all_found_areas, &areas, &args.weather, out_dir);
+    write_map_to_area(&grids, &assignments, &areas, out_dir);
     
-    // Export area info with graph-colored colors
-    println!("\nGenerating area info...");
-    let area_info_path = out_dir.join("AreaInfo.lua");
-    if let Err(e) = export_area_info(&all_found_areas, &areas, &colors, &neighbor_graph, &area_info_path) {
-        eprintln!("Failed to write area info: {}", e);
-    } else {
-        println!("  Wrote: {}", area_info_path.display());
+    // Report which areas players actually visit
+    match &heat {
+        Some(heat) => report_coverage(heat, &grids, &areas),
+        None => info!("\nSkipping coverage ({} not found)", args.heatmap.display()),
     }
     
-    // Export area hierarchy grouped by root parent
-    println!("\nGenerating area hierarchy...");
-    let hierarchy_path = out_dir.join("AreaHierarchy.lua");
-    if let Err(e) = export_area_hierarchy(&all_found_areas, &areas, &hierarchy_path) {
-        eprintln!("Failed to write area hierarchy: {}", e);
-    } else {
-        println!("  Wrote: {}", hierarchy_path.display());
-    }
-    
-    // Export map ID to area ID mapping
-    let map_csv_path = Path::new("mapIdToArea.csv");
-    if map_csv_path.exists() {
-        println!("\nGenerating map to area mapping...");
-        match parse_map_to_area_csv(map_csv_path) {
-            Ok(entries) => {
-                println!("  Loaded {} map-to-area entries", entries.len());
-                let map_path = out_dir.join("MapToArea.lua");
-                if let Err(e) = export_map_to_area(&entries, &map_path) {
-                    eprintln!("Failed to write map to area: {}", e);
-                } else {
-                    println!("  Wrote: {}", map_path.display());
-                }
-            }
-            Err(e) => {
-                eprintln!("Failed to parse mapIdToArea.csv: {}", e);
-            }
+    // Data version and release notes against the previous export:
+    // --previous, or the newest stored snapshot for this build. The
+    // comparison reads the Lua files, so a JSON-only run skips it.
+    let previous = Some(args.previous.clone())
+        .filter(|p| p.is_dir())
+        .or_else(|| Store::new(&args.store).latest(client_build))
+        .filter(|_| args.format.lua());
+    let data_version = match version_export(out_dir, previous.as_deref(), client_build) {
+        Ok((data_version, changelog)) => {
+            run_report.changelog = changelog;
+            data_version
         }
-    } else {
-        println!("\nSkipping map-to-area (mapIdToArea.csv not found)");
+        Err(e) => {
+            error!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    
+    // Sign the bundle so servers can check it wasn't altered in distribution
+    #[cfg(feature = "signing")]
+    if let Err(e) = sign_export(out_dir, &args.signing_key) {
+        error!("{}", e);
+        return ExitCode::FAILURE;
+    }
+    
+    // Keep a snapshot of this build's output when the store directory exists
+    store_export(&args.store, args.hash, client_build, out_dir);
+    
+    if let Some(limit) = args.profile_slowest {
+        info!("\n{}", render_slowest(&timings, limit).trim_end());
+    }
+    
+    run_report.title = format!("ZoneMap data {} ({})", data_version, client_build);
+    write_run_report(args, run_report, &grids, &colors, &areas);
+    
+    #[cfg(feature = "sqlite")]
+    if let Some(db_path) = &args.sqlite {
+        write_sqlite(db_path, &grids, &areas, data_version, client_build);
+    }
+    
+    #[cfg(feature = "trends")]
+    if let Some(db_path) = &args.runs_db {
+        record_run(db_path, started, &metrics, data_version, client_build, out_dir);
     }
     
-    println!("\nDone!");
+    info!("\nDone! ({:.1}s)", started.elapsed().as_secs_f64());
+    ExitCode::SUCCESS
 }
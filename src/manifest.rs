@@ -0,0 +1,426 @@
+#[cfg(feature = "dbc")]
+use crate::dbc::Dbc;
+use crate::grid::MissingTilePolicy;
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+// ============================================================================
+// Batch manifest
+// ============================================================================
+
+/// One map to generate
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ManifestEntry {
+    /// Map name as used in ADT file names and output files
+    pub map: String,
+    /// Map.dbc ID
+    pub map_id: u32,
+    /// ADT directory, TrinityCore `maps/` directory or chunk CSV
+    pub source: PathBuf,
+    /// Overrides the global sub-chunk refinement setting
+    pub subchunks: Option<bool>,
+    /// Output file name in Data/ (default `<Map>_tiles.lua`)
+    pub output: Option<String>,
+    /// Lookups on tiles this map doesn't have
+    pub missing_tiles: MissingTilePolicy,
+}
+
+impl ManifestEntry {
+    pub fn new(map: &str, map_id: u32, source: &str) -> Self {
+        Self {
+            map: map.to_string(),
+            map_id,
+            source: PathBuf::from(source),
+            subchunks: None,
+            output: None,
+            missing_tiles: MissingTilePolicy::default(),
+        }
+    }
+
+    pub fn output_name(&self) -> String {
+        self.output.clone().unwrap_or_else(|| format!("{}_tiles.lua", self.map))
+    }
+}
+
+/// Map.dbc ID of a well-known map, by the name its ADT files use
+pub fn known_map_id(map: &str) -> Option<u32> {
+    const KNOWN: &[(&str, u32)] = &[("Azeroth", 0), ("Kalimdor", 1), ("Expansion01", 530), ("Northrend", 571)];
+    KNOWN.iter().find(|(name, _)| name.eq_ignore_ascii_case(map)).map(|&(_, id)| id)
+}
+
+// ============================================================================
+// Map.dbc discovery
+// ============================================================================
+//
+// Every map the client knows (continents, dungeons, raids, battlegrounds,
+// arenas) is a Map.dbc row whose `Directory` is the name its ADTs use
+// (`World\Maps\<Directory>\<Directory>_<x>_<y>.adt`). The first three
+// fields, ID, Directory and InstanceType, sit at the same place from 1.12
+// through 4.x; later columns moved between expansions and aren't read.
+
+/// Fields of Map.dbc that `parse_map_dbc` reads
+#[cfg(feature = "dbc")]
+const MAP_DBC_FIELDS: usize = 3;
+
+/// Map.dbc InstanceType
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MapKind {
+    Continent,
+    Dungeon,
+    Raid,
+    Battleground,
+    Arena,
+    /// Any other InstanceType (scenarios and the like)
+    Other(u32),
+}
+
+impl MapKind {
+    pub fn from_instance_type(value: u32) -> Self {
+        match value {
+            0 => MapKind::Continent,
+            1 => MapKind::Dungeon,
+            2 => MapKind::Raid,
+            3 => MapKind::Battleground,
+            4 => MapKind::Arena,
+            other => MapKind::Other(other),
+        }
+    }
+
+    /// The `--maps` word selecting every map of this kind
+    fn selector(&self) -> Option<&'static str> {
+        match self {
+            MapKind::Continent => Some("continents"),
+            MapKind::Dungeon => Some("dungeons"),
+            MapKind::Raid => Some("raids"),
+            MapKind::Battleground => Some("battlegrounds"),
+            MapKind::Arena => Some("arenas"),
+            MapKind::Other(_) => None,
+        }
+    }
+}
+
+/// One Map.dbc row
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MapRecord {
+    pub id: u32,
+    /// Internal name, as used in ADT paths and file names
+    pub directory: String,
+    pub kind: MapKind,
+}
+
+/// Maps of a WDBC/WDB2 Map.dbc
+#[cfg(feature = "dbc")]
+pub fn parse_map_dbc(dbc: &Dbc) -> Result<Vec<MapRecord>, String> {
+    if dbc.field_count < MAP_DBC_FIELDS {
+        return Err(format!("Map.dbc has {} fields, expected at least {}", dbc.field_count, MAP_DBC_FIELDS));
+    }
+    Ok(dbc
+        .records()
+        .map(|r| MapRecord { id: r.u32(0), directory: r.string(1).to_string(), kind: MapKind::from_instance_type(r.u32(2)) })
+        .filter(|m| !m.directory.is_empty())
+        .collect())
+}
+
+/// Parse a Map CSV export (wago.tools column names)
+pub fn parse_map_csv(csv_path: &Path) -> Result<Vec<MapRecord>, Box<dyn std::error::Error>> {
+    let reader = BufReader::new(File::open(csv_path)?);
+    let mut lines = reader.lines();
+    let header = lines.next().ok_or("Empty CSV")??;
+
+    let columns: Vec<&str> = header.split(',').map(|c| c.trim()).collect();
+    let col = |name: &str| columns.iter().position(|&c| c == name).ok_or_else(|| format!("No {} column", name));
+    let id_idx = col("ID")?;
+    let dir_idx = col("Directory")?;
+    let type_idx = col("InstanceType")?;
+
+    let mut maps = Vec::new();
+    for line in lines {
+        let line = line?;
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim().trim_matches('"')).collect();
+        let (Some(id), Some(directory), Some(kind)) = (fields.get(id_idx), fields.get(dir_idx), fields.get(type_idx)) else {
+            continue;
+        };
+        let (Ok(id), Ok(kind)) = (id.parse(), kind.parse()) else {
+            continue;
+        };
+        if !directory.is_empty() {
+            maps.push(MapRecord { id, directory: directory.to_string(), kind: MapKind::from_instance_type(kind) });
+        }
+    }
+    Ok(maps)
+}
+
+/// The maps a `--maps` selection names: a comma-separated list of
+/// directories, IDs, kinds (`continents`, `dungeons`, `raids`,
+/// `battlegrounds`, `arenas`) or `all`. Each map is listed once, in Map.dbc
+/// order.
+pub fn select_maps<'a>(maps: &'a [MapRecord], selection: &str) -> Result<Vec<&'a MapRecord>, String> {
+    let mut selected = vec![false; maps.len()];
+    for word in selection.split(',').map(str::trim).filter(|w| !w.is_empty()) {
+        let matches: Vec<usize> = (0..maps.len())
+            .filter(|&i| {
+                let map = &maps[i];
+                word.eq_ignore_ascii_case("all")
+                    || map.directory.eq_ignore_ascii_case(word)
+                    || word.parse() == Ok(map.id)
+                    || map.kind.selector().is_some_and(|s| s.eq_ignore_ascii_case(word))
+            })
+            .collect();
+        if matches.is_empty() {
+            return Err(format!("no map matches `{}`", word));
+        }
+        for i in matches {
+            selected[i] = true;
+        }
+    }
+    Ok(maps.iter().zip(selected).filter(|(_, s)| *s).map(|(m, _)| m).collect())
+}
+
+/// Maps processed in one run.
+///
+/// One entry per line, `#` starts a comment:
+/// ```text
+/// # map     id  source          options
+/// Kalimdor  1   kalimdor_adts
+/// Azeroth   0   azeroth_adts    subchunks=true
+/// Kalimdor  1   Kalimdor_chunks.csv output=Kalimdor_imported.lua
+/// Expansion01 530 maps          missing=ocean:3525
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Manifest {
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl Default for Manifest {
+    /// The two classic continents from their usual ADT directories
+    fn default() -> Self {
+        Self {
+            entries: vec![
+                ManifestEntry::new("Kalimdor", 1, "kalimdor_adts"),
+                ManifestEntry::new("Azeroth", 0, "azeroth_adts"),
+            ],
+        }
+    }
+}
+
+impl Manifest {
+    /// One entry per map. When `root` holds a directory named after any of
+    /// the maps (matched case-insensitively), each map reads from its own
+    /// `root/<Directory>` and maps without one are left out. Otherwise every
+    /// map reads from `root` itself: a client's `Data/` folder, a CASC
+    /// install, a `maps/` directory or one directory of every map's ADTs.
+    pub fn from_maps(maps: &[&MapRecord], root: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let subdirs: Vec<PathBuf> = fs::read_dir(root)
+            .map(|entries| entries.flatten().map(|e| e.path()).filter(|p| p.is_dir()).collect())
+            .unwrap_or_default();
+        let subdir = |map: &MapRecord| {
+            subdirs.iter().find(|dir| dir.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.eq_ignore_ascii_case(&map.directory)))
+        };
+        let per_map = maps.iter().any(|map| subdir(map).is_some());
+        let entries: Vec<ManifestEntry> = maps
+            .iter()
+            .filter_map(|map| {
+                let source = match subdir(map) {
+                    Some(dir) => dir.clone(),
+                    None if per_map => return None,
+                    None => root.to_path_buf(),
+                };
+                let mut entry = ManifestEntry::new(&map.directory, map.id, "");
+                entry.source = source;
+                Some(entry)
+            })
+            .collect();
+        if entries.is_empty() {
+            return Err("no maps selected".into());
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut entries = Vec::new();
+
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let err = |msg: String| format!("line {}: {}", line_no + 1, msg);
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [map, map_id, source, options @ ..] = fields.as_slice() else {
+                return Err(err("expected `<map> <map-id> <source> [options]`".to_string()).into());
+            };
+            let map_id: u32 = map_id.parse().map_err(|_| err(format!("bad map ID `{}`", map_id)))?;
+            let mut entry = ManifestEntry::new(map, map_id, source);
+
+            for option in options {
+                let (key, value) = option
+                    .split_once('=')
+                    .ok_or_else(|| err(format!("expected key=value, got `{}`", option)))?;
+                match key {
+                    "subchunks" => {
+                        entry.subchunks = Some(value.parse().map_err(|_| err(format!("bad subchunks `{}`", value)))?)
+                    }
+                    "output" => entry.output = Some(value.to_string()),
+                    "missing" => entry.missing_tiles = value.parse().map_err(err)?,
+                    other => {
+                        return Err(err(format!("unknown option `{}` (subchunks, output, missing)", other)).into())
+                    }
+                }
+            }
+
+            if entries.iter().any(|e: &ManifestEntry| e.output_name() == entry.output_name()) {
+                return Err(err(format!("{} is written by an earlier entry", entry.output_name())).into());
+            }
+            entries.push(entry);
+        }
+
+        if entries.is_empty() {
+            return Err("manifest has no entries".into());
+        }
+        Ok(Self { entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("zonemap-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn record(id: u32, directory: &str, kind: MapKind) -> MapRecord {
+        MapRecord { id, directory: directory.to_string(), kind }
+    }
+
+    #[test]
+    fn entries_and_options_are_read() {
+        let manifest = Manifest::parse(
+            "# map     id  source          options\n\
+             Kalimdor  1   kalimdor_adts\n\
+             \n\
+             Azeroth   0   azeroth_adts    subchunks=true   # refined\n\
+             Kalimdor  1   Kalimdor_chunks.csv output=Kalimdor_imported.lua\n\
+             Expansion01 530 maps          missing=ocean:3525\n",
+        )
+        .unwrap();
+        let entries = &manifest.entries;
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0], ManifestEntry::new("Kalimdor", 1, "kalimdor_adts"));
+        assert_eq!(entries[1].subchunks, Some(true));
+        assert_eq!(entries[2].output_name(), "Kalimdor_imported.lua");
+        assert_eq!(entries[3].output_name(), "Expansion01_tiles.lua");
+        assert_eq!(entries[3].missing_tiles, MissingTilePolicy::Ocean(3525));
+    }
+
+    #[test]
+    fn malformed_manifests_are_errors() {
+        let error = |text: &str| Manifest::parse(text).unwrap_err().to_string();
+        assert_eq!(error("# nothing\n\n"), "manifest has no entries");
+        assert_eq!(error("Azeroth 0\n"), "line 1: expected `<map> <map-id> <source> [options]`");
+        assert_eq!(error("\nAzeroth zero adts\n"), "line 2: bad map ID `zero`");
+        assert_eq!(error("Azeroth 0 adts subchunks\n"), "line 1: expected key=value, got `subchunks`");
+        assert_eq!(error("Azeroth 0 adts subchunks=maybe\n"), "line 1: bad subchunks `maybe`");
+        assert_eq!(error("Azeroth 0 adts colour=red\n"), "line 1: unknown option `colour` (subchunks, output, missing)");
+        assert!(error("Azeroth 0 adts missing=ocean:sea\n").starts_with("line 1: bad ocean area"));
+        assert_eq!(
+            error("Azeroth 0 a\nKalimdor 1 b output=Azeroth_tiles.lua\n"),
+            "line 2: Azeroth_tiles.lua is written by an earlier entry"
+        );
+    }
+
+    #[test]
+    fn selections_name_maps_ids_and_kinds() {
+        let maps = vec![
+            record(0, "Azeroth", MapKind::Continent),
+            record(1, "Kalimdor", MapKind::Continent),
+            record(33, "Shadowfang", MapKind::Dungeon),
+            record(489, "PVPZone03", MapKind::Battleground),
+            record(1000, "Scenario", MapKind::Other(5)),
+        ];
+        let ids = |selection: &str| select_maps(&maps, selection).map(|m| m.iter().map(|m| m.id).collect::<Vec<u32>>());
+        assert_eq!(ids("kalimdor, 33"), Ok(vec![1, 33]));
+        // Listed once each, in Map.dbc order
+        assert_eq!(ids("battlegrounds,CONTINENTS,0"), Ok(vec![0, 1, 489]));
+        assert_eq!(ids("all"), Ok(vec![0, 1, 33, 489, 1000]));
+        assert_eq!(ids("raids"), Err("no map matches `raids`".to_string()));
+        assert_eq!(ids(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn map_csvs_skip_unreadable_rows() {
+        let dir = scratch("manifest-csv");
+        let path = dir.join("Map.csv");
+        fs::write(&path, "ID,Directory,MapName_lang,InstanceType\n0,Azeroth,\"Eastern Kingdoms\",0\n36,,Deadmines,1\nx,Broken,Broken,1\n409,MoltenCore,\"Molten Core\",2\n").unwrap();
+        let maps = parse_map_csv(&path).unwrap();
+        assert_eq!(maps, vec![record(0, "Azeroth", MapKind::Continent), record(409, "MoltenCore", MapKind::Raid)]);
+
+        fs::write(&path, "ID,Directory\n0,Azeroth\n").unwrap();
+        assert_eq!(parse_map_csv(&path).unwrap_err().to_string(), "No InstanceType column");
+        fs::write(&path, "").unwrap();
+        assert!(parse_map_csv(&path).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "dbc")]
+    #[test]
+    fn map_dbcs_read_id_directory_and_kind() {
+        let wdbc = |fields: u32, rows: &[[u32; 3]]| {
+            let strings = b"\0Azeroth\0";
+            let mut data = b"WDBC".to_vec();
+            for v in [rows.len() as u32, fields, 12, strings.len() as u32] {
+                data.extend_from_slice(&v.to_le_bytes());
+            }
+            data.extend(rows.iter().flatten().flat_map(|v| v.to_le_bytes()));
+            data.extend_from_slice(strings);
+            Dbc::parse(&data).unwrap()
+        };
+        // The second row has no directory and is left out
+        let maps = parse_map_dbc(&wdbc(3, &[[0, 1, 0], [13, 0, 1]])).unwrap();
+        assert_eq!(maps, vec![record(0, "Azeroth", MapKind::Continent)]);
+        assert_eq!(parse_map_dbc(&wdbc(2, &[])).unwrap_err(), "Map.dbc has 2 fields, expected at least 3");
+    }
+
+    #[test]
+    fn maps_read_from_their_own_directory_when_there_is_one() {
+        let dir = scratch("manifest-maps");
+        let (azeroth, kalimdor) = (record(0, "Azeroth", MapKind::Continent), record(1, "Kalimdor", MapKind::Continent));
+        let maps = [&azeroth, &kalimdor];
+
+        // No per-map directories: every map reads the root
+        let manifest = Manifest::from_maps(&maps, &dir).unwrap();
+        assert!(manifest.entries.iter().all(|e| e.source == dir));
+
+        // Only maps with a directory are kept once any has one
+        fs::create_dir(dir.join("azeroth")).unwrap();
+        let manifest = Manifest::from_maps(&maps, &dir).unwrap();
+        assert_eq!(manifest.entries.len(), 1);
+        assert_eq!((manifest.entries[0].map_id, &manifest.entries[0].source), (0, &dir.join("azeroth")));
+
+        assert!(Manifest::from_maps(&[], &dir).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn known_maps_are_found_by_any_case() {
+        assert_eq!(known_map_id("northrend"), Some(571));
+        assert_eq!(known_map_id("Outland"), None);
+        assert_eq!(MapKind::from_instance_type(4), MapKind::Arena);
+        assert_eq!(MapKind::from_instance_type(7), MapKind::Other(7));
+    }
+}
@@ -0,0 +1,113 @@
+use crate::grid::TILES_PER_SIDE;
+#[cfg(feature = "png")]
+use crate::png::encode_rgb;
+use std::collections::BTreeMap;
+
+// ============================================================================
+// Tile coverage matrix
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum TileState {
+    /// Expected (by the WDT) but no file was found
+    Missing,
+    /// A file was found but didn't parse
+    Failed,
+    Present,
+}
+
+impl TileState {
+    fn symbol(&self) -> char {
+        match self {
+            TileState::Missing => '?',
+            TileState::Failed => '!',
+            TileState::Present => '#',
+        }
+    }
+
+    #[cfg(feature = "png")]
+    fn color(&self) -> [u8; 3] {
+        match self {
+            TileState::Missing => [230, 170, 40],
+            TileState::Failed => [210, 50, 50],
+            TileState::Present => [60, 170, 80],
+        }
+    }
+}
+
+/// Tiles nothing is expected on, in `render_png`
+#[cfg(feature = "png")]
+const EMPTY_COLOR: [u8; 3] = [24, 24, 24];
+
+/// Per-tile state of one map's 64x64 grid
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileMatrix {
+    tiles: BTreeMap<u32, TileState>,
+}
+
+impl TileMatrix {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a tile's state; a better state (present over failed over missing) wins
+    pub fn mark(&mut self, tile_x: u32, tile_y: u32, state: TileState) {
+        let entry = self.tiles.entry(tile_y * TILES_PER_SIDE + tile_x).or_insert(state);
+        *entry = (*entry).max(state);
+    }
+
+    /// Mark every expected tile that nothing else marked as missing
+    pub fn expect(&mut self, tile_keys: impl IntoIterator<Item = u32>) {
+        for key in tile_keys {
+            self.tiles.entry(key).or_insert(TileState::Missing);
+        }
+    }
+
+    pub fn count(&self, state: TileState) -> usize {
+        self.tiles.values().filter(|&&s| s == state).count()
+    }
+
+    /// Text matrix cropped to the tiles that have a state, one row per tile_y.
+    /// `#` present, `!` failed, `?` missing, `.` nothing expected.
+    pub fn render_text(&self) -> String {
+        let Some((min_x, max_x, min_y, max_y)) = self.bounds() else {
+            return String::from("(no tiles)\n");
+        };
+
+        let mut out = String::new();
+        out.push_str(&format!("     x {}..{}\n", min_x, max_x));
+        for y in min_y..=max_y {
+            out.push_str(&format!("{:>4}  ", y));
+            for x in min_x..=max_x {
+                let cell = self.tiles.get(&(y * TILES_PER_SIDE + x)).map(|s| s.symbol()).unwrap_or('.');
+                out.push(cell);
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// PNG of the whole 64x64 grid, `scale` pixels per tile side, x to the
+    /// right and y down: green present, red failed, amber missing
+    #[cfg(feature = "png")]
+    pub fn render_png(&self, scale: u32) -> Result<Vec<u8>, String> {
+        let side = TILES_PER_SIDE * scale;
+        let mut pixels = Vec::with_capacity((side * side * 3) as usize);
+        for py in 0..side {
+            for px in 0..side {
+                let key = (py / scale) * TILES_PER_SIDE + px / scale;
+                pixels.extend_from_slice(&self.tiles.get(&key).map_or(EMPTY_COLOR, |s| s.color()));
+            }
+        }
+        encode_rgb(side, side, &pixels)
+    }
+
+    fn bounds(&self) -> Option<(u32, u32, u32, u32)> {
+        let xs = self.tiles.keys().map(|k| k % TILES_PER_SIDE);
+        let ys = self.tiles.keys().map(|k| k / TILES_PER_SIDE);
+        Some((xs.clone().min()?, xs.max()?, ys.clone().min()?, ys.max()?))
+    }
+}
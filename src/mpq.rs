@@ -0,0 +1,264 @@
+use crate::adt::split_sibling;
+use crate::failure::is_split_adt;
+use crate::info;
+use crate::pipeline::TileFile;
+use crate::source::{ReadLimits, TileSource};
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use wow_mpq::PatchChain;
+
+// ============================================================================
+// MPQ archives
+// ============================================================================
+//
+// A pre-CASC client's Data/ folder read as one virtual file system. Archives
+// load in the client's order, later ones overriding earlier ones:
+//
+//     common.MPQ, common-2.MPQ, expansion.MPQ, lichking.MPQ    base
+//     <locale>/locale-<locale>.MPQ, speech-..., ...            locale
+//     patch.MPQ, patch-2.MPQ, ..., patch-A.MPQ, ...            patches
+//     <locale>/patch-<locale>.MPQ, patch-<locale>-2.MPQ, ...   locale patches
+//
+// Only one locale folder is read, as the client does. It is the one named by
+// `SET locale` in the client's WTF/Config.wtf, else the only one there is,
+// else enUS or enGB, else the first by name; `--locale` picks it outright.
+// Other folders under Data/ (Cache, Interface) are never read.
+//
+// Root ADTs are listed from the archives' (listfile)s, so a client whose
+// archives were stripped of them can't be scanned.
+
+/// Base archives in load order; unknown base archives load after these
+const BASE_ARCHIVES: &[&str] = &["common", "common-2", "expansion", "lichking"];
+
+/// Locale archives in load order, without their `-<locale>` suffix
+const LOCALE_ARCHIVES: &[&str] = &[
+    "base",
+    "backup",
+    "locale",
+    "speech",
+    "expansion-locale",
+    "lichking-locale",
+    "expansion-speech",
+    "lichking-speech",
+];
+
+/// Locale folder names the client ships
+pub const LOCALES: &[&str] = &[
+    "enUS", "enGB", "deDE", "frFR", "esES", "esMX", "ruRU", "koKR", "zhCN", "zhTW", "enCN", "enTW", "ptBR", "ptPT", "itIT",
+];
+
+/// Locales preferred when several folders are present and nothing says which
+const DEFAULT_LOCALES: &[&str] = &["enUS", "enGB"];
+
+fn is_mpq(path: &Path) -> bool {
+    path.is_file() && path.extension().is_some_and(|e| e.eq_ignore_ascii_case("mpq"))
+}
+
+/// Load priority of an archive (higher overrides lower), from its file stem
+/// and whether it sits in a locale subfolder
+pub fn archive_priority(stem: &str, in_locale_dir: bool) -> i32 {
+    let stem = stem.to_ascii_lowercase();
+    let Some(rest) = stem.strip_prefix("patch") else {
+        if in_locale_dir {
+            // locale-enus, expansion-speech-enus
+            let name = stem.rsplit_once('-').filter(|(_, l)| is_locale(l)).map_or(stem.as_str(), |(name, _)| name);
+            let order = LOCALE_ARCHIVES.iter().position(|&a| a == name).unwrap_or(LOCALE_ARCHIVES.len());
+            return 100 + order as i32;
+        }
+        return BASE_ARCHIVES.iter().position(|&b| b == stem).unwrap_or(BASE_ARCHIVES.len()) as i32;
+    };
+
+    // patch, patch-2 ... patch-9, then patch-a ... patch-z; locale patches
+    // look like patch-enus, patch-enus-2
+    let suffix = rest.rsplit('-').next().filter(|_| !rest.is_empty()).unwrap_or("");
+    let order = match suffix.as_bytes() {
+        [d @ b'0'..=b'9'] => i32::from(d - b'0'),
+        [c @ b'a'..=b'z'] => 10 + i32::from(c - b'a'),
+        _ => 0,
+    };
+    if in_locale_dir {
+        300 + order
+    } else {
+        200 + order
+    }
+}
+
+fn is_locale(name: &str) -> bool {
+    LOCALES.iter().any(|l| l.eq_ignore_ascii_case(name))
+}
+
+/// Locale folders under `data_dir`, as named on disk, sorted
+pub fn locale_dirs(data_dir: &Path) -> Vec<String> {
+    let mut dirs: Vec<String> = fs::read_dir(data_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| e.file_name().into_string().ok())
+        .filter(|name| is_locale(name))
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// `SET locale "xxYY"` from the client's `WTF/Config.wtf`, next to `data_dir`
+pub fn configured_locale(data_dir: &Path) -> Option<String> {
+    let config = fs::read_to_string(data_dir.parent()?.join("WTF").join("Config.wtf")).ok()?;
+    config.lines().find_map(|line| {
+        let mut words = line.split_whitespace();
+        let (set, name, value) = (words.next()?, words.next()?, words.next()?);
+        (set.eq_ignore_ascii_case("SET") && name.eq_ignore_ascii_case("locale")).then(|| value.trim_matches('"').to_string())
+    })
+}
+
+/// The locale folder to read from `dirs` (as `locale_dirs` lists them), and
+/// why it was picked. `None` when there are no locale folders.
+pub fn choose_locale(dirs: &[String], configured: Option<&str>) -> Option<(String, &'static str)> {
+    let find = |locale: &str| dirs.iter().find(|d| d.eq_ignore_ascii_case(locale)).cloned();
+    if let Some(dir) = configured.and_then(find) {
+        return Some((dir, "from WTF/Config.wtf"));
+    }
+    match dirs {
+        [] => None,
+        [only] => Some((only.clone(), "the only locale folder")),
+        _ => Some(match DEFAULT_LOCALES.iter().find_map(|l| find(l)) {
+            Some(dir) => (dir, "default of several locale folders"),
+            None => (dirs[0].clone(), "first of several locale folders"),
+        }),
+    }
+}
+
+/// A client's archives as one patch chain
+pub struct MpqSource {
+    chain: PatchChain,
+    /// Archive paths and priorities, lowest priority first
+    archives: Vec<(PathBuf, i32)>,
+    /// Locale folder read, if the client has any
+    locale: Option<String>,
+}
+
+impl MpqSource {
+    /// Open every `.MPQ` in `data_dir` and in one locale subfolder: `locale`
+    /// when given (an error if the folder is missing), else the detected one.
+    /// Archives stay open while the source lives, so there must be no more
+    /// of them than `limits` leave files for.
+    pub fn open(data_dir: &Path, locale: Option<&str>, limits: ReadLimits) -> Result<Self, Box<dyn std::error::Error>> {
+        let dirs = locale_dirs(data_dir);
+        let locale = match locale {
+            Some(wanted) => match dirs.iter().find(|d| d.eq_ignore_ascii_case(wanted)) {
+                Some(dir) => Some(dir.clone()),
+                None if dirs.is_empty() => return Err(format!("no locale folders in {} for --locale {}", data_dir.display(), wanted).into()),
+                None => return Err(format!("no {} folder in {} (found {})", wanted, data_dir.display(), dirs.join(", ")).into()),
+            },
+            None => choose_locale(&dirs, configured_locale(data_dir).as_deref()).map(|(dir, why)| {
+                info!("  Locale: {} ({})", dir, why);
+                dir
+            }),
+        };
+
+        let mut archives = Vec::new();
+        for entry in fs::read_dir(data_dir)? {
+            let path = entry?.path();
+            if is_mpq(&path) {
+                let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+                archives.push((archive_priority(stem, false), path));
+            }
+        }
+        if let Some(locale) = &locale {
+            for entry in fs::read_dir(data_dir.join(locale))? {
+                let path = entry?.path();
+                if is_mpq(&path) {
+                    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+                    archives.push((archive_priority(stem, true), path));
+                }
+            }
+        }
+        if archives.is_empty() {
+            return Err(format!("no MPQ archives in {}", data_dir.display()).into());
+        }
+        archives.sort();
+        if let Some(files) = limits.read_files().filter(|&files| archives.len() > files) {
+            return Err(format!(
+                "{} has {} archives, all open while it's read; --max-open-files leaves room for {}",
+                data_dir.display(),
+                archives.len(),
+                files
+            )
+            .into());
+        }
+
+        let chain = PatchChain::from_archives_parallel(archives.iter().map(|(p, a)| (a.clone(), *p)).collect())?;
+        Ok(Self { chain, archives: archives.into_iter().map(|(p, a)| (a, p)).collect(), locale })
+    }
+
+    /// Locale folder the archives were read from
+    pub fn locale(&self) -> Option<&str> {
+        self.locale.as_deref()
+    }
+
+    /// Archive paths and priorities, lowest priority first
+    pub fn archives(&self) -> &[(PathBuf, i32)] {
+        &self.archives
+    }
+
+    /// Root ADTs of `map` across the chain, sorted by path. Paths are the
+    /// archive-internal names with `/` separators.
+    pub fn adt_files(&mut self, map: &str) -> Result<Vec<TileFile>, Box<dyn std::error::Error>> {
+        let prefix = format!("world\\maps\\{}\\", map.to_ascii_lowercase());
+        let mut files: Vec<TileFile> = self
+            .chain
+            .list()?
+            .into_iter()
+            .filter(|f| f.name.to_ascii_lowercase().starts_with(&prefix))
+            .map(|f| PathBuf::from(f.name.replace('\\', "/")))
+            .filter(|path| !is_split_adt(path))
+            .filter_map(|path| TileFile::from_path(&path))
+            .filter(|file| file.map.eq_ignore_ascii_case(map))
+            .collect();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(files)
+    }
+
+    /// Bytes of a file from the highest-priority archive that has it
+    pub fn read(&mut self, path: &Path) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let name = path.to_string_lossy().replace('/', "\\");
+        Ok(self.chain.read_file(&name)?)
+    }
+
+    /// Archive a file would be read from
+    pub fn archive_of(&self, path: &Path) -> Option<&Path> {
+        self.chain.find_file_archive(&path.to_string_lossy().replace('/', "\\"))
+    }
+}
+
+impl TileSource for MpqSource {
+    fn describe(&self) -> String {
+        let dir = self.archives.first().and_then(|(p, _)| p.parent()).unwrap_or(Path::new("."));
+        match &self.locale {
+            Some(locale) => format!("{} ({} archives, {})", dir.display(), self.archives.len(), locale),
+            None => format!("{} ({} archives)", dir.display(), self.archives.len()),
+        }
+    }
+
+    fn adt_files(&mut self, map: &str) -> Result<Vec<TileFile>, Box<dyn std::error::Error>> {
+        MpqSource::adt_files(self, map)
+    }
+
+    fn read(&mut self, path: &Path) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        MpqSource::read(self, path)
+    }
+
+    fn split_sibling(&self, root: &Path, suffix: &str) -> Option<PathBuf> {
+        let sibling = split_sibling(root, suffix);
+        self.archive_of(&sibling).map(|_| sibling)
+    }
+
+    fn wdt(&mut self, map: &str) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+        let name = format!("World\\Maps\\{}\\{}.wdt", map, map);
+        if self.chain.find_file_archive(&name).is_none() {
+            return Ok(None);
+        }
+        Ok(Some(self.chain.read_file(&name)?))
+    }
+}
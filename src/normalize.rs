@@ -0,0 +1,112 @@
+use crate::failure::is_split_adt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// ============================================================================
+// Extraction cleanup
+// ============================================================================
+
+/// Where split ADT siblings (`_tex0`, `_lod`, ...) are moved to; `_obj0`
+/// stays by its root, which it supplies the WMOs of
+pub const SPLIT_DIR: &str = "split";
+
+/// One planned move within an ADT directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rename {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// Moves that would turn `dir` into the flat `<Map>_<x>_<y>.adt` layout the
+/// generator scans:
+/// - `%2f`/`%5c` escapes and backslash paths are reduced to the file name
+/// - files in map subfolders (`World/Maps/Azeroth/...`) move to the top
+/// - the extension becomes `.adt` and the map name takes `map_name`'s case
+/// - split siblings other than `_obj0` move into `split/`
+///
+/// Moves onto an existing file, or onto another move's target, are dropped.
+pub fn plan_normalize(dir: &Path, map_name: &str) -> std::io::Result<Vec<Rename>> {
+    let mut renames: Vec<Rename> = Vec::new();
+
+    for from in collect_files(dir)? {
+        let Some(name) = from.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let Some(name) = normalize_name(name, map_name) else {
+            continue;
+        };
+        let to = if is_split_adt(Path::new(&name)) && !name.to_ascii_lowercase().ends_with("_obj0.adt") {
+            dir.join(SPLIT_DIR).join(&name)
+        } else {
+            dir.join(&name)
+        };
+
+        if to == from || to.exists() || renames.iter().any(|r| r.to == to) {
+            continue;
+        }
+        renames.push(Rename { from, to });
+    }
+
+    Ok(renames)
+}
+
+/// Carry out planned moves, removing map subfolders left empty
+pub fn apply_normalize(dir: &Path, renames: &[Rename]) -> std::io::Result<()> {
+    for rename in renames {
+        if let Some(parent) = rename.to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(&rename.from, &rename.to)?;
+    }
+
+    for rename in renames {
+        let mut parent = rename.from.parent();
+        while let Some(p) = parent {
+            if p == dir || fs::remove_dir(p).is_err() {
+                break;
+            }
+            parent = p.parent();
+        }
+    }
+    Ok(())
+}
+
+/// Cleaned-up file name, or `None` when it isn't an ADT at all
+fn normalize_name(name: &str, map_name: &str) -> Option<String> {
+    let decoded = name.replace("%2f", "/").replace("%2F", "/").replace("%5c", "/").replace("%5C", "/").replace('\\', "/");
+    let base = decoded.rsplit('/').next()?;
+
+    let (stem, ext) = base.rsplit_once('.')?;
+    if !ext.eq_ignore_ascii_case("adt") {
+        return None;
+    }
+
+    let stem = stem.to_ascii_lowercase();
+    let stem = match stem.strip_prefix(&map_name.to_ascii_lowercase()) {
+        Some(rest) => format!("{}{}", map_name, rest),
+        None => stem,
+    };
+    Some(format!("{}.adt", stem))
+}
+
+/// Every file under `dir`, skipping the `split/` folder
+fn collect_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                if !(current == dir && entry.file_name() == SPLIT_DIR) {
+                    pending.push(path);
+                }
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
@@ -0,0 +1,155 @@
+use crate::json;
+use crate::report::RunReport;
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// ============================================================================
+// Run notifications
+// ============================================================================
+//
+// A scheduled regeneration can post its outcome to a webhook or mail it, so
+// nobody has to read cron logs. Delivery goes through the host's tools
+// rather than an HTTP or SMTP client in the binary: `curl` for webhooks
+// (which also covers HTTPS) and `sendmail -t` for mail. The message is the
+// run report's summary: one line per map, skipped maps, failed quality gates
+// and the changelog.
+
+/// Discord rejects messages longer than this
+const DISCORD_LIMIT: usize = 2000;
+/// Seconds a webhook post may take
+const WEBHOOK_TIMEOUT: u32 = 30;
+
+/// How a run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunStatus {
+    Succeeded,
+    GatesFailed,
+    Interrupted,
+    Failed,
+}
+
+impl RunStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RunStatus::Succeeded => "succeeded",
+            RunStatus::GatesFailed => "failed quality gates",
+            RunStatus::Interrupted => "was interrupted",
+            RunStatus::Failed => "failed",
+        }
+    }
+}
+
+/// Payload shape a webhook expects, from its URL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookKind {
+    /// `{"content": ...}`, at most 2000 characters
+    Discord,
+    /// `{"text": ...}`
+    Slack,
+    /// The summary plus structured fields, for anything else
+    Generic,
+}
+
+impl WebhookKind {
+    pub fn for_url(url: &str) -> Self {
+        if url.contains("discord.com/api/webhooks") || url.contains("discordapp.com/api/webhooks") {
+            WebhookKind::Discord
+        } else if url.contains("hooks.slack.com") {
+            WebhookKind::Slack
+        } else {
+            WebhookKind::Generic
+        }
+    }
+}
+
+/// `text` cut to `limit` characters, marking the cut
+fn truncate(text: &str, limit: usize) -> String {
+    if text.chars().count() <= limit {
+        return text.to_string();
+    }
+    let marker = "\n...";
+    let mut cut: String = text.chars().take(limit - marker.len()).collect();
+    cut.push_str(marker);
+    cut
+}
+
+/// First line of every notification
+pub fn headline(report: &RunReport, status: RunStatus) -> String {
+    format!("{} {}", report.title, status.label())
+}
+
+/// The JSON body to post to `url`
+pub fn webhook_payload(url: &str, report: &RunReport, status: RunStatus) -> String {
+    let text = format!("**{}**\n{}", headline(report, status), report.render_summary());
+    match WebhookKind::for_url(url) {
+        WebhookKind::Discord => format!("{{\"content\":{}}}", json::string(&truncate(&text, DISCORD_LIMIT))),
+        WebhookKind::Slack => format!("{{\"text\":{}}}", json::string(&text)),
+        WebhookKind::Generic => {
+            let maps: Vec<String> = report
+                .maps
+                .iter()
+                .map(|m| {
+                    format!(
+                        "{{\"name\":{},\"tiles\":{},\"areas\":{},\"failures\":{},\"sentinels\":{}}}",
+                        json::string(&m.name),
+                        m.tiles,
+                        m.areas,
+                        m.failures,
+                        m.sentinels
+                    )
+                })
+                .collect();
+            let gates: Vec<String> = report.gate_failures.iter().map(|g| json::string(g)).collect();
+            format!(
+                "{{\"title\":{},\"status\":{},\"text\":{},\"maps\":[{}],\"gateFailures\":[{}],\"changelog\":{}}}",
+                json::string(&report.title),
+                json::string(status.label()),
+                json::string(&text),
+                maps.join(","),
+                gates.join(","),
+                report.changelog.as_deref().map_or("null".to_string(), json::string)
+            )
+        }
+    }
+}
+
+/// POST `body` as JSON to `url` with curl
+pub fn post_webhook(url: &str, body: &str) -> Result<(), String> {
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--max-time", &WEBHOOK_TIMEOUT.to_string()])
+        .args(["-H", "Content-Type: application/json", "--data-binary", "@-", url])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("can't run curl: {}", e))?;
+    child.stdin.take().ok_or("no stdin for curl")?.write_all(body.as_bytes()).map_err(|e| e.to_string())?;
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(())
+}
+
+/// Mail the summary to `to` with `sendmail -t`
+pub fn send_email(to: &str, report: &RunReport, status: RunStatus) -> Result<(), String> {
+    let message = format!(
+        "To: {}\nSubject: {}\nContent-Type: text/plain; charset=utf-8\n\n{}",
+        to,
+        headline(report, status),
+        report.render_summary()
+    );
+    let mut child = Command::new("sendmail")
+        .arg("-t")
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("can't run sendmail: {}", e))?;
+    child.stdin.take().ok_or("no stdin for sendmail")?.write_all(message.as_bytes()).map_err(|e| e.to_string())?;
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!("sendmail exited with {}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
@@ -0,0 +1,701 @@
+use crate::adt::CHUNKS_PER_SIDE;
+use crate::grid::{TILES_PER_SIDE, TILE_SIZE};
+use crate::json;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+// ============================================================================
+// Zone outlines
+// ============================================================================
+//
+// Polygons around each area, for drawing crisp borders instead of filling
+// chunks. Coordinates are map-wide chunk corners: x = tileX * 16 + chunkX,
+// y = tileY * 16 + chunkY, 0 to 1024 with y down. Every chunk side between
+// an area and anything else (another area, or no tile) is a boundary edge,
+// directed so the area is on its right; chaining the edges gives closed
+// rings, clockwise around the area (on screen) and anticlockwise around its
+// holes. Where two chunks of an area touch only at a corner, the rings are
+// kept apart there, so every ring is simple.
+//
+// Rings are then cut into border chains at junctions, the corners where
+// more than two areas (or no area) meet or two meet only diagonally. A chain
+// separates the same two areas all along, and is simplified once for both:
+// the rings on either side are rebuilt from the same vertices, so neighbours
+// keep sharing their border with no gaps or overlaps. Simplifying drops the
+// vertices along straight runs, which is lossless, and with a tolerance runs
+// Douglas-Peucker over each chain with its junctions fixed.
+//
+// Small regions and holes are dealt with on the chunks before tracing: a
+// region under the minimum area joins the neighbour it shares the longest
+// border with, and a filled hole becomes part of the area around it. The
+// outlines are traced from the result, so they still tile the map.
+
+/// Chunks along one side of a map
+const MAP_CHUNKS: i32 = (TILES_PER_SIDE as usize * CHUNKS_PER_SIDE) as i32;
+
+pub type Point = (i32, i32);
+
+/// An outline and the holes in it, each a closed ring without the first
+/// point repeated
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Polygon {
+    pub outer: Vec<Point>,
+    pub holes: Vec<Vec<Point>>,
+}
+
+/// What `trace_outlines` does with the holes in an area's outline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum HoleHandling {
+    /// Every hole stays
+    #[default]
+    Keep,
+    /// Every hole is filled with the area around it
+    Fill,
+    /// Holes under this many chunks are filled
+    FillUnder(usize),
+}
+
+impl HoleHandling {
+    fn fills(self, chunks: usize) -> bool {
+        match self {
+            Self::Keep => false,
+            Self::Fill => true,
+            Self::FillUnder(limit) => chunks < limit,
+        }
+    }
+}
+
+impl FromStr for HoleHandling {
+    type Err = String;
+
+    /// `keep`, `fill` or `fill:<chunks>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(Self::Keep),
+            "fill" => Ok(Self::Fill),
+            _ => match s.strip_prefix("fill:") {
+                Some(n) => n.parse().map(Self::FillUnder).map_err(|_| format!("bad hole size `{}`", n)),
+                None => Err(format!("unknown hole handling `{}` (keep, fill, fill:<chunks>)", s)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for HoleHandling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Keep => f.write_str("keep"),
+            Self::Fill => f.write_str("fill"),
+            Self::FillUnder(n) => write!(f, "fill:{}", n),
+        }
+    }
+}
+
+/// How much detail `trace_outlines` keeps
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct OutlineOptions {
+    /// Simplify borders to within this many chunks; 0 keeps them exact
+    pub tolerance: f64,
+    /// Regions under this many chunks join the neighbour they share the
+    /// longest border with, or go when that's no area
+    pub min_area: usize,
+    pub holes: HoleHandling,
+}
+
+/// Twice the signed area of `ring`: positive when clockwise on screen (y down)
+fn signed_area2(ring: &[Point]) -> i64 {
+    (0..ring.len())
+        .map(|i| {
+            let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+            a.0 as i64 * b.1 as i64 - b.0 as i64 * a.1 as i64
+        })
+        .sum()
+}
+
+/// Whether (x, y) is inside `ring` (even-odd rule)
+fn contains(ring: &[Point], x: f64, y: f64) -> bool {
+    let mut inside = false;
+    for i in 0..ring.len() {
+        let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+        let (ax, ay, bx, by) = (a.0 as f64, a.1 as f64, b.0 as f64, b.1 as f64);
+        if (ay > y) != (by > y) && x < ax + (y - ay) * (bx - ax) / (by - ay) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// `path` without the vertices in the middle of straight runs; its ends stay
+fn drop_collinear(path: &[Point]) -> Vec<Point> {
+    let n = path.len();
+    (0..n)
+        .filter(|&i| {
+            if i == 0 || i + 1 == n {
+                return true;
+            }
+            let (p, v, q) = (path[i - 1], path[i], path[i + 1]);
+            (v.0 - p.0) as i64 * (q.1 - v.1) as i64 != (v.1 - p.1) as i64 * (q.0 - v.0) as i64
+        })
+        .map(|i| path[i])
+        .collect()
+}
+
+fn distance_to_segment(p: Point, a: Point, b: Point) -> f64 {
+    let (px, py, ax, ay, bx, by) = (p.0 as f64, p.1 as f64, a.0 as f64, a.1 as f64, b.0 as f64, b.1 as f64);
+    let (dx, dy) = (bx - ax, by - ay);
+    let len2 = dx * dx + dy * dy;
+    let t = if len2 == 0.0 { 0.0 } else { (((px - ax) * dx + (py - ay) * dy) / len2).clamp(0.0, 1.0) };
+    ((px - ax - t * dx).powi(2) + (py - ay - t * dy).powi(2)).sqrt()
+}
+
+/// Douglas-Peucker over the open path `points`, marking the vertices kept
+fn douglas_peucker(points: &[Point], tolerance: f64, keep: &mut [bool]) {
+    if points.len() < 3 {
+        return;
+    }
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let (index, distance) = points[1..points.len() - 1]
+        .iter()
+        .enumerate()
+        .map(|(i, &p)| (i + 1, distance_to_segment(p, first, last)))
+        .fold((0, 0.0), |best, d| if d.1 > best.1 { d } else { best });
+    if distance > tolerance {
+        keep[index] = true;
+        douglas_peucker(&points[..=index], tolerance, &mut keep[..=index]);
+        douglas_peucker(&points[index..], tolerance, &mut keep[index..]);
+    }
+}
+
+/// `ring` simplified to within `tolerance` chunks; unchanged when that would
+/// leave fewer than 3 vertices
+pub fn simplify(ring: &[Point], tolerance: f64) -> Vec<Point> {
+    if tolerance <= 0.0 || ring.len() <= 4 {
+        return ring.to_vec();
+    }
+    // Split the ring at its first vertex and the one farthest from it
+    let far = (1..ring.len())
+        .max_by_key(|&i| (ring[i].0 - ring[0].0).pow(2) + (ring[i].1 - ring[0].1).pow(2))
+        .unwrap_or(0);
+    let mut path = ring.to_vec();
+    path.push(ring[0]);
+    let mut keep = vec![false; path.len()];
+    keep[0] = true;
+    keep[far] = true;
+    douglas_peucker(&path[..=far], tolerance, &mut keep[..=far]);
+    douglas_peucker(&path[far..], tolerance, &mut keep[far..]);
+    let simplified: Vec<Point> = ring.iter().zip(&keep).filter(|(_, &k)| k).map(|(&p, _)| p).collect();
+    if simplified.len() < 3 {
+        ring.to_vec()
+    } else {
+        simplified
+    }
+}
+
+/// `chain` simplified to within `tolerance` chunks, keeping its ends. A
+/// closed chain (first point repeated at the end) stays a ring of at least
+/// 3 vertices; with `keep_corner` an open one keeps a vertex besides its ends.
+fn simplify_chain(chain: &[Point], tolerance: f64, keep_corner: bool) -> Vec<Point> {
+    let path = drop_collinear(chain);
+    let last = path.len() - 1;
+    if path[0] == path[last] {
+        let mut ring = simplify(&path[..last], tolerance);
+        ring.push(ring[0]);
+        return ring;
+    }
+    if tolerance <= 0.0 || path.len() < 3 {
+        return path;
+    }
+    let mut keep = vec![false; path.len()];
+    keep[0] = true;
+    keep[last] = true;
+    douglas_peucker(&path, tolerance, &mut keep);
+    if keep_corner && keep.iter().filter(|&&k| k).count() == 2 {
+        let distance = |i: &usize| distance_to_segment(path[*i], path[0], path[last]);
+        if let Some(far) = (1..last).max_by(|a, b| distance(a).total_cmp(&distance(b))) {
+            keep[far] = true;
+        }
+    }
+    path.iter().zip(&keep).filter(|(_, &k)| k).map(|(&p, _)| p).collect()
+}
+
+/// `chain` in the direction both rings along it agree on, and whether that
+/// is the reverse of `chain`
+fn canonical(chain: &[Point]) -> (Vec<Point>, bool) {
+    let reversed: Vec<Point> = chain.iter().rev().copied().collect();
+    if reversed.as_slice() < chain {
+        (reversed, true)
+    } else {
+        (chain.to_vec(), false)
+    }
+}
+
+/// Area of each chunk across a map; `None` where there's no tile or the
+/// area is 0, neither of which gets an outline
+struct Cells(Vec<Option<u32>>);
+
+impl Cells {
+    fn new(tiles: &HashMap<u32, Vec<u32>>) -> Self {
+        let side = MAP_CHUNKS as usize;
+        let mut cells = vec![None; side * side];
+        for (&key, ids) in tiles {
+            let (tile_x, tile_y) = ((key % TILES_PER_SIDE) as usize, (key / TILES_PER_SIDE) as usize);
+            for (i, &id) in ids.iter().enumerate().take(CHUNKS_PER_SIDE * CHUNKS_PER_SIDE) {
+                let (x, y) = (tile_x * CHUNKS_PER_SIDE + i % CHUNKS_PER_SIDE, tile_y * CHUNKS_PER_SIDE + i / CHUNKS_PER_SIDE);
+                cells[y * side + x] = Some(id).filter(|&id| id != 0);
+            }
+        }
+        Self(cells)
+    }
+
+    fn at(&self, x: i32, y: i32) -> Option<u32> {
+        if (0..MAP_CHUNKS).contains(&x) && (0..MAP_CHUNKS).contains(&y) {
+            self.0[y as usize * MAP_CHUNKS as usize + x as usize]
+        } else {
+            None
+        }
+    }
+
+    fn set(&mut self, (x, y): Point, id: Option<u32>) {
+        self.0[y as usize * MAP_CHUNKS as usize + x as usize] = id;
+    }
+
+    /// Chunks reachable from `seed` through chunk sides without crossing
+    /// `walls` (chunk sides, as the two corners in either order), and
+    /// staying within `id` unless it's `None`
+    fn flood(&self, seed: Point, id: Option<u32>, walls: &HashSet<(Point, Point)>) -> Vec<Point> {
+        let mut seen = HashSet::from([seed]);
+        let mut stack = vec![seed];
+        let mut region = Vec::new();
+        while let Some((x, y)) = stack.pop() {
+            region.push((x, y));
+            let sides = [((x, y - 1), (x, y), (x + 1, y)), ((x + 1, y), (x + 1, y), (x + 1, y + 1)), ((x, y + 1), (x + 1, y + 1), (x, y + 1)), ((x - 1, y), (x, y + 1), (x, y))];
+            for (next, a, b) in sides {
+                let inside = (0..MAP_CHUNKS).contains(&next.0) && (0..MAP_CHUNKS).contains(&next.1);
+                if !inside || walls.contains(&(a, b)) || walls.contains(&(b, a)) || (id.is_some() && self.at(next.0, next.1) != id) {
+                    continue;
+                }
+                if seen.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+        region
+    }
+
+    /// Fill holes and merge small regions as `options` say; whether any
+    /// chunk changed
+    fn prune(&mut self, outlines: &BTreeMap<u32, Vec<Polygon>>, options: &OutlineOptions) -> bool {
+        let mut changed = false;
+        // The chunk left of a ring's first edge (in a hole), or right of it (in
+        // the area)
+        let beside = |ring: &[Point], left: bool| {
+            let (a, b) = (ring[0], ring[1 % ring.len()]);
+            let (dx, dy) = ((b.0 - a.0).signum(), (b.1 - a.1).signum());
+            let side = if left { -1 } else { 1 };
+            (a.0 + (dx - side * dy - 1) / 2, a.1 + (dy + side * dx - 1) / 2)
+        };
+        let no_walls = HashSet::new();
+
+        if options.holes != HoleHandling::Keep {
+            for (&id, polygons) in outlines {
+                for hole in polygons.iter().flat_map(|p| &p.holes) {
+                    let walls: HashSet<(Point, Point)> = (0..hole.len()).map(|i| (hole[i], hole[(i + 1) % hole.len()])).collect();
+                    let inside = self.flood(beside(hole, true), None, &walls);
+                    if options.holes.fills(inside.len()) {
+                        for &cell in &inside {
+                            self.set(cell, Some(id));
+                        }
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        if options.min_area > 0 {
+            let mut regions: Vec<(u32, &Polygon)> = outlines.iter().flat_map(|(&id, polygons)| polygons.iter().map(move |p| (id, p))).collect();
+            regions.sort_by_key(|(_, p)| signed_area2(&p.outer));
+            for (id, polygon) in regions {
+                let seed = beside(&polygon.outer, false);
+                if self.at(seed.0, seed.1) != Some(id) {
+                    continue;
+                }
+                let region = self.flood(seed, Some(id), &no_walls);
+                if region.len() >= options.min_area {
+                    continue;
+                }
+                let mut border: BTreeMap<Option<u32>, usize> = BTreeMap::new();
+                for &(x, y) in &region {
+                    for (nx, ny) in [(x, y - 1), (x + 1, y), (x, y + 1), (x - 1, y)] {
+                        let other = self.at(nx, ny);
+                        if other != Some(id) {
+                            *border.entry(other).or_default() += 1;
+                        }
+                    }
+                }
+                let into = border.into_iter().max_by_key(|&(_, n)| n).and_then(|(other, _)| other);
+                for &cell in &region {
+                    self.set(cell, into);
+                }
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Whether corner `p` ends the borders through it: more than two areas
+    /// meet there, or two meet only diagonally
+    fn is_junction(&self, p: Point) -> bool {
+        let (x, y) = p;
+        let around = [self.at(x - 1, y - 1), self.at(x, y - 1), self.at(x, y), self.at(x - 1, y)];
+        (0..4).filter(|&i| around[i] != around[(i + 1) % 4]).count() > 2
+    }
+
+    /// `ring` cut at its junctions, each chain running from one junction to
+    /// the next. A ring without junctions is one closed chain from its
+    /// smallest point, which the ring on its other side starts from too.
+    fn chains(&self, ring: &[Point]) -> Vec<Vec<Point>> {
+        let n = ring.len();
+        let mut cuts: Vec<usize> = (0..n).filter(|&i| self.is_junction(ring[i])).collect();
+        if cuts.is_empty() {
+            cuts.extend((0..n).min_by_key(|&i| ring[i]));
+        }
+        (0..cuts.len())
+            .map(|j| {
+                let start = cuts[j];
+                let len = match (cuts[(j + 1) % cuts.len()] + n - start) % n {
+                    0 => n,
+                    len => len,
+                };
+                (0..=len).map(|k| ring[(start + k) % n]).collect()
+            })
+            .collect()
+    }
+
+    /// Outlines of every area, with a vertex at every chunk corner
+    fn trace(&self) -> BTreeMap<u32, Vec<Polygon>> {
+        // Area -> edge start -> (end, cell the edge belongs to)
+        let mut edges: BTreeMap<u32, HashMap<Point, Vec<(Point, Point)>>> = BTreeMap::new();
+        for y in 0..MAP_CHUNKS {
+            for x in 0..MAP_CHUNKS {
+                let Some(id) = self.at(x, y) else {
+                    continue;
+                };
+                let area = edges.entry(id).or_default();
+                let sides = [
+                    ((x, y - 1), (x, y), (x + 1, y)),
+                    ((x + 1, y), (x + 1, y), (x + 1, y + 1)),
+                    ((x, y + 1), (x + 1, y + 1), (x, y + 1)),
+                    ((x - 1, y), (x, y + 1), (x, y)),
+                ];
+                for (neighbour, from, to) in sides {
+                    if self.at(neighbour.0, neighbour.1) != Some(id) {
+                        area.entry(from).or_default().push((to, (x, y)));
+                    }
+                }
+            }
+        }
+
+        let mut outlines = BTreeMap::new();
+        for (id, mut from) in edges {
+            let mut rings = Vec::new();
+            let mut starts: Vec<Point> = from.keys().copied().collect();
+            starts.sort_unstable();
+            for start in starts {
+                while let Some((mut to, mut cell)) = from.get_mut(&start).and_then(|out| out.pop()) {
+                    let mut ring = vec![start];
+                    while to != start {
+                        ring.push(to);
+                        let Some(out) = from.get_mut(&to).filter(|out| !out.is_empty()) else {
+                            break;
+                        };
+                        // At a corner two chunks share diagonally, stay with the
+                        // chunk the ring came along
+                        let next = out.iter().position(|&(_, c)| c == cell).unwrap_or(0);
+                        (to, cell) = out.swap_remove(next);
+                    }
+                    rings.push(ring);
+                }
+            }
+
+            let (outers, holes): (Vec<Vec<Point>>, Vec<Vec<Point>>) = rings.into_iter().partition(|r| signed_area2(r) > 0);
+            let mut polygons: Vec<Polygon> = outers.into_iter().map(|outer| Polygon { outer, holes: Vec::new() }).collect();
+            for hole in holes {
+                // A chunk of the area just outside the hole: right of its first edge
+                let (a, b) = (hole[0], hole[1 % hole.len()]);
+                let (dx, dy) = ((b.0 - a.0).signum(), (b.1 - a.1).signum());
+                let (cx, cy) = (a.0 as f64 + dx as f64 * 0.5 - dy as f64 * 0.5, a.1 as f64 + dy as f64 * 0.5 + dx as f64 * 0.5);
+                let owner = polygons
+                    .iter_mut()
+                    .filter(|p| contains(&p.outer, cx, cy))
+                    .min_by_key(|p| signed_area2(&p.outer));
+                if let Some(polygon) = owner {
+                    polygon.holes.push(hole);
+                }
+            }
+            outlines.insert(id, polygons);
+        }
+        outlines
+    }
+
+    /// Every ring of `outlines` simplified to within `tolerance` chunks one
+    /// border chain at a time, each chain once for the rings on both sides
+    fn simplify_borders(&self, outlines: &mut BTreeMap<u32, Vec<Polygon>>, tolerance: f64) {
+        // A ring with fewer than 3 junctions could fold into a line if its
+        // chains lost every vertex between them
+        let mut keep_corner: HashSet<Vec<Point>> = HashSet::new();
+        for ring in outlines.values().flatten().flat_map(|p| std::iter::once(&p.outer).chain(&p.holes)) {
+            let chains = self.chains(ring);
+            let junctions: HashSet<Point> = chains.iter().map(|c| c[0]).collect();
+            if junctions.len() < 3 {
+                keep_corner.extend(chains.iter().map(|c| canonical(c).0));
+            }
+        }
+
+        let mut simplified: HashMap<Vec<Point>, Vec<Point>> = HashMap::new();
+        for polygon in outlines.values_mut().flatten() {
+            for ring in std::iter::once(&mut polygon.outer).chain(&mut polygon.holes) {
+                let mut rebuilt = Vec::with_capacity(ring.len());
+                for chain in self.chains(ring) {
+                    let (key, reversed) = canonical(&chain);
+                    let done = simplified
+                        .entry(key)
+                        .or_insert_with_key(|key| simplify_chain(key, tolerance, keep_corner.contains(key)));
+                    // Each chain starts where the one before ended
+                    if reversed {
+                        rebuilt.extend(done.iter().rev().skip(1));
+                    } else {
+                        rebuilt.extend(done.iter().skip(1));
+                    }
+                }
+                if rebuilt.len() < 3 {
+                    rebuilt = drop_collinear(ring);
+                }
+                // Start from the smallest point, as traced rings do
+                let first = (0..rebuilt.len()).min_by_key(|&i| rebuilt[i]).unwrap_or(0);
+                rebuilt.rotate_left(first);
+                *ring = rebuilt;
+            }
+        }
+    }
+}
+
+/// Outlines of every area on a map from its tiles (tile key -> 256 area
+/// IDs), with the detail `options` keep. Area 0 gets none.
+pub fn trace_outlines(tiles: &HashMap<u32, Vec<u32>>, options: &OutlineOptions) -> BTreeMap<u32, Vec<Polygon>> {
+    let mut cells = Cells::new(tiles);
+    let mut outlines = cells.trace();
+    if cells.prune(&outlines, options) {
+        outlines = cells.trace();
+    }
+    cells.simplify_borders(&mut outlines, options.tolerance);
+    outlines
+}
+
+// ============================================================================
+// Outline export
+// ============================================================================
+
+/// World (x, y) of a chunk corner
+fn to_world(p: Point) -> (f32, f32) {
+    let half = TILE_SIZE * (TILES_PER_SIDE / 2) as f32;
+    let chunk_size = TILE_SIZE / CHUNKS_PER_SIDE as f32;
+    (half - p.1 as f32 * chunk_size, half - p.0 as f32 * chunk_size)
+}
+
+/// Flat `x1, y1, x2, y2, ...` list of a ring
+fn flat(ring: &[Point]) -> String {
+    ring.iter().map(|p| format!("{},{}", p.0, p.1)).collect::<Vec<_>>().join(",")
+}
+
+/// Write `<Map>_outlines.lua` for `addon:RegisterOutlines`
+pub fn write_lua(map: &str, outlines: &BTreeMap<u32, Vec<Polygon>>, area_name: impl Fn(u32) -> Option<String>, out_path: &Path) -> std::io::Result<()> {
+    let mut f = BufWriter::new(File::create(out_path)?);
+    writeln!(f, "-- Auto-generated zone outlines for {}", map)?;
+    writeln!(f, "-- Per area, a list of polygons; each polygon is its outline ring then its holes.")?;
+    writeln!(f, "-- Rings are flat {{x1, y1, x2, y2, ...}} lists of chunk corners across the map:")?;
+    writeln!(f, "-- x = tileX * 16 + chunkX, y = tileY * 16 + chunkY, y down.")?;
+    writeln!(f)?;
+    writeln!(f, "local _, addon = ...")?;
+    writeln!(f)?;
+    writeln!(f, "addon:RegisterOutlines(\"{}\", {{", map)?;
+    for (id, polygons) in outlines {
+        match area_name(*id) {
+            Some(name) => writeln!(f, "  [{}] = {{ -- {}", id, name)?,
+            None => writeln!(f, "  [{}] = {{", id)?,
+        }
+        for polygon in polygons {
+            let rings: Vec<String> = std::iter::once(&polygon.outer).chain(&polygon.holes).map(|r| format!("{{{}}}", flat(r))).collect();
+            writeln!(f, "    {{{}}},", rings.join(", "))?;
+        }
+        writeln!(f, "  }},")?;
+    }
+    writeln!(f, "}})")?;
+    f.flush()
+}
+
+/// Write `<Map>_outlines.geojson`: a feature per area with a MultiPolygon
+/// in world coordinates, outlines anticlockwise and holes clockwise
+pub fn write_geojson(map: &str, outlines: &BTreeMap<u32, Vec<Polygon>>, area_name: impl Fn(u32) -> Option<String>, out_path: &Path) -> std::io::Result<()> {
+    let ring_json = |ring: &[Point], outer: bool| {
+        let mut points: Vec<(f32, f32)> = ring.iter().map(|&p| to_world(p)).collect();
+        let area2: f64 = (0..points.len())
+            .map(|i| {
+                let (a, b) = (points[i], points[(i + 1) % points.len()]);
+                a.0 as f64 * b.1 as f64 - b.0 as f64 * a.1 as f64
+            })
+            .sum();
+        if (area2 > 0.0) != outer {
+            points.reverse();
+        }
+        if let Some(&first) = points.first() {
+            points.push(first);
+        }
+        let coords: Vec<String> = points.iter().map(|(x, y)| format!("[{:.2},{:.2}]", x, y)).collect();
+        format!("[{}]", coords.join(","))
+    };
+
+    let mut f = BufWriter::new(File::create(out_path)?);
+    writeln!(f, "{{")?;
+    writeln!(f, "  \"type\": \"FeatureCollection\",")?;
+    writeln!(f, "  \"name\": {},", json::string(map))?;
+    writeln!(f, "  \"features\": [")?;
+    for (i, (id, polygons)) in outlines.iter().enumerate() {
+        let polygons: Vec<String> = polygons
+            .iter()
+            .map(|p| {
+                let rings: Vec<String> = std::iter::once(ring_json(&p.outer, true)).chain(p.holes.iter().map(|h| ring_json(h, false))).collect();
+                format!("[{}]", rings.join(","))
+            })
+            .collect();
+        let name = area_name(*id).map_or("null".to_string(), |n| json::string(&n));
+        let comma = if i + 1 < outlines.len() { "," } else { "" };
+        writeln!(
+            f,
+            "    {{\"type\":\"Feature\",\"properties\":{{\"areaId\":{},\"name\":{}}},\"geometry\":{{\"type\":\"MultiPolygon\",\"coordinates\":[{}]}}}}{}",
+            id,
+            name,
+            polygons.join(","),
+            comma
+        )?;
+    }
+    writeln!(f, "  ]")?;
+    writeln!(f, "}}")?;
+    f.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One tile, at key 0, with each chunk's area from `area(x, y)`
+    fn tile(area: impl Fn(usize, usize) -> u32) -> HashMap<u32, Vec<u32>> {
+        HashMap::from([(0, (0..256).map(|i| area(i % 16, i / 16)).collect())])
+    }
+
+    /// Every edge of every ring, as (from, to)
+    fn edges(polygons: &[Polygon]) -> Vec<(Point, Point)> {
+        polygons
+            .iter()
+            .flat_map(|p| std::iter::once(&p.outer).chain(&p.holes))
+            .flat_map(|r| (0..r.len()).map(move |i| (r[i], r[(i + 1) % r.len()])))
+            .collect()
+    }
+
+    #[test]
+    fn single_chunk_is_a_square() {
+        let outlines = trace_outlines(&tile(|x, y| if (x, y) == (3, 2) { 5 } else { 0 }), &OutlineOptions::default());
+        assert_eq!(outlines.len(), 1);
+        assert_eq!(outlines[&5], vec![Polygon { outer: vec![(3, 2), (4, 2), (4, 3), (3, 3)], holes: vec![] }]);
+    }
+
+    #[test]
+    fn l_shape_keeps_its_inner_corner() {
+        let tiles = tile(|x, y| matches!((x, y), (0, 0) | (0, 1) | (1, 1)) as u32);
+        let outlines = trace_outlines(&tiles, &OutlineOptions::default());
+        assert_eq!(outlines[&1][0].outer, vec![(0, 0), (1, 0), (1, 1), (2, 1), (2, 2), (0, 2)]);
+    }
+
+    #[test]
+    fn hole_runs_the_other_way() {
+        let tiles = tile(|x, y| (x < 3 && y < 3 && (x, y) != (1, 1)) as u32);
+        let polygon = &trace_outlines(&tiles, &OutlineOptions::default())[&1][0];
+        assert_eq!(polygon.outer, vec![(0, 0), (3, 0), (3, 3), (0, 3)]);
+        assert_eq!(polygon.holes, vec![vec![(1, 1), (1, 2), (2, 2), (2, 1)]]);
+        assert!(signed_area2(&polygon.outer) > 0 && signed_area2(&polygon.holes[0]) < 0);
+    }
+
+    #[test]
+    fn zero_tolerance_is_lossless() {
+        let area = |x: usize, y: usize| [0, 1, 1, 2, 3][(x * 7 + y * 13 + x * y) % 5];
+        let outlines = trace_outlines(&tile(area), &OutlineOptions::default());
+        // Each chunk is inside exactly its own area's rings
+        for y in 0..16 {
+            for x in 0..16 {
+                let (cx, cy) = (x as f64 + 0.5, y as f64 + 0.5);
+                for (&id, polygons) in &outlines {
+                    let inside = polygons.iter().any(|p| contains(&p.outer, cx, cy) && !p.holes.iter().any(|h| contains(h, cx, cy)));
+                    assert_eq!(inside, area(x, y) == id, "chunk {},{} in area {}", x, y, id);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn neighbours_keep_a_shared_border_when_simplified() {
+        let tiles = tile(|x, y| if x < 4 + y * 7 % 5 + y / 4 { 1 } else { 2 });
+        let exact = trace_outlines(&tiles, &OutlineOptions::default());
+        let outlines = trace_outlines(&tiles, &OutlineOptions { tolerance: 1.5, ..OutlineOptions::default() });
+        assert!(outlines[&1][0].outer.len() < exact[&1][0].outer.len());
+
+        // Apart from the tile's own edges, every edge of one area runs back
+        // along the other's
+        let on_tile_edge = |&(a, b): &(Point, Point)| (a.0 == b.0 && a.0 % 16 == 0) || (a.1 == b.1 && a.1 % 16 == 0);
+        let (one, two) = (edges(&outlines[&1]), edges(&outlines[&2]));
+        for (ours, theirs) in [(&one, &two), (&two, &one)] {
+            for &(a, b) in ours.iter().filter(|e| !on_tile_edge(e)) {
+                assert!(theirs.contains(&(b, a)), "{:?} -> {:?} is on one side of the border only", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn holes_are_filled_by_size() {
+        // Area 1 with a 2x2 hole of area 2 in it
+        let tiles = tile(|x, y| if (6..8).contains(&x) && (6..8).contains(&y) { 2 } else { 1 });
+        let trace = |holes| trace_outlines(&tiles, &OutlineOptions { holes, ..OutlineOptions::default() });
+
+        let kept = trace(HoleHandling::FillUnder(4));
+        assert_eq!(kept[&1][0].holes.len(), 1);
+        assert!(kept.contains_key(&2));
+
+        for holes in [HoleHandling::FillUnder(5), HoleHandling::Fill] {
+            let filled = trace(holes);
+            assert_eq!(filled[&1], vec![Polygon { outer: vec![(0, 0), (16, 0), (16, 16), (0, 16)], holes: vec![] }]);
+            assert!(!filled.contains_key(&2));
+        }
+        assert_eq!("fill:5".parse(), Ok(HoleHandling::FillUnder(5)));
+        assert!("fill:x".parse::<HoleHandling>().is_err());
+    }
+
+    #[test]
+    fn small_regions_join_their_longest_neighbour() {
+        // A single chunk of area 3 on the border between areas 1 and 2, with
+        // three of its sides on area 1
+        let tiles = tile(|x, y| match (x, y) {
+            (7, 4) => 3,
+            (x, _) if x < 8 => 1,
+            _ => 2,
+        });
+        let outlines = trace_outlines(&tiles, &OutlineOptions { min_area: 2, ..OutlineOptions::default() });
+        assert!(!outlines.contains_key(&3));
+        assert_eq!(outlines[&1][0].outer, vec![(0, 0), (8, 0), (8, 16), (0, 16)]);
+        assert_eq!(outlines, trace_outlines(&tile(|x, _| if x < 8 { 1 } else { 2 }), &OutlineOptions::default()));
+    }
+}
@@ -0,0 +1,199 @@
+use crate::adt::CHUNKS_PER_SIDE;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+// ============================================================================
+// ADT area ID writer
+// ============================================================================
+
+/// Byte offset of `area_id` inside the MCNK header (after the 8-byte chunk header)
+const MCNK_AREA_ID_OFFSET: usize = 8 + 0x34;
+
+/// Chunk index -> new area ID for one tile
+pub type ChunkEdits = BTreeMap<usize, u32>;
+
+/// Copy `src` to `dest` with the area IDs of the given chunks replaced.
+///
+/// Only the `area_id` field of each MCNK header is touched, so everything
+/// else in the file survives byte for byte. Returns how many chunks changed.
+pub fn patch_area_ids(src: &Path, dest: &Path, edits: &ChunkEdits) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut data = fs::read(src)?;
+    let mcnks = mcnk_offsets(&data)?;
+
+    let mut changed = 0;
+    for (&index, &area_id) in edits {
+        let offset = *mcnks
+            .get(&index)
+            .ok_or_else(|| format!("{}: no MCNK for chunk {}", src.display(), index))?;
+        let field = &mut data[offset + MCNK_AREA_ID_OFFSET..offset + MCNK_AREA_ID_OFFSET + 4];
+        if field != area_id.to_le_bytes() {
+            field.copy_from_slice(&area_id.to_le_bytes());
+            changed += 1;
+        }
+    }
+
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(dest, data)?;
+    Ok(changed)
+}
+
+/// File offset of every MCNK keyed by chunk index, walking the top-level
+/// chunks and taking the index from each MCNK header
+fn mcnk_offsets(data: &[u8]) -> Result<BTreeMap<usize, usize>, Box<dyn std::error::Error>> {
+    let mut offsets = BTreeMap::new();
+    let mut pos = 0;
+
+    while pos + 8 <= data.len() {
+        let magic = &data[pos..pos + 4];
+        let size = u32::from_le_bytes(data[pos + 4..pos + 8].try_into()?) as usize;
+        let end = pos + 8 + size;
+        if end > data.len() {
+            return Err(format!("chunk at {:#x} runs past end of file", pos).into());
+        }
+
+        if magic == b"KNCM" || magic == b"MCNK" {
+            if size < MCNK_AREA_ID_OFFSET + 4 - 8 {
+                return Err(format!("MCNK at {:#x} too small", pos).into());
+            }
+            let index_x = u32::from_le_bytes(data[pos + 12..pos + 16].try_into()?) as usize;
+            let index_y = u32::from_le_bytes(data[pos + 16..pos + 20].try_into()?) as usize;
+            if index_x >= CHUNKS_PER_SIDE || index_y >= CHUNKS_PER_SIDE {
+                return Err(format!("MCNK at {:#x} has bad index ({}, {})", pos, index_x, index_y).into());
+            }
+            offsets.insert(index_y * CHUNKS_PER_SIDE + index_x, pos);
+        }
+
+        pos = end;
+    }
+
+    if offsets.is_empty() {
+        return Err("no MCNK chunks".into());
+    }
+    Ok(offsets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// An empty scratch directory under the system temp dir
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("zonemap-patch-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn chunk(magic: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut out = magic.to_vec();
+        out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        out.extend_from_slice(body);
+        out
+    }
+
+    /// A 128-byte MCNK header for chunk (x, y) in `area_id`, plus a
+    /// sub-chunk so the MCNK isn't only its header
+    fn mcnk(x: u32, y: u32, area_id: u32) -> Vec<u8> {
+        let mut body = vec![0xAB; 128];
+        body[4..8].copy_from_slice(&x.to_le_bytes());
+        body[8..12].copy_from_slice(&y.to_le_bytes());
+        body[0x34..0x38].copy_from_slice(&area_id.to_le_bytes());
+        body.extend_from_slice(&chunk(b"TVCM", &[7; 16]));
+        chunk(b"KNCM", &body)
+    }
+
+    /// An ADT with MVER, an MHDR and a full 16x16 set of MCNKs, chunk i in area i
+    fn adt() -> Vec<u8> {
+        let mut data = chunk(b"REVM", &18u32.to_le_bytes());
+        data.extend_from_slice(&chunk(b"RDHM", &[0; 64]));
+        for i in 0..256u32 {
+            data.extend_from_slice(&mcnk(i % 16, i / 16, i));
+        }
+        data
+    }
+
+    fn area_of(data: &[u8], index: usize) -> u32 {
+        let at = mcnk_offsets(data).unwrap()[&index] + MCNK_AREA_ID_OFFSET;
+        u32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn only_area_ids_change() {
+        let dir = scratch("edit");
+        let (src, dest) = (dir.join("Test_32_48.adt"), dir.join("out/Test_32_48.adt"));
+        let original = adt();
+        fs::write(&src, &original).unwrap();
+
+        // Chunk 5 already has area 5, so it doesn't count as a change
+        let edits: ChunkEdits = [(0, 1519), (5, 5), (17, 12), (255, 40)].into_iter().collect();
+        assert_eq!(patch_area_ids(&src, &dest, &edits).unwrap(), 3);
+        let patched = fs::read(&dest).unwrap();
+        assert_eq!(patched.len(), original.len());
+        assert_eq!((area_of(&patched, 0), area_of(&patched, 5), area_of(&patched, 17), area_of(&patched, 255)), (1519, 5, 12, 40));
+        assert_eq!(area_of(&patched, 16), 16);
+
+        // Every other byte is untouched
+        let fields: Vec<usize> = [0, 17, 255].iter().map(|i| mcnk_offsets(&original).unwrap()[i] + MCNK_AREA_ID_OFFSET).collect();
+        for (at, (a, b)) in original.iter().zip(&patched).enumerate() {
+            if !fields.iter().any(|&f| (f..f + 4).contains(&at)) {
+                assert_eq!(a, b, "byte {:#x}", at);
+            }
+        }
+        assert_eq!(fs::read(&src).unwrap(), original);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn chunks_are_found_by_their_header_index() {
+        // Stored out of order, and with the file's other chunk names as-is
+        let mut data = chunk(b"REVM", &18u32.to_le_bytes());
+        data.extend_from_slice(&mcnk(3, 1, 7));
+        data.extend_from_slice(&mcnk(0, 0, 9));
+        data.extend_from_slice(&chunk(b"O2HM", &[1; 24]));
+        let offsets = mcnk_offsets(&data).unwrap();
+        assert_eq!(offsets.keys().copied().collect::<Vec<_>>(), [0, 19]);
+        assert_eq!((area_of(&data, 19), area_of(&data, 0)), (7, 9));
+    }
+
+    #[test]
+    fn malformed_files_are_errors() {
+        let good = adt();
+        let mut past_end = good.clone();
+        past_end.truncate(good.len() - 1);
+        let mut bad_index = chunk(b"REVM", &[0; 4]);
+        bad_index.extend_from_slice(&mcnk(16, 0, 1));
+        let tiny_mcnk = chunk(b"KNCM", &[0; 40]);
+        let huge_size = [b"REVM".as_slice(), &u32::MAX.to_le_bytes()].concat();
+
+        for (data, expected) in [
+            (Vec::new(), "no MCNK chunks".to_string()),
+            (chunk(b"REVM", &[0; 4]), "no MCNK chunks".to_string()),
+            (past_end, format!("chunk at {:#x} runs past end of file", good.len() - mcnk(15, 15, 255).len())),
+            (bad_index, "MCNK at 0xc has bad index (16, 0)".to_string()),
+            (tiny_mcnk, "MCNK at 0x0 too small".to_string()),
+            (huge_size, "chunk at 0x0 runs past end of file".to_string()),
+        ] {
+            assert_eq!(mcnk_offsets(&data).unwrap_err().to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn edits_for_missing_chunks_write_nothing() {
+        let dir = scratch("missing");
+        let (src, dest) = (dir.join("Test_0_0.adt"), dir.join("out.adt"));
+        let mut data = chunk(b"REVM", &[0; 4]);
+        data.extend_from_slice(&mcnk(0, 0, 1));
+        fs::write(&src, &data).unwrap();
+
+        let edits: ChunkEdits = [(1, 12)].into_iter().collect();
+        let err = patch_area_ids(&src, &dest, &edits).unwrap_err().to_string();
+        assert!(err.ends_with("no MCNK for chunk 1"), "{}", err);
+        assert!(!dest.exists());
+        assert!(patch_area_ids(&dir.join("absent.adt"), &dest, &edits).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,329 @@
+use crate::adt::{parse_adt, TileData};
+use crate::codec::{Codec, LayerCodecs};
+use crate::failure::is_split_adt;
+use crate::grid::TILES_PER_SIDE;
+use crate::layers::LayerExtractor;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub mod areas;
+pub mod decoders;
+pub mod maps;
+#[cfg(feature = "dbc")]
+pub mod overlays;
+pub mod publish;
+
+// ============================================================================
+// Pipeline
+// ============================================================================
+//
+// The generator's stages as iterator adapters:
+//
+//     discover(dir)?.parse().extract(&layers).encode(&codecs).write(&mut sink)
+//
+// Any iterator adapter can go between stages (`filter`, `map`, `take`, ...).
+// Errors travel down the pipeline as `Err` items so one bad file doesn't
+// stop the rest; `write` collects them.
+//
+// `cancel_on(token)` after any stage stops the pipeline between files once
+// the token is cancelled or its deadline passes.
+//
+// What `generate` writes from the scanned tiles is in the submodules, one
+// function per step: `maps` for each map's files, `areas` for the AreaTable
+// files, `overlays` for game data placed on the grids and `publish` for
+// versioning, signing and storing the export.
+
+/// A root ADT found on disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileFile {
+    pub path: PathBuf,
+    pub map: String,
+    pub tile_x: u32,
+    pub tile_y: u32,
+}
+
+impl TileFile {
+    /// `<Map>_<x>_<y>.adt`, or `None` for anything else
+    pub fn from_path(path: &Path) -> Option<Self> {
+        if !path.extension()?.to_str()?.eq_ignore_ascii_case("adt") {
+            return None;
+        }
+        let stem = path.file_stem()?.to_str()?;
+        let parts: Vec<&str> = stem.split('_').collect();
+        if parts.len() != 3 {
+            return None;
+        }
+        Some(Self {
+            path: path.to_path_buf(),
+            map: parts[0].to_string(),
+            tile_x: parts[1].parse().ok()?,
+            tile_y: parts[2].parse().ok()?,
+        })
+    }
+
+    pub fn key(&self) -> u32 {
+        self.tile_y * TILES_PER_SIDE + self.tile_x
+    }
+}
+
+/// A file that failed at some stage
+#[derive(Debug)]
+pub struct TileError {
+    pub path: PathBuf,
+    pub error: Box<dyn std::error::Error>,
+}
+
+impl fmt::Display for TileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.error)
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParsedTile {
+    pub file: TileFile,
+    pub tile: TileData,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtractedTile {
+    pub file: TileFile,
+    /// Layer name -> 256 values
+    pub layers: BTreeMap<String, Vec<u32>>,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EncodedTile {
+    pub file: TileFile,
+    /// Layer name -> codec and encoded bytes
+    pub layers: BTreeMap<String, (Codec, Vec<u8>)>,
+}
+
+/// Root ADTs in `dir`, sorted by path; split files and non-ADTs are skipped
+pub fn discover(dir: &Path) -> std::io::Result<std::vec::IntoIter<TileFile>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !path.is_file() || is_split_adt(&path) {
+            continue;
+        }
+        if let Some(file) = TileFile::from_path(&path) {
+            files.push(file);
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files.into_iter())
+}
+
+// ============================================================================
+// Stages
+// ============================================================================
+
+pub struct Parse<I> {
+    inner: I,
+}
+
+impl<I: Iterator<Item = TileFile>> Iterator for Parse<I> {
+    type Item = Result<ParsedTile, TileError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let file = self.inner.next()?;
+        Some(match parse_adt(&file.path) {
+            Ok(Some(tile)) => Ok(ParsedTile { file, tile }),
+            Ok(None) => Err(TileError { path: file.path, error: "no MCNK chunks".into() }),
+            Err(error) => Err(TileError { path: file.path, error }),
+        })
+    }
+}
+
+pub struct Extract<'a, I> {
+    inner: I,
+    layers: &'a [&'a dyn LayerExtractor],
+}
+
+impl<I: Iterator<Item = Result<ParsedTile, TileError>>> Iterator for Extract<'_, I> {
+    type Item = Result<ExtractedTile, TileError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.inner.next()?.map(|parsed| ExtractedTile {
+            layers: self
+                .layers
+                .iter()
+                .map(|layer| (layer.name().to_string(), layer.extract(&parsed.tile)))
+                .collect(),
+            file: parsed.file,
+        }))
+    }
+}
+
+pub struct Encode<'a, I> {
+    inner: I,
+    codecs: &'a LayerCodecs,
+}
+
+impl<I: Iterator<Item = Result<ExtractedTile, TileError>>> Iterator for Encode<'_, I> {
+    type Item = Result<EncodedTile, TileError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.inner.next()?.map(|extracted| EncodedTile {
+            layers: extracted
+                .layers
+                .into_iter()
+                .map(|(name, values)| {
+                    let codec = self.codecs.get(&name);
+                    let bytes = codec.encode(&values);
+                    (name, (codec, bytes))
+                })
+                .collect(),
+            file: extracted.file,
+        }))
+    }
+}
+
+/// Where encoded tiles end up
+pub trait TileSink {
+    fn write_tile(&mut self, tile: EncodedTile) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+impl TileSink for Vec<EncodedTile> {
+    fn write_tile(&mut self, tile: EncodedTile) -> Result<(), Box<dyn std::error::Error>> {
+        self.push(tile);
+        Ok(())
+    }
+}
+
+/// Outcome of `write`: tiles written and every per-file error on the way.
+/// Check the token to tell a cancelled run from a finished one.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct WriteSummary {
+    pub written: usize,
+    pub errors: Vec<TileError>,
+}
+
+/// Shared flag for stopping a pipeline from another thread (e.g. a GUI's
+/// cancel button), optionally with a deadline
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels this token along with its parent and any derived tokens
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed) || self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+
+    /// A token that is also cancelled `timeout` from now. Cancelling the
+    /// parent still cancels it; use one per stage for per-stage timeouts.
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        let deadline = Instant::now() + timeout;
+        Self {
+            cancelled: Arc::clone(&self.cancelled),
+            deadline: Some(self.deadline.map_or(deadline, |d| d.min(deadline))),
+        }
+    }
+}
+
+pub struct Cancellable<I> {
+    inner: I,
+    token: CancellationToken,
+}
+
+impl<I: Iterator> Iterator for Cancellable<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.token.is_cancelled() {
+            return None;
+        }
+        self.inner.next()
+    }
+}
+
+// ============================================================================
+// Adapter traits
+// ============================================================================
+//
+// Sealed: they are implemented for every matching iterator, and new stages
+// or methods can be added without breaking downstream code. Custom stages
+// are plain iterator adapters; custom layers and sinks implement
+// `LayerExtractor` and `TileSink`.
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl<I: Iterator> Sealed for I {}
+}
+
+pub trait CancelStage: Iterator + Sized + sealed::Sealed {
+    /// Stop yielding once `token` is cancelled. The item in progress finishes;
+    /// nothing after it is started.
+    fn cancel_on(self, token: &CancellationToken) -> Cancellable<Self> {
+        Cancellable { inner: self, token: token.clone() }
+    }
+}
+
+impl<I: Iterator> CancelStage for I {}
+
+pub trait ParseStage: Iterator<Item = TileFile> + Sized + sealed::Sealed {
+    fn parse(self) -> Parse<Self> {
+        Parse { inner: self }
+    }
+}
+
+impl<I: Iterator<Item = TileFile>> ParseStage for I {}
+
+pub trait ExtractStage: Iterator<Item = Result<ParsedTile, TileError>> + Sized + sealed::Sealed {
+    fn extract<'a>(self, layers: &'a [&'a dyn LayerExtractor]) -> Extract<'a, Self> {
+        Extract { inner: self, layers }
+    }
+}
+
+impl<I: Iterator<Item = Result<ParsedTile, TileError>>> ExtractStage for I {}
+
+pub trait EncodeStage: Iterator<Item = Result<ExtractedTile, TileError>> + Sized + sealed::Sealed {
+    fn encode(self, codecs: &LayerCodecs) -> Encode<'_, Self> {
+        Encode { inner: self, codecs }
+    }
+}
+
+impl<I: Iterator<Item = Result<ExtractedTile, TileError>>> EncodeStage for I {}
+
+pub trait WriteStage: Iterator<Item = Result<EncodedTile, TileError>> + Sized + sealed::Sealed {
+    /// Drain the pipeline into `sink`. A sink error on a tile is recorded
+    /// against that tile and the rest keep going.
+    fn write(self, sink: &mut impl TileSink) -> WriteSummary {
+        let mut summary = WriteSummary::default();
+        for item in self {
+            match item {
+                Ok(tile) => {
+                    let path = tile.file.path.clone();
+                    match sink.write_tile(tile) {
+                        Ok(()) => summary.written += 1,
+                        Err(error) => summary.errors.push(TileError { path, error }),
+                    }
+                }
+                Err(e) => summary.errors.push(e),
+            }
+        }
+        summary
+    }
+}
+
+impl<I: Iterator<Item = Result<EncodedTile, TileError>>> WriteStage for I {}
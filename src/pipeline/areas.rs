@@ -0,0 +1,680 @@
+use crate::attributes::{fields_to_json, fields_to_lua, lua_key, AttributeTable};
+#[cfg(feature = "dbc")]
+use crate::dbc::Dbc;
+use crate::export::OutputFormat;
+use crate::theme::{tint, Theme};
+use crate::{error, info, json, warn};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+// ============================================================================
+// Areas
+// ============================================================================
+//
+// The AreaTable and the files written from it once every map is scanned:
+// area colours from the neighbour graph, AreaInfo, AreaHierarchy and each
+// map's `<Map>_areas.lua`.
+
+// ============================================================================
+// Area table
+// ============================================================================
+
+/// One AreaTable row
+#[derive(Debug, Clone)]
+pub struct AreaInfo {
+    pub name: String,
+    pub parent_id: u32,
+    pub exploration_level: i32,
+    /// Exploration bit (AreaTable AreaBit / exploreFlag), when the CSV has one
+    pub area_bit: Option<u32>,
+}
+
+fn parse_area_table(csv_path: &Path) -> Result<HashMap<u32, AreaInfo>, Box<dyn std::error::Error>> {
+    let file = File::open(csv_path)?;
+    let reader = BufReader::new(file);
+    let mut areas = HashMap::new();
+    
+    let mut lines = reader.lines();
+    let header = lines.next().ok_or("Empty CSV")??;
+    
+    let columns: Vec<&str> = header.split(',').collect();
+    let id_idx = columns.iter().position(|&c| c == "ID").ok_or("No ID column")?;
+    let name_idx = columns.iter().position(|&c| c == "AreaName_lang").ok_or("No AreaName_lang column")?;
+    let parent_idx = columns.iter().position(|&c| c == "ParentAreaID").ok_or("No ParentAreaID column")?;
+    let level_idx = columns.iter().position(|&c| c == "ExplorationLevel").ok_or("No ExplorationLevel column")?;
+    let bit_idx = columns.iter().position(|&c| c == "AreaBit" || c == "exploreFlag");
+    
+    for line in lines {
+        let line = line?;
+        let fields: Vec<&str> = parse_csv_line(&line);
+        
+        if fields.len() <= id_idx.max(name_idx).max(parent_idx).max(level_idx) {
+            continue;
+        }
+        
+        let id: u32 = match fields[id_idx].parse() {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        
+        let name = fields[name_idx].trim_matches('"').to_string();
+        let parent_id: u32 = fields[parent_idx].parse().unwrap_or(0);
+        let exploration_level: i32 = fields[level_idx].parse().unwrap_or(0);
+        // Negative AreaBit marks areas without exploration
+        let area_bit = bit_idx.and_then(|i| fields.get(i)).and_then(|b| b.parse().ok());
+        
+        areas.insert(id, AreaInfo { name, parent_id, exploration_level, area_bit });
+    }
+    
+    Ok(areas)
+}
+
+/// AreaTable.dbc field layout shared by 1.12 through 4.x: the name's first
+/// (enUS) locale column is 11 in all of them
+#[cfg(feature = "dbc")]
+const AREA_DBC_FIELDS: usize = 12;
+
+/// Areas of a WDBC/WDB2 AreaTable
+#[cfg(feature = "dbc")]
+fn parse_area_table_dbc(dbc: &Dbc) -> Result<HashMap<u32, AreaInfo>, String> {
+    if dbc.field_count < AREA_DBC_FIELDS {
+        return Err(format!("AreaTable has {} fields, expected at least {}", dbc.field_count, AREA_DBC_FIELDS));
+    }
+    Ok(dbc
+        .records()
+        .map(|r| {
+            let info = AreaInfo {
+                name: r.string(11).to_string(),
+                parent_id: r.u32(2),
+                exploration_level: r.u32(10) as i32,
+                area_bit: Some(r.u32(3)).filter(|&b| (b as i32) >= 0),
+            };
+            (r.u32(0), info)
+        })
+        .collect())
+}
+
+/// Fields of a CSV line, splitting on commas outside quotes
+pub(crate) fn parse_csv_line(line: &str) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let mut start = 0;
+    let mut in_quotes = false;
+    let bytes = line.as_bytes();
+
+    for i in 0..bytes.len() {
+        match bytes[i] {
+            b'"' => in_quotes = !in_quotes,
+            b',' if !in_quotes => {
+                fields.push(&line[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    fields.push(&line[start..]);
+    fields
+}
+
+/// The zone `area_id` is in: its top-most parent, or itself
+pub fn find_root_parent(area_id: u32, areas: &HashMap<u32, AreaInfo>) -> u32 {
+    let mut current = area_id;
+    let mut visited = BTreeSet::new();
+
+    while let Some(area) = areas.get(&current) {
+        if area.parent_id == 0 || visited.contains(&current) {
+            return current;
+        }
+        visited.insert(current);
+        current = area.parent_id;
+    }
+    area_id
+}
+
+/// AreaTable from an `AreaTable.dbc`/`.db2` (with the `dbc` feature), or
+/// else the CSV for `client_build`, in the working directory; empty (with a
+/// warning) when none is there or it is unreadable
+pub fn load_area_table(client_build: &str) -> HashMap<u32, AreaInfo> {
+    #[cfg(feature = "dbc")]
+    if let Some(dbc_path) = ["AreaTable.dbc", "AreaTable.db2"].iter().map(Path::new).find(|p| p.exists()) {
+        return match Dbc::from_file(dbc_path).and_then(|dbc| Ok(parse_area_table_dbc(&dbc)?)) {
+            Ok(a) => {
+                info!("Loaded {} areas from {}\n", a.len(), dbc_path.display());
+                a
+            }
+            Err(e) => {
+                warn!("Warning: Failed to parse {}: {}", dbc_path.display(), e);
+                HashMap::new()
+            }
+        };
+    }
+    let csv_name = format!("AreaTable.{}.csv", client_build);
+    let csv_path = Path::new(&csv_name);
+    if csv_path.exists() {
+        match parse_area_table(csv_path) {
+            Ok(a) => {
+                info!("Loaded {} areas from CSV\n", a.len());
+                a
+            }
+            Err(e) => {
+                warn!("Warning: Failed to parse area table: {}", e);
+                HashMap::new()
+            }
+        }
+    } else {
+        warn!("Warning: AreaTable.dbc/.db2 and AreaTable CSV not found\n");
+        HashMap::new()
+    }
+}
+
+// ============================================================================
+// Neighbor detection and graph coloring
+// ============================================================================
+
+/// Areas that share a chunk edge with each area
+pub type NeighborGraph = HashMap<u32, HashSet<u32>>;
+
+/// Add a neighbor relationship (bidirectional)
+fn add_neighbor(graph: &mut NeighborGraph, a: u32, b: u32) {
+    if a != 0 && b != 0 && a != b {
+        graph.entry(a).or_default().insert(b);
+        graph.entry(b).or_default().insert(a);
+    }
+}
+
+/// Find neighbors within a single tile (adjacent chunks with different area IDs)
+fn find_tile_neighbors(area_ids: &[u32], graph: &mut NeighborGraph) {
+    // area_ids is 256 elements, 16x16 grid
+    // Check horizontal neighbors (left-right)
+    for y in 0..16 {
+        for x in 0..15 {
+            let idx1 = y * 16 + x;
+            let idx2 = y * 16 + x + 1;
+            add_neighbor(graph, area_ids[idx1], area_ids[idx2]);
+        }
+    }
+    
+    // Check vertical neighbors (up-down)
+    for y in 0..15 {
+        for x in 0..16 {
+            let idx1 = y * 16 + x;
+            let idx2 = (y + 1) * 16 + x;
+            add_neighbor(graph, area_ids[idx1], area_ids[idx2]);
+        }
+    }
+}
+
+/// Find neighbors between adjacent tiles
+fn find_inter_tile_neighbors(
+    tiles: &HashMap<u32, Vec<u32>>,
+    graph: &mut NeighborGraph,
+) {
+    for (&key, area_ids) in tiles {
+        let tile_x = key % 64;
+        let tile_y = key / 64;
+        
+        // Check right neighbor tile
+        if tile_x < 63 {
+            let right_key = tile_y * 64 + tile_x + 1;
+            if let Some(right_ids) = tiles.get(&right_key) {
+                // Compare rightmost column of current tile with leftmost column of right tile
+                for y in 0..16 {
+                    let idx_current = y * 16 + 15;  // Rightmost column
+                    let idx_right = y * 16;         // Leftmost column
+                    add_neighbor(graph, area_ids[idx_current], right_ids[idx_right]);
+                }
+            }
+        }
+        
+        // Check bottom neighbor tile
+        if tile_y < 63 {
+            let bottom_key = (tile_y + 1) * 64 + tile_x;
+            if let Some(bottom_ids) = tiles.get(&bottom_key) {
+                // Compare bottom row of current tile with top row of bottom tile
+                for x in 0..16 {
+                    let idx_current = 15 * 16 + x;  // Bottom row
+                    let idx_bottom = x;             // Top row
+                    add_neighbor(graph, area_ids[idx_current], bottom_ids[idx_bottom]);
+                }
+            }
+        }
+    }
+}
+
+/// Generate distinct colors using graph coloring
+/// Returns a map of area_id -> (r, g, b)
+fn generate_colors_with_graph(
+    found_areas: &BTreeSet<u32>,
+    neighbors: &NeighborGraph,
+    areas: &HashMap<u32, AreaInfo>,
+) -> HashMap<u32, (f32, f32, f32)> {
+    let mut colors: HashMap<u32, (f32, f32, f32)> = HashMap::new();
+    
+    // Predefined palette of visually distinct colors
+    let palette: Vec<(f32, f32, f32)> = vec![
+        (0.90, 0.30, 0.30),  // Red
+        (0.30, 0.70, 0.30),  // Green
+        (0.30, 0.50, 0.90),  // Blue
+        (0.90, 0.80, 0.20),  // Yellow
+        (0.80, 0.40, 0.80),  // Purple
+        (0.20, 0.80, 0.80),  // Cyan
+        (0.95, 0.60, 0.30),  // Orange
+        (0.60, 0.80, 0.40),  // Lime
+        (0.80, 0.50, 0.60),  // Pink
+        (0.50, 0.70, 0.80),  // Sky blue
+        (0.70, 0.60, 0.40),  // Tan
+        (0.60, 0.40, 0.70),  // Violet
+        (0.40, 0.60, 0.50),  // Teal
+        (0.85, 0.70, 0.70),  // Light pink
+        (0.70, 0.85, 0.70),  // Light green
+        (0.70, 0.70, 0.85),  // Light blue
+    ];
+    
+    // Sort areas by number of neighbors (descending) for better coloring
+    let mut area_list: Vec<u32> = found_areas.iter().copied().filter(|&a| a != 0).collect();
+    area_list.sort_by_key(|&a| std::cmp::Reverse(neighbors.get(&a).map(|n| n.len()).unwrap_or(0)));
+    
+    for area_id in area_list {
+        // Find colors used by neighbors
+        let neighbor_colors: HashSet<usize> = neighbors
+            .get(&area_id)
+            .map(|ns| {
+                ns.iter()
+                    .filter_map(|&n| {
+                        colors.get(&n).and_then(|c| {
+                            palette.iter().position(|p| {
+                                (p.0 - c.0).abs() < 0.01 && 
+                                (p.1 - c.1).abs() < 0.01 && 
+                                (p.2 - c.2).abs() < 0.01
+                            })
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        
+        // Also avoid parent color
+        let parent_id = areas.get(&area_id).map(|a| a.parent_id).unwrap_or(0);
+        let parent_color_idx: Option<usize> = colors.get(&parent_id).and_then(|c| {
+            palette.iter().position(|p| {
+                (p.0 - c.0).abs() < 0.01 && 
+                (p.1 - c.1).abs() < 0.01 && 
+                (p.2 - c.2).abs() < 0.01
+            })
+        });
+        
+        // Find first available color
+        let mut chosen_idx = 0;
+        for i in 0..palette.len() {
+            if !neighbor_colors.contains(&i) && parent_color_idx != Some(i) {
+                chosen_idx = i;
+                break;
+            }
+        }
+        
+        // If all colors used, generate a unique one based on area_id
+        let color = if chosen_idx < palette.len() && !neighbor_colors.contains(&chosen_idx) {
+            palette[chosen_idx]
+        } else {
+            // Fallback: generate unique color
+            let golden_ratio = 0.618033988749895_f64;
+            let hue = ((area_id as f64) * golden_ratio) % 1.0;
+            let s = 0.7_f64;
+            let v = 0.9_f64;
+            let c = v * s;
+            let x = c * (1.0 - ((hue * 6.0) % 2.0 - 1.0).abs());
+            let m = v - c;
+            
+            let (r, g, b) = match (hue * 6.0) as i32 {
+                0 => (c, x, 0.0),
+                1 => (x, c, 0.0),
+                2 => (0.0, c, x),
+                3 => (0.0, x, c),
+                4 => (x, 0.0, c),
+                _ => (c, 0.0, x),
+            };
+            ((r + m) as f32, (g + m) as f32, (b + m) as f32)
+        };
+        
+        colors.insert(area_id, color);
+    }
+    
+    colors
+}
+
+/// Add the area borders of one map's tiles to `graph`, within and between tiles
+pub fn add_map_neighbors(tiles: &HashMap<u32, Vec<u32>>, graph: &mut NeighborGraph) {
+    for area_ids in tiles.values() {
+        find_tile_neighbors(area_ids, graph);
+    }
+    find_inter_tile_neighbors(tiles, graph);
+}
+
+/// The colour theme in `theme_file`, or else the built-in one named by
+/// `theme` (`auto` picks the one for `client_build`)
+pub fn load_theme(theme_file: &Path, theme: Option<&str>, client_build: &str) -> Option<Theme> {
+    if theme_file.exists() {
+        return Theme::from_file(theme_file).map_err(|e| error!("Failed to parse {}: {}", theme_file.display(), e)).ok();
+    }
+    match theme {
+        Some("auto") => Theme::for_build(client_build),
+        Some(name) => Theme::builtin(name).or_else(|| {
+            warn!("Unknown colour theme: {}", name);
+            None
+        }),
+        None => None,
+    }
+}
+
+/// Colours for the areas found, apart from their neighbours and parents.
+/// Themed zones take the theme colour; their subzones are tinted towards it.
+pub fn area_colors(found_areas: &BTreeSet<u32>, neighbors: &NeighborGraph, areas: &HashMap<u32, AreaInfo>, theme: Option<&Theme>) -> HashMap<u32, (f32, f32, f32)> {
+    info!("\nBuilding neighbor graph...");
+    info!("  Found {} areas with neighbor relationships", neighbors.len());
+    let mut colors = generate_colors_with_graph(found_areas, neighbors, areas);
+    if let Some(theme) = theme {
+        let mut tinted = 0;
+        for (&area_id, color) in colors.iter_mut() {
+            if let Some(zone_color) = theme.color(area_id) {
+                *color = zone_color;
+                tinted += 1;
+            } else if let Some(zone_color) = theme.color(find_root_parent(area_id, areas)) {
+                *color = tint(*color, zone_color);
+                tinted += 1;
+            }
+        }
+        info!("  Applied colour theme '{}' to {} areas", theme.name, tinted);
+    }
+    colors
+}
+
+// ============================================================================
+// Area files
+// ============================================================================
+
+/// Fields `export_area_info` writes itself; attribute tables can't reuse them
+const AREA_INFO_FIELDS: &[&str] =
+    &["name", "parentId", "rootParentId", "explorationLevel", "exploreBit", "color", "neighborCount"];
+
+/// Export area info to Lua
+fn export_area_info(
+    found_areas: &BTreeSet<u32>,
+    areas: &HashMap<u32, AreaInfo>,
+    colors: &HashMap<u32, (f32, f32, f32)>,
+    neighbors: &NeighborGraph,
+    attributes: &[AttributeTable],
+    out_path: &Path,
+) -> std::io::Result<()> {
+    let mut f = File::create(out_path)?;
+
+    writeln!(f, "-- Auto-generated Area Info")?;
+    writeln!(f, "-- Contains name, parent, level, exploration bit, color, and neighbors for each area")?;
+    writeln!(f)?;
+    writeln!(f, "local _, addon = ...")?;
+    writeln!(f)?;
+    writeln!(f, "addon.AreaInfo = {{")?;
+
+    for &area_id in found_areas {
+        if area_id == 0 {
+            continue;
+        }
+
+        let (name, parent_id, root_parent, level, area_bit) = if let Some(area) = areas.get(&area_id) {
+            let root = find_root_parent(area_id, areas);
+            (area.name.clone(), area.parent_id, root, area.exploration_level, area.area_bit)
+        } else {
+            (format!("Unknown_{}", area_id), 0, area_id, 0, None)
+        };
+
+        let (r, g, b) = colors.get(&area_id).copied().unwrap_or((0.5, 0.5, 0.5));
+        let escaped_name = name.replace("\"", "\\\"");
+
+        // Get neighbor count for info
+        let neighbor_count = neighbors.get(&area_id).map(|n| n.len()).unwrap_or(0);
+
+        writeln!(f, "  [{}] = {{", area_id)?;
+        writeln!(f, "    name = \"{}\",", escaped_name)?;
+        writeln!(f, "    parentId = {},", parent_id)?;
+        writeln!(f, "    rootParentId = {},", root_parent)?;
+        writeln!(f, "    explorationLevel = {},", level)?;
+        if let Some(bit) = area_bit {
+            writeln!(f, "    exploreBit = {},", bit)?;
+        }
+        writeln!(f, "    color = {{{:.3}, {:.3}, {:.3}}},", r, g, b)?;
+        writeln!(f, "    neighborCount = {},", neighbor_count)?;
+        for table in attributes {
+            if let Some(fields) = table.rows.get(&area_id) {
+                writeln!(f, "    {} = {},", lua_key(&table.name), fields_to_lua(fields))?;
+            }
+        }
+        writeln!(f, "  }},")?;
+    }
+
+    writeln!(f, "}}")?;
+    Ok(())
+}
+
+/// The same fields as `export_area_info`, as a JSON object keyed by area ID
+fn export_area_info_json(
+    found_areas: &BTreeSet<u32>,
+    areas: &HashMap<u32, AreaInfo>,
+    colors: &HashMap<u32, (f32, f32, f32)>,
+    neighbors: &NeighborGraph,
+    attributes: &[AttributeTable],
+    out_path: &Path,
+) -> std::io::Result<()> {
+    let mut entries = Vec::new();
+    for &area_id in found_areas {
+        if area_id == 0 {
+            continue;
+        }
+
+        let (name, parent_id, root_parent, level, area_bit) = if let Some(area) = areas.get(&area_id) {
+            let root = find_root_parent(area_id, areas);
+            (area.name.clone(), area.parent_id, root, area.exploration_level, area.area_bit)
+        } else {
+            (format!("Unknown_{}", area_id), 0, area_id, 0, None)
+        };
+        let (r, g, b) = colors.get(&area_id).copied().unwrap_or((0.5, 0.5, 0.5));
+        let neighbor_count = neighbors.get(&area_id).map(|n| n.len()).unwrap_or(0);
+
+        let mut fields = vec![
+            format!("\"name\":{}", json::string(&name)),
+            format!("\"parentId\":{}", parent_id),
+            format!("\"rootParentId\":{}", root_parent),
+            format!("\"explorationLevel\":{}", level),
+        ];
+        if let Some(bit) = area_bit {
+            fields.push(format!("\"exploreBit\":{}", bit));
+        }
+        fields.push(format!("\"color\":[{:.3},{:.3},{:.3}]", r, g, b));
+        fields.push(format!("\"neighborCount\":{}", neighbor_count));
+        for table in attributes {
+            if let Some(row) = table.rows.get(&area_id) {
+                fields.push(format!("{}:{}", json::string(&table.name), fields_to_json(row)));
+            }
+        }
+        entries.push(format!("  \"{}\": {{{}}}", area_id, fields.join(",")));
+    }
+
+    fs::write(out_path, format!("{{\n{}\n}}\n", entries.join(",\n")))
+}
+
+fn export_area_hierarchy(
+    found_areas: &BTreeSet<u32>,
+    areas: &HashMap<u32, AreaInfo>,
+    out_path: &Path,
+) -> std::io::Result<()> {
+    // Group areas by root parent
+    let mut hierarchy: BTreeMap<u32, BTreeMap<u32, String>> = BTreeMap::new();
+    
+    for &area_id in found_areas {
+        if area_id == 0 {
+            continue;
+        }
+        
+        let root_parent = find_root_parent(area_id, areas);
+        let name = if let Some(area) = areas.get(&area_id) {
+            area.name.clone()
+        } else {
+            format!("Unknown_{}", area_id)
+        };
+        
+        hierarchy
+            .entry(root_parent)
+            .or_default()
+            .insert(area_id, name);
+    }
+    
+    let mut f = File::create(out_path)?;
+    
+    writeln!(f, "-- Auto-generated Area Hierarchy")?;
+    writeln!(f, "-- Groups areas by their root parent zone")?;
+    writeln!(f)?;
+    writeln!(f, "local _, addon = ...")?;
+    writeln!(f)?;
+    writeln!(f, "addon.AreaHierarchy = {{")?;
+    
+    for (root_id, children) in &hierarchy {
+        let root_name = if let Some(area) = areas.get(root_id) {
+            area.name.replace("\"", "\\\"")
+        } else {
+            format!("Unknown_{}", root_id)
+        };
+        
+        writeln!(f, "  [{}] = {{  -- {}", root_id, root_name)?;
+        writeln!(f, "    name = \"{}\",", root_name)?;
+        writeln!(f, "    children = {{")?;
+        
+        for (child_id, child_name) in children {
+            let escaped = child_name.replace("\"", "\\\"");
+            writeln!(f, "      [{}] = \"{}\",", child_id, escaped)?;
+        }
+        
+        writeln!(f, "    }},")?;
+        writeln!(f, "  }},")?;
+    }
+    
+    writeln!(f, "}}")?;
+    
+    info!("  {} root zones, {} total areas", hierarchy.len(), found_areas.len());
+    Ok(())
+}
+
+/// Export the names of one continent's areas, with each area's parent and
+/// root zone, for `addon:GetContinentArea`
+fn export_continent_areas(
+    map: &str,
+    found_areas: &BTreeSet<u32>,
+    areas: &HashMap<u32, AreaInfo>,
+    out_path: &Path,
+) -> std::io::Result<usize> {
+    let mut f = File::create(out_path)?;
+    
+    writeln!(f, "-- Auto-generated area names for {}", map)?;
+    writeln!(f, "-- Every area ID in the {} grid with its name, parent and root zone", map)?;
+    writeln!(f)?;
+    writeln!(f, "local _, addon = ...")?;
+    writeln!(f)?;
+    writeln!(f, "addon.ContinentAreas = addon.ContinentAreas or {{}}")?;
+    writeln!(f, "addon.ContinentAreas[\"{}\"] = {{", map)?;
+    
+    let mut written = 0;
+    for &area_id in found_areas {
+        let Some(area) = areas.get(&area_id) else {
+            continue;
+        };
+        let zone_id = find_root_parent(area_id, areas);
+        let zone_name = areas.get(&zone_id).map_or(area.name.as_str(), |z| z.name.as_str());
+        writeln!(
+            f,
+            "  [{}] = {{ name = \"{}\", parentId = {}, zoneId = {}, zoneName = \"{}\" }},",
+            area_id,
+            area.name.replace('"', "\\\""),
+            area.parent_id,
+            zone_id,
+            zone_name.replace('"', "\\\"")
+        )?;
+        written += 1;
+    }
+    
+    writeln!(f, "}}")?;
+    Ok(written)
+}
+
+/// The attribute tables in `dir`, none when it doesn't exist. Fails on a
+/// table that can't be read or that is named after a built-in field.
+pub fn load_attributes(dir: &Path, areas: &HashMap<u32, AreaInfo>) -> Result<Vec<AttributeTable>, String> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let tables = AttributeTable::load_dir(dir).map_err(|e| format!("Failed to load attribute tables: {}", e))?;
+    for table in &tables {
+        if AREA_INFO_FIELDS.contains(&table.name.as_str()) {
+            return Err(format!("Attribute table '{}' clashes with a built-in AreaInfo field; rename the CSV", table.name));
+        }
+        let unknown = table.unknown_areas(|id| areas.contains_key(&id));
+        info!("  Attribute table '{}': {} areas", table.name, table.rows.len());
+        if !unknown.is_empty() {
+            let listed: Vec<String> = unknown.iter().take(10).map(|id| id.to_string()).collect();
+            warn!("  Warning: {} areas in '{}' are not in the AreaTable: {}{}", unknown.len(), table.name, listed.join(", "), if unknown.len() > listed.len() { ", ..." } else { "" });
+        }
+    }
+    Ok(tables)
+}
+
+/// Write AreaInfo.lua and/or AreaInfo.json to `out_dir`
+pub fn write_area_info(
+    found_areas: &BTreeSet<u32>,
+    areas: &HashMap<u32, AreaInfo>,
+    colors: &HashMap<u32, (f32, f32, f32)>,
+    neighbors: &NeighborGraph,
+    attributes: &[AttributeTable],
+    format: OutputFormat,
+    out_dir: &Path,
+) {
+    info!("\nGenerating area info...");
+    if format.lua() {
+        let area_info_path = out_dir.join("AreaInfo.lua");
+        if let Err(e) = export_area_info(found_areas, areas, colors, neighbors, attributes, &area_info_path) {
+            error!("Failed to write area info: {}", e);
+        } else {
+            info!("  Wrote: {}", area_info_path.display());
+        }
+    }
+    if format.json() {
+        let area_info_path = out_dir.join("AreaInfo.json");
+        if let Err(e) = export_area_info_json(found_areas, areas, colors, neighbors, attributes, &area_info_path) {
+            error!("Failed to write area info: {}", e);
+        } else {
+            info!("  Wrote: {}", area_info_path.display());
+        }
+    }
+}
+
+/// Write AreaHierarchy.lua, the areas grouped by root parent, to `out_dir`
+pub fn write_area_hierarchy(found_areas: &BTreeSet<u32>, areas: &HashMap<u32, AreaInfo>, out_dir: &Path) {
+    info!("\nGenerating area hierarchy...");
+    let hierarchy_path = out_dir.join("AreaHierarchy.lua");
+    if let Err(e) = export_area_hierarchy(found_areas, areas, &hierarchy_path) {
+        error!("Failed to write area hierarchy: {}", e);
+    } else {
+        info!("  Wrote: {}", hierarchy_path.display());
+    }
+}
+
+/// Write `<Map>_areas.lua` to `out_dir`; the path, when it was written
+pub fn write_continent_areas(map: &str, found_areas: &BTreeSet<u32>, areas: &HashMap<u32, AreaInfo>, out_dir: &Path) -> Option<PathBuf> {
+    let out_path = out_dir.join(format!("{}_areas.lua", map));
+    match export_continent_areas(map, found_areas, areas, &out_path) {
+        Ok(named) => {
+            info!("  Wrote: {} ({} of {} areas named)", out_path.display(), named, found_areas.len());
+            Some(out_path)
+        }
+        Err(e) => {
+            error!("Failed to write: {}", e);
+            None
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use crate::codec::Codec;
+use crate::export::Exporter;
+use crate::{error, info};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// ============================================================================
+// Addon decoders
+// ============================================================================
+//
+// ZoneMap.lua only decodes base64 and raw tiles. Data/Decoders.lua, written
+// with each Lua export, registers an expander for each codec the grids use
+// (their `codecs` and `tileCodecs`) and defines the accessors of the extra
+// layers they list, so the addon carries no decoder for data it doesn't have.
+
+pub const DECODERS_FILE: &str = "Decoders.lua";
+
+const READ_U32_LE: &str = r#"local function read_u32_le(s, i)
+  local b1, b2, b3, b4 = s:byte(i, i + 3)
+  if not b1 then return 0 end
+  return b1 + b2 * 256 + b3 * 65536 + b4 * 16777216
+end
+"#;
+
+const U32_LE_BYTES: &str = r#"local function u32_le_bytes(v)
+  return string.char(v % 256, math.floor(v / 256) % 256, math.floor(v / 65536) % 256, math.floor(v / 16777216) % 256)
+end
+"#;
+
+const EXPAND_PALETTE: &str = r#"-- palette: u8 count (0 = 256), count u32 entries, 256 u8 indices
+addon.codecs.palette = function(data)
+  local count = data:byte(1)
+  if count == 0 then count = 256 end
+  local palette = {}
+  for i = 0, count - 1 do
+    palette[i] = u32_le_bytes(read_u32_le(data, 2 + i * 4))
+  end
+  local out = {}
+  local base = 1 + count * 4
+  for i = 1, #data - base do
+    out[i] = palette[data:byte(base + i)] or u32_le_bytes(0)
+  end
+  return table.concat(out)
+end
+"#;
+
+const EXPAND_RLE: &str = r#"-- rle: (u8 run - 1, u32 value) pairs
+addon.codecs.rle = function(data)
+  local out = {}
+  for i = 1, #data - 4, 5 do
+    out[#out + 1] = string.rep(data:sub(i + 1, i + 4), data:byte(i) + 1)
+  end
+  return table.concat(out)
+end
+"#;
+
+const EXPAND_DELTA: &str = r#"-- delta: u8 version (1), then (varint run, varint zigzag delta) pairs;
+-- varints are LEB128 and deltas wrap at 2^32
+local DELTA_VERSION = 1
+
+local function read_varint(data, i)
+  local v, scale = 0, 1
+  while true do
+    local b = data:byte(i)
+    if not b then return nil, i end
+    i = i + 1
+    if b < 128 then return v + b * scale, i end
+    v = v + (b - 128) * scale
+    scale = scale * 128
+  end
+end
+
+addon.codecs.delta = function(data)
+  if data:byte(1) ~= DELTA_VERSION then return nil end
+  local out = {}
+  local value, i = 0, 2
+  while i <= #data do
+    local run, zigzag
+    run, i = read_varint(data, i)
+    zigzag, i = read_varint(data, i)
+    if not run or not zigzag then return nil end
+    local delta = (zigzag % 2 == 0) and zigzag / 2 or -(zigzag + 1) / 2
+    value = (value + delta) % 4294967296
+    out[#out + 1] = string.rep(u32_le_bytes(value), run)
+  end
+  return table.concat(out)
+end
+"#;
+
+const GET_LIQUID_DEPTH: &str = r#"-- -------------------------
+-- Public API: Liquid depth range (yards) for one chunk, from the liquiddepth layer.
+-- Returns shallowest, deepest; 0, 0 on dry land; nil when the grid wasn't
+-- exported with the layer (see HasLayer).
+-- -------------------------
+function addon:GetLiquidDepth(gridName, tileX, tileY, chunkX, chunkY)
+  local packed = self:GetLayerValue(gridName, "liquiddepth", tileX, tileY, chunkX, chunkY)
+  if not packed then return nil end
+  return (packed % 65536) / 10, math.floor(packed / 65536) / 10
+end
+"#;
+
+const IS_CHUNK_HOLE: &str = r#"-- -------------------------
+-- Public API: Whether cell (cellX, cellY) of a chunk's 4x4 holes grid is open
+-- terrain (a pit, cave mouth or gate); nil when the holes layer wasn't exported
+-- -------------------------
+function addon:IsChunkHole(gridName, tileX, tileY, chunkX, chunkY, cellX, cellY)
+  local holes = self:GetLayerValue(gridName, "holes", tileX, tileY, chunkX, chunkY)
+  if not holes then return nil end
+  return math.floor(holes / 2 ^ (cellY * 4 + cellX)) % 2 == 1
+end
+"#;
+
+const GET_CHUNK_LIQUID: &str = r#"-- -------------------------
+-- Public API: Liquid in one chunk, from the liquid and liquidlevel layers.
+-- Returns the liquid type and surface height (yards, nil without the
+-- liquidlevel layer); nil on dry land or when the liquid layer wasn't exported.
+-- -------------------------
+function addon:GetChunkLiquid(gridName, tileX, tileY, chunkX, chunkY)
+  local liquidType = self:GetLayerValue(gridName, "liquid", tileX, tileY, chunkX, chunkY)
+  if not liquidType or liquidType == 0 then return nil end
+  local level = self:GetLayerValue(gridName, "liquidlevel", tileX, tileY, chunkX, chunkY)
+  if level and level ~= 0 then
+    return liquidType, level - 32768
+  end
+  return liquidType, nil
+end
+"#;
+
+/// Codecs and extra layers of the Lua grids written so far
+#[derive(Debug, Clone, Default)]
+pub struct AddonDecoders {
+    codecs: Vec<Codec>,
+    layers: BTreeSet<String>,
+}
+
+impl AddonDecoders {
+    /// Note what the addon needs to read `grid`
+    pub fn add_grid(&mut self, grid: &Exporter) {
+        for codec in grid.codecs_used() {
+            if !self.codecs.contains(&codec) {
+                self.codecs.push(codec);
+            }
+        }
+        self.layers.extend(grid.layer_names().map(str::to_string));
+    }
+
+    /// Note what another set of grids needs
+    pub fn merge(&mut self, other: AddonDecoders) {
+        for codec in other.codecs {
+            if !self.codecs.contains(&codec) {
+                self.codecs.push(codec);
+            }
+        }
+        self.layers.extend(other.layers);
+    }
+
+    /// Data/Decoders.lua for the codecs and layers noted
+    pub fn render_lua(&self) -> String {
+        let uses = |codec| self.codecs.contains(&codec);
+        let has = |layer: &str| self.layers.contains(layer);
+        let mut out = String::from("-- Auto-generated tile decoders\n");
+        out += "-- Expanders for the codecs this export's grids use and accessors for their extra layers\n\n";
+        out += "local _, addon = ...\n";
+
+        let mut sections: Vec<&str> = Vec::new();
+        if uses(Codec::Palette) {
+            sections.push(READ_U32_LE);
+        }
+        if uses(Codec::Palette) || uses(Codec::Delta) {
+            sections.push(U32_LE_BYTES);
+        }
+        for (codec, expander) in [(Codec::Palette, EXPAND_PALETTE), (Codec::Rle, EXPAND_RLE), (Codec::Delta, EXPAND_DELTA)] {
+            if uses(codec) {
+                sections.push(expander);
+            }
+        }
+        for (layer, accessor) in [("liquiddepth", GET_LIQUID_DEPTH), ("holes", IS_CHUNK_HOLE), ("liquid", GET_CHUNK_LIQUID)] {
+            if has(layer) {
+                sections.push(accessor);
+            }
+        }
+        for section in sections {
+            out += "\n";
+            out += section;
+        }
+        out
+    }
+}
+
+/// Write Data/Decoders.lua to `out_dir`; the path, when it was written
+pub fn write_decoders(decoders: &AddonDecoders, out_dir: &Path) -> Option<PathBuf> {
+    info!("\nGenerating addon decoders...");
+    let out_path = out_dir.join(DECODERS_FILE);
+    match fs::write(&out_path, decoders.render_lua()) {
+        Ok(()) => {
+            info!("  Wrote: {} ({} codecs, {} extra layers)", out_path.display(), decoders.codecs.len(), decoders.layers.len());
+            Some(out_path)
+        }
+        Err(e) => {
+            error!("Failed to write decoders: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{Compression, LayerCodecs};
+
+    #[test]
+    fn only_what_the_grids_use_is_written() {
+        let mut codecs = LayerCodecs::default();
+        codecs.set("holes", Codec::Rle);
+        let mut grid = Exporter::new("Test", &codecs, Compression::None);
+        grid.add(0, "areaid", &[7; 256]).unwrap();
+        let mut decoders = AddonDecoders::default();
+        decoders.add_grid(&grid);
+        let lua = decoders.render_lua();
+        assert!(!lua.contains("addon.codecs") && !lua.contains("function addon:"));
+
+        // A layer is listed even when it's all zero, and brings its codec
+        grid.add_layer("holes");
+        grid.set_tile_codec(0, Codec::Palette).unwrap();
+        decoders.add_grid(&grid);
+        let lua = decoders.render_lua();
+        assert!(lua.contains("addon.codecs.rle") && lua.contains("addon.codecs.palette"));
+        assert!(!lua.contains("addon.codecs.delta"));
+        assert!(lua.contains("function addon:IsChunkHole"));
+        assert!(!lua.contains("GetChunkLiquid") && !lua.contains("GetLiquidDepth"));
+    }
+}
@@ -0,0 +1,358 @@
+use super::areas::AreaInfo;
+use super::decoders::AddonDecoders;
+use crate::adt::CHUNKS_PER_SIDE;
+use crate::binary::encode_grid;
+use crate::budget::{fit_lua, format_size, Budget};
+use crate::codec::{encode_tile, Codec, Compression};
+use crate::diff::read_tile_grid;
+use crate::export::{Exporter, JsonExporter, OutputFormat};
+use crate::grid::ZoneGrid;
+use crate::heatmap::HeatMap;
+use crate::heights::HeightExport;
+use crate::outline::{self, trace_outlines, OutlineOptions};
+use crate::splat::SUBCELLS_PER_SIDE;
+use crate::{error, info};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// ============================================================================
+// Map files
+// ============================================================================
+//
+// The files written for each map once it is scanned: the grid as Lua, JSON
+// and binary, height maps, outlines and sub-chunks. Each step logs what it
+// wrote and adds the paths to the map's `MapOutputs`, along with any budget
+// or `--verify` failure, so the caller can size the output and fail the run.
+
+/// Mismatched tiles listed per grid before the rest are only counted
+const VERIFY_LISTED: usize = 10;
+
+/// What the steps wrote for one map, and the checks it failed
+#[derive(Debug, Default)]
+pub struct MapOutputs {
+    /// Every file written for the map
+    pub files: Vec<PathBuf>,
+    /// Grids over their `--budget`
+    pub budget_failures: Vec<String>,
+    /// Grids whose tiles don't decode to the area IDs read
+    pub verify_failures: Vec<String>,
+    /// Codecs and layers of the Lua grids written, for Data/Decoders.lua
+    pub decoders: AddonDecoders,
+}
+
+impl MapOutputs {
+    /// Total size of the files written, skipping any that are gone
+    pub fn size(&self) -> u64 {
+        self.files.iter().filter_map(|p| fs::metadata(p).ok()).map(|m| m.len()).sum()
+    }
+}
+
+/// How `write_lua_grid` writes a map's `<Map>_tiles.lua`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LuaGridOptions<'a> {
+    /// Split into shards of this many tiles per side
+    pub shard: Option<u32>,
+    /// Also write `<Map>_tiles.toc` listing the files
+    pub shard_toc: bool,
+    /// Re-encode with other codecs until the grid fits
+    pub budget: Option<&'a Budget>,
+    /// Read the file back and check every tile against `tiles_raw`
+    pub verify: bool,
+}
+
+/// Encode the tiles players visit most, `share` percent of all visits, as
+/// `codec`, and order shards by visits
+pub fn apply_heat(map: &str, grid: &mut Exporter, heat: &HeatMap, share: f64, codec: Codec) {
+    let hot = heat.hot_tiles(map, share);
+    for &key in &hot {
+        if let Err(e) = grid.set_tile_codec(key, codec) {
+            error!("Failed to re-encode {}: {}", map, e);
+        }
+    }
+    grid.set_heat(heat.tile_heats(map));
+    if !hot.is_empty() {
+        info!("  {} hot tiles ({}% of visits) encoded as {}", hot.len(), share, codec);
+    }
+}
+
+/// Write `grid` to `out_path` as Lua, escalating codecs to fit the budget
+pub fn write_lua_grid(map: &str, grid: &Exporter, tiles_raw: &HashMap<u32, Vec<u32>>, out_path: &Path, options: LuaGridOptions, outputs: &mut MapOutputs) {
+    let fit = match options.budget.map(|b| fit_lua(grid, b.bytes)).transpose() {
+        Ok(fit) => fit,
+        Err(e) => {
+            error!("Failed to fit {} in its budget: {}", map, e);
+            None
+        }
+    };
+    if let (Some(fit), Some(budget)) = (&fit, options.budget) {
+        if !fit.fits {
+            let msg = format!("{}: Lua grid doesn't fit {} with any codec ({})", map, budget, fit.describe_attempts());
+            error!("  {}", msg);
+            outputs.budget_failures.push(msg);
+        } else if let Some((codec, compression, len)) = fit.attempts.last().filter(|_| fit.attempts.len() > 1) {
+            info!("  Escalated to {}/{} to fit {} ({})", codec, compression, budget, format_size(*len as u64));
+        }
+    }
+    let grid = fit.as_ref().map_or(grid, |fit| &fit.grid);
+    let written = match options.shard {
+        Some(size) => grid.write_lua_sharded(out_path, size),
+        None => grid.write_lua(out_path).map(|()| vec![out_path.to_path_buf()]),
+    };
+    let files = match written {
+        Ok(files) => files,
+        Err(e) => {
+            error!("Failed to write: {}", e);
+            return;
+        }
+    };
+    match options.shard {
+        Some(size) => info!("  Wrote: {} ({} shards of {}x{} tiles)", out_path.display(), files.len() - 1, size, size),
+        None => info!("  Wrote: {}", out_path.display()),
+    }
+    outputs.files.extend(files.iter().cloned());
+    outputs.decoders.add_grid(grid);
+    if options.shard_toc {
+        let toc_path = out_path.with_extension("toc");
+        let folder = out_path.parent().and_then(Path::file_name).map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let lines: String = files.iter().filter_map(|f| f.file_name()).map(|n| format!("{}/{}\n", folder, n.to_string_lossy())).collect();
+        match fs::write(&toc_path, lines) {
+            Ok(()) => {
+                info!("  Wrote: {}", toc_path.display());
+                outputs.files.push(toc_path);
+            }
+            Err(e) => error!("Failed to write: {}", e),
+        }
+    }
+    if options.verify {
+        match verify_lua_grid(out_path, tiles_raw) {
+            Ok(problems) if problems.is_empty() => info!("  Verified: {} tiles decode to the area IDs read", tiles_raw.len()),
+            Ok(problems) => {
+                error!("  {} tiles don't decode to the area IDs read:", problems.len());
+                for problem in problems.iter().take(VERIFY_LISTED) {
+                    error!("    {}", problem);
+                }
+                if problems.len() > VERIFY_LISTED {
+                    error!("    ... and {} more", problems.len() - VERIFY_LISTED);
+                }
+                outputs.verify_failures.push(format!("{}: {} of {} tiles don't round-trip", map, problems.len(), tiles_raw.len()));
+            }
+            Err(e) => {
+                error!("  Can't read {} back: {}", out_path.display(), e);
+                outputs.verify_failures.push(format!("{}: {}", out_path.display(), e));
+            }
+        }
+    }
+}
+
+/// Tiles of the grid file at `path` that don't decode to the area IDs read
+/// for them, as `x,y: problem`. Decodes with `read_tile_grid`, so it checks
+/// the writer against our reader, not against ZoneMap.lua.
+fn verify_lua_grid(path: &Path, expected: &HashMap<u32, Vec<u32>>) -> Result<Vec<String>, String> {
+    let written = read_tile_grid(path)?;
+    let mut problems = Vec::new();
+    let mut keys: Vec<u32> = expected.keys().chain(written.keys()).copied().collect();
+    keys.sort_unstable();
+    keys.dedup();
+    for key in keys {
+        let (x, y) = (key % 64, key / 64);
+        let problem = match (expected.get(&key), written.get(&key)) {
+            (Some(_), None) => "missing from the file".to_string(),
+            (None, Some(_)) => "in the file but never read".to_string(),
+            (Some(read), Some(decoded)) if read.len() != decoded.len() => format!("decodes to {} area IDs, not {}", decoded.len(), read.len()),
+            (Some(read), Some(decoded)) => {
+                let mut differ = (0..read.len()).filter(|&i| read[i] != decoded[i]);
+                let Some(first) = differ.next() else {
+                    continue;
+                };
+                format!(
+                    "{} of {} chunks differ, first {},{}: read {}, decodes to {}",
+                    differ.count() + 1,
+                    read.len(),
+                    first % CHUNKS_PER_SIDE,
+                    first / CHUNKS_PER_SIDE,
+                    read[first],
+                    decoded[first]
+                )
+            }
+            (None, None) => continue,
+        };
+        problems.push(format!("{},{}: {}", x, y, problem));
+    }
+    Ok(problems)
+}
+
+/// Write `json` to `out_path`, failing the budget when it's over
+pub fn write_json_grid(map: &str, json: &JsonExporter, out_path: &Path, budget: Option<&Budget>, outputs: &mut MapOutputs) {
+    if let Err(e) = json.write_json(out_path) {
+        error!("Failed to write: {}", e);
+        return;
+    }
+    info!("  Wrote: {}", out_path.display());
+    let size = fs::metadata(out_path).map(|m| m.len()).unwrap_or(0);
+    outputs.files.push(out_path.to_path_buf());
+    if let Some(budget) = budget.filter(|b| size > b.bytes) {
+        let msg = format!("{}: JSON grid is {}, over {}", map, format_size(size), budget);
+        error!("  {}", msg);
+        outputs.budget_failures.push(msg);
+    }
+}
+
+/// Write the area IDs read as a binary container at `out_path`
+pub fn write_binary_grid(map_id: u32, tiles_raw: &HashMap<u32, Vec<u32>>, out_path: &Path, outputs: &mut MapOutputs) {
+    let written = encode_grid(map_id, tiles_raw).and_then(|data| fs::write(out_path, &data).map(|()| data.len()).map_err(|e| e.to_string()));
+    match written {
+        Ok(len) => {
+            info!("  Wrote: {} ({})", out_path.display(), format_size(len as u64));
+            outputs.files.push(out_path.to_path_buf());
+        }
+        Err(e) => error!("Failed to write: {}", e),
+    }
+}
+
+/// Write `<Map>_heights.lua` and/or `.json` to `out_dir`
+pub fn write_heights(map: &str, heights: &HeightExport, format: OutputFormat, out_dir: &Path, outputs: &mut MapOutputs) {
+    if format.lua() {
+        let out_path = out_dir.join(format!("{}_heights.lua", map));
+        if let Err(e) = heights.write_lua(&out_path) {
+            error!("Failed to write: {}", e);
+        } else {
+            info!("  Wrote: {} ({} tiles)", out_path.display(), heights.len());
+            outputs.files.push(out_path);
+        }
+    }
+    if format.json() {
+        let out_path = out_dir.join(format!("{}_heights.json", map));
+        if let Err(e) = heights.write_json(&out_path) {
+            error!("Failed to write: {}", e);
+        } else {
+            info!("  Wrote: {} ({} tiles)", out_path.display(), heights.len());
+            outputs.files.push(out_path);
+        }
+    }
+}
+
+/// Trace the areas in `tiles_raw` and write `<Map>_outlines.lua` and/or
+/// `.geojson` to `out_dir`
+pub fn write_outlines(map: &str, tiles_raw: &HashMap<u32, Vec<u32>>, options: &OutlineOptions, areas: &HashMap<u32, AreaInfo>, format: OutputFormat, out_dir: &Path, outputs: &mut MapOutputs) {
+    let outlines = trace_outlines(tiles_raw, options);
+    let name = |id: u32| areas.get(&id).map(|a| a.name.clone());
+    let rings: usize = outlines.values().flatten().map(|p| 1 + p.holes.len()).sum();
+    if format.lua() {
+        let out_path = out_dir.join(format!("{}_outlines.lua", map));
+        if let Err(e) = outline::write_lua(map, &outlines, name, &out_path) {
+            error!("Failed to write: {}", e);
+        } else {
+            info!("  Wrote: {} ({} areas, {} rings)", out_path.display(), outlines.len(), rings);
+            outputs.files.push(out_path);
+        }
+    }
+    if format.json() {
+        let out_path = out_dir.join(format!("{}_outlines.geojson", map));
+        if let Err(e) = outline::write_geojson(map, &outlines, name, &out_path) {
+            error!("Failed to write: {}", e);
+        } else {
+            info!("  Wrote: {} ({} areas, {} rings)", out_path.display(), outlines.len(), rings);
+            outputs.files.push(out_path);
+        }
+    }
+}
+
+/// Write the experimental sub-chunk refinement to `<Map>_subchunks.lua` in `out_dir`
+pub fn write_subchunks(map: &str, subchunks: &BTreeMap<u32, BTreeMap<usize, Vec<u32>>>, out_dir: &Path, outputs: &mut MapOutputs) {
+    let out_path = out_dir.join(format!("{}_subchunks.lua", map));
+    if let Err(e) = export_subchunks_lua(map, subchunks, &out_path) {
+        error!("Failed to write: {}", e);
+    } else {
+        info!("  Wrote: {} ({} tiles refined)", out_path.display(), subchunks.len());
+        outputs.files.push(out_path);
+    }
+}
+
+/// Export the sub-chunk refinement as a separate, clearly flagged file
+fn export_subchunks_lua(map: &str, subchunks: &BTreeMap<u32, BTreeMap<usize, Vec<u32>>>, out_path: &Path) -> std::io::Result<()> {
+    let mut f = File::create(out_path)?;
+
+    writeln!(f, "-- Auto-generated sub-chunk area refinement for {}", map)?;
+    writeln!(f, "-- HEURISTIC: estimated from texture splats, not read from game data.")?;
+    writeln!(f, "-- Border chunks only: [tileKey] = {{ [chunkIndex] = 8x8 u32 AreaIDs, base64 encoded }}")?;
+    writeln!(f)?;
+    writeln!(f, "local _, addon = ...")?;
+    writeln!(f)?;
+    writeln!(f, "addon.SubchunkAreas = addon.SubchunkAreas or {{}}")?;
+    writeln!(f, "addon.SubchunkAreas[\"{}\"] = {{", map)?;
+    writeln!(f, "  heuristic = true,")?;
+    writeln!(f, "  cellsPerChunk = {},", SUBCELLS_PER_SIDE)?;
+    writeln!(f, "  tiles = {{")?;
+
+    for (key, chunks) in subchunks {
+        writeln!(f, "    [{}] = {{", key)?;
+        for (idx, cells) in chunks {
+            writeln!(f, "      [{}] = [[{}]],", idx, encode_tile(Codec::Raw, Compression::None, cells))?;
+        }
+        writeln!(f, "    }},")?;
+    }
+
+    writeln!(f, "  }},")?;
+    writeln!(f, "}}")?;
+    Ok(())
+}
+
+// ============================================================================
+// Run summary
+// ============================================================================
+
+/// Per-map line of the batch summary
+#[derive(Debug, Clone)]
+pub struct MapReport {
+    pub tiles: usize,
+    pub areas: usize,
+    pub failures: usize,
+    pub sentinels: usize,
+    /// Bytes of the files written for the map
+    pub output_bytes: u64,
+    pub duration: Duration,
+}
+
+/// One line per map, `None` for maps that weren't loaded, and totals when
+/// there are several. `areas_found` is the number of distinct areas overall.
+pub fn print_map_summary(report: &[(String, Option<MapReport>)], areas_found: usize) {
+    info!("\nMap summary:");
+    info!("  {:<20} {:>6} {:>6} {:>6} {:>9} {:>10} {:>8}", "Map", "Tiles", "Areas", "Failed", "Sentinels", "Output", "Time");
+    for (map, result) in report {
+        match result {
+            Some(r) => {
+                info!("  {:<20} {:>6} {:>6} {:>6} {:>9} {:>10} {:>7.1}s", map, r.tiles, r.areas, r.failures, r.sentinels, format_size(r.output_bytes), r.duration.as_secs_f64())
+            }
+            None => info!("  {:<20} not loaded", map),
+        }
+    }
+    let loaded: Vec<&MapReport> = report.iter().filter_map(|(_, r)| r.as_ref()).collect();
+    if loaded.len() > 1 {
+        info!(
+            "  {:<20} {:>6} {:>6} {:>6} {:>9} {:>10} {:>7.1}s",
+            "Total",
+            loaded.iter().map(|r| r.tiles).sum::<usize>(),
+            areas_found,
+            loaded.iter().map(|r| r.failures).sum::<usize>(),
+            loaded.iter().map(|r| r.sentinels).sum::<usize>(),
+            format_size(loaded.iter().map(|r| r.output_bytes).sum()),
+            loaded.iter().map(|r| r.duration.as_secs_f64()).sum::<f64>()
+        );
+    }
+}
+
+/// Log the areas players visit on each grid, and how many they never do
+pub fn report_coverage(heat: &HeatMap, grids: &[ZoneGrid], areas: &HashMap<u32, AreaInfo>) {
+    info!("\nComputing visited-area coverage...");
+    for grid in grids {
+        let coverage = heat.coverage(grid);
+        info!("  {}: {} areas visited, {} never visited", grid.name, coverage.visits_per_area.len(), coverage.unvisited.len());
+        for (area_id, visits) in coverage.hottest().into_iter().take(10) {
+            let name = areas.get(&area_id).map(|a| a.name.as_str()).unwrap_or("?");
+            info!("    {} ({}): {} visits", area_id, name, visits);
+        }
+    }
+}
@@ -0,0 +1,642 @@
+use super::areas::{find_root_parent, parse_csv_line, AreaInfo};
+use crate::adt::{WmoFile, WmoPlacement};
+use crate::areatrigger::{
+    bin_triggers, parse_area_trigger_csv, parse_area_trigger_dbc, AreaTrigger, TriggerShape,
+};
+use crate::attributes::{fields_to_lua, AttrValue};
+use crate::dbc::Dbc;
+use crate::entrance::{find_entrances, parse_entrances_csv, EntranceRule};
+use crate::graveyard::{
+    graveyards_by_zone, parse_graveyard_links_csv, parse_safe_locs_csv, parse_safe_locs_dbc,
+    GraveyardLink, SafeLoc,
+};
+use crate::grid::{world_to_chunk, ZoneGrid};
+use crate::triggers::{crossing_segments, recommended_hysteresis};
+use crate::uimap::{parse_ui_map_assignment_csv, zone_assignment, UiMapAssignment};
+use crate::weather::WeatherTable;
+use crate::{error, info, warn};
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+// ============================================================================
+// Overlays
+// ============================================================================
+//
+// Files that place game data on the finished grids: zone crossings, area
+// triggers, graveyards, dungeon entrances, weather and UI map lookups. Each
+// `write_*` step reads its inputs from the working directory and skips
+// itself, saying so, when they aren't there.
+
+/// One row of mapIdToArea.csv: a UI map and the area it shows
+#[derive(Debug)]
+struct MapToAreaEntry {
+    zone_name: String,
+    map_id: u32,
+    area_id: u32,
+}
+
+fn parse_map_to_area_csv(csv_path: &Path) -> Result<Vec<MapToAreaEntry>, Box<dyn std::error::Error>> {
+    let file = File::open(csv_path)?;
+    let reader = BufReader::new(file);
+    let mut entries = Vec::new();
+    
+    let mut lines = reader.lines();
+    let header = lines.next().ok_or("Empty CSV")??;
+    
+    // Parse header to find column indices
+    let columns: Vec<&str> = header.split(',').collect();
+    let zone_idx = columns.iter().position(|&c| c.trim() == "Zone").ok_or("No Zone column")?;
+    let map_id_idx = columns.iter().position(|&c| c.trim() == "mapId").ok_or("No mapId column")?;
+    let area_id_idx = columns.iter().position(|&c| c.trim() == "AreaId").ok_or("No AreaId column")?;
+    
+    for line in lines {
+        let line = line?;
+        let fields: Vec<&str> = parse_csv_line(&line);
+        
+        if fields.len() <= zone_idx.max(map_id_idx).max(area_id_idx) {
+            continue;
+        }
+        
+        let zone_name = fields[zone_idx].trim_matches('"').to_string();
+        let map_id: u32 = match fields[map_id_idx].trim().parse() {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        let area_id: u32 = match fields[area_id_idx].trim().parse() {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        
+        entries.push(MapToAreaEntry { zone_name, map_id, area_id });
+    }
+    
+    Ok(entries)
+}
+
+fn export_map_to_area(entries: &[MapToAreaEntry], out_path: &Path) -> std::io::Result<()> {
+    let mut f = File::create(out_path)?;
+    
+    writeln!(f, "-- Auto-generated Map ID to Area ID mapping")?;
+    writeln!(f, "-- Maps WoW UI map IDs to parent area IDs")?;
+    writeln!(f)?;
+    writeln!(f, "local _, addon = ...")?;
+    writeln!(f)?;
+    writeln!(f, "addon.MapToArea = {{")?;
+    
+    for entry in entries {
+        let escaped_name = entry.zone_name.replace("\"", "\\\"");
+        writeln!(f, "  [{}] = {{ areaId = {}, name = \"{}\" }},", 
+            entry.map_id, entry.area_id, escaped_name)?;
+    }
+    
+    writeln!(f, "}}")?;
+    
+    // Also create reverse lookup (areaId -> mapId)
+    writeln!(f)?;
+    writeln!(f, "addon.AreaToMap = {{")?;
+    
+    for entry in entries {
+        writeln!(f, "  [{}] = {},", entry.area_id, entry.map_id)?;
+    }
+    
+    writeln!(f, "}}")?;
+    
+    Ok(())
+}
+
+/// UI maps for the nearest ancestor of an area (itself included) that has any
+fn best_ui_maps(area_id: u32, entries: &[MapToAreaEntry], areas: &HashMap<u32, AreaInfo>) -> Vec<u32> {
+    let mut current = area_id;
+    let mut visited = BTreeSet::new();
+    
+    while visited.insert(current) {
+        let maps: Vec<u32> = entries
+            .iter()
+            .filter(|e| e.area_id == current)
+            .map(|e| e.map_id)
+            .collect();
+        if !maps.is_empty() {
+            return maps;
+        }
+        match areas.get(&current) {
+            Some(area) if area.parent_id != 0 => current = area.parent_id,
+            _ => break,
+        }
+    }
+    Vec::new()
+}
+
+/// Export area ID -> UI map lookup with world bounds and, when UiMapAssignment
+/// data is available, the area's normalized rect on its best UI map
+fn export_area_to_ui_map(
+    grids: &[ZoneGrid],
+    entries: &[MapToAreaEntry],
+    assignments: &[UiMapAssignment],
+    areas: &HashMap<u32, AreaInfo>,
+    out_path: &Path,
+) -> std::io::Result<()> {
+    let mut f = File::create(out_path)?;
+    
+    writeln!(f, "-- Auto-generated Area ID to UI map lookup")?;
+    writeln!(f, "-- world = {{minX, maxX, minY, maxY}}, rect = {{left, top, right, bottom}} on uiMaps[1]")?;
+    writeln!(f, "-- zoneRect = the same box on the owning root zone's map, zoneMap = that map's ID")?;
+    writeln!(f)?;
+    writeln!(f, "local _, addon = ...")?;
+    writeln!(f)?;
+    writeln!(f, "addon.AreaToUiMap = {{")?;
+    
+    let mut written = 0usize;
+    let mut with_rect = 0usize;
+    for grid in grids {
+        for (area_id, bounds) in grid.area_bounds() {
+            let ui_maps = best_ui_maps(area_id, entries, areas);
+            if ui_maps.is_empty() {
+                continue;
+            }
+            
+            let maps_str: Vec<String> = ui_maps.iter().map(|m| m.to_string()).collect();
+            let rect = assignments
+                .iter()
+                .find(|a| a.ui_map_id == ui_maps[0] && a.map_id == grid.map_id)
+                .map(|a| a.normalize(&bounds));
+            let rect_str = match rect {
+                Some([l, t, r, b]) => {
+                    with_rect += 1;
+                    format!(", rect = {{{:.4}, {:.4}, {:.4}, {:.4}}}", l, t, r, b)
+                }
+                None => String::new(),
+            };
+            let zone = find_root_parent(area_id, areas);
+            let zone_rect_str = match zone_assignment(assignments, grid.map_id, zone) {
+                Some(a) => {
+                    let [l, t, r, b] = a.normalize(&bounds);
+                    format!(", zoneMap = {}, zoneRect = {{{:.4}, {:.4}, {:.4}, {:.4}}}", a.ui_map_id, l, t, r, b)
+                }
+                None => String::new(),
+            };
+            let name = areas.get(&area_id).map(|a| a.name.as_str()).unwrap_or("?");
+            
+            writeln!(
+                f,
+                "  [{}] = {{ uiMaps = {{{}}}, world = {{{:.1}, {:.1}, {:.1}, {:.1}}}{}{} }},  -- {}",
+                area_id,
+                maps_str.join(", "),
+                bounds.min_x, bounds.max_x, bounds.min_y, bounds.max_y,
+                rect_str,
+                zone_rect_str,
+                name
+            )?;
+            written += 1;
+        }
+    }
+    
+    writeln!(f, "}}")?;
+    
+    info!("  {} areas mapped, {} with normalized rects", written, with_rect);
+    Ok(())
+}
+
+/// Export weather per area in the grids; subzones get their zone's row
+fn export_area_weather(
+    all_found_areas: &BTreeSet<u32>,
+    weather: &WeatherTable,
+    areas: &HashMap<u32, AreaInfo>,
+    out_path: &Path,
+) -> std::io::Result<usize> {
+    let mut f = File::create(out_path)?;
+    
+    writeln!(f, "-- Auto-generated area weather, joined from weather.csv")?;
+    writeln!(f, "-- zone = the area the row came from (the area itself or a parent)")?;
+    writeln!(f)?;
+    writeln!(f, "local _, addon = ...")?;
+    writeln!(f)?;
+    writeln!(f, "addon.AreaWeather = {{")?;
+    
+    let mut written = 0usize;
+    for &area_id in all_found_areas {
+        let parent_of = |id: u32| areas.get(&id).map(|a| a.parent_id);
+        let Some((zone, fields)) = weather.resolve(area_id, parent_of) else {
+            continue;
+        };
+        let mut fields = fields.clone();
+        fields.insert("zone".to_string(), AttrValue::Number(zone as f64));
+        let name = areas.get(&area_id).map(|a| a.name.as_str()).unwrap_or("?");
+        writeln!(f, "  [{}] = {},  -- {}", area_id, fields_to_lua(&fields), name)?;
+        written += 1;
+    }
+    
+    writeln!(f, "}}")?;
+    Ok(written)
+}
+
+/// Export zone-to-zone crossing segments per continent, for entry/exit triggers
+fn export_zone_triggers(
+    grids: &[ZoneGrid],
+    areas: &HashMap<u32, AreaInfo>,
+    assignments: &[UiMapAssignment],
+    out_path: &Path,
+) -> std::io::Result<()> {
+    let mut f = File::create(out_path)?;
+    
+    writeln!(f, "-- Auto-generated zone crossing segments")?;
+    writeln!(f, "-- Per continent, each entry is a pair of root zones and their shared border")?;
+    writeln!(f, "-- as a flat list of world coordinates: x1, y1, x2, y2, x1, y1, ...")?;
+    writeln!(f, "-- hysteresis is the distance (yards) to move past the border before switching zones")?;
+    writeln!(f, "-- zoneSegments[zone] is the same border normalized (0-1) on that zone's own map")?;
+    writeln!(f)?;
+    writeln!(f, "local _, addon = ...")?;
+    writeln!(f)?;
+    writeln!(f, "addon.ZoneTriggers = {{")?;
+    
+    for grid in grids {
+        let pairs = crossing_segments(grid, |area_id| find_root_parent(area_id, areas));
+        let segment_count: usize = pairs.values().map(|s| s.len()).sum();
+        
+        writeln!(f, "  [\"{}\"] = {{", grid.name)?;
+        for ((a, b), segments) in &pairs {
+            let coords: Vec<String> = segments
+                .iter()
+                .map(|s| format!("{:.1}, {:.1}, {:.1}, {:.1}", s.x1, s.y1, s.x2, s.y2))
+                .collect();
+            let zone_coords: Vec<String> = [a, b]
+                .into_iter()
+                .filter_map(|&zone| {
+                    let assignment = zone_assignment(assignments, grid.map_id, zone)?;
+                    let points: Vec<String> = segments
+                        .iter()
+                        .map(|s| {
+                            let [x1, y1] = assignment.normalize_point(s.x1, s.y1);
+                            let [x2, y2] = assignment.normalize_point(s.x2, s.y2);
+                            format!("{:.4}, {:.4}, {:.4}, {:.4}", x1, y1, x2, y2)
+                        })
+                        .collect();
+                    Some(format!("[{}] = {{ {} }}", zone, points.join(", ")))
+                })
+                .collect();
+            let zone_str = if zone_coords.is_empty() {
+                String::new()
+            } else {
+                format!(", zoneSegments = {{ {} }}", zone_coords.join(", "))
+            };
+            writeln!(
+                f,
+                "    {{ a = {}, b = {}, hysteresis = {:.1}, segments = {{ {} }}{} }},",
+                a,
+                b,
+                recommended_hysteresis(segments),
+                coords.join(", "),
+                zone_str
+            )?;
+        }
+        writeln!(f, "  }},")?;
+        
+        info!("  {}: {} zone pairs, {} segments", grid.name, pairs.len(), segment_count);
+    }
+    
+    writeln!(f, "}}")?;
+    Ok(())
+}
+
+/// Export the area triggers on each grid's map and the chunks each one touches
+fn export_area_triggers(grids: &[ZoneGrid], triggers: &[AreaTrigger], out_path: &Path) -> std::io::Result<()> {
+    let mut f = File::create(out_path)?;
+    
+    writeln!(f, "-- Auto-generated area triggers (AreaTrigger.dbc), per continent")?;
+    writeln!(f, "-- triggers[id] is a sphere (radius) or a box (length, width, height, yaw in radians)")?;
+    writeln!(f, "-- chunks[tileKey][chunkY * 16 + chunkX] lists the triggers whose footprint touches that chunk")?;
+    writeln!(f)?;
+    writeln!(f, "local _, addon = ...")?;
+    writeln!(f)?;
+    writeln!(f, "addon.AreaTriggers = {{")?;
+    
+    for grid in grids {
+        let on_map: Vec<&AreaTrigger> = triggers.iter().filter(|t| t.map_id == grid.map_id).collect();
+        let bins = bin_triggers(on_map.iter().copied());
+        
+        writeln!(f, "  [\"{}\"] = {{", grid.name)?;
+        writeln!(f, "    triggers = {{")?;
+        for t in &on_map {
+            let [x, y, z] = t.pos;
+            let shape = match t.shape {
+                TriggerShape::Sphere { radius } => format!("radius = {:.1}", radius),
+                TriggerShape::Box { length, width, height, yaw } => {
+                    format!("length = {:.1}, width = {:.1}, height = {:.1}, yaw = {:.4}", length, width, height, yaw)
+                }
+            };
+            writeln!(f, "      [{}] = {{ id = {}, x = {:.1}, y = {:.1}, z = {:.1}, {} }},", t.id, t.id, x, y, z, shape)?;
+        }
+        writeln!(f, "    }},")?;
+        writeln!(f, "    chunks = {{")?;
+        for (tile_key, chunks) in &bins {
+            let entries: Vec<String> = chunks
+                .iter()
+                .map(|(chunk, ids)| {
+                    let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+                    format!("[{}] = {{ {} }}", chunk, ids.join(", "))
+                })
+                .collect();
+            writeln!(f, "      [{}] = {{ {} }},", tile_key, entries.join(", "))?;
+        }
+        writeln!(f, "    }},")?;
+        writeln!(f, "  }},")?;
+        
+        let binned = bins.values().map(|c| c.len()).sum::<usize>();
+        info!("  {}: {} triggers over {} chunks", grid.name, on_map.len(), binned);
+    }
+    
+    writeln!(f, "}}")?;
+    Ok(())
+}
+
+/// Export graveyards per zone with the grid cell and area each stands in
+fn export_graveyards(
+    grids: &[ZoneGrid],
+    locs: &[SafeLoc],
+    links: &[GraveyardLink],
+    areas: &HashMap<u32, AreaInfo>,
+    out_path: &Path,
+) -> std::io::Result<usize> {
+    let grid_for = |map_id: u32| grids.iter().find(|g| g.map_id == map_id);
+    let zone_at = |map_id: u32, x: f32, y: f32| {
+        let area = grid_for(map_id)?.area_at(x, y).filter(|&id| id != 0)?;
+        Some(find_root_parent(area, areas))
+    };
+    let zones = graveyards_by_zone(locs, links, zone_at);
+    let mut zone_ids: Vec<u32> = zones.keys().copied().collect();
+    zone_ids.sort_unstable();
+    
+    let mut f = File::create(out_path)?;
+    
+    writeln!(f, "-- Auto-generated graveyards per zone (WorldSafeLocs.dbc)")?;
+    writeln!(f, "-- Each graveyard has its world position and, on a continent in the grids, its tile key")?;
+    writeln!(f, "-- (tileY * 64 + tileX), chunk index (chunkY * 16 + chunkX) and the area it stands in")?;
+    writeln!(f, "-- faction is \"alliance\" or \"horde\" when only one may use it")?;
+    writeln!(f)?;
+    writeln!(f, "local _, addon = ...")?;
+    writeln!(f)?;
+    writeln!(f, "addon.Graveyards = {{")?;
+    
+    let mut written = 0usize;
+    for zone in zone_ids {
+        let name = areas.get(&zone).map(|a| a.name.as_str()).unwrap_or("?");
+        writeln!(f, "  [{}] = {{  -- {}", zone, name)?;
+        for (loc, faction) in &zones[&zone] {
+            let [x, y, z] = loc.pos;
+            let mut fields = vec![
+                format!("id = {}", loc.id),
+                format!("name = \"{}\"", loc.name.replace('\\', "\\\\").replace('"', "\\\"")),
+                format!("map = {}", loc.map_id),
+                format!("x = {:.1}, y = {:.1}, z = {:.1}", x, y, z),
+            ];
+            if let Some(grid) = grid_for(loc.map_id) {
+                fields.push(format!("grid = \"{}\"", grid.name));
+                if let Some(pos) = world_to_chunk(x, y) {
+                    fields.push(format!("tile = {}, chunk = {}", pos.tile_key(), pos.chunk_index()));
+                }
+                if let Some(area) = grid.area_at(x, y).filter(|&id| id != 0) {
+                    fields.push(format!("area = {}", area));
+                }
+            }
+            if let Some(faction) = faction {
+                fields.push(format!("faction = \"{}\"", faction.label()));
+            }
+            writeln!(f, "    {{ {} }},", fields.join(", "))?;
+            written += 1;
+        }
+        writeln!(f, "  }},")?;
+    }
+    
+    writeln!(f, "}}")?;
+    Ok(written)
+}
+
+/// Export the entrance WMOs placed on each map, with the instance each leads to
+fn export_dungeon_entrances(
+    grids: &[ZoneGrid],
+    wmos: &HashMap<u32, Vec<WmoPlacement>>,
+    rules: &[EntranceRule],
+    out_path: &Path,
+) -> std::io::Result<usize> {
+    let mut f = File::create(out_path)?;
+    
+    writeln!(f, "-- Auto-generated dungeon entrances (entrance WMOs placed in the ADTs)")?;
+    writeln!(f, "-- Per grid, each entrance's destination map, world position, tile key")?;
+    writeln!(f, "-- (tileY * 64 + tileX), chunk index (chunkY * 16 + chunkX) and the area it stands in")?;
+    writeln!(f)?;
+    writeln!(f, "local _, addon = ...")?;
+    writeln!(f)?;
+    writeln!(f, "addon.DungeonEntrances = {{")?;
+    
+    let mut written = 0usize;
+    for grid in grids {
+        let placements = wmos.get(&grid.map_id).map(Vec::as_slice).unwrap_or_default();
+        let entrances = find_entrances(rules, placements);
+        if entrances.is_empty() {
+            continue;
+        }
+        writeln!(f, "  [\"{}\"] = {{", grid.name)?;
+        for entrance in entrances {
+            let [x, y, z] = entrance.placement.position;
+            let wmo = match &entrance.placement.file {
+                WmoFile::Name(name) => format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\"")),
+                WmoFile::FileDataId(id) => id.to_string(),
+            };
+            let mut fields = vec![
+                format!("map = {}", entrance.rule.map_id),
+                format!("name = \"{}\"", entrance.rule.name.replace('\\', "\\\\").replace('"', "\\\"")),
+                format!("x = {:.1}, y = {:.1}, z = {:.1}", x, y, z),
+            ];
+            if let Some(pos) = world_to_chunk(x, y) {
+                fields.push(format!("tile = {}, chunk = {}", pos.tile_key(), pos.chunk_index()));
+            }
+            if let Some(area) = grid.area_at(x, y).filter(|&id| id != 0) {
+                fields.push(format!("area = {}", area));
+            }
+            fields.push(format!("wmo = {}", wmo));
+            writeln!(f, "    {{ {} }},", fields.join(", "))?;
+            written += 1;
+        }
+        writeln!(f, "  }},")?;
+    }
+    
+    writeln!(f, "}}")?;
+    Ok(written)
+}
+
+// ============================================================================
+// Steps
+// ============================================================================
+
+/// UiMapAssignment rows for `client_build`, none when the CSV isn't there
+pub fn load_ui_map_assignments(client_build: &str) -> Vec<UiMapAssignment> {
+    let assignment_name = format!("UiMapAssignment.{}.csv", client_build);
+    let assignment_path = Path::new(&assignment_name);
+    if assignment_path.exists() {
+        parse_ui_map_assignment_csv(assignment_path).unwrap_or_else(|e| {
+            warn!("Warning: Failed to parse UiMapAssignment: {}", e);
+            Vec::new()
+        })
+    } else {
+        info!("\nUiMapAssignment CSV not found, writing world coordinates only");
+        Vec::new()
+    }
+}
+
+/// Write ZoneTriggers.lua to `out_dir`
+pub fn write_zone_triggers(grids: &[ZoneGrid], areas: &HashMap<u32, AreaInfo>, assignments: &[UiMapAssignment], out_dir: &Path) {
+    info!("\nGenerating zone triggers...");
+    let triggers_path = out_dir.join("ZoneTriggers.lua");
+    if let Err(e) = export_zone_triggers(grids, areas, assignments, &triggers_path) {
+        error!("Failed to write zone triggers: {}", e);
+    } else {
+        info!("  Wrote: {}", triggers_path.display());
+    }
+}
+
+/// Write AreaTriggers.lua to `out_dir` from AreaTrigger.dbc, or else the
+/// AreaTrigger CSV for `client_build`
+pub fn write_area_triggers(grids: &[ZoneGrid], client_build: &str, out_dir: &Path) {
+    let trigger_dbc = Path::new("AreaTrigger.dbc");
+    let trigger_csv_name = format!("AreaTrigger.{}.csv", client_build);
+    let trigger_csv = Path::new(&trigger_csv_name);
+    let triggers = if trigger_dbc.exists() {
+        Some(Dbc::from_file(trigger_dbc).and_then(|dbc| Ok(parse_area_trigger_dbc(&dbc)?)))
+    } else if trigger_csv.exists() {
+        Some(parse_area_trigger_csv(trigger_csv))
+    } else {
+        None
+    };
+    match triggers {
+        Some(Ok(triggers)) => {
+            info!("\nGenerating area triggers...");
+            info!("  Loaded {} area triggers", triggers.len());
+            let out_path = out_dir.join("AreaTriggers.lua");
+            if let Err(e) = export_area_triggers(grids, &triggers, &out_path) {
+                error!("Failed to write area triggers: {}", e);
+            } else {
+                info!("  Wrote: {}", out_path.display());
+            }
+        }
+        Some(Err(e)) => error!("Failed to parse area triggers: {}", e),
+        None => info!("\nSkipping area triggers (AreaTrigger.dbc not found)"),
+    }
+}
+
+/// Write Graveyards.lua to `out_dir` from WorldSafeLocs (.dbc, or else the
+/// CSV for `client_build`) and the server's graveyard_zone.csv links
+pub fn write_graveyards(grids: &[ZoneGrid], areas: &HashMap<u32, AreaInfo>, client_build: &str, out_dir: &Path) {
+    let safe_locs_dbc = Path::new("WorldSafeLocs.dbc");
+    let safe_locs_csv_name = format!("WorldSafeLocs.{}.csv", client_build);
+    let safe_locs_csv = Path::new(&safe_locs_csv_name);
+    let safe_locs = if safe_locs_dbc.exists() {
+        Some(Dbc::from_file(safe_locs_dbc).and_then(|dbc| Ok(parse_safe_locs_dbc(&dbc)?)))
+    } else if safe_locs_csv.exists() {
+        Some(parse_safe_locs_csv(safe_locs_csv))
+    } else {
+        None
+    };
+    match safe_locs {
+        Some(Ok(locs)) => {
+            info!("\nGenerating graveyards...");
+            info!("  Loaded {} safe locations", locs.len());
+            let links_path = Path::new("graveyard_zone.csv");
+            let links = if links_path.exists() {
+                parse_graveyard_links_csv(links_path).unwrap_or_else(|e| {
+                    warn!("Warning: Failed to parse graveyard_zone.csv: {}", e);
+                    Vec::new()
+                })
+            } else {
+                Vec::new()
+            };
+            if links.is_empty() {
+                info!("  No graveyard_zone.csv links; grouping graveyards by the zone they stand in");
+            } else {
+                info!("  Loaded {} graveyard links", links.len());
+            }
+            let out_path = out_dir.join("Graveyards.lua");
+            match export_graveyards(grids, &locs, &links, areas, &out_path) {
+                Ok(written) => info!("  Wrote: {} ({} graveyards)", out_path.display(), written),
+                Err(e) => error!("Failed to write graveyards: {}", e),
+            }
+        }
+        Some(Err(e)) => error!("Failed to parse safe locations: {}", e),
+        None => info!("\nSkipping graveyards (WorldSafeLocs.dbc not found)"),
+    }
+}
+
+/// Write DungeonEntrances.lua to `out_dir`: the WMOs placed on the maps that
+/// `entrances_path` lists as instance entrances. `fast` runs read no WMOs.
+pub fn write_dungeon_entrances(grids: &[ZoneGrid], wmos: &HashMap<u32, Vec<WmoPlacement>>, entrances_path: &Path, fast: bool, out_dir: &Path) {
+    if !entrances_path.exists() {
+        info!("\nSkipping dungeon entrances ({} not found)", entrances_path.display());
+        return;
+    }
+    info!("\nGenerating dungeon entrances...");
+    match parse_entrances_csv(entrances_path) {
+        Ok(rules) => {
+            info!("  Loaded {} entrance WMOs", rules.len());
+            if fast {
+                warn!("  Warning: --fast skips WMO placements; no entrances will be found");
+            }
+            let out_path = out_dir.join("DungeonEntrances.lua");
+            match export_dungeon_entrances(grids, wmos, &rules, &out_path) {
+                Ok(written) => info!("  Wrote: {} ({} entrances)", out_path.display(), written),
+                Err(e) => error!("Failed to write dungeon entrances: {}", e),
+            }
+        }
+        Err(e) => error!("Failed to parse {}: {}", entrances_path.display(), e),
+    }
+}
+
+/// Write AreaWeather.lua to `out_dir`, joining `weather_path` onto the areas found
+pub fn write_area_weather(found_areas: &BTreeSet<u32>, areas: &HashMap<u32, AreaInfo>, weather_path: &Path, out_dir: &Path) {
+    if !weather_path.exists() {
+        info!("\nSkipping weather ({} not found)", weather_path.display());
+        return;
+    }
+    info!("\nGenerating area weather...");
+    match WeatherTable::from_csv(weather_path) {
+        Ok(weather) => {
+            info!("  Loaded weather for {} zones", weather.len());
+            let out_path = out_dir.join("AreaWeather.lua");
+            match export_area_weather(found_areas, &weather, areas, &out_path) {
+                Ok(written) => info!("  Wrote: {} ({} areas)", out_path.display(), written),
+                Err(e) => error!("Failed to write area weather: {}", e),
+            }
+        }
+        Err(e) => error!("Failed to parse {}: {}", weather_path.display(), e),
+    }
+}
+
+/// Write MapToArea.lua and AreaToUiMap.lua to `out_dir` from mapIdToArea.csv
+pub fn write_map_to_area(grids: &[ZoneGrid], assignments: &[UiMapAssignment], areas: &HashMap<u32, AreaInfo>, out_dir: &Path) {
+    let map_csv_path = Path::new("mapIdToArea.csv");
+    if !map_csv_path.exists() {
+        info!("\nSkipping map-to-area (mapIdToArea.csv not found)");
+        return;
+    }
+    info!("\nGenerating map to area mapping...");
+    match parse_map_to_area_csv(map_csv_path) {
+        Ok(entries) => {
+            info!("  Loaded {} map-to-area entries", entries.len());
+            let map_path = out_dir.join("MapToArea.lua");
+            if let Err(e) = export_map_to_area(&entries, &map_path) {
+                error!("Failed to write map to area: {}", e);
+            } else {
+                info!("  Wrote: {}", map_path.display());
+            }
+
+            let reverse_path = out_dir.join("AreaToUiMap.lua");
+            if let Err(e) = export_area_to_ui_map(grids, &entries, assignments, areas, &reverse_path) {
+                error!("Failed to write area to UI map: {}", e);
+            } else {
+                info!("  Wrote: {}", reverse_path.display());
+            }
+        }
+        Err(e) => {
+            error!("Failed to parse mapIdToArea.csv: {}", e);
+        }
+    }
+}
@@ -0,0 +1,193 @@
+#[cfg(feature = "sqlite")]
+use super::areas::AreaInfo;
+use crate::changelog::render_changelog;
+use crate::diff::{diff_exports, ExportSnapshot};
+#[cfg(feature = "trends")]
+use crate::gate::RunMetrics;
+#[cfg(feature = "sqlite")]
+use crate::grid::ZoneGrid;
+#[cfg(feature = "store")]
+use crate::hash::HashAlgorithm;
+use crate::import::read_chunk_rows;
+use crate::manifest::ManifestEntry;
+use crate::patch::{patch_area_ids, ChunkEdits};
+#[cfg(feature = "signing")]
+use crate::signing::{load_signing_key, sign_dir, SIGNATURE_FILE};
+#[cfg(feature = "sqlite")]
+use crate::sqlite::{AreaRow, ZoneDb};
+#[cfg(feature = "store")]
+use crate::store::Store;
+#[cfg(feature = "trends")]
+use crate::trends::{RunRow, RunsDb};
+use crate::version::{
+    files_changed, read_version, required_bump, stamp_export, DataVersion, DATA_SCHEMA,
+};
+use crate::{error, info, warn};
+use std::collections::BTreeMap;
+#[cfg(feature = "sqlite")]
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+#[cfg(feature = "trends")]
+use std::time::Instant;
+
+// ============================================================================
+// Publishing
+// ============================================================================
+//
+// The steps after every file is written: patched ADTs for hand edits, the
+// data version and changelog, the signature, the stored snapshot and the
+// SQLite databases.
+
+/// Where `patch_edited_adts` writes patched ADTs
+const PATCHED_ADTS_DIR: &str = "patched_adts";
+
+/// For every map with a `<Map>_edits.csv` in the working directory, write
+/// copies of its source ADTs with the edited area IDs
+pub fn patch_edited_adts(entries: &[ManifestEntry]) {
+    for entry in entries {
+        let edits_name = format!("{}_edits.csv", entry.map);
+        let edits_path = Path::new(&edits_name);
+        if !edits_path.exists() || !entry.source.is_dir() {
+            continue;
+        }
+        info!("\nPatching {} ADTs from {}...", entry.map, edits_name);
+        if let Err(e) = patch_continent(&entry.source, &entry.map, edits_path, Path::new(PATCHED_ADTS_DIR)) {
+            error!("Failed to patch {} ADTs: {}", entry.map, e);
+        }
+    }
+}
+
+/// Write copies of the ADTs in `adt_dir` with the area IDs from `edits_csv`
+fn patch_continent(
+    adt_dir: &Path,
+    continent_name: &str,
+    edits_csv: &Path,
+    out_dir: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut edits: BTreeMap<(u32, u32), ChunkEdits> = BTreeMap::new();
+    for (tile_x, tile_y, chunk_x, chunk_y, area_id) in read_chunk_rows(edits_csv)? {
+        edits.entry((tile_x, tile_y)).or_default().insert((chunk_y * 16 + chunk_x) as usize, area_id);
+    }
+
+    for ((tile_x, tile_y), chunk_edits) in &edits {
+        let file_name = format!("{}_{}_{}.adt", continent_name, tile_x, tile_y);
+        let src = adt_dir.join(&file_name);
+        let dest = out_dir.join(&file_name);
+        let changed = patch_area_ids(&src, &dest, chunk_edits)?;
+        info!("  Wrote: {} ({} chunks changed)", dest.display(), changed);
+    }
+    Ok(())
+}
+
+/// Data version of the export in `out_dir` and the release notes since
+/// `previous`, which are also written to DATA_CHANGES.md. Without a previous
+/// export the version is the initial one. Either way every file is stamped;
+/// failing to stamp them is the only error.
+pub fn version_export(out_dir: &Path, previous: Option<&Path>, client_build: &str) -> Result<(DataVersion, Option<String>), String> {
+    let mut data_version = DataVersion::INITIAL;
+    let mut changelog = None;
+    if let Some(previous) = previous {
+        info!("\nComparing with {}...", previous.display());
+        let loaded = ExportSnapshot::load(previous).and_then(|old| Ok((old, ExportSnapshot::load(out_dir)?)));
+        match loaded {
+            Ok((old, new)) => {
+                let diff = diff_exports(&old, &new);
+                let (old_version, old_schema) = match read_version(previous) {
+                    Ok(found) => found.unwrap_or((DataVersion::INITIAL, DATA_SCHEMA)),
+                    Err(e) => {
+                        warn!("Warning: {}; treating previous export as {}", e, DataVersion::INITIAL);
+                        (DataVersion::INITIAL, DATA_SCHEMA)
+                    }
+                };
+                let changed = files_changed(previous, out_dir).unwrap_or(true);
+                let bump = required_bump(old_schema, &diff, changed);
+                data_version = old_version.bump(bump);
+                info!("  Data version {} -> {} ({} bump)", old_version, data_version, bump);
+
+                let title = format!("Zone data {} ({})", data_version, client_build);
+                let notes = render_changelog(&title, &diff, &old, &new);
+                let changelog_path = Path::new("DATA_CHANGES.md");
+                if let Err(e) = fs::write(changelog_path, &notes) {
+                    error!("Failed to write changelog: {}", e);
+                } else {
+                    info!("  Wrote: {}", changelog_path.display());
+                }
+                changelog = Some(notes);
+            }
+            Err(e) => error!("Failed to load exports for changelog: {}", e),
+        }
+    }
+    let files = stamp_export(out_dir, data_version, client_build).map_err(|e| format!("Failed to write data version: {}", e))?;
+    info!("  Stamped {} files with data version {}", files, data_version);
+    Ok((data_version, changelog))
+}
+
+/// Sign every file in `out_dir` with the key at `key_path`, when there is one
+#[cfg(feature = "signing")]
+pub fn sign_export(out_dir: &Path, key_path: &Path) -> Result<(), String> {
+    if !key_path.exists() {
+        return Ok(());
+    }
+    info!("\nSigning export...");
+    let files = load_signing_key(key_path).and_then(|key| sign_dir(out_dir, &key)).map_err(|e| format!("Failed to sign export: {}", e))?;
+    info!("  Wrote: {} ({} files)", out_dir.join(SIGNATURE_FILE).display(), files);
+    Ok(())
+}
+
+/// Keep a snapshot of `out_dir` for `client_build` when `store_dir` exists
+#[cfg(feature = "store")]
+pub fn store_export(store_dir: &Path, algorithm: HashAlgorithm, client_build: &str, out_dir: &Path) {
+    if !store_dir.is_dir() {
+        info!("\nSkipping store ({} not found)", store_dir.display());
+        return;
+    }
+    info!("\nStoring export...");
+    match Store::new(store_dir).with_algorithm(algorithm).put(client_build, out_dir) {
+        Ok(snapshot) => info!("  Stored: {}", snapshot.display()),
+        Err(e) => error!("Failed to store export: {}", e),
+    }
+}
+
+/// Write the grids and the AreaTable to the SQLite database at `db_path`
+#[cfg(feature = "sqlite")]
+pub fn write_sqlite(db_path: &Path, grids: &[ZoneGrid], areas: &HashMap<u32, AreaInfo>, data_version: DataVersion, client_build: &str) {
+    let mut rows: Vec<AreaRow> = areas.iter().map(|(&area_id, a)| AreaRow { area_id, name: a.name.clone(), parent_id: a.parent_id }).collect();
+    rows.sort_by_key(|a| a.area_id);
+    let written = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let meta = [("data_version", data_version.to_string()), ("build", client_build.to_string()), ("written", written.to_string())];
+    match ZoneDb::open(db_path).and_then(|mut db| db.write(grids, &rows, &meta)) {
+        Ok(chunks) => info!("\nWrote {} chunks of {} continents to {}", chunks, grids.len(), db_path.display()),
+        Err(e) => error!("Failed to write {}: {}", db_path.display(), e),
+    }
+}
+
+/// Add the run that began at `started` to the run history at `db_path`
+#[cfg(feature = "trends")]
+pub fn record_run(db_path: &Path, started: Instant, metrics: &RunMetrics, data_version: DataVersion, client_build: &str, out_dir: &Path) {
+    let run = RunRow {
+        started: (std::time::SystemTime::now() - started.elapsed()).duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        build: client_build.to_string(),
+        data_version: data_version.to_string(),
+        tiles_parsed: metrics.tiles_parsed as u64,
+        parse_failures: metrics.parse_failures as u64,
+        areas_found: metrics.areas_found as u64,
+        unknown_areas: metrics.unknown_areas as u64,
+        chunks_scanned: metrics.chunks_scanned as u64,
+        sentinel_chunks: metrics.sentinel_chunks as u64,
+        output_bytes: dir_size(out_dir),
+        duration_ms: started.elapsed().as_millis() as u64,
+    };
+    match RunsDb::open(db_path).and_then(|db| db.record(&run)) {
+        Ok(()) => info!("\nRecorded run in {}", db_path.display()),
+        Err(e) => error!("Failed to record run: {}", e),
+    }
+}
+
+/// Total size of the files directly in `dir`
+#[cfg(feature = "trends")]
+fn dir_size(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .map(|entries| entries.flatten().filter_map(|e| e.metadata().ok()).filter(|m| m.is_file()).map(|m| m.len()).sum())
+        .unwrap_or(0)
+}
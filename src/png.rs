@@ -0,0 +1,56 @@
+// ============================================================================
+// PNG encoding
+// ============================================================================
+//
+// Just enough PNG to write 8-bit RGB images (map thumbnails): a signature,
+// IHDR, one zlib-compressed IDAT with filter byte 0 on every row, and IEND.
+// Each chunk is length, type, data, then a CRC-32 over type and data.
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+/// CRC-32 (ISO-HDLC, as PNG and zip use it)
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Encode `width` x `height` RGB pixels, 3 bytes each, row-major from the top
+pub fn encode_rgb(width: u32, height: u32, pixels: &[u8]) -> Result<Vec<u8>, String> {
+    let row = width as usize * 3;
+    if pixels.len() != row * height as usize {
+        return Err(format!("{}x{} image needs {} bytes, got {}", width, height, row * height as usize, pixels.len()));
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8-bit depth, colour type 2 (RGB), deflate, filter method 0, no interlace
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut raw = Vec::with_capacity((row + 1) * height as usize);
+    for line in pixels.chunks(row.max(1)) {
+        raw.push(0);
+        raw.extend_from_slice(line);
+    }
+
+    let mut out = SIGNATURE.to_vec();
+    write_chunk(&mut out, b"IHDR", &header);
+    write_chunk(&mut out, b"IDAT", &miniz_oxide::deflate::compress_to_vec_zlib(&raw, 6));
+    write_chunk(&mut out, b"IEND", &[]);
+    Ok(out)
+}
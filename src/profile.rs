@@ -0,0 +1,100 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// ============================================================================
+// Per-tile profiling
+// ============================================================================
+//
+// Every ADT read during a run records how long it took to parse (decoding
+// the root, and its _obj0 on split tiles, into area IDs and layers) and to
+// encode (adding it to the grid exporters, where codecs and compression
+// run), along with the bytes read. `--profile-slowest N` prints the N files
+// that took longest and their share of all tile time, which is usually a
+// handful of huge, object-heavy tiles, plus per-map totals and percentiles.
+// Files replayed from a checkpoint aren't timed.
+
+/// Time spent on one ADT
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileTiming {
+    pub map: String,
+    pub path: PathBuf,
+    /// Root ADT plus `_obj0`, if one was read
+    pub bytes: usize,
+    pub parse: Duration,
+    pub encode: Duration,
+}
+
+impl TileTiming {
+    pub fn total(&self) -> Duration {
+        self.parse + self.encode
+    }
+}
+
+fn ms(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+/// The `p`th percentile (0-100) of sorted `values`
+fn percentile(sorted: &[Duration], p: usize) -> Duration {
+    sorted.get((sorted.len() * p / 100).min(sorted.len().saturating_sub(1))).copied().unwrap_or_default()
+}
+
+/// The `limit` slowest files, then each map's totals
+pub fn render_slowest(timings: &[TileTiming], limit: usize) -> String {
+    if timings.is_empty() {
+        return String::from("No tiles were timed\n");
+    }
+    let total: Duration = timings.iter().map(TileTiming::total).sum();
+    let parse: Duration = timings.iter().map(|t| t.parse).sum();
+    let mut slowest: Vec<&TileTiming> = timings.iter().collect();
+    slowest.sort_by(|a, b| b.total().cmp(&a.total()).then_with(|| a.path.cmp(&b.path)));
+    slowest.truncate(limit);
+
+    let mut out = format!(
+        "Slowest {} of {} tiles ({:.2}s parsing, {:.2}s encoding in all):\n",
+        slowest.len(),
+        timings.len(),
+        parse.as_secs_f64(),
+        (total - parse).as_secs_f64()
+    );
+    out.push_str(&format!("  {:>9} {:>9} {:>8} {:>6}  file\n", "parse ms", "encode ms", "KiB", "share"));
+    for t in &slowest {
+        out.push_str(&format!(
+            "  {:>9.1} {:>9.1} {:>8} {:>5.1}%  {}\n",
+            ms(t.parse),
+            ms(t.encode),
+            t.bytes.div_ceil(1024),
+            100.0 * t.total().as_secs_f64() / total.as_secs_f64().max(f64::EPSILON),
+            t.path.display()
+        ));
+    }
+    let top: Duration = slowest.iter().map(|t| t.total()).sum();
+    out.push_str(&format!(
+        "  These {} files took {:.1}% of all tile time\n",
+        slowest.len(),
+        100.0 * top.as_secs_f64() / total.as_secs_f64().max(f64::EPSILON)
+    ));
+
+    let mut by_map: BTreeMap<&str, Vec<&TileTiming>> = BTreeMap::new();
+    for t in timings {
+        by_map.entry(&t.map).or_default().push(t);
+    }
+    out.push_str("\nPer map:\n");
+    for (map, tiles) in by_map {
+        let mut totals: Vec<Duration> = tiles.iter().map(|t| t.total()).collect();
+        totals.sort_unstable();
+        let sum: Duration = totals.iter().sum();
+        out.push_str(&format!(
+            "  {:<20} {:>5} tiles {:>8.2}s  median {:.1}ms  p95 {:.1}ms  max {:.1}ms\n",
+            map,
+            tiles.len(),
+            sum.as_secs_f64(),
+            ms(percentile(&totals, 50)),
+            ms(percentile(&totals, 95)),
+            ms(totals.last().copied().unwrap_or_default())
+        ));
+    }
+    out
+}
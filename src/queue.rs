@@ -0,0 +1,262 @@
+use crate::grid::TILES_PER_SIDE;
+use crate::manifest::ManifestEntry;
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// ============================================================================
+// Work queue
+// ============================================================================
+//
+// Distributed generation over a directory every machine can reach (NFS, SMB,
+// a synced folder), with no server to run:
+//
+//     pending/<job>.job       waiting for a worker
+//     claimed/<job>.job       being scanned, with its worker and claim time
+//     done/<job>.job          finished, next to <job>.journal: its tiles as
+//                             a checkpoint journal
+//     failed/<job>.job        with the error appended
+//     work/<job>/             a worker's scratch space while it scans
+//
+// A job is one map, or one band of its tile rows. Workers claim jobs by
+// renaming them from pending/ to claimed/, which exactly one of them wins.
+
+const DIRS: [&str; 5] = ["pending", "claimed", "done", "failed", "work"];
+
+/// One map, or a band of its rows, to scan
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Job {
+    /// `<Map>.<first row>-<last row>`, unique within a queue
+    pub id: String,
+    pub map: String,
+    pub map_id: u32,
+    /// As in the manifest; must be reachable from every worker
+    pub source: PathBuf,
+    pub subchunks: Option<bool>,
+    /// Tile rows (tile_y) to scan
+    pub rows: RangeInclusive<u32>,
+}
+
+impl Job {
+    /// Split a manifest entry into `bands` jobs of consecutive tile rows
+    pub fn split(entry: &ManifestEntry, bands: u32) -> Vec<Job> {
+        let bands = bands.clamp(1, TILES_PER_SIDE);
+        (0..bands)
+            .map(|band| {
+                let first = band * TILES_PER_SIDE / bands;
+                let last = (band + 1) * TILES_PER_SIDE / bands - 1;
+                Job {
+                    id: format!("{}.{:02}-{:02}", entry.map, first, last),
+                    map: entry.map.clone(),
+                    map_id: entry.map_id,
+                    source: entry.source.clone(),
+                    subchunks: entry.subchunks,
+                    rows: first..=last,
+                }
+            })
+            .collect()
+    }
+
+    /// The job file: `key = value` lines
+    fn to_text(&self) -> String {
+        let mut text = format!(
+            "map = {}\nmap_id = {}\nsource = {}\nrows = {}-{}\n",
+            self.map,
+            self.map_id,
+            self.source.display(),
+            self.rows.start(),
+            self.rows.end()
+        );
+        if let Some(subchunks) = self.subchunks {
+            text.push_str(&format!("subchunks = {}\n", subchunks));
+        }
+        text
+    }
+
+    fn parse(id: &str, text: &str) -> Result<Self, String> {
+        let field = |name: &str| {
+            text.lines()
+                .find_map(|l| l.split_once(" = ").filter(|(k, _)| k.trim() == name).map(|(_, v)| v.trim()))
+        };
+        let required = |name: &str| field(name).ok_or_else(|| format!("job {} has no {}", id, name));
+        let map_id = required("map_id")?;
+        let rows = required("rows")?;
+        let (first, last) = rows
+            .split_once('-')
+            .and_then(|(a, b)| Some((a.parse().ok()?, b.parse().ok()?)))
+            .ok_or_else(|| format!("job {}: bad rows `{}`", id, rows))?;
+        Ok(Job {
+            id: id.to_string(),
+            map: required("map")?.to_string(),
+            map_id: map_id.parse().map_err(|_| format!("job {}: bad map_id `{}`", id, map_id))?,
+            source: PathBuf::from(required("source")?),
+            subchunks: field("subchunks").map(|v| v == "true"),
+            rows: first..=last,
+        })
+    }
+}
+
+/// Jobs in each state
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QueueStatus {
+    pub pending: usize,
+    pub claimed: usize,
+    pub done: usize,
+    pub failed: usize,
+}
+
+pub struct WorkQueue {
+    dir: PathBuf,
+}
+
+impl WorkQueue {
+    /// Create the queue's directories under `dir` if they don't exist yet
+    pub fn create(dir: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        for sub in DIRS {
+            fs::create_dir_all(dir.join(sub))?;
+        }
+        Ok(Self { dir: dir.to_path_buf() })
+    }
+
+    pub fn open(dir: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if let Some(missing) = DIRS.iter().find(|sub| !dir.join(sub).is_dir()) {
+            return Err(format!("{} is not a work queue (no {}/)", dir.display(), missing).into());
+        }
+        Ok(Self { dir: dir.to_path_buf() })
+    }
+
+    fn job_path(&self, state: &str, id: &str) -> PathBuf {
+        self.dir.join(state).join(format!("{}.job", id))
+    }
+
+    /// IDs of the jobs in `state`, sorted
+    fn ids(&self, state: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut ids: Vec<String> = fs::read_dir(self.dir.join(state))?
+            .filter_map(|e| e.ok())
+            .filter_map(|e| e.file_name().to_str()?.strip_suffix(".job").map(str::to_string))
+            .collect();
+        ids.sort();
+        Ok(ids)
+    }
+
+    fn load(&self, state: &str, id: &str) -> Result<Job, Box<dyn std::error::Error>> {
+        Ok(Job::parse(id, &fs::read_to_string(self.job_path(state, id))?)?)
+    }
+
+    /// Queue `job` unless a job with its ID is already in any state.
+    /// Returns whether it was added.
+    pub fn add(&self, job: &Job) -> Result<bool, Box<dyn std::error::Error>> {
+        if ["pending", "claimed", "done", "failed"].iter().any(|state| self.job_path(state, &job.id).exists()) {
+            return Ok(false);
+        }
+        // Written aside and renamed in, so workers never see half a job
+        let staged = self.dir.join("work").join(format!("{}.job", job.id));
+        fs::write(&staged, job.to_text())?;
+        fs::rename(staged, self.job_path("pending", &job.id))?;
+        Ok(true)
+    }
+
+    /// Claim the first pending job for `worker`, or `None` when there is none
+    /// left. Workers racing for the same job each get a different one.
+    pub fn claim(&self, worker: &str) -> Result<Option<Job>, Box<dyn std::error::Error>> {
+        for id in self.ids("pending")? {
+            match fs::rename(self.job_path("pending", &id), self.job_path("claimed", &id)) {
+                Ok(()) => {}
+                // Another worker got there first
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            }
+            let job = self.load("claimed", &id)?;
+            let mut file = OpenOptions::new().append(true).open(self.job_path("claimed", &id))?;
+            writeln!(file, "worker = {}", worker)?;
+            writeln!(file, "claimed = {}", SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())?;
+            return Ok(Some(job));
+        }
+        Ok(None)
+    }
+
+    /// Scratch directory for `job` while it is scanned
+    pub fn work_dir(&self, job: &Job) -> PathBuf {
+        self.dir.join("work").join(&job.id)
+    }
+
+    /// Move a claimed job to done/ with the journal of its tiles
+    pub fn finish(&self, job: &Job, journal: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        fs::copy(journal, self.dir.join("done").join(format!("{}.journal", job.id)))?;
+        fs::rename(self.job_path("claimed", &job.id), self.job_path("done", &job.id))?;
+        let _ = fs::remove_dir_all(self.work_dir(job));
+        Ok(())
+    }
+
+    /// Move a claimed job to failed/, noting why
+    pub fn fail(&self, job: &Job, message: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let claimed = self.job_path("claimed", &job.id);
+        let mut file = OpenOptions::new().append(true).open(&claimed)?;
+        writeln!(file, "error = {}", message.replace('\n', " "))?;
+        fs::rename(claimed, self.job_path("failed", &job.id))?;
+        let _ = fs::remove_dir_all(self.work_dir(job));
+        Ok(())
+    }
+
+    /// Put a claimed job back in pending/ unscanned, e.g. when its worker is stopped
+    pub fn release(&self, job: &Job) -> Result<(), Box<dyn std::error::Error>> {
+        let _ = fs::remove_dir_all(self.work_dir(job));
+        fs::write(self.job_path("claimed", &job.id), job.to_text())?;
+        fs::rename(self.job_path("claimed", &job.id), self.job_path("pending", &job.id))?;
+        Ok(())
+    }
+
+    /// Put claims older than `max_age` back in pending/, for workers that died
+    /// mid-job. Returns the requeued job IDs.
+    pub fn requeue_stale(&self, max_age: Duration) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let mut requeued = Vec::new();
+        for id in self.ids("claimed")? {
+            let text = fs::read_to_string(self.job_path("claimed", &id))?;
+            let claimed_at = text
+                .lines()
+                .filter_map(|l| l.strip_prefix("claimed = ")?.trim().parse::<u64>().ok())
+                .next_back();
+            // A claim still being written counts as fresh
+            if claimed_at.is_some_and(|at| now.saturating_sub(at) > max_age.as_secs()) {
+                self.release(&Job::parse(&id, &text)?)?;
+                requeued.push(id);
+            }
+        }
+        Ok(requeued)
+    }
+
+    pub fn status(&self) -> Result<QueueStatus, Box<dyn std::error::Error>> {
+        Ok(QueueStatus {
+            pending: self.ids("pending")?.len(),
+            claimed: self.ids("claimed")?.len(),
+            done: self.ids("done")?.len(),
+            failed: self.ids("failed")?.len(),
+        })
+    }
+
+    /// Finished jobs with their journals, sorted by ID
+    pub fn done(&self) -> Result<Vec<(Job, PathBuf)>, Box<dyn std::error::Error>> {
+        self.ids("done")?
+            .into_iter()
+            .map(|id| Ok((self.load("done", &id)?, self.dir.join("done").join(format!("{}.journal", id)))))
+            .collect()
+    }
+
+    /// Failed jobs with the error each recorded
+    pub fn failed(&self) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+        self.ids("failed")?
+            .into_iter()
+            .map(|id| {
+                let text = fs::read_to_string(self.job_path("failed", &id))?;
+                let error = text.lines().filter_map(|l| l.strip_prefix("error = ")).next_back().unwrap_or("").to_string();
+                Ok((id, error))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,861 @@
+use crate::gif;
+use crate::grid::TILES_PER_SIDE;
+use crate::png;
+use crate::report::escape;
+use crate::theme::Rgb;
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+// ============================================================================
+// Map previews
+// ============================================================================
+//
+// `zone-map render` draws a generated grid to check by eye before it ships:
+// every chunk becomes a `scale` x `scale` block in its area's colour, cropped
+// to the tiles present, on a dark background where there are none. The
+// legend lists the areas drawn, largest first. PNG has no font beyond a 3x5
+// digit set, so its legend is a swatch and an area ID; the SVG legend adds
+// names, and hovering a chunk in a browser shows its area.
+//
+// `Annotations` add proofing marks on top: tile borders, each tile's ADT
+// coordinates, and a label on every area at the chunk deepest inside it
+// (the name in SVG, the ID in PNG).
+//
+// `render_compare` sets two exports of a map side by side, as a mask of the
+// chunks that changed, or as a GIF that flips between them.
+//
+// `render --all` draws every grid of a directory padded to one size, so they
+// line up in the `gallery_html` index it writes next to them.
+//
+// Colours come from a `Palette`. Each one picks an area's colour from its
+// ID alone, so an area keeps its colour across runs and continents.
+
+const CHUNKS_PER_TILE_SIDE: u32 = 16;
+/// Where there is no tile
+pub const BACKGROUND: [u8; 3] = [24, 24, 24];
+/// Chunks with area ID 0
+pub const NO_AREA: [u8; 3] = [0, 0, 0];
+/// Areas without a colour of their own
+pub const UNCOLORED: [u8; 3] = [128, 128, 128];
+const TEXT: [u8; 3] = [230, 230, 230];
+/// Tile border lines
+const GRID: [u8; 3] = [200, 200, 200];
+/// Chunks a compared export no longer has
+const REMOVED: [u8; 3] = [255, 0, 255];
+/// How long each frame of a blink comparison shows, in hundredths of a second
+const BLINK_DELAY: u16 = 80;
+
+/// Legend layout, in pixels
+const MARGIN: u32 = 8;
+const ROW: u32 = 14;
+const SWATCH: u32 = 10;
+/// Pixels per font pixel
+const FONT_SCALE: u32 = 2;
+/// Legend rows per column at least, for maps too small to set the height
+const MIN_LEGEND_ROWS: u32 = 16;
+
+/// Digits 0-9, 3x5 pixels each, row-major from the top left in the high bits
+const DIGITS: [u16; 10] = [
+    0b111_101_101_101_111,
+    0b010_110_010_010_111,
+    0b111_001_111_100_111,
+    0b111_001_111_001_111,
+    0b101_101_111_001_001,
+    0b111_100_111_001_111,
+    0b111_100_111_101_111,
+    0b111_001_001_001_001,
+    0b111_101_111_101_111,
+    0b111_101_111_001_111,
+];
+const UNDERSCORE: u16 = 0b000_000_000_000_111;
+
+/// `color` as 8-bit components
+pub fn rgb_bytes(color: Rgb) -> [u8; 3] {
+    [color.0, color.1, color.2].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+/// A colour of its own for each area ID: consecutive IDs step around the
+/// hue wheel by the golden angle, so they never end up alike
+pub fn distinct_color(area_id: u32) -> [u8; 3] {
+    let hue = (area_id as f64 * 0.618_033_988_75).fract() * 6.0;
+    let (saturation, value) = (0.65, 0.92);
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+    [r, g, b].map(|c| ((c + m) * 255.0).round() as u8)
+}
+
+/// Okabe-Ito's colour-blind-safe set, without its black (chunks with no area)
+const OKABE_ITO: [[u8; 3]; 7] = [
+    [0xE6, 0x9F, 0x00],
+    [0x56, 0xB4, 0xE9],
+    [0x00, 0x9E, 0x73],
+    [0xF0, 0xE4, 0x42],
+    [0x00, 0x72, 0xB2],
+    [0xD5, 0x5E, 0x00],
+    [0xCC, 0x79, 0xA7],
+];
+
+/// Viridis at ten even steps from 0 to 1
+const VIRIDIS: [[u8; 3]; 10] = [
+    [0x44, 0x01, 0x54],
+    [0x48, 0x28, 0x78],
+    [0x3E, 0x4A, 0x89],
+    [0x31, 0x68, 0x8E],
+    [0x26, 0x82, 0x8E],
+    [0x1F, 0x9E, 0x89],
+    [0x35, 0xB7, 0x79],
+    [0x6D, 0xCD, 0x59],
+    [0xB4, 0xDE, 0x2C],
+    [0xFD, 0xE7, 0x25],
+];
+
+/// Viridis bins without a count
+const DEFAULT_VIRIDIS_BINS: usize = 8;
+
+/// Viridis at `t` in 0..=1
+fn viridis(t: f64) -> [u8; 3] {
+    let at = t.clamp(0.0, 1.0) * (VIRIDIS.len() - 1) as f64;
+    let (low, frac) = (at.floor() as usize, at.fract());
+    let high = (low + 1).min(VIRIDIS.len() - 1);
+    [0, 1, 2].map(|c| (VIRIDIS[low][c] as f64 + (VIRIDIS[high][c] as f64 - VIRIDIS[low][c] as f64) * frac).round() as u8)
+}
+
+/// One of `count` slots for `area_id`, spread by the golden ratio so
+/// consecutive IDs (often neighbouring subzones) land far apart
+fn slot(area_id: u32, count: usize) -> usize {
+    ((area_id as f64 * 0.618_033_988_75).fract() * count as f64) as usize % count.max(1)
+}
+
+/// How `render` colours areas
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Palette {
+    /// A hue of its own per area ID (`distinct_color`)
+    #[default]
+    Distinct,
+    /// The seven Okabe-Ito colours, safe for the common kinds of colour blindness
+    OkabeIto,
+    /// Viridis cut into this many bins, which stay apart in greyscale too
+    Viridis(usize),
+    /// Colours given by the user
+    Colors(Vec<[u8; 3]>),
+}
+
+impl Palette {
+    /// Colour of `area_id`; it depends on nothing else
+    pub fn color(&self, area_id: u32) -> [u8; 3] {
+        match self {
+            Self::Distinct => distinct_color(area_id),
+            Self::OkabeIto => OKABE_ITO[slot(area_id, OKABE_ITO.len())],
+            Self::Viridis(bins) => {
+                let bins = (*bins).max(1);
+                viridis(if bins == 1 { 0.5 } else { slot(area_id, bins) as f64 / (bins - 1) as f64 })
+            }
+            Self::Colors(colors) => colors.get(slot(area_id, colors.len())).copied().unwrap_or(UNCOLORED),
+        }
+    }
+}
+
+fn parse_hex(s: &str) -> Result<[u8; 3], String> {
+    let digits = s.strip_prefix('#').unwrap_or(s);
+    let value = match digits.len() {
+        6 => u32::from_str_radix(digits, 16).ok(),
+        _ => None,
+    };
+    value.map(|v| [(v >> 16) as u8, (v >> 8) as u8, v as u8]).ok_or_else(|| format!("bad colour `{}` (want #rrggbb)", s))
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    /// `distinct`, `okabe-ito`, `viridis`, `viridis:<bins>` or
+    /// `colors:#rrggbb,#rrggbb,...`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "distinct" => Ok(Self::Distinct),
+            "okabe-ito" => Ok(Self::OkabeIto),
+            "viridis" => Ok(Self::Viridis(DEFAULT_VIRIDIS_BINS)),
+            _ => {
+                if let Some(bins) = s.strip_prefix("viridis:") {
+                    return match bins.parse() {
+                        Ok(bins) if bins > 0 => Ok(Self::Viridis(bins)),
+                        _ => Err(format!("bad viridis bin count `{}`", bins)),
+                    };
+                }
+                match s.strip_prefix("colors:") {
+                    Some(list) => {
+                        let colors = list.split(',').map(|c| parse_hex(c.trim())).collect::<Result<Vec<_>, _>>()?;
+                        Ok(Self::Colors(colors))
+                    }
+                    None => Err(format!("unknown palette `{}` (distinct, okabe-ito, viridis[:<bins>], colors:<#rrggbb,...>)", s)),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Distinct => f.write_str("distinct"),
+            Self::OkabeIto => f.write_str("okabe-ito"),
+            Self::Viridis(bins) => write!(f, "viridis:{}", bins),
+            Self::Colors(colors) => write!(f, "colors:{}", colors.iter().map(|&c| hex(c)).collect::<Vec<_>>().join(",")),
+        }
+    }
+}
+
+/// One area in a legend
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegendEntry {
+    pub area_id: u32,
+    pub name: String,
+    pub color: [u8; 3],
+    pub chunks: usize,
+}
+
+/// A map's chunks, cropped to the tiles present, row-major
+#[derive(Debug, Clone)]
+pub struct ChunkRaster {
+    /// Size in chunks
+    pub width: u32,
+    pub height: u32,
+    /// ADT coordinates of the top left tile
+    pub origin: (u32, u32),
+    /// Area ID of each chunk; `None` outside any tile
+    pub cells: Vec<Option<u32>>,
+}
+
+impl ChunkRaster {
+    /// Raster of `tiles` (tile key -> 256 area IDs); `None` when there are none
+    pub fn from_tiles<'a>(tiles: impl IntoIterator<Item = (&'a u32, &'a Vec<u32>)>) -> Option<Self> {
+        let tiles: Vec<(u32, &Vec<u32>)> = tiles.into_iter().map(|(&k, ids)| (k, ids)).collect();
+        let keys: Vec<u32> = tiles.iter().map(|&(k, _)| k).collect();
+        Self::over(&tiles, &keys)
+    }
+
+    /// Rasters of two exports of a map, both over the tiles either has so
+    /// their chunks line up; `None` when neither has any
+    pub fn aligned<'a>(
+        old: impl IntoIterator<Item = (&'a u32, &'a Vec<u32>)>,
+        new: impl IntoIterator<Item = (&'a u32, &'a Vec<u32>)>,
+    ) -> Option<(Self, Self)> {
+        let old: Vec<(u32, &Vec<u32>)> = old.into_iter().map(|(&k, ids)| (k, ids)).collect();
+        let new: Vec<(u32, &Vec<u32>)> = new.into_iter().map(|(&k, ids)| (k, ids)).collect();
+        let keys: Vec<u32> = old.iter().chain(&new).map(|&(k, _)| k).collect();
+        Some((Self::over(&old, &keys)?, Self::over(&new, &keys)?))
+    }
+
+    /// Raster of `tiles` cropped to the tiles `keys` span
+    fn over(tiles: &[(u32, &Vec<u32>)], keys: &[u32]) -> Option<Self> {
+        let xs = keys.iter().map(|k| k % TILES_PER_SIDE);
+        let ys = keys.iter().map(|k| k / TILES_PER_SIDE);
+        let (min_x, max_x, min_y, max_y) = (xs.clone().min()?, xs.max()?, ys.clone().min()?, ys.max()?);
+        let width = (max_x - min_x + 1) * CHUNKS_PER_TILE_SIDE;
+        let height = (max_y - min_y + 1) * CHUNKS_PER_TILE_SIDE;
+
+        let mut cells = vec![None; (width * height) as usize];
+        for &(key, ids) in tiles {
+            let (tile_x, tile_y) = (key % TILES_PER_SIDE - min_x, key / TILES_PER_SIDE - min_y);
+            for (i, &id) in ids.iter().enumerate().take((CHUNKS_PER_TILE_SIDE * CHUNKS_PER_TILE_SIDE) as usize) {
+                let (chunk_x, chunk_y) = (i as u32 % CHUNKS_PER_TILE_SIDE, i as u32 / CHUNKS_PER_TILE_SIDE);
+                let (x, y) = (tile_x * CHUNKS_PER_TILE_SIDE + chunk_x, tile_y * CHUNKS_PER_TILE_SIDE + chunk_y);
+                cells[(y * width + x) as usize] = Some(id);
+            }
+        }
+        Some(Self { width, height, origin: (min_x, min_y), cells })
+    }
+
+    /// This raster grown to at least `width` x `height` chunks, with empty
+    /// chunks added right and below
+    pub fn padded(&self, width: u32, height: u32) -> Self {
+        let (new_width, new_height) = (self.width.max(width), self.height.max(height));
+        let mut cells = vec![None; (new_width * new_height) as usize];
+        for (y, row) in self.cells.chunks(self.width as usize).enumerate() {
+            let start = y * new_width as usize;
+            cells[start..start + row.len()].copy_from_slice(row);
+        }
+        Self { width: new_width, height: new_height, origin: self.origin, cells }
+    }
+
+    /// The areas drawn, most chunks first
+    pub fn legend(&self, color: impl Fn(u32) -> [u8; 3], name: impl Fn(u32) -> String) -> Vec<LegendEntry> {
+        let mut chunks: BTreeMap<u32, usize> = BTreeMap::new();
+        for &id in self.cells.iter().flatten() {
+            *chunks.entry(id).or_default() += 1;
+        }
+        let mut entries: Vec<LegendEntry> = chunks
+            .into_iter()
+            .map(|(area_id, chunks)| LegendEntry { area_id, name: name(area_id), color: color(area_id), chunks })
+            .collect();
+        entries.sort_by_key(|e| (std::cmp::Reverse(e.chunks), e.area_id));
+        entries
+    }
+
+    /// Tiles with any chunks, as (column, row) counted from the top left tile
+    pub fn tiles(&self) -> Vec<(u32, u32)> {
+        let (columns, rows) = (self.width / CHUNKS_PER_TILE_SIDE, self.height / CHUNKS_PER_TILE_SIDE);
+        let mut tiles = Vec::new();
+        for row in 0..rows {
+            for column in 0..columns {
+                let present = (0..CHUNKS_PER_TILE_SIDE).any(|y| {
+                    let start = ((row * CHUNKS_PER_TILE_SIDE + y) * self.width + column * CHUNKS_PER_TILE_SIDE) as usize;
+                    self.cells[start..start + CHUNKS_PER_TILE_SIDE as usize].iter().any(Option::is_some)
+                });
+                if present {
+                    tiles.push((column, row));
+                }
+            }
+        }
+        tiles
+    }
+
+    /// Where to label each area: its chunk farthest from any other area or
+    /// the raster's edge, counting diagonal steps as one, and of those the
+    /// one nearest the area's centre. Area 0 gets none.
+    pub fn anchors(&self) -> BTreeMap<u32, (u32, u32)> {
+        let (width, height) = (self.width as i64, self.height as i64);
+        let mut depth = vec![0u32; self.cells.len()];
+        // Depth of the neighbour at (x, y) when it's in the same area, else 0
+        let neighbour = |depth: &[u32], i: usize, x: i64, y: i64| {
+            if x < 0 || y < 0 || x >= width || y >= height {
+                return 0;
+            }
+            let j = (y * width + x) as usize;
+            if self.cells[j] == self.cells[i] {
+                depth[j]
+            } else {
+                0
+            }
+        };
+
+        for y in 0..height {
+            for x in 0..width {
+                let i = (y * width + x) as usize;
+                if self.cells[i].is_some() {
+                    let nearest = [(-1, -1), (0, -1), (1, -1), (-1, 0)].iter().map(|(dx, dy)| neighbour(&depth, i, x + dx, y + dy)).min();
+                    depth[i] = nearest.unwrap_or(0) + 1;
+                }
+            }
+        }
+        for y in (0..height).rev() {
+            for x in (0..width).rev() {
+                let i = (y * width + x) as usize;
+                if self.cells[i].is_some() {
+                    let nearest = [(1, 1), (0, 1), (-1, 1), (1, 0)].iter().map(|(dx, dy)| neighbour(&depth, i, x + dx, y + dy)).min();
+                    depth[i] = depth[i].min(nearest.unwrap_or(0) + 1);
+                }
+            }
+        }
+
+        let position = |i: usize| (i as u32 % self.width, i as u32 / self.width);
+        let areas = || self.cells.iter().enumerate().filter_map(|(i, cell)| cell.filter(|&id| id != 0).map(|id| (i, id)));
+        let mut centres: BTreeMap<u32, (f64, f64, f64)> = BTreeMap::new();
+        for (i, id) in areas() {
+            let (x, y) = position(i);
+            let centre = centres.entry(id).or_default();
+            *centre = (centre.0 + x as f64, centre.1 + y as f64, centre.2 + 1.0);
+        }
+        let off_centre = |i: usize, id: u32| {
+            let ((x, y), (sum_x, sum_y, count)) = (position(i), centres[&id]);
+            (x as f64 - sum_x / count).powi(2) + (y as f64 - sum_y / count).powi(2)
+        };
+
+        let mut best: BTreeMap<u32, usize> = BTreeMap::new();
+        for (i, id) in areas() {
+            let current = best.entry(id).or_insert(i);
+            if depth[i] > depth[*current] || (depth[i] == depth[*current] && off_centre(i, id) < off_centre(*current, id)) {
+                *current = i;
+            }
+        }
+        best.into_iter().map(|(id, i)| (id, position(i))).collect()
+    }
+}
+
+/// Marks drawn over the map, each off by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Annotations {
+    /// Each area's name (SVG) or ID (PNG) at its anchor, see `ChunkRaster::anchors`
+    pub labels: bool,
+    /// Lines along tile borders
+    pub tile_grid: bool,
+    /// ADT coordinates, `<x>_<y>`, in the corner of each tile
+    pub coords: bool,
+}
+
+/// Legend columns: rows per column and the width of one, in pixels
+fn legend_layout(map_height: u32, legend: &[LegendEntry]) -> (u32, u32) {
+    let rows = (map_height.saturating_sub(2 * MARGIN) / ROW).max(MIN_LEGEND_ROWS);
+    let digits = legend.iter().map(|e| e.area_id.to_string().len() as u32).max().unwrap_or(1);
+    (rows, SWATCH + 4 + digits * 4 * FONT_SCALE + MARGIN)
+}
+
+/// Width of `text` in pixels at `size` pixels per font pixel
+fn text_width(text: &str, size: u32) -> u32 {
+    (text.len() as u32 * 4).saturating_sub(1) * size
+}
+
+/// An RGB image being drawn, 3 bytes per pixel, row-major from the top
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32) -> Self {
+        Self { width, height, pixels: BACKGROUND.repeat((width * height) as usize) }
+    }
+
+    /// Fill a rectangle, clipped to the image
+    fn fill(&mut self, x0: u32, y0: u32, w: u32, h: u32, rgb: [u8; 3]) {
+        for y in y0..(y0 + h).min(self.height) {
+            for x in x0..(x0 + w).min(self.width) {
+                let at = ((y * self.width + x) * 3) as usize;
+                self.pixels[at..at + 3].copy_from_slice(&rgb);
+            }
+        }
+    }
+
+    /// Draw `text` (digits and `_`) from its top left corner, `size` pixels per font pixel
+    fn text(&mut self, x: u32, y: u32, text: &str, size: u32, rgb: [u8; 3]) {
+        for (i, c) in text.chars().enumerate() {
+            let glyph = match c {
+                '0'..='9' => DIGITS[c as usize - '0' as usize],
+                '_' => UNDERSCORE,
+                _ => 0,
+            };
+            for bit in 0..15 {
+                if glyph & (1 << (14 - bit)) != 0 {
+                    self.fill(x + (i as u32 * 4 + bit % 3) * size, y + bit / 3 * size, size, size, rgb);
+                }
+            }
+        }
+    }
+
+    /// `self` and `other` in one image, `gap` pixels apart
+    fn beside(&self, other: &Canvas, gap: u32) -> Canvas {
+        let mut out = Canvas::new(self.width + gap + other.width, self.height.max(other.height));
+        for (canvas, left) in [(self, 0), (other, self.width + gap)] {
+            for y in 0..canvas.height {
+                let from = (y * canvas.width * 3) as usize;
+                let to = ((y * out.width + left) * 3) as usize;
+                out.pixels[to..to + (canvas.width * 3) as usize].copy_from_slice(&canvas.pixels[from..from + (canvas.width * 3) as usize]);
+            }
+        }
+        out
+    }
+
+    /// `text` on a background box, so it reads over any colour
+    fn label(&mut self, x: u32, y: u32, text: &str, size: u32) {
+        self.fill(x.saturating_sub(size), y.saturating_sub(size), text_width(text, size) + 2 * size, 7 * size, BACKGROUND);
+        self.text(x, y, text, size, TEXT);
+    }
+}
+
+/// Encode `raster` as a PNG, `scale` pixels per chunk side, with `legend`
+/// to the right of the map when it isn't empty
+pub fn render_png(
+    raster: &ChunkRaster,
+    color: impl Fn(u32) -> [u8; 3],
+    scale: u32,
+    legend: &[LegendEntry],
+    annotations: Annotations,
+) -> Result<Vec<u8>, String> {
+    let canvas = draw(raster, |i| raster.cells[i].map(&color), scale, legend, annotations);
+    png::encode_rgb(canvas.width, canvas.height, &canvas.pixels)
+}
+
+/// Draw `raster` with `paint` giving each chunk's colour by index (`None`
+/// leaves the background), then its annotations and `legend`
+fn draw(raster: &ChunkRaster, paint: impl Fn(usize) -> Option<[u8; 3]>, scale: u32, legend: &[LegendEntry], annotations: Annotations) -> Canvas {
+    let (map_width, map_height) = (raster.width * scale, raster.height * scale);
+    let (rows, column_width) = legend_layout(map_height, legend);
+    let columns = (legend.len() as u32).div_ceil(rows);
+    let (width, height) = if legend.is_empty() {
+        (map_width, map_height)
+    } else {
+        let used_rows = rows.min(legend.len() as u32);
+        (map_width + MARGIN + columns * column_width, map_height.max(2 * MARGIN + used_rows * ROW))
+    };
+
+    let mut canvas = Canvas::new(width, height);
+    for i in 0..raster.cells.len() {
+        if let Some(rgb) = paint(i) {
+            let (x, y) = (i as u32 % raster.width, i as u32 / raster.width);
+            canvas.fill(x * scale, y * scale, scale, scale, rgb);
+        }
+    }
+
+    let tile = CHUNKS_PER_TILE_SIDE * scale;
+    // Map text grows with the scale, up to the legend's size
+    let size = (scale / 4).clamp(1, FONT_SCALE);
+    if annotations.tile_grid {
+        for x in (tile..map_width).step_by(tile as usize) {
+            canvas.fill(x, 0, 1, map_height, GRID);
+        }
+        for y in (tile..map_height).step_by(tile as usize) {
+            canvas.fill(0, y, map_width, 1, GRID);
+        }
+    }
+    if annotations.coords {
+        for (column, row) in raster.tiles() {
+            let text = format!("{}_{}", raster.origin.0 + column, raster.origin.1 + row);
+            canvas.label(column * tile + 2 * size, row * tile + 2 * size, &text, size);
+        }
+    }
+    if annotations.labels {
+        for (id, (x, y)) in raster.anchors() {
+            let text = id.to_string();
+            let (center_x, center_y) = (x * scale + scale / 2, y * scale + scale / 2);
+            canvas.label(center_x.saturating_sub(text_width(&text, size) / 2), center_y.saturating_sub(5 * size / 2), &text, size);
+        }
+    }
+
+    for (i, entry) in legend.iter().enumerate() {
+        let (column, row) = (i as u32 / rows, i as u32 % rows);
+        let (x, y) = (map_width + MARGIN + column * column_width, MARGIN + row * ROW);
+        canvas.fill(x, y, SWATCH, SWATCH, entry.color);
+        canvas.text(x + SWATCH + 4, y, &entry.area_id.to_string(), FONT_SCALE, TEXT);
+    }
+    canvas
+}
+
+/// How `render_compare` shows two exports of a map
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum CompareMode {
+    /// The old map left of the new one
+    #[default]
+    SideBySide,
+    /// The new map with the chunks that didn't change dimmed, and the ones
+    /// it no longer has in magenta
+    Diff,
+    /// An animated GIF flipping between the two
+    Blink,
+}
+
+impl FromStr for CompareMode {
+    type Err = String;
+
+    /// `side-by-side`, `diff` or `blink`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "side-by-side" => Ok(Self::SideBySide),
+            "diff" => Ok(Self::Diff),
+            "blink" => Ok(Self::Blink),
+            _ => Err(format!("unknown compare mode `{}` (side-by-side, diff, blink)", s)),
+        }
+    }
+}
+
+impl fmt::Display for CompareMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::SideBySide => "side-by-side",
+            Self::Diff => "diff",
+            Self::Blink => "blink",
+        })
+    }
+}
+
+/// Draw `old` against `new`, a pair from `ChunkRaster::aligned`, as `mode`
+/// asks: a PNG, or for `Blink` a GIF
+pub fn render_compare(
+    old: &ChunkRaster,
+    new: &ChunkRaster,
+    color: impl Fn(u32) -> [u8; 3],
+    scale: u32,
+    annotations: Annotations,
+    mode: CompareMode,
+) -> Result<Vec<u8>, String> {
+    if (old.width, old.height, old.origin) != (new.width, new.height, new.origin) {
+        return Err("compared rasters don't cover the same tiles".to_string());
+    }
+    let before = draw(old, |i| old.cells[i].map(&color), scale, &[], annotations);
+    let after = draw(new, |i| new.cells[i].map(&color), scale, &[], annotations);
+    match mode {
+        CompareMode::SideBySide => {
+            let both = before.beside(&after, MARGIN);
+            png::encode_rgb(both.width, both.height, &both.pixels)
+        }
+        CompareMode::Diff => {
+            let paint = |i: usize| match (old.cells[i], new.cells[i]) {
+                (Some(_), None) => Some(REMOVED),
+                (was, Some(id)) if was == Some(id) => Some(color(id).map(|c| c / 3)),
+                (_, now) => now.map(&color),
+            };
+            let mask = draw(new, paint, scale, &[], annotations);
+            png::encode_rgb(mask.width, mask.height, &mask.pixels)
+        }
+        CompareMode::Blink => gif::encode_animation(after.width, after.height, &[&before.pixels, &after.pixels], BLINK_DELAY),
+    }
+}
+
+/// One image of a gallery
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GalleryEntry {
+    /// Grid name
+    pub name: String,
+    /// Image path, relative to the index
+    pub file: String,
+    pub tiles: usize,
+    pub areas: usize,
+}
+
+const GALLERY_STYLE: &str = "body{font-family:sans-serif;margin:2em;background:#181818;color:#e6e6e6}\
+main{display:flex;flex-wrap:wrap;gap:1.5em}\
+figure{margin:0}\
+img{image-rendering:pixelated;max-width:480px;border:1px solid #444}\
+a{color:inherit}";
+
+/// An HTML page of `entries`, each image linking to itself at full size
+pub fn gallery_html(title: &str, entries: &[GalleryEntry]) -> String {
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!("<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n", escape(title), GALLERY_STYLE));
+    out.push_str(&format!("<h1>{}</h1>\n<main>\n", escape(title)));
+    for entry in entries {
+        out.push_str(&format!(
+            "<figure><a href=\"{0}\"><img src=\"{0}\" alt=\"{1}\" loading=\"lazy\"></a><figcaption>{1}: {2} tiles, {3} areas</figcaption></figure>\n",
+            escape(&entry.file),
+            escape(&entry.name),
+            entry.tiles,
+            entry.areas
+        ));
+    }
+    out.push_str("</main>\n</body>\n</html>\n");
+    out
+}
+
+fn hex(rgb: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2])
+}
+
+/// `raster` as an SVG document, one group of rectangles per area (a run of
+/// chunks along a row is one rectangle), `scale` pixels per chunk side.
+/// `legend` supplies the names shown on hover and as labels, and is drawn
+/// to the right of the map when `show_legend` is set.
+pub fn render_svg(raster: &ChunkRaster, scale: u32, legend: &[LegendEntry], show_legend: bool, annotations: Annotations) -> String {
+    let (map_width, map_height) = (raster.width * scale, raster.height * scale);
+    let (width, height) = if show_legend && !legend.is_empty() {
+        let longest = legend.iter().map(|e| e.name.chars().count() + e.area_id.to_string().len() + 3).max().unwrap_or(0);
+        (map_width + MARGIN * 2 + SWATCH + 4 + longest as u32 * 7, map_height.max(2 * MARGIN + legend.len() as u32 * ROW))
+    } else {
+        (map_width, map_height)
+    };
+
+    let mut runs: BTreeMap<u32, Vec<(u32, u32, u32)>> = BTreeMap::new();
+    for y in 0..raster.height {
+        let row = &raster.cells[(y * raster.width) as usize..((y + 1) * raster.width) as usize];
+        let mut x = 0;
+        while x < raster.width {
+            let Some(id) = row[x as usize] else {
+                x += 1;
+                continue;
+            };
+            let start = x;
+            while x < raster.width && row[x as usize] == Some(id) {
+                x += 1;
+            }
+            runs.entry(id).or_default().push((start, y, x - start));
+        }
+    }
+
+    let mut out = String::new();
+    out.push_str(&format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" shape-rendering=\"crispEdges\">\n",
+        width, height
+    ));
+    out.push_str(&format!("<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>\n", width, height, hex(BACKGROUND)));
+    for entry in legend {
+        let Some(runs) = runs.get(&entry.area_id) else {
+            continue;
+        };
+        out.push_str(&format!("<g fill=\"{}\">\n<title>{} ({})</title>\n", hex(entry.color), escape(&entry.name), entry.area_id));
+        for &(x, y, len) in runs {
+            out.push_str(&format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>\n", x * scale, y * scale, len * scale, scale));
+        }
+        out.push_str("</g>\n");
+    }
+
+    let tile = CHUNKS_PER_TILE_SIDE * scale;
+    let mut path = String::new();
+    if annotations.tile_grid {
+        for x in (tile..map_width).step_by(tile as usize) {
+            path.push_str(&format!("M{} 0V{}", x, map_height));
+        }
+        for y in (tile..map_height).step_by(tile as usize) {
+            path.push_str(&format!("M0 {}H{}", y, map_width));
+        }
+    }
+    if !path.is_empty() {
+        out.push_str(&format!("<path d=\"{}\" stroke=\"{}\" stroke-width=\"1\" fill=\"none\"/>\n", path, hex(GRID)));
+    }
+    if annotations.coords || annotations.labels {
+        out.push_str(&format!(
+            "<g font-family=\"monospace\" font-size=\"11\" fill=\"{}\" stroke=\"{}\" stroke-width=\"3\" paint-order=\"stroke\">\n",
+            hex(TEXT),
+            hex(BACKGROUND)
+        ));
+        if annotations.coords {
+            for (column, row) in raster.tiles() {
+                out.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" dominant-baseline=\"hanging\">{}_{}</text>\n",
+                    column * tile + 3,
+                    row * tile + 3,
+                    raster.origin.0 + column,
+                    raster.origin.1 + row
+                ));
+            }
+        }
+        if annotations.labels {
+            let names: BTreeMap<u32, &str> = legend.iter().map(|e| (e.area_id, e.name.as_str())).collect();
+            for (id, (x, y)) in raster.anchors() {
+                let name = names.get(&id).map_or_else(|| id.to_string(), |name| escape(name));
+                out.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>\n",
+                    x * scale + scale / 2,
+                    y * scale + scale / 2,
+                    name
+                ));
+            }
+        }
+        out.push_str("</g>\n");
+    }
+
+    if show_legend {
+        out.push_str(&format!("<g font-family=\"monospace\" font-size=\"11\" fill=\"{}\">\n", hex(TEXT)));
+        for (i, entry) in legend.iter().enumerate() {
+            let (x, y) = (map_width + MARGIN, MARGIN + i as u32 * ROW);
+            out.push_str(&format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n", x, y, SWATCH, SWATCH, hex(entry.color)));
+            out.push_str(&format!("<text x=\"{}\" y=\"{}\">{} ({})</text>\n", x + SWATCH + 4, y + SWATCH, escape(&entry.name), entry.area_id));
+        }
+        out.push_str("</g>\n");
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A raster of one tile at (30, 40) whose chunks take `area(x, y)`
+    fn raster(area: impl Fn(u32, u32) -> u32) -> ChunkRaster {
+        let ids: Vec<u32> = (0..256).map(|i| area(i % 16, i / 16)).collect();
+        ChunkRaster::from_tiles([(&(40 * TILES_PER_SIDE + 30), &ids)]).unwrap()
+    }
+
+    #[test]
+    fn anchors_sit_deepest_inside_each_area() {
+        // Area 1 fills columns 0-3, area 2 the rest
+        let raster = raster(|x, _| if x < 4 { 1 } else { 2 });
+        assert_eq!(raster.origin, (30, 40));
+        let anchors = raster.anchors();
+        assert_eq!(anchors.len(), 2);
+        // Two chunks from the edge is as deep as area 1 goes, anywhere down
+        // columns 1 and 2; the middle row wins
+        assert_eq!(anchors[&1], (1, 7));
+        assert_eq!(anchors[&2], (9, 7));
+        assert!(!raster.anchors().contains_key(&0));
+    }
+
+    #[test]
+    fn svg_annotations_are_optional() {
+        let raster = raster(|x, _| if x < 8 { 1 } else { 2 });
+        let legend = raster.legend(distinct_color, |id| format!("Area <{}>", id));
+        let plain = render_svg(&raster, 4, &legend, false, Annotations::default());
+        assert!(!plain.contains("<text") && !plain.contains("<path"));
+
+        let all = Annotations { labels: true, tile_grid: true, coords: true };
+        let marked = render_svg(&raster, 4, &legend, false, all);
+        assert!(marked.contains(">30_40</text>"));
+        assert!(marked.contains(">Area &lt;1&gt;</text>"));
+        assert!(marked.contains(">Area &lt;2&gt;</text>"));
+        // One tile has no inner borders
+        assert!(!marked.contains("<path"));
+        assert!(render_png(&raster, distinct_color, 4, &legend, all).is_ok());
+    }
+
+    #[test]
+    fn compared_exports_line_up() {
+        let old_tile: Vec<u32> = vec![1; 256];
+        let new_tile: Vec<u32> = (0..256).map(|i| if i < 128 { 1 } else { 2 }).collect();
+        let (old_key, new_key) = (10 * TILES_PER_SIDE + 10, 10 * TILES_PER_SIDE + 11);
+        // The old export has tile (10, 10), the new one that and (11, 10)
+        let (old, new) = ChunkRaster::aligned([(&old_key, &old_tile)], [(&old_key, &new_tile), (&new_key, &new_tile)]).unwrap();
+        assert_eq!((old.width, old.height, old.origin), (32, 16, (10, 10)));
+        assert_eq!((new.width, new.height, new.origin), (32, 16, (10, 10)));
+        assert_eq!(old.cells[16], None);
+        assert_eq!(new.cells[16], Some(1));
+
+        let changed = old.cells.iter().zip(&new.cells).filter(|(a, b)| a != b).count();
+        assert_eq!(changed, 128 + 256);
+        for mode in [CompareMode::SideBySide, CompareMode::Diff, CompareMode::Blink] {
+            assert_eq!(mode.to_string().parse::<CompareMode>(), Ok(mode));
+            let image = render_compare(&old, &new, distinct_color, 2, Annotations::default(), mode).unwrap();
+            let magic: &[u8] = if mode == CompareMode::Blink { b"GIF89a" } else { b"\x89PNG" };
+            assert!(image.starts_with(magic));
+        }
+        let (lone, _) = ChunkRaster::aligned([(&old_key, &old_tile)], []).unwrap();
+        assert!(render_compare(&lone, &new, distinct_color, 1, Annotations::default(), CompareMode::Diff).is_err());
+    }
+
+    #[test]
+    fn padding_keeps_chunks_in_place() {
+        let small = raster(|x, y| x + y * 16);
+        let padded = small.padded(48, 32);
+        assert_eq!((padded.width, padded.height, padded.origin), (48, 32, small.origin));
+        assert_eq!(padded.cells[48 * 3 + 5], small.cells[16 * 3 + 5]);
+        assert_eq!(padded.cells[16], None);
+        assert_eq!(padded.tiles(), small.tiles());
+        // Never shrinks
+        assert_eq!(small.padded(1, 1).cells, small.cells);
+
+        let page = gallery_html("Data <new>", &[GalleryEntry { name: "Azeroth".into(), file: "Azeroth.png".into(), tiles: 1, areas: 2 }]);
+        assert!(page.contains("<title>Data &lt;new&gt;</title>"));
+        assert!(page.contains("<img src=\"Azeroth.png\""));
+        assert!(page.contains("Azeroth: 1 tiles, 2 areas"));
+    }
+
+    #[test]
+    fn palettes_parse_and_print_back() {
+        for text in ["distinct", "okabe-ito", "viridis:8", "viridis:3", "colors:#112233,#abcdef"] {
+            assert_eq!(text.parse::<Palette>().unwrap().to_string(), text);
+        }
+        assert_eq!("viridis".parse::<Palette>(), Ok(Palette::Viridis(8)));
+        assert!("viridis:0".parse::<Palette>().is_err());
+        assert!("colors:".parse::<Palette>().is_err());
+        assert!("colors:#12345".parse::<Palette>().is_err());
+        assert!("rainbow".parse::<Palette>().is_err());
+    }
+
+    #[test]
+    fn palette_colours_depend_on_the_area_alone() {
+        let palettes = ["okabe-ito", "viridis:5", "colors:#ff0000,#00ff00"].map(|p| p.parse::<Palette>().unwrap());
+        for palette in &palettes {
+            let first: Vec<[u8; 3]> = (0..200).map(|id| palette.color(id)).collect();
+            let again: Vec<[u8; 3]> = (0..200).map(|id| palette.clone().color(id)).collect();
+            assert_eq!(first, again);
+        }
+        let okabe_ito: Vec<[u8; 3]> = (1..=7).map(|id| palettes[0].color(id)).collect();
+        assert!(okabe_ito.iter().all(|c| OKABE_ITO.contains(c)));
+        // Consecutive IDs don't share a colour
+        assert!(okabe_ito.windows(2).all(|pair| pair[0] != pair[1]));
+        assert_eq!(viridis(0.0), VIRIDIS[0]);
+        assert_eq!(viridis(1.0), VIRIDIS[9]);
+        assert!((0..50).map(|id| palettes[1].color(id)).all(|c| (0..5).any(|bin| viridis(bin as f64 / 4.0) == c)));
+    }
+}
@@ -0,0 +1,227 @@
+use crate::grid::ZoneGrid;
+use crate::matrix::TileMatrix;
+use crate::render::{render_png, rgb_bytes, Annotations, ChunkRaster};
+
+use base64::{engine::general_purpose, Engine as _};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+// ============================================================================
+// HTML run report
+// ============================================================================
+//
+// One HTML file summing up a generation run for people who won't read the
+// log: the per-map table, each map's coverage matrix and thumbnail, the
+// chunks and tiles per zone, parse errors, failed quality gates and the
+// changelog against the previous export. Everything is inline (styles, and
+// thumbnails as PNG data URIs at one pixel per chunk), so the file can be
+// mailed or attached to a ticket as-is.
+
+/// Pixels a thumbnail is drawn at across, whatever the map's size
+const THUMBNAIL_WIDTH: u32 = 512;
+/// Errors listed per map before the rest are summarised
+const MAX_LISTED_ERRORS: usize = 50;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin:0.5em 0}\
+th,td{border:1px solid #ccc;padding:2px 8px;text-align:right}\
+th:first-child,td:first-child{text-align:left}\
+pre{background:#f4f4f4;padding:0.5em;overflow:auto}\
+img{image-rendering:pixelated;border:1px solid #ccc}\
+.map{display:flex;gap:2em;align-items:flex-start;flex-wrap:wrap}\
+.bad{color:#b00}";
+
+/// One map's line of the summary and its matrix and errors
+#[derive(Debug, Clone)]
+pub struct MapSection {
+    /// Grid name, matching `ZoneGrid::name`
+    pub name: String,
+    pub tiles: usize,
+    pub areas: usize,
+    pub failures: usize,
+    pub sentinels: usize,
+    pub matrix: TileMatrix,
+    /// Parse errors, one line each
+    pub errors: Vec<String>,
+}
+
+/// Chunks and tiles one area covers on a grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZoneStats {
+    pub area_id: u32,
+    pub chunks: usize,
+    pub tiles: usize,
+}
+
+/// Everything the report shows besides the grids themselves
+#[derive(Debug, Clone, Default)]
+pub struct RunReport {
+    pub title: String,
+    pub maps: Vec<MapSection>,
+    /// Maps that weren't generated, with why
+    pub skipped: Vec<(String, String)>,
+    pub gate_failures: Vec<String>,
+    /// Markdown changelog against the previous export, when there was one
+    pub changelog: Option<String>,
+}
+
+/// Areas on a grid, most chunks first
+pub fn zone_stats(grid: &ZoneGrid) -> Vec<ZoneStats> {
+    let mut by_area: BTreeMap<u32, (usize, usize)> = BTreeMap::new();
+    for ids in grid.tiles().values() {
+        let mut counts: BTreeMap<u32, usize> = BTreeMap::new();
+        for &id in ids {
+            *counts.entry(id).or_default() += 1;
+        }
+        for (id, n) in counts {
+            let entry = by_area.entry(id).or_default();
+            entry.0 += n;
+            entry.1 += 1;
+        }
+    }
+    let mut stats: Vec<ZoneStats> =
+        by_area.into_iter().map(|(area_id, (chunks, tiles))| ZoneStats { area_id, chunks, tiles }).collect();
+    stats.sort_by_key(|s| (std::cmp::Reverse(s.chunks), s.area_id));
+    stats
+}
+
+/// A PNG of the grid cropped to its tiles, one pixel per chunk in the area's
+/// colour (grey for areas without one). `None` for an empty grid.
+pub fn thumbnail_png(grid: &ZoneGrid, colors: &HashMap<u32, (f32, f32, f32)>) -> Option<Result<Vec<u8>, String>> {
+    let raster = ChunkRaster::from_tiles(grid.tiles())?;
+    Some(render_png(&raster, |id| rgb_bytes(colors.get(&id).copied().unwrap_or((0.5, 0.5, 0.5))), 1, &[], Annotations::default()))
+}
+
+pub(crate) fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+impl RunReport {
+    /// Plain text version for chat and mail: a line per map, skipped maps,
+    /// failed gates and the changelog
+    pub fn render_summary(&self) -> String {
+        let mut out = String::new();
+        for map in &self.maps {
+            out.push_str(&format!(
+                "{}: {} tiles, {} areas, {} failed, {} sentinels\n",
+                map.name, map.tiles, map.areas, map.failures, map.sentinels
+            ));
+        }
+        for (name, why) in &self.skipped {
+            out.push_str(&format!("Skipped {}: {}\n", name, why));
+        }
+        for msg in &self.gate_failures {
+            out.push_str(&format!("Quality gate failed: {}\n", msg));
+        }
+        if let Some(changelog) = &self.changelog {
+            out.push('\n');
+            out.push_str(changelog);
+        }
+        out
+    }
+
+    /// The report as one HTML document. `grids` supply the thumbnails and
+    /// zone tables of the sections with the same name; `area_name` labels
+    /// area IDs.
+    pub fn render_html(&self, grids: &[ZoneGrid], colors: &HashMap<u32, (f32, f32, f32)>, area_name: impl Fn(u32) -> String) -> String {
+        let mut out = String::new();
+        out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        out.push_str(&format!("<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n", escape(&self.title), STYLE));
+        out.push_str(&format!("<h1>{}</h1>\n", escape(&self.title)));
+
+        if !self.gate_failures.is_empty() {
+            out.push_str("<h2 class=\"bad\">Quality gates failed</h2>\n<ul>\n");
+            for msg in &self.gate_failures {
+                out.push_str(&format!("<li>{}</li>\n", escape(msg)));
+            }
+            out.push_str("</ul>\n");
+        }
+
+        out.push_str("<h2>Maps</h2>\n<table>\n<tr><th>Map</th><th>Tiles</th><th>Areas</th><th>Failed</th><th>Sentinels</th></tr>\n");
+        for map in &self.maps {
+            out.push_str(&format!(
+                "<tr><td><a href=\"#{0}\">{0}</a></td><td>{1}</td><td>{2}</td><td{3}>{4}</td><td>{5}</td></tr>\n",
+                escape(&map.name),
+                map.tiles,
+                map.areas,
+                if map.failures > 0 { " class=\"bad\"" } else { "" },
+                map.failures,
+                map.sentinels
+            ));
+        }
+        for (name, why) in &self.skipped {
+            out.push_str(&format!("<tr><td>{}</td><td class=\"bad\" colspan=\"4\">skipped: {}</td></tr>\n", escape(name), escape(why)));
+        }
+        out.push_str("</table>\n");
+
+        for map in &self.maps {
+            let grid = grids.iter().find(|g| g.name == map.name);
+            out.push_str(&format!("<h2 id=\"{0}\">{0}</h2>\n<div class=\"map\">\n", escape(&map.name)));
+            match grid.and_then(|g| thumbnail_png(g, colors)) {
+                Some(Ok(png)) => {
+                    out.push_str(&format!(
+                        "<img width=\"{}\" alt=\"{} thumbnail\" src=\"data:image/png;base64,{}\">\n",
+                        THUMBNAIL_WIDTH,
+                        escape(&map.name),
+                        general_purpose::STANDARD.encode(png)
+                    ));
+                }
+                Some(Err(e)) => {
+                    out.push_str(&format!("<p class=\"bad\">No thumbnail: {}</p>\n", escape(&e)));
+                }
+                None => {}
+            }
+            out.push_str("<div>\n<h3>Tile coverage</h3>\n<p># present, ! failed, ? missing, . not expected</p>\n");
+            out.push_str(&format!("<pre>{}</pre>\n</div>\n</div>\n", escape(&map.matrix.render_text())));
+
+            if let Some(grid) = grid {
+                let total: usize = grid.tiles().values().map(|ids| ids.len()).sum();
+                out.push_str("<h3>Zones</h3>\n<table>\n<tr><th>Area</th><th>ID</th><th>Chunks</th><th>Tiles</th><th>Share</th></tr>\n");
+                for zone in zone_stats(grid) {
+                    out.push_str(&format!(
+                        "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:.1}%</td></tr>\n",
+                        escape(&area_name(zone.area_id)),
+                        zone.area_id,
+                        zone.chunks,
+                        zone.tiles,
+                        100.0 * zone.chunks as f64 / total.max(1) as f64
+                    ));
+                }
+                out.push_str("</table>\n");
+            }
+
+            if !map.errors.is_empty() {
+                out.push_str(&format!("<h3 class=\"bad\">Errors ({})</h3>\n<ul>\n", map.errors.len()));
+                for error in map.errors.iter().take(MAX_LISTED_ERRORS) {
+                    out.push_str(&format!("<li>{}</li>\n", escape(error)));
+                }
+                if map.errors.len() > MAX_LISTED_ERRORS {
+                    out.push_str(&format!("<li>... and {} more</li>\n", map.errors.len() - MAX_LISTED_ERRORS));
+                }
+                out.push_str("</ul>\n");
+            }
+        }
+
+        out.push_str("<h2>Changes since the previous export</h2>\n");
+        match &self.changelog {
+            Some(changelog) => {
+                out.push_str(&format!("<pre>{}</pre>\n", escape(changelog)));
+            }
+            None => {
+                out.push_str("<p>No previous export to compare with.</p>\n");
+            }
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+
+    pub fn write_html(
+        &self,
+        out_path: &Path,
+        grids: &[ZoneGrid],
+        colors: &HashMap<u32, (f32, f32, f32)>,
+        area_name: impl Fn(u32) -> String,
+    ) -> std::io::Result<()> {
+        std::fs::write(out_path, self.render_html(grids, colors, area_name))
+    }
+}
@@ -0,0 +1,103 @@
+use crate::adt::{TileData, CHUNKS_PER_SIDE};
+use crate::error;
+use crate::layers::LayerExtractor;
+
+use rhai::{Array, Dynamic, Engine, Scope, AST, INT};
+
+use std::fs;
+use std::path::Path;
+
+// ============================================================================
+// Scripted layers
+// ============================================================================
+
+/// Upper bound on script operations per chunk
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// Layer extractor that runs a Rhai script once per chunk.
+///
+/// The script sees `area_id`, `flags`, `holes`, `liquid_type`, `x`, `y` and
+/// `neighbors` (area IDs to the left, right, up and down within the tile,
+/// 0 at the tile edge) and evaluates to an integer, e.g.
+/// `if area_id in [12, 40, 44] { 1 } else { 0 }`.
+pub struct ScriptExtractor {
+    name: String,
+    engine: Engine,
+    ast: AST,
+}
+
+impl ScriptExtractor {
+    /// Load a script from a `.rhai` file, named after its file stem
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .ok_or("script path has no file name")?
+            .to_string();
+        Self::from_source(&name, &fs::read_to_string(path)?)
+    }
+
+    pub fn from_source(name: &str, source: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let ast = engine.compile(source)?;
+
+        Ok(Self { name: name.to_string(), engine, ast })
+    }
+
+    fn eval_chunk(&self, tile: &TileData, x: usize, y: usize) -> Result<u32, Box<dyn std::error::Error>> {
+        let chunk = tile.chunk(x, y);
+        let area_at = |nx: Option<usize>, ny: Option<usize>| -> Dynamic {
+            let id = match (nx, ny) {
+                (Some(nx), Some(ny)) if nx < CHUNKS_PER_SIDE && ny < CHUNKS_PER_SIDE => {
+                    tile.chunk(nx, ny).area_id
+                }
+                _ => 0,
+            };
+            Dynamic::from(id as INT)
+        };
+        let neighbors: Array = vec![
+            area_at(x.checked_sub(1), Some(y)),
+            area_at(Some(x + 1), Some(y)),
+            area_at(Some(x), y.checked_sub(1)),
+            area_at(Some(x), Some(y + 1)),
+        ];
+
+        let mut scope = Scope::new();
+        scope.push_constant("area_id", chunk.area_id as INT);
+        scope.push_constant("flags", chunk.flags as INT);
+        scope.push_constant("holes", chunk.holes as INT);
+        scope.push_constant("liquid_type", chunk.liquid.map(|l| l.liquid_type as INT).unwrap_or(0));
+        scope.push_constant("x", x as INT);
+        scope.push_constant("y", y as INT);
+        scope.push_constant("neighbors", neighbors);
+
+        let value: INT = self.engine.eval_ast_with_scope(&mut scope, &self.ast)?;
+        Ok(u32::try_from(value).map_err(|_| format!("script returned {} (not a u32)", value))?)
+    }
+}
+
+impl LayerExtractor for ScriptExtractor {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn extract(&self, tile: &TileData) -> Vec<u32> {
+        let mut failed = false;
+
+        (0..CHUNKS_PER_SIDE * CHUNKS_PER_SIDE)
+            .map(|i| {
+                match self.eval_chunk(tile, i % CHUNKS_PER_SIDE, i / CHUNKS_PER_SIDE) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        if !failed {
+                            error!("  ERROR in script {}: {}", self.name, e);
+                            failed = true;
+                        }
+                        0
+                    }
+                }
+            })
+            .collect()
+    }
+}
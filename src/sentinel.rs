@@ -0,0 +1,59 @@
+use std::collections::BTreeMap;
+
+// ============================================================================
+// Area ID sentinels
+// ============================================================================
+//
+// Some extractors and editors leave placeholder area IDs such as 0xFFFFFFFF
+// in chunks they never assigned. Exporting those would hand the addon IDs
+// that don't exist in AreaTable, so they are swapped for a replacement ID
+// (0, "no area", by default) and counted instead.
+
+/// Area IDs treated as "unset" unless configured otherwise
+pub const DEFAULT_SENTINELS: &[u32] = &[0xFFFF_FFFF];
+
+/// Sentinel value -> number of chunks it was found in
+pub type SentinelHits = BTreeMap<u32, usize>;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SentinelGuard {
+    sentinels: Vec<u32>,
+    replacement: u32,
+}
+
+impl Default for SentinelGuard {
+    fn default() -> Self {
+        Self::new(DEFAULT_SENTINELS, 0)
+    }
+}
+
+impl SentinelGuard {
+    pub fn new(sentinels: &[u32], replacement: u32) -> Self {
+        Self { sentinels: sentinels.to_vec(), replacement }
+    }
+
+    pub fn is_sentinel(&self, area_id: u32) -> bool {
+        self.sentinels.contains(&area_id)
+    }
+
+    /// Replace sentinels in `area_ids` in place, counting each one in `hits`.
+    /// Returns how many chunks were replaced.
+    pub fn scrub(&self, area_ids: &mut [u32], hits: &mut SentinelHits) -> usize {
+        let mut replaced = 0;
+        for aid in area_ids.iter_mut().filter(|aid| self.sentinels.contains(aid)) {
+            *hits.entry(*aid).or_default() += 1;
+            *aid = self.replacement;
+            replaced += 1;
+        }
+        replaced
+    }
+}
+
+/// `0xFFFFFFFF x12, 0xFFFE x3` style summary of sentinel hits
+pub fn format_hits(hits: &SentinelHits) -> String {
+    hits.iter()
+        .map(|(value, count)| format!("0x{:X} x{}", value, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,233 @@
+use crate::hash::sha256_hex;
+use crate::store::collect_files;
+
+use base64::{engine::general_purpose, Engine as _};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use std::fs;
+use std::path::{Path, PathBuf};
+use zonemap_format::Container;
+
+// ============================================================================
+// Signed exports
+// ============================================================================
+//
+// A bundle (the Data/ directory) is signed by writing `ZoneMap.sig` next to
+// the files it covers:
+//
+//     zonemap-signature 1
+//     key <base64 public key>
+//     file <sha256 hex> <relative path>
+//     ...
+//     sig <base64 ed25519 signature of every line above>
+//
+// Single files (binary containers) get a detached `<file>.sig` with the same
+// key/sig lines and no file list; the signature covers the file's bytes.
+
+pub const SIGNATURE_FILE: &str = "ZoneMap.sig";
+
+const HEADER: &str = "zonemap-signature 1";
+
+/// Public key baked in at build time (`ZONEMAP_TRUSTED_KEY`, base64), checked
+/// by readers so a bundle re-signed with someone else's key is rejected
+pub fn embedded_key() -> Option<VerifyingKey> {
+    parse_public_key(option_env!("ZONEMAP_TRUSTED_KEY")?).ok()
+}
+
+pub fn parse_public_key(b64: &str) -> Result<VerifyingKey, String> {
+    let bytes = general_purpose::STANDARD.decode(b64.trim()).map_err(|e| format!("bad public key: {}", e))?;
+    let bytes: [u8; 32] = bytes.try_into().map_err(|_| "public key must be 32 bytes".to_string())?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| format!("bad public key: {}", e))
+}
+
+pub fn public_key_b64(key: &VerifyingKey) -> String {
+    general_purpose::STANDARD.encode(key.as_bytes())
+}
+
+/// Read a signing key file: the base64 32-byte seed written by `generate_key`
+pub fn load_signing_key(path: &Path) -> Result<SigningKey, Box<dyn std::error::Error>> {
+    let seed = general_purpose::STANDARD.decode(fs::read_to_string(path)?.trim())?;
+    let seed: [u8; 32] = seed.try_into().map_err(|_| format!("{}: key must be 32 bytes", path.display()))?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+/// Write a new random signing key to `path`; refuses to overwrite one
+pub fn generate_key(path: &Path) -> Result<VerifyingKey, Box<dyn std::error::Error>> {
+    if path.exists() {
+        return Err(format!("{} already exists", path.display()).into());
+    }
+    let mut seed = [0u8; 32];
+    getrandom::getrandom(&mut seed).map_err(|e| format!("no randomness: {}", e))?;
+    fs::write(path, general_purpose::STANDARD.encode(seed) + "\n")?;
+    Ok(SigningKey::from_bytes(&seed).verifying_key())
+}
+
+// ============================================================================
+// Bundles
+// ============================================================================
+
+/// Sign every file under `dir`, writing `dir/ZoneMap.sig`. Returns the number of files covered.
+pub fn sign_dir(dir: &Path, key: &SigningKey) -> Result<usize, Box<dyn std::error::Error>> {
+    let files: Vec<PathBuf> = collect_files(dir)?
+        .into_iter()
+        .filter(|rel| rel != Path::new(SIGNATURE_FILE))
+        .collect();
+
+    let mut body = format!("{}\nkey {}\n", HEADER, public_key_b64(&key.verifying_key()));
+    for rel in &files {
+        let rel_str = rel.to_string_lossy().replace('\\', "/");
+        body.push_str(&format!("file {} {}\n", sha256_hex(&fs::read(dir.join(rel))?), rel_str));
+    }
+    let signature = key.sign(body.as_bytes());
+    body.push_str(&format!("sig {}\n", general_purpose::STANDARD.encode(signature.to_bytes())));
+
+    fs::write(dir.join(SIGNATURE_FILE), body)?;
+    Ok(files.len())
+}
+
+/// Parsed signature file: signed text, key, listed files and signature
+struct SignatureFile {
+    signed: String,
+    key: VerifyingKey,
+    files: Vec<(String, String)>,
+    signature: Signature,
+}
+
+fn parse_signature(text: &str) -> Result<SignatureFile, String> {
+    let sig_at = text.rfind("\nsig ").ok_or("no sig line")? + 1;
+    let (signed, sig_line) = text.split_at(sig_at);
+
+    let mut lines = signed.lines();
+    if lines.next() != Some(HEADER) {
+        return Err(format!("not a signature file (expected `{}`)", HEADER));
+    }
+    let key = parse_public_key(lines.next().and_then(|l| l.strip_prefix("key ")).ok_or("no key line")?)?;
+
+    let mut files = Vec::new();
+    for line in lines {
+        let (hash, path) = line
+            .strip_prefix("file ")
+            .and_then(|rest| rest.split_once(' '))
+            .ok_or_else(|| format!("bad line `{}`", line))?;
+        files.push((hash.to_string(), path.to_string()));
+    }
+
+    let sig_bytes = general_purpose::STANDARD
+        .decode(sig_line.trim_start_matches("sig ").trim())
+        .map_err(|e| format!("bad signature: {}", e))?;
+    let signature = Signature::from_slice(&sig_bytes).map_err(|e| format!("bad signature: {}", e))?;
+
+    Ok(SignatureFile { signed: signed.to_string(), key, files, signature })
+}
+
+/// Reject a signature made with any key but `trusted`, when one is given
+fn check_key(sig: &SignatureFile, trusted: Option<&VerifyingKey>) -> Result<(), String> {
+    match trusted {
+        Some(trusted) if trusted != &sig.key => Err(format!(
+            "signed with {}, expected {}",
+            public_key_b64(&sig.key),
+            public_key_b64(trusted)
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// What `verify_dir` found
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct VerifyReport {
+    pub signer: String,
+    pub verified: usize,
+    /// Listed files whose contents changed
+    pub modified: Vec<String>,
+    /// Listed files that are gone
+    pub missing: Vec<String>,
+    /// Files present but not covered by the signature
+    pub unsigned: Vec<String>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.modified.is_empty() && self.missing.is_empty() && self.unsigned.is_empty()
+    }
+}
+
+/// Check `dir/ZoneMap.sig`: the signature itself (and the signer against
+/// `trusted`, or the embedded key), then every file against its listed hash.
+/// A bad signature is an `Err`; file mismatches are listed in the report.
+pub fn verify_dir(dir: &Path, trusted: Option<&VerifyingKey>) -> Result<VerifyReport, Box<dyn std::error::Error>> {
+    let sig = parse_signature(&fs::read_to_string(dir.join(SIGNATURE_FILE))?)?;
+    let embedded = embedded_key();
+    check_key(&sig, trusted.or(embedded.as_ref()))?;
+    sig.key
+        .verify(sig.signed.as_bytes(), &sig.signature)
+        .map_err(|_| "signature does not match the file list")?;
+
+    let mut report = VerifyReport { signer: public_key_b64(&sig.key), ..Default::default() };
+    for (hash, rel) in &sig.files {
+        match fs::read(dir.join(rel)) {
+            Ok(data) if &sha256_hex(&data) == hash => report.verified += 1,
+            Ok(_) => report.modified.push(rel.clone()),
+            Err(_) => report.missing.push(rel.clone()),
+        }
+    }
+    for rel in collect_files(dir)? {
+        let rel = rel.to_string_lossy().replace('\\', "/");
+        if rel != SIGNATURE_FILE && !sig.files.iter().any(|(_, p)| p == &rel) {
+            report.unsigned.push(rel);
+        }
+    }
+    Ok(report)
+}
+
+// ============================================================================
+// Single files
+// ============================================================================
+
+fn detached_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".sig");
+    PathBuf::from(name)
+}
+
+/// Write `<path>.sig` for one file
+pub fn sign_file(path: &Path, key: &SigningKey) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let signature = key.sign(&fs::read(path)?);
+    let sig_path = detached_path(path);
+    fs::write(
+        &sig_path,
+        format!(
+            "{}\nkey {}\nsig {}\n",
+            HEADER,
+            public_key_b64(&key.verifying_key()),
+            general_purpose::STANDARD.encode(signature.to_bytes())
+        ),
+    )?;
+    Ok(sig_path)
+}
+
+/// Check `data` against its detached signature text; returns the signer
+pub fn verify_bytes(data: &[u8], sig_text: &str, trusted: Option<&VerifyingKey>) -> Result<VerifyingKey, String> {
+    let sig = parse_signature(sig_text)?;
+    if !sig.files.is_empty() {
+        return Err("this is a bundle signature, not a file signature".to_string());
+    }
+    check_key(&sig, trusted)?;
+    sig.key
+        .verify(data, &sig.signature)
+        .map_err(|_| "signature does not match the file".to_string())?;
+    Ok(sig.key)
+}
+
+/// Read a binary container only if `<path>.sig` verifies. With no explicit
+/// `trusted` key the embedded one is used, if the build has one.
+pub fn read_verified_container(
+    path: &Path,
+    trusted: Option<&VerifyingKey>,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let data = fs::read(path)?;
+    let sig_text = fs::read_to_string(detached_path(path))?;
+    let embedded = embedded_key();
+    verify_bytes(&data, &sig_text, trusted.or(embedded.as_ref()))?;
+    Container::parse(&data)?;
+    Ok(data)
+}
@@ -0,0 +1,97 @@
+use crate::grid::{find_grid, ZoneGrid};
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+// ============================================================================
+// Movement path replay
+// ============================================================================
+
+/// Recorded player position
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Waypoint {
+    /// Seconds since the start of the recording
+    pub time: f64,
+    /// Grid name; when empty, the first grid with a tile there is used
+    pub continent: String,
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Area change between two consecutive waypoints
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transition {
+    pub time: f64,
+    pub x: f32,
+    pub y: f32,
+    pub from: Option<u32>,
+    pub to: Option<u32>,
+    /// Part of an A -> B -> A bounce within the flicker window
+    pub flicker: bool,
+}
+
+/// Load waypoints from a CSV with `time`, `x`, `y` and an optional `continent` column
+pub fn parse_path_csv(csv_path: &Path) -> Result<Vec<Waypoint>, Box<dyn std::error::Error>> {
+    let reader = BufReader::new(File::open(csv_path)?);
+    let mut lines = reader.lines();
+    let header = lines.next().ok_or("Empty CSV")??;
+
+    let columns: Vec<&str> = header.split(',').map(|c| c.trim()).collect();
+    let time_idx = columns.iter().position(|&c| c == "time").ok_or("No time column")?;
+    let x_idx = columns.iter().position(|&c| c == "x").ok_or("No x column")?;
+    let y_idx = columns.iter().position(|&c| c == "y").ok_or("No y column")?;
+    let continent_idx = columns.iter().position(|&c| c == "continent");
+
+    let mut waypoints = Vec::new();
+    for line in lines {
+        let line = line?;
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim().trim_matches('"')).collect();
+        if fields.len() <= time_idx.max(x_idx).max(y_idx) {
+            continue;
+        }
+
+        let (Ok(time), Ok(x), Ok(y)) = (fields[time_idx].parse(), fields[x_idx].parse(), fields[y_idx].parse()) else {
+            continue;
+        };
+        let continent = continent_idx
+            .and_then(|i| fields.get(i))
+            .map(|c| c.to_string())
+            .unwrap_or_default();
+
+        waypoints.push(Waypoint { time, continent, x, y });
+    }
+
+    Ok(waypoints)
+}
+
+/// Replay a path against the grids and list every area transition.
+/// A transition is flagged as flicker when the next one returns to the
+/// previous area within `flicker_window` seconds.
+pub fn simulate(path: &[Waypoint], grids: &[ZoneGrid], flicker_window: f64) -> Vec<Transition> {
+    let mut transitions: Vec<Transition> = Vec::new();
+    let mut current: Option<Option<u32>> = None;
+
+    for wp in path {
+        let area = find_grid(grids, &wp.continent, wp.x, wp.y).and_then(|g| g.area_at(wp.x, wp.y));
+        match current {
+            Some(prev) if prev != area => {
+                transitions.push(Transition { time: wp.time, x: wp.x, y: wp.y, from: prev, to: area, flicker: false });
+            }
+            _ => {}
+        }
+        current = Some(area);
+    }
+
+    for i in 1..transitions.len() {
+        let (prev, next) = (&transitions[i - 1], &transitions[i]);
+        if next.to == prev.from && next.time - prev.time <= flicker_window {
+            transitions[i - 1].flicker = true;
+            transitions[i].flicker = true;
+        }
+    }
+
+    transitions
+}
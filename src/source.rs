@@ -0,0 +1,207 @@
+use crate::adt::split_sibling;
+use crate::failure::is_split_adt;
+use crate::pipeline::TileFile;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// ============================================================================
+// Tile sources
+// ============================================================================
+//
+// Where root ADTs come from: a plain directory, a pre-CASC client's MPQ
+// archives (feature `mpq`) or a modern client's CASC storage (feature
+// `casc`). Each lists a map's tiles and reads them by the path it listed, so
+// the generator's export loop doesn't care which one it is scanning.
+
+/// A file's bytes, or why it couldn't be read
+pub type FileRead = Result<Vec<u8>, Box<dyn std::error::Error>>;
+
+/// Files the process keeps open besides the ones being read: standard
+/// streams, the log, the checkpoint journal and the file being written
+pub const RESERVED_FILES: usize = 8;
+
+/// What reading may hold at once (`--max-memory`, `--max-open-files`).
+/// Sources that read ahead shrink their window to fit, down to one file at
+/// a time; sources that keep archives open refuse a limit they can't meet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ReadLimits {
+    /// Bytes of file data read ahead, whether in flight or waiting to be parsed
+    pub max_memory: Option<u64>,
+    /// Files open at once, `RESERVED_FILES` of them the process's own
+    pub max_open_files: Option<usize>,
+}
+
+impl ReadLimits {
+    /// Files left for reading, at least one; `None` without a limit
+    pub fn read_files(&self) -> Option<usize> {
+        self.max_open_files.map(|n| n.saturating_sub(RESERVED_FILES).max(1))
+    }
+
+    /// `wanted` reads in flight, cut to the files left for reading
+    pub fn read_depth(&self, wanted: usize) -> usize {
+        self.read_files().map_or(wanted, |n| wanted.min(n)).max(1)
+    }
+}
+
+pub trait TileSource {
+    /// Short description for progress output, e.g. the directory and archive count
+    fn describe(&self) -> String;
+
+    /// Root ADTs of `map`, sorted by path
+    fn adt_files(&mut self, map: &str) -> Result<Vec<TileFile>, Box<dyn std::error::Error>>;
+
+    /// Bytes of a file listed by `adt_files`
+    fn read(&mut self, path: &Path) -> Result<Vec<u8>, Box<dyn std::error::Error>>;
+
+    /// Read `paths`, handing each to `each` with its index, in order. Stops at
+    /// the first error `each` returns. Sources that can overlap reads with
+    /// the caller's work on earlier files override this.
+    fn read_each(
+        &mut self,
+        paths: &[PathBuf],
+        each: &mut dyn FnMut(usize, FileRead) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for (index, path) in paths.iter().enumerate() {
+            let data = self.read(path);
+            each(index, data)?;
+        }
+        Ok(())
+    }
+
+    /// Split ADTs (`_obj0`, `_tex0`, ...) passed over by the last `adt_files`
+    fn split_files(&self) -> &[PathBuf] {
+        &[]
+    }
+
+    /// Path of `root`'s split sibling with `suffix` (e.g. `_obj0`), if the
+    /// source has one; it can be passed to `read`
+    fn split_sibling(&self, _root: &Path, _suffix: &str) -> Option<PathBuf> {
+        None
+    }
+
+    /// Bytes of `map`'s `<Map>.wdt`, if the source has one
+    fn wdt(&mut self, _map: &str) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+        Ok(None)
+    }
+}
+
+/// `<Map>_<x>_<y>.adt` files in one directory. Every root ADT in it is listed,
+/// whatever its map name, as the directory is taken to hold one map.
+pub struct DirSource {
+    dir: PathBuf,
+    split: Vec<PathBuf>,
+    limits: ReadLimits,
+}
+
+impl DirSource {
+    pub fn open(dir: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if !dir.is_dir() {
+            return Err(format!("Directory not found: {}", dir.display()).into());
+        }
+        Ok(Self { dir: dir.to_path_buf(), split: Vec::new(), limits: ReadLimits::default() })
+    }
+
+    /// Read ahead no more than `limits` allow
+    pub fn with_limits(mut self, limits: ReadLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+impl TileSource for DirSource {
+    fn describe(&self) -> String {
+        self.dir.display().to_string()
+    }
+
+    fn adt_files(&mut self, _map: &str) -> Result<Vec<TileFile>, Box<dyn std::error::Error>> {
+        self.split.clear();
+        let mut files = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if !path.is_file() {
+                continue;
+            }
+            if is_split_adt(&path) {
+                self.split.push(path);
+            } else if let Some(file) = TileFile::from_path(&path) {
+                files.push(file);
+            }
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        self.split.sort();
+        Ok(files)
+    }
+
+    fn read(&mut self, path: &Path) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        Ok(fs::read(path)?)
+    }
+
+    /// With the `uring` feature on Linux, reads go through io_uring so they
+    /// overlap with parsing, as many files ahead as the limits allow; plain
+    /// reads are used if the kernel has no ring
+    #[cfg(all(feature = "uring", target_os = "linux"))]
+    fn read_each(
+        &mut self,
+        paths: &[PathBuf],
+        each: &mut dyn FnMut(usize, FileRead) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut delivered = 0;
+        let depth = self.limits.read_depth(crate::uring::DEFAULT_DEPTH);
+        let result = crate::uring::read_in_order(paths, depth, self.limits.max_memory, &mut |index, data| {
+            delivered = index + 1;
+            each(index, data.map_err(|e| e.into()))
+        });
+        match result {
+            Err(e) if delivered == 0 => {
+                crate::warn!("  io_uring unavailable ({}); reading files one by one", e);
+                for (index, path) in paths.iter().enumerate() {
+                    each(index, fs::read(path).map_err(|e| e.into()))?;
+                }
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    fn split_files(&self) -> &[PathBuf] {
+        &self.split
+    }
+
+    fn split_sibling(&self, root: &Path, suffix: &str) -> Option<PathBuf> {
+        let sibling = split_sibling(root, suffix);
+        let name = sibling.file_name()?.to_string_lossy();
+        self.split.iter().find(|p| p.file_name().is_some_and(|n| n.to_string_lossy().eq_ignore_ascii_case(&name))).cloned()
+    }
+
+    fn wdt(&mut self, map: &str) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+        let name = format!("{}.wdt", map);
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.eq_ignore_ascii_case(&name)) {
+                return Ok(Some(fs::read(path)?));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_file_limit_leaves_room_for_the_process() {
+        let unlimited = ReadLimits::default();
+        assert_eq!(unlimited.read_files(), None);
+        assert_eq!(unlimited.read_depth(64), 64);
+
+        let limited = ReadLimits { max_open_files: Some(RESERVED_FILES + 16), ..Default::default() };
+        assert_eq!(limited.read_files(), Some(16));
+        assert_eq!(limited.read_depth(64), 16);
+        assert_eq!(limited.read_depth(4), 4);
+        // Too few to spare any still reads one file at a time
+        let tight = ReadLimits { max_open_files: Some(2), ..Default::default() };
+        assert_eq!(tight.read_depth(64), 1);
+    }
+}
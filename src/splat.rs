@@ -0,0 +1,342 @@
+use crate::adt::{CHUNKS_PER_SIDE, CHUNKS_PER_TILE};
+
+use wow_adt::Adt;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+// ============================================================================
+// Texture splats
+// ============================================================================
+
+/// Sub-cells along one side of a chunk for the refined layer
+pub const SUBCELLS_PER_SIDE: usize = 8;
+
+/// Alpha map pixels along one side of a chunk
+const ALPHA_SIDE: usize = 64;
+
+/// MCLY flag: alpha map is RLE compressed (WotLK+)
+const MCLY_ALPHA_COMPRESSED: u32 = 0x200;
+
+/// Texture blend of one chunk, summarized per 8x8 sub-cell
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChunkSplat {
+    /// Per sub-cell (row-major), texture ID -> share of the cell (sums to 1)
+    pub subcells: Vec<HashMap<u32, f32>>,
+}
+
+/// Read the texture layers and alpha maps of every chunk in a root ADT.
+/// Alpha maps are decoded from the raw MCAL data since their layout
+/// (4-bit, 8-bit or compressed) depends on the client version.
+pub fn parse_splats(path: &Path) -> Result<Vec<ChunkSplat>, Box<dyn std::error::Error>> {
+    parse_splats_bytes(&fs::read(path)?)
+}
+
+/// `parse_splats` for a file already in memory
+pub fn parse_splats_bytes(data: &[u8]) -> Result<Vec<ChunkSplat>, Box<dyn std::error::Error>> {
+    let adt = Adt::from_reader(Cursor::new(data))?;
+    let mcin = adt.mcin.as_ref().ok_or("no MCIN chunk")?;
+
+    let mut splats = Vec::with_capacity(CHUNKS_PER_TILE);
+    for (mcnk, entry) in adt.mcnk_chunks.iter().zip(&mcin.entries) {
+        let mcal = mcal_data(data, entry.offset as usize, mcnk.mcal_offset as usize).unwrap_or(&[]);
+
+        // Layer 0 has no alpha map; it covers whatever the others leave
+        let mut weights: Vec<(u32, Vec<u8>)> = Vec::new();
+        let layers = &mcnk.texture_layers;
+        for (i, layer) in layers.iter().enumerate().skip(1) {
+            let start = layer.alpha_map_offset as usize;
+            let end = layers
+                .get(i + 1)
+                .map(|next| next.alpha_map_offset as usize)
+                .unwrap_or(mcal.len())
+                .min(mcal.len());
+            if start >= end {
+                continue;
+            }
+            let raw = &mcal[start..end];
+            let alpha = if layer.flags & MCLY_ALPHA_COMPRESSED != 0 {
+                decode_alpha_rle(raw)
+            } else if raw.len() >= ALPHA_SIDE * ALPHA_SIDE {
+                raw[..ALPHA_SIDE * ALPHA_SIDE].to_vec()
+            } else {
+                decode_alpha_4bit(raw)
+            };
+            weights.push((layer.texture_id, alpha));
+        }
+
+        let base_texture = layers.first().map(|l| l.texture_id);
+        splats.push(summarize(base_texture, &weights));
+    }
+
+    splats.resize(CHUNKS_PER_TILE, ChunkSplat::default());
+    Ok(splats)
+}
+
+/// MCAL payload of a chunk, skipping the subchunk header
+fn mcal_data(file: &[u8], mcnk_offset: usize, mcal_offset: usize) -> Option<&[u8]> {
+    if mcal_offset == 0 {
+        return None;
+    }
+    let header = mcnk_offset.checked_add(mcal_offset)?;
+    let magic = file.get(header..header + 4)?;
+    if magic != b"LACM" && magic != b"MCAL" {
+        return None;
+    }
+    let size = u32::from_le_bytes(file.get(header + 4..header + 8)?.try_into().ok()?) as usize;
+    file.get(header + 8..header + 8 + size)
+}
+
+/// Unpack 4-bit alpha (two pixels per byte, low nibble first) to 64x64 8-bit
+fn decode_alpha_4bit(raw: &[u8]) -> Vec<u8> {
+    let mut out: Vec<u8> = raw.iter().flat_map(|&b| [(b & 0x0F) * 17, (b >> 4) * 17]).collect();
+    out.resize(ALPHA_SIDE * ALPHA_SIDE, 0);
+    out
+}
+
+/// Expand RLE compressed alpha: high bit set = fill the next byte, else copy
+fn decode_alpha_rle(raw: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(ALPHA_SIDE * ALPHA_SIDE);
+    let mut i = 0;
+    while i < raw.len() && out.len() < ALPHA_SIDE * ALPHA_SIDE {
+        let count = (raw[i] & 0x7F) as usize;
+        let fill = raw[i] & 0x80 != 0;
+        i += 1;
+        if fill {
+            let Some(&v) = raw.get(i) else { break };
+            out.extend(std::iter::repeat_n(v, count));
+            i += 1;
+        } else {
+            let end = (i + count).min(raw.len());
+            out.extend_from_slice(&raw[i..end]);
+            i = end;
+        }
+    }
+    out.resize(ALPHA_SIDE * ALPHA_SIDE, 0);
+    out
+}
+
+/// Average layer alphas over each sub-cell
+fn summarize(base_texture: Option<u32>, weights: &[(u32, Vec<u8>)]) -> ChunkSplat {
+    let cell = ALPHA_SIDE / SUBCELLS_PER_SIDE;
+    let mut subcells = Vec::with_capacity(SUBCELLS_PER_SIDE * SUBCELLS_PER_SIDE);
+
+    for sy in 0..SUBCELLS_PER_SIDE {
+        for sx in 0..SUBCELLS_PER_SIDE {
+            let mut shares: HashMap<u32, f32> = HashMap::new();
+            for py in sy * cell..(sy + 1) * cell {
+                for px in sx * cell..(sx + 1) * cell {
+                    let mut remaining = 255.0_f32;
+                    for (texture, alpha) in weights {
+                        let a = alpha[py * ALPHA_SIDE + px] as f32 * remaining / 255.0;
+                        *shares.entry(*texture).or_default() += a;
+                        remaining -= a;
+                    }
+                    if let Some(base) = base_texture {
+                        *shares.entry(base).or_default() += remaining;
+                    }
+                }
+            }
+            let total: f32 = shares.values().sum();
+            if total > 0.0 {
+                shares.values_mut().for_each(|v| *v /= total);
+            }
+            subcells.push(shares);
+        }
+    }
+
+    ChunkSplat { subcells }
+}
+
+// ============================================================================
+// Sub-chunk refinement (heuristic)
+// ============================================================================
+
+/// Estimate 8x8 sub-cell area IDs for chunks on an area border.
+///
+/// Each area in the tile gets a texture signature averaged over its interior
+/// chunks. Every sub-cell of a border chunk is assigned to whichever of the
+/// chunk's own or neighboring areas has the closest signature. This is a
+/// heuristic: it only helps where zone borders follow texture changes.
+/// Returns chunk index -> 64 area IDs, for border chunks only.
+pub fn refine_tile(area_ids: &[u32], splats: &[ChunkSplat]) -> BTreeMap<usize, Vec<u32>> {
+    let side = CHUNKS_PER_SIDE;
+    let neighbors_of = |idx: usize| -> HashSet<u32> {
+        let (x, y) = (idx % side, idx / side);
+        let mut n = HashSet::new();
+        if x > 0 {
+            n.insert(area_ids[idx - 1]);
+        }
+        if x + 1 < side {
+            n.insert(area_ids[idx + 1]);
+        }
+        if y > 0 {
+            n.insert(area_ids[idx - side]);
+        }
+        if y + 1 < side {
+            n.insert(area_ids[idx + side]);
+        }
+        n.remove(&area_ids[idx]);
+        n.remove(&0);
+        n
+    };
+
+    let border: Vec<usize> = (0..area_ids.len().min(splats.len()))
+        .filter(|&i| area_ids[i] != 0 && !neighbors_of(i).is_empty())
+        .collect();
+    if border.is_empty() {
+        return BTreeMap::new();
+    }
+
+    // Area signatures, preferring interior chunks
+    let mut interior: HashMap<u32, Vec<usize>> = HashMap::new();
+    let mut any: HashMap<u32, Vec<usize>> = HashMap::new();
+    for (i, &area) in area_ids.iter().enumerate().take(splats.len()) {
+        any.entry(area).or_default().push(i);
+        if !border.contains(&i) {
+            interior.entry(area).or_default().push(i);
+        }
+    }
+    let signature = |area: u32| -> Option<HashMap<u32, f32>> {
+        let chunks = interior.get(&area).or_else(|| any.get(&area))?;
+        let mut sig: HashMap<u32, f32> = HashMap::new();
+        let mut n = 0.0;
+        for &c in chunks {
+            for cell in &splats[c].subcells {
+                for (&t, &w) in cell {
+                    *sig.entry(t).or_default() += w;
+                }
+                n += 1.0;
+            }
+        }
+        if n == 0.0 {
+            return None;
+        }
+        sig.values_mut().for_each(|v| *v /= n);
+        Some(sig)
+    };
+
+    let mut refined = BTreeMap::new();
+    for &idx in &border {
+        let own = area_ids[idx];
+        let mut candidates: Vec<(u32, HashMap<u32, f32>)> = std::iter::once(own)
+            .chain(neighbors_of(idx))
+            .filter_map(|a| signature(a).map(|s| (a, s)))
+            .collect();
+        candidates.sort_by_key(|(a, _)| (*a != own, *a));
+
+        let cells: Vec<u32> = splats[idx]
+            .subcells
+            .iter()
+            .map(|cell| {
+                candidates
+                    .iter()
+                    .map(|(area, sig)| (*area, distance(cell, sig)))
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(area, _)| area)
+                    .unwrap_or(own)
+            })
+            .collect();
+        if cells.len() == SUBCELLS_PER_SIDE * SUBCELLS_PER_SIDE {
+            refined.insert(idx, cells);
+        }
+    }
+    refined
+}
+
+/// L1 distance between two texture share maps
+fn distance(a: &HashMap<u32, f32>, b: &HashMap<u32, f32>) -> f32 {
+    let keys: HashSet<&u32> = a.keys().chain(b.keys()).collect();
+    keys.into_iter()
+        .map(|k| (a.get(k).unwrap_or(&0.0) - b.get(k).unwrap_or(&0.0)).abs())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A chunk whose sub-cells are all `texture`
+    fn uniform(texture: u32) -> ChunkSplat {
+        split(texture, texture)
+    }
+
+    /// A chunk of `left` in its western sub-cells and `right` in the rest
+    fn split(left: u32, right: u32) -> ChunkSplat {
+        let subcells = (0..SUBCELLS_PER_SIDE * SUBCELLS_PER_SIDE)
+            .map(|i| HashMap::from([(if i % SUBCELLS_PER_SIDE < SUBCELLS_PER_SIDE / 2 { left } else { right }, 1.0)]))
+            .collect();
+        ChunkSplat { subcells }
+    }
+
+    #[test]
+    fn alpha_maps_decode_to_64x64() {
+        let alpha = decode_alpha_4bit(&[0x1F, 0xF0]);
+        assert_eq!(alpha.len(), ALPHA_SIDE * ALPHA_SIDE);
+        assert_eq!(&alpha[..5], &[255, 17, 0, 255, 0]);
+
+        // A fill of three, a copy of two, then a fill cut short
+        let alpha = decode_alpha_rle(&[0x83, 9, 0x02, 1, 2, 0x85]);
+        assert_eq!(alpha.len(), ALPHA_SIDE * ALPHA_SIDE);
+        assert_eq!(&alpha[..6], &[9, 9, 9, 1, 2, 0]);
+        // Runs past the end of the map stop there
+        assert_eq!(decode_alpha_rle(&[0xFF, 7].repeat(40)), vec![7; ALPHA_SIDE * ALPHA_SIDE]);
+        assert_eq!(decode_alpha_rle(&[0x7F, 1, 2]).len(), ALPHA_SIDE * ALPHA_SIDE);
+    }
+
+    #[test]
+    fn mcal_is_found_only_where_it_fits() {
+        let mut file = vec![0u8; 16];
+        file.extend_from_slice(b"LACM");
+        file.extend_from_slice(&3u32.to_le_bytes());
+        file.extend_from_slice(&[1, 2, 3]);
+        assert_eq!(mcal_data(&file, 8, 8), Some(&[1, 2, 3][..]));
+        assert_eq!(mcal_data(&file, 8, 0), None);
+        assert_eq!(mcal_data(&file, 8, 4), None);
+        assert_eq!(mcal_data(&file[..25], 8, 8), None);
+        assert_eq!(mcal_data(&file, usize::MAX, 8), None);
+    }
+
+    #[test]
+    fn layers_share_each_subcell() {
+        // Texture 2 painted fully over the left half of a texture 1 chunk
+        let alpha: Vec<u8> = (0..ALPHA_SIDE * ALPHA_SIDE).map(|i| if i % ALPHA_SIDE < 32 { 255 } else { 0 }).collect();
+        let splat = summarize(Some(1), &[(2, alpha)]);
+        assert_eq!(splat.subcells.len(), SUBCELLS_PER_SIDE * SUBCELLS_PER_SIDE);
+        assert_eq!(splat.subcells[0].get(&2), Some(&1.0));
+        assert_eq!(splat.subcells[7].get(&1), Some(&1.0));
+        assert!(splat.subcells.iter().all(|cell| (cell.values().sum::<f32>() - 1.0).abs() < 1e-6));
+    }
+
+    #[test]
+    fn border_chunks_split_along_texture_changes() {
+        // Area 10 on texture 1 to the west, area 20 on texture 2 to the east;
+        // the border column of area 10 is half each
+        let area_ids: Vec<u32> = (0..CHUNKS_PER_TILE).map(|i| if i % CHUNKS_PER_SIDE < 8 { 10 } else { 20 }).collect();
+        let mut splats: Vec<ChunkSplat> =
+            (0..CHUNKS_PER_TILE).map(|i| uniform(if i % CHUNKS_PER_SIDE < 8 { 1 } else { 2 })).collect();
+        for y in 0..CHUNKS_PER_SIDE {
+            splats[y * CHUNKS_PER_SIDE + 7] = split(1, 2);
+        }
+
+        let refined = refine_tile(&area_ids, &splats);
+        // Both sides of the border, every row
+        assert_eq!(refined.len(), 2 * CHUNKS_PER_SIDE);
+        let cells = &refined[&7];
+        assert!(cells.iter().enumerate().all(|(i, &area)| area == if i % SUBCELLS_PER_SIDE < 4 { 10 } else { 20 }));
+        assert!(refined[&8].iter().all(|&area| area == 20));
+
+        assert!(refine_tile(&[10; CHUNKS_PER_TILE], &splats).is_empty());
+        assert!(refine_tile(&area_ids, &[]).is_empty());
+    }
+
+    #[test]
+    fn malformed_adts_are_errors() {
+        assert!(parse_splats_bytes(&[]).is_err());
+        assert!(parse_splats_bytes(b"REVM\x04\x00\x00\x00\x12\x00\x00\x00").is_err());
+        assert!(parse_splats_bytes(&[0xAB; 1000]).is_err());
+    }
+}
@@ -0,0 +1,122 @@
+use crate::adt::CHUNKS_PER_SIDE;
+use crate::grid::{ZoneGrid, TILES_PER_SIDE};
+
+use rusqlite::{params, Connection};
+use std::path::Path;
+
+// ============================================================================
+// SQLite export
+// ============================================================================
+//
+// With `--sqlite`, the grids also go to a SQLite database, for servers and
+// websites that would rather query zone data than parse Lua:
+//
+//     continents (map_id, name, tiles)
+//     chunks     (map_id, tile_x, tile_y, chunk_x, chunk_y, area_id)
+//     areas      (area_id, name, parent_id)
+//     meta       (key, value)      data_version, build, written
+//
+// Chunks are one row each, indexed by area, so "which chunks are Elwynn
+// Forest" is a single query. A run replaces the continents it wrote and
+// leaves the others, so several jobs can fill one database; areas are
+// replaced by ID. Everything is written in one transaction, so readers see
+// the previous data until the new data is complete.
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS continents (
+        map_id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE,
+        tiles INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS chunks (
+        map_id INTEGER NOT NULL REFERENCES continents (map_id),
+        tile_x INTEGER NOT NULL,
+        tile_y INTEGER NOT NULL,
+        chunk_x INTEGER NOT NULL,
+        chunk_y INTEGER NOT NULL,
+        area_id INTEGER NOT NULL,
+        PRIMARY KEY (map_id, tile_x, tile_y, chunk_x, chunk_y)
+    ) WITHOUT ROWID;
+    CREATE INDEX IF NOT EXISTS chunks_by_area ON chunks (area_id);
+    CREATE TABLE IF NOT EXISTS areas (
+        area_id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        parent_id INTEGER NOT NULL
+    );
+";
+
+/// Area as written to the `areas` table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AreaRow {
+    pub area_id: u32,
+    pub name: String,
+    pub parent_id: u32,
+}
+
+pub struct ZoneDb {
+    conn: Connection,
+}
+
+impl ZoneDb {
+    /// Open the database at `path`, creating it and its tables if needed
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Replace the continents of `grids`, then write `areas` and `meta`.
+    /// Returns the chunks written.
+    pub fn write(&mut self, grids: &[ZoneGrid], areas: &[AreaRow], meta: &[(&str, String)]) -> rusqlite::Result<usize> {
+        let tx = self.conn.transaction()?;
+        let mut chunks = 0;
+        {
+            let mut insert_chunk = tx.prepare(
+                "INSERT INTO chunks (map_id, tile_x, tile_y, chunk_x, chunk_y, area_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for grid in grids {
+                // The name may have moved to another ID, or the ID to another name
+                let stale: Vec<i64> = tx
+                    .prepare("SELECT map_id FROM continents WHERE map_id = ?1 OR name = ?2")?
+                    .query_map(params![grid.map_id, grid.name], |row| row.get(0))?
+                    .collect::<rusqlite::Result<_>>()?;
+                for map_id in stale {
+                    tx.execute("DELETE FROM chunks WHERE map_id = ?1", [map_id])?;
+                    tx.execute("DELETE FROM continents WHERE map_id = ?1", [map_id])?;
+                }
+                tx.execute(
+                    "INSERT INTO continents (map_id, name, tiles) VALUES (?1, ?2, ?3)",
+                    params![grid.map_id, grid.name, grid.tiles().len()],
+                )?;
+                for (&key, area_ids) in grid.tiles() {
+                    let (tile_x, tile_y) = (key % TILES_PER_SIDE, key / TILES_PER_SIDE);
+                    for (chunk, &area_id) in area_ids.iter().enumerate() {
+                        insert_chunk.execute(params![
+                            grid.map_id,
+                            tile_x,
+                            tile_y,
+                            chunk % CHUNKS_PER_SIDE,
+                            chunk / CHUNKS_PER_SIDE,
+                            area_id
+                        ])?;
+                        chunks += 1;
+                    }
+                }
+            }
+            let mut insert_area = tx.prepare("INSERT OR REPLACE INTO areas (area_id, name, parent_id) VALUES (?1, ?2, ?3)")?;
+            for area in areas {
+                insert_area.execute(params![area.area_id, area.name, area.parent_id])?;
+            }
+            let mut insert_meta = tx.prepare("INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)")?;
+            for (key, value) in meta {
+                insert_meta.execute(params![key, value])?;
+            }
+        }
+        tx.commit()?;
+        Ok(chunks)
+    }
+}
@@ -0,0 +1,147 @@
+use crate::hash::{hash_files, HashAlgorithm};
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// ============================================================================
+// Content-addressed output store
+// ============================================================================
+
+/// Hex digits of the content hash used to name a snapshot directory
+const HASH_LEN: usize = 16;
+
+const INDEX_FILE: &str = "index.csv";
+
+/// One stored export: `<root>/<build>/<hash>/`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StoreEntry {
+    pub build: String,
+    pub hash: String,
+    pub files: usize,
+}
+
+/// Keeps every export under `<root>/<build>/<hash>/...`.
+///
+/// Identical exports hash the same and are stored once. Each build gets a
+/// `latest` link to its newest snapshot, and `<root>/index.csv` lists every
+/// snapshot in the order it was stored. Snapshots are named by SHA-256
+/// unless `with_algorithm` picks another hash.
+#[derive(Debug, Clone)]
+pub struct Store {
+    root: PathBuf,
+    algorithm: HashAlgorithm,
+}
+
+impl Store {
+    pub fn new(root: &Path) -> Self {
+        Self { root: root.to_path_buf(), algorithm: HashAlgorithm::default() }
+    }
+
+    pub fn with_algorithm(mut self, algorithm: HashAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Snapshot every file under `src_dir` for `build`. Returns the snapshot directory.
+    pub fn put(&self, build: &str, src_dir: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let files = collect_files(src_dir)?;
+        if files.is_empty() {
+            return Err(format!("nothing to store in {}", src_dir.display()).into());
+        }
+
+        let hash = hash_files(self.algorithm, src_dir, &files)?[..HASH_LEN].to_string();
+
+        let build_dir = self.root.join(build);
+        let snapshot = build_dir.join(&hash);
+        if !snapshot.exists() {
+            for rel in &files {
+                let dest = snapshot.join(rel);
+                if let Some(parent) = dest.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::copy(src_dir.join(rel), dest)?;
+            }
+        }
+
+        let entry = StoreEntry { build: build.to_string(), hash: hash.clone(), files: files.len() };
+        if !self.index()?.contains(&entry) {
+            let mut f = fs::OpenOptions::new().create(true).append(true).open(self.root.join(INDEX_FILE))?;
+            writeln!(f, "{},{},{}", entry.build, entry.hash, entry.files)?;
+        }
+
+        set_latest(&build_dir, &hash)?;
+        Ok(snapshot)
+    }
+
+    /// Newest snapshot stored for `build`
+    pub fn latest(&self, build: &str) -> Option<PathBuf> {
+        let link = self.root.join(build).join("latest");
+        let hash = match fs::read_link(&link) {
+            Ok(target) => target.to_string_lossy().into_owned(),
+            Err(_) => fs::read_to_string(&link).ok()?.trim().to_string(),
+        };
+        let snapshot = self.root.join(build).join(hash);
+        snapshot.is_dir().then_some(snapshot)
+    }
+
+    /// Every snapshot, oldest first
+    pub fn index(&self) -> Result<Vec<StoreEntry>, Box<dyn std::error::Error>> {
+        let path = self.root.join(INDEX_FILE);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut entries = Vec::new();
+        for (line_no, line) in fs::read_to_string(&path)?.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let cols: Vec<&str> = line.split(',').collect();
+            if cols.len() != 3 {
+                return Err(format!("{} line {}: expected build,hash,files", INDEX_FILE, line_no + 1).into());
+            }
+            entries.push(StoreEntry {
+                build: cols[0].to_string(),
+                hash: cols[1].to_string(),
+                files: cols[2].parse()?,
+            });
+        }
+        Ok(entries)
+    }
+}
+
+/// Relative paths of all files under `dir`, sorted so the hash is stable
+pub(crate) fn collect_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(rel) = pending.pop() {
+        for entry in fs::read_dir(dir.join(&rel))? {
+            let entry = entry?;
+            let rel_path = rel.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                pending.push(rel_path);
+            } else {
+                files.push(rel_path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(unix)]
+fn set_latest(build_dir: &Path, hash: &str) -> std::io::Result<()> {
+    let link = build_dir.join("latest");
+    if link.symlink_metadata().is_ok() {
+        fs::remove_file(&link)?;
+    }
+    std::os::unix::fs::symlink(hash, link)
+}
+
+/// No portable symlinks, so `latest` holds the hash as text
+#[cfg(not(unix))]
+fn set_latest(build_dir: &Path, hash: &str) -> std::io::Result<()> {
+    fs::write(build_dir.join("latest"), hash)
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+// ============================================================================
+// Colour themes
+// ============================================================================
+
+/// Themes shipped with the crate, by name. Each is a CSV table in `themes/`.
+pub const BUILTIN_THEMES: &[(&str, &str)] = &[("classic", include_str!("../themes/classic.csv"))];
+
+pub type Rgb = (f32, f32, f32);
+
+/// Fixed colours for some areas, usually root zones.
+///
+/// Tables are `area_id,r,g,b[,name]` with components in 0..=1 and `#`
+/// comments.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Theme {
+    pub name: String,
+    colors: HashMap<u32, Rgb>,
+}
+
+impl Theme {
+    pub fn builtin(name: &str) -> Option<Self> {
+        let (name, table) = BUILTIN_THEMES.iter().find(|(n, _)| n.eq_ignore_ascii_case(name))?;
+        // Bundled tables are checked by hand; a bad one is a bug, not user error
+        Some(Self::parse(name, table).expect("bundled theme table is valid"))
+    }
+
+    /// Built-in theme for a client build's expansion (`1.x` is classic)
+    pub fn for_build(build: &str) -> Option<Self> {
+        match build.split('.').next()? {
+            "1" => Self::builtin("classic"),
+            _ => None,
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let name = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        Self::parse(&name, &fs::read_to_string(path)?)
+    }
+
+    pub fn parse(name: &str, text: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut colors = HashMap::new();
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+            if fields.len() < 4 {
+                return Err(format!("line {}: expected area_id,r,g,b", line_no + 1).into());
+            }
+            let area_id: u32 = fields[0].parse().map_err(|_| format!("line {}: bad area ID", line_no + 1))?;
+            let mut rgb = [0.0f32; 3];
+            for (c, field) in rgb.iter_mut().zip(&fields[1..4]) {
+                *c = field
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|v| (0.0..=1.0).contains(v))
+                    .ok_or_else(|| format!("line {}: colour components must be 0..1", line_no + 1))?;
+            }
+            colors.insert(area_id, (rgb[0], rgb[1], rgb[2]));
+        }
+        Ok(Self { name: name.to_string(), colors })
+    }
+
+    pub fn color(&self, area_id: u32) -> Option<Rgb> {
+        self.colors.get(&area_id).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+}
+
+/// Tint `color` towards a theme colour, keeping enough of it that
+/// neighbouring subzones stay distinguishable
+pub fn tint(color: Rgb, theme_color: Rgb) -> Rgb {
+    const THEME_WEIGHT: f32 = 0.65;
+    let mix = |a: f32, b: f32| b * THEME_WEIGHT + a * (1.0 - THEME_WEIGHT);
+    (mix(color.0, theme_color.0), mix(color.1, theme_color.1), mix(color.2, theme_color.2))
+}
@@ -0,0 +1,375 @@
+// ============================================================================
+// TOML
+// ============================================================================
+//
+// Enough TOML to read job files by hand, like JSON is written by hand: bare
+// or quoted keys, `[table]` and `[[array of tables]]` headers, basic and
+// literal strings, integers, floats, booleans and (possibly multi-line)
+// arrays, with `#` comments. Dotted keys, inline tables, multi-line strings
+// and dates aren't supported and are reported as errors rather than misread.
+
+/// A table's entries, in file order
+pub type Table = Vec<(String, Value)>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Array(Vec<Value>),
+    Table(Table),
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Integer(_) => "integer",
+            Value::Float(_) => "float",
+            Value::Boolean(_) => "boolean",
+            Value::Array(_) => "array",
+            Value::Table(_) => "table",
+        }
+    }
+}
+
+/// The value under `key` in `table`
+pub fn get<'a>(table: &'a Table, key: &str) -> Option<&'a Value> {
+    table.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+}
+
+fn get_mut<'a>(table: &'a mut Table, key: &str) -> Option<&'a mut Value> {
+    table.iter_mut().find(|(k, _)| k == key).map(|(_, v)| v)
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+}
+
+impl Parser<'_> {
+    fn err<T>(&self, msg: impl std::fmt::Display) -> Result<T, String> {
+        Err(format!("line {}: {}", self.line, msg))
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c == Some('\n') {
+            self.line += 1;
+        }
+        c
+    }
+
+    /// Skip spaces and tabs, and also newlines and comments when `newlines`
+    fn skip(&mut self, newlines: bool) {
+        while let Some(&c) = self.chars.peek() {
+            match c {
+                ' ' | '\t' | '\r' => {}
+                '\n' if newlines => {}
+                '#' => {
+                    while self.chars.peek().is_some_and(|&c| c != '\n') {
+                        self.next();
+                    }
+                    continue;
+                }
+                _ => return,
+            }
+            self.next();
+        }
+    }
+
+    fn expect(&mut self, want: char) -> Result<(), String> {
+        match self.next() {
+            Some(c) if c == want => Ok(()),
+            Some(c) => self.err(format!("expected `{}`, found `{}`", want, c)),
+            None => self.err(format!("expected `{}`, found the end of the file", want)),
+        }
+    }
+
+    /// Nothing but a comment before the next line
+    fn end_of_line(&mut self) -> Result<(), String> {
+        self.skip(false);
+        match self.next() {
+            None | Some('\n') => Ok(()),
+            Some(c) => self.err(format!("expected the end of the line, found `{}`", c)),
+        }
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        let key = match self.chars.peek() {
+            Some('"') | Some('\'') => self.string()?,
+            _ => {
+                let mut key = String::new();
+                while let Some(&c) = self.chars.peek().filter(|c| c.is_ascii_alphanumeric() || **c == '_' || **c == '-') {
+                    key.push(c);
+                    self.next();
+                }
+                key
+            }
+        };
+        self.skip(false);
+        match self.chars.peek() {
+            _ if key.is_empty() => self.err("expected a key"),
+            Some('.') => self.err("dotted keys aren't supported"),
+            _ => Ok(key),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let quote = self.next();
+        let mut s = String::new();
+        loop {
+            if self.chars.peek() == Some(&'\n') {
+                return self.err("unterminated string");
+            }
+            match self.next() {
+                None => return self.err("unterminated string"),
+                Some(c) if Some(c) == quote => return Ok(s),
+                Some('\\') if quote == Some('"') => {
+                    let c = match self.next() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some(u @ ('u' | 'U')) => {
+                            let digits: String = (0..if u == 'u' { 4 } else { 8 }).filter_map(|_| self.next()).collect();
+                            match u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32) {
+                                Some(c) => c,
+                                None => return self.err(format!("bad escape `\\{}{}`", u, digits)),
+                            }
+                        }
+                        Some(c) => return self.err(format!("bad escape `\\{}`", c)),
+                        None => return self.err("unterminated string"),
+                    };
+                    s.push(c);
+                }
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.chars.peek() {
+            Some('"') | Some('\'') => {
+                let s = self.string()?;
+                if s.is_empty() && self.chars.peek() == Some(&'"') {
+                    return self.err("multi-line strings aren't supported");
+                }
+                Ok(Value::String(s))
+            }
+            Some('[') => {
+                self.next();
+                let mut items = Vec::new();
+                loop {
+                    self.skip(true);
+                    if self.chars.peek() == Some(&']') {
+                        self.next();
+                        return Ok(Value::Array(items));
+                    }
+                    items.push(self.value()?);
+                    self.skip(true);
+                    match self.next() {
+                        Some(',') => {}
+                        Some(']') => return Ok(Value::Array(items)),
+                        _ => return self.err("expected `,` or `]` in an array"),
+                    }
+                }
+            }
+            Some('{') => self.err("inline tables aren't supported"),
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = self.chars.peek().filter(|c| c.is_ascii_alphanumeric() || "+-._".contains(**c)) {
+                    word.push(c);
+                    self.next();
+                }
+                let digits = word.replace('_', "");
+                match word.as_str() {
+                    "true" => Ok(Value::Boolean(true)),
+                    "false" => Ok(Value::Boolean(false)),
+                    _ if digits.parse::<i64>().is_ok() => Ok(Value::Integer(digits.parse().unwrap_or_default())),
+                    _ if !word.is_empty() && digits.parse::<f64>().is_ok() => Ok(Value::Float(digits.parse().unwrap_or_default())),
+                    "" => self.err("expected a value"),
+                    _ => self.err(format!("unsupported value `{}`", word)),
+                }
+            }
+        }
+    }
+}
+
+/// Parse a TOML document into its root table
+pub fn parse(text: &str) -> Result<Table, String> {
+    let mut parser = Parser { chars: text.chars().peekable(), line: 1 };
+    let mut root = Table::new();
+    // Header of the table keys go into: its name, and whether it is an
+    // array of tables (keys go into the last element)
+    let mut current: Option<(String, bool)> = None;
+    loop {
+        parser.skip(true);
+        let Some(&c) = parser.chars.peek() else {
+            return Ok(root);
+        };
+        if c == '[' {
+            parser.next();
+            let array = parser.chars.peek() == Some(&'[');
+            if array {
+                parser.next();
+            }
+            parser.skip(false);
+            let name = parser.key()?;
+            parser.expect(']')?;
+            if array {
+                parser.expect(']')?;
+            }
+            parser.end_of_line()?;
+            match (get_mut(&mut root, &name), array) {
+                (None, false) => root.push((name.clone(), Value::Table(Table::new()))),
+                (None, true) => root.push((name.clone(), Value::Array(vec![Value::Table(Table::new())]))),
+                (Some(Value::Array(items)), true) if items.iter().all(|v| matches!(v, Value::Table(_))) => items.push(Value::Table(Table::new())),
+                _ => return parser.err(format!("`{}` is defined twice", name)),
+            }
+            current = Some((name, array));
+            continue;
+        }
+
+        let line = parser.line;
+        let key = parser.key()?;
+        parser.expect('=')?;
+        parser.skip(false);
+        let value = parser.value()?;
+        parser.end_of_line()?;
+        let table = match &current {
+            None => &mut root,
+            Some((name, array)) => match (get_mut(&mut root, name), array) {
+                (Some(Value::Table(t)), false) => t,
+                (Some(Value::Array(items)), true) => match items.last_mut() {
+                    Some(Value::Table(t)) => t,
+                    _ => unreachable!("arrays of tables only hold tables"),
+                },
+                _ => unreachable!("headers add their table"),
+            },
+        };
+        if get(table, &key).is_some() {
+            return Err(format!("line {}: `{}` is set twice", line, key));
+        }
+        table.push((key, value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn string(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+
+    #[test]
+    fn strings_unescape() {
+        let root = parse(r#"
+basic = "tab\there \"quoted\" back\\slash\nnew line"
+unicode = "caf\u00e9 \U0001F30D"
+literal = 'C:\maps\no escapes "here"'
+"quoted key" = "x"
+"#)
+        .unwrap();
+        assert_eq!(get(&root, "basic"), Some(&string("tab\there \"quoted\" back\\slash\nnew line")));
+        assert_eq!(get(&root, "unicode"), Some(&string("café 🌍")));
+        assert_eq!(get(&root, "literal"), Some(&string(r#"C:\maps\no escapes "here""#)));
+        assert_eq!(get(&root, "quoted key"), Some(&string("x")));
+    }
+
+    #[test]
+    fn scalars_and_arrays() {
+        let root = parse("
+count = 1_000
+negative = -7
+ratio = 0.5
+exp = 1e3
+on = true
+off = false
+empty = []
+maps = [
+    \"Azeroth\",  # the Eastern Kingdoms
+    \"Kalimdor\",
+]
+nested = [[1, 2], [3]]
+")
+        .unwrap();
+        assert_eq!(get(&root, "count"), Some(&Value::Integer(1000)));
+        assert_eq!(get(&root, "negative"), Some(&Value::Integer(-7)));
+        assert_eq!(get(&root, "ratio"), Some(&Value::Float(0.5)));
+        assert_eq!(get(&root, "exp"), Some(&Value::Float(1000.0)));
+        assert_eq!(get(&root, "on"), Some(&Value::Boolean(true)));
+        assert_eq!(get(&root, "off"), Some(&Value::Boolean(false)));
+        assert_eq!(get(&root, "empty"), Some(&Value::Array(Vec::new())));
+        assert_eq!(get(&root, "maps"), Some(&Value::Array(vec![string("Azeroth"), string("Kalimdor")])));
+        let nested = Value::Array(vec![Value::Array(vec![Value::Integer(1), Value::Integer(2)]), Value::Array(vec![Value::Integer(3)])]);
+        assert_eq!(get(&root, "nested"), Some(&nested));
+    }
+
+    #[test]
+    fn tables_and_arrays_of_tables() {
+        let root = parse("
+# Release jobs
+name = \"release\"   # trailing comment
+
+[defaults]
+format = \"lua\"
+
+[[job]]
+name = \"continents\"
+
+[[job]]
+name = \"old\"
+fast = true
+")
+        .unwrap();
+        let keys: Vec<&str> = root.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, ["name", "defaults", "job"]);
+        assert_eq!(get(&root, "defaults"), Some(&Value::Table(vec![("format".to_string(), string("lua"))])));
+        let Some(Value::Array(jobs)) = get(&root, "job") else {
+            panic!("job isn't an array");
+        };
+        assert_eq!(jobs.len(), 2);
+        let Value::Table(second) = &jobs[1] else {
+            panic!("job 2 isn't a table");
+        };
+        assert_eq!(get(second, "name"), Some(&string("old")));
+        assert_eq!(get(second, "fast"), Some(&Value::Boolean(true)));
+    }
+
+    #[test]
+    fn malformed_input_is_an_error() {
+        for (text, expected) in [
+            ("name = \"open", "line 1: unterminated string"),
+            ("name = \"split\nline\"", "line 1: unterminated string"),
+            ("name = \"\\q\"", "line 1: bad escape `\\q`"),
+            ("name = \"\\u12\"", "line 1: bad escape `\\u12\"`"),
+            ("a.b = 1", "line 1: dotted keys aren't supported"),
+            ("a = { b = 1 }", "line 1: inline tables aren't supported"),
+            ("a = \"\"\"x\"\"\"", "line 1: multi-line strings aren't supported"),
+            ("a = 1979-05-27", "line 1: unsupported value `1979-05-27`"),
+            ("a =", "line 1: expected a value"),
+            ("a 1", "line 1: expected `=`, found `1`"),
+            ("= 1", "line 1: expected a key"),
+            ("a = 1 2", "line 1: expected the end of the line, found `2`"),
+            ("a = [1, 2", "line 1: expected `,` or `]` in an array"),
+            ("\na = 1\na = 2", "line 3: `a` is set twice"),
+            ("[t]\n[t]", "line 2: `t` is defined twice"),
+            ("a = 1\n[[a]]", "line 2: `a` is defined twice"),
+            ("[t", "line 1: expected `]`, found the end of the file"),
+        ] {
+            assert_eq!(parse(text), Err(expected.to_string()), "{:?}", text);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn garbage_never_panics(text in "[\\[\\]\"'=#.,{}a-z0-9 \\\\\n_-]{0,64}") {
+            let _ = parse(&text);
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use rusqlite::{params, Connection};
+
+use std::path::Path;
+
+// ============================================================================
+// Run history
+// ============================================================================
+//
+// With `--runs-db`, every generate run appends one row of metrics to a small
+// SQLite database. `zone-map trends` reads the latest rows back and prints
+// them with a sparkline per metric, flagging changes from one run to the
+// next that usually mean something broke: fewer tiles, more parse failures
+// or unknown areas, output that grew or shrank sharply, or a run that took
+// much longer than the ones before it.
+
+/// Output size change between runs worth flagging, as a fraction
+const SIZE_CHANGE: f64 = 0.10;
+/// A run this many times slower than the median of the earlier ones is flagged
+const SLOWDOWN: f64 = 1.5;
+
+const SPARK: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// A metric's label and how to read it from a run
+type Metric = (&'static str, fn(&RunRow) -> u64);
+
+/// Metrics `render_trends` draws a sparkline for
+const SPARKLINES: [Metric; 6] = [
+    ("tiles", |r| r.tiles_parsed),
+    ("failed", |r| r.parse_failures),
+    ("areas", |r| r.areas_found),
+    ("unknown", |r| r.unknown_areas),
+    ("bytes", |r| r.output_bytes),
+    ("duration", |r| r.duration_ms),
+];
+
+/// One generate run's metrics
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunRow {
+    /// Unix time the run started
+    pub started: u64,
+    pub build: String,
+    pub data_version: String,
+    pub tiles_parsed: u64,
+    pub parse_failures: u64,
+    pub areas_found: u64,
+    pub unknown_areas: u64,
+    pub chunks_scanned: u64,
+    pub sentinel_chunks: u64,
+    /// Bytes written to the output directory
+    pub output_bytes: u64,
+    pub duration_ms: u64,
+}
+
+pub struct RunsDb {
+    conn: Connection,
+}
+
+impl RunsDb {
+    /// Open the database at `path`, creating it and its table if needed
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS runs (
+                id INTEGER PRIMARY KEY,
+                started INTEGER NOT NULL,
+                build TEXT NOT NULL,
+                data_version TEXT NOT NULL,
+                tiles_parsed INTEGER NOT NULL,
+                parse_failures INTEGER NOT NULL,
+                areas_found INTEGER NOT NULL,
+                unknown_areas INTEGER NOT NULL,
+                chunks_scanned INTEGER NOT NULL,
+                sentinel_chunks INTEGER NOT NULL,
+                output_bytes INTEGER NOT NULL,
+                duration_ms INTEGER NOT NULL
+            )",
+        )?;
+        Ok(Self { conn })
+    }
+
+    pub fn record(&self, run: &RunRow) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO runs (started, build, data_version, tiles_parsed, parse_failures, areas_found, unknown_areas,
+                chunks_scanned, sentinel_chunks, output_bytes, duration_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                run.started as i64,
+                run.build,
+                run.data_version,
+                run.tiles_parsed as i64,
+                run.parse_failures as i64,
+                run.areas_found as i64,
+                run.unknown_areas as i64,
+                run.chunks_scanned as i64,
+                run.sentinel_chunks as i64,
+                run.output_bytes as i64,
+                run.duration_ms as i64,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The last `limit` runs, oldest first
+    pub fn recent(&self, limit: usize) -> rusqlite::Result<Vec<RunRow>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT started, build, data_version, tiles_parsed, parse_failures, areas_found, unknown_areas,
+                chunks_scanned, sentinel_chunks, output_bytes, duration_ms
+             FROM runs ORDER BY id DESC LIMIT ?1",
+        )?;
+        let mut runs = stmt
+            .query_map([limit as i64], |row| {
+                let n = |i: usize| row.get::<_, i64>(i).map(|v| v.max(0) as u64);
+                Ok(RunRow {
+                    started: n(0)?,
+                    build: row.get(1)?,
+                    data_version: row.get(2)?,
+                    tiles_parsed: n(3)?,
+                    parse_failures: n(4)?,
+                    areas_found: n(5)?,
+                    unknown_areas: n(6)?,
+                    chunks_scanned: n(7)?,
+                    sentinel_chunks: n(8)?,
+                    output_bytes: n(9)?,
+                    duration_ms: n(10)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        runs.reverse();
+        Ok(runs)
+    }
+
+    /// `started` as `YYYY-MM-DD HH:MM:SS` UTC
+    fn format_time(&self, started: u64) -> String {
+        self.conn
+            .query_row("SELECT datetime(?1, 'unixepoch')", [started as i64], |row| row.get(0))
+            .unwrap_or_else(|_| started.to_string())
+    }
+
+    /// The runs as a table, a sparkline per metric and the regressions
+    /// between consecutive runs
+    pub fn render_trends(&self, runs: &[RunRow]) -> String {
+        if runs.is_empty() {
+            return String::from("No runs recorded\n");
+        }
+        let mut out = format!(
+            "{:<19}  {:<16} {:<8} {:>7} {:>7} {:>6} {:>7} {:>11} {:>9}\n",
+            "started", "build", "version", "tiles", "failed", "areas", "unknown", "bytes", "seconds"
+        );
+        for run in runs {
+            out.push_str(&format!(
+                "{:<19}  {:<16} {:<8} {:>7} {:>7} {:>6} {:>7} {:>11} {:>9.1}\n",
+                self.format_time(run.started),
+                run.build,
+                run.data_version,
+                run.tiles_parsed,
+                run.parse_failures,
+                run.areas_found,
+                run.unknown_areas,
+                run.output_bytes,
+                run.duration_ms as f64 / 1000.0
+            ));
+        }
+
+        out.push('\n');
+        for (name, value) in SPARKLINES {
+            let values: Vec<u64> = runs.iter().map(value).collect();
+            out.push_str(&format!("{:<9} {}\n", name, sparkline(&values)));
+        }
+
+        let regressions = regressions(runs);
+        out.push('\n');
+        if regressions.is_empty() {
+            out.push_str("No regressions\n");
+        }
+        for (i, msg) in regressions {
+            out.push_str(&format!("! {}: {}\n", self.format_time(runs[i].started), msg));
+        }
+        out
+    }
+}
+
+/// One block per value, scaled between the lowest and highest
+pub fn sparkline(values: &[u64]) -> String {
+    let (Some(&lo), Some(&hi)) = (values.iter().min(), values.iter().max()) else {
+        return String::new();
+    };
+    values
+        .iter()
+        .map(|&v| if hi == lo { SPARK[0] } else { SPARK[((v - lo) * (SPARK.len() as u64 - 1) / (hi - lo)) as usize] })
+        .collect()
+}
+
+/// Changes from each run to the next that look like regressions, with the
+/// index of the later run
+pub fn regressions(runs: &[RunRow]) -> Vec<(usize, String)> {
+    let mut found = Vec::new();
+    for i in 1..runs.len() {
+        let (prev, run) = (&runs[i - 1], &runs[i]);
+        if run.tiles_parsed < prev.tiles_parsed {
+            found.push((i, format!("tiles parsed fell from {} to {}", prev.tiles_parsed, run.tiles_parsed)));
+        }
+        if run.parse_failures > prev.parse_failures {
+            found.push((i, format!("parse failures rose from {} to {}", prev.parse_failures, run.parse_failures)));
+        }
+        if run.unknown_areas > prev.unknown_areas {
+            found.push((i, format!("unknown areas rose from {} to {}", prev.unknown_areas, run.unknown_areas)));
+        }
+        if prev.output_bytes > 0 {
+            let change = (run.output_bytes as f64 - prev.output_bytes as f64) / prev.output_bytes as f64;
+            if change.abs() > SIZE_CHANGE {
+                found.push((i, format!("output size changed {:+.0}% ({} -> {} bytes)", change * 100.0, prev.output_bytes, run.output_bytes)));
+            }
+        }
+        let mut earlier: Vec<u64> = runs[..i].iter().map(|r| r.duration_ms).collect();
+        earlier.sort_unstable();
+        let median = earlier[earlier.len() / 2];
+        if median > 0 && run.duration_ms as f64 > median as f64 * SLOWDOWN {
+            found.push((i, format!("took {:.1}s, median of earlier runs {:.1}s", run.duration_ms as f64 / 1000.0, median as f64 / 1000.0)));
+        }
+    }
+    found
+}
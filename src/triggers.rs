@@ -0,0 +1,144 @@
+use crate::adt::CHUNKS_PER_SIDE;
+use crate::grid::{ZoneGrid, TILES_PER_SIDE, TILE_SIZE};
+
+use std::collections::{BTreeMap, HashMap};
+
+// ============================================================================
+// Zone crossing segments
+// ============================================================================
+
+/// Line segment in world coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Segment {
+    pub x1: f32,
+    pub y1: f32,
+    pub x2: f32,
+    pub y2: f32,
+}
+
+/// Edge between chunk cells in global chunk coordinates (0..1024 per axis)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Edge {
+    /// Between columns `line - 1` and `line`, spanning row `at`
+    Vertical { line: u32, at: u32 },
+    /// Between rows `line - 1` and `line`, spanning column `at`
+    Horizontal { line: u32, at: u32 },
+}
+
+/// Border segments between every pair of adjacent zones in a grid.
+///
+/// `zone_of` maps an area ID to the zone it belongs to (e.g. its root parent),
+/// so subzone changes inside one zone produce no segments. Chunk edges are
+/// merged into maximal straight runs. Keys are `(lower zone, higher zone)`.
+pub fn crossing_segments(grid: &ZoneGrid, zone_of: impl Fn(u32) -> u32) -> BTreeMap<(u32, u32), Vec<Segment>> {
+    let side = CHUNKS_PER_SIDE as u32;
+
+    // Global chunk cell -> zone
+    let mut cells: HashMap<(u32, u32), u32> = HashMap::new();
+    for (&key, tile) in grid.tiles() {
+        let tile_x = key % TILES_PER_SIDE;
+        let tile_y = key / TILES_PER_SIDE;
+        for (idx, &area_id) in tile.iter().enumerate() {
+            if area_id != 0 {
+                let gx = tile_x * side + (idx % CHUNKS_PER_SIDE) as u32;
+                let gy = tile_y * side + (idx / CHUNKS_PER_SIDE) as u32;
+                cells.insert((gx, gy), zone_of(area_id));
+            }
+        }
+    }
+
+    let mut edges: BTreeMap<(u32, u32), Vec<Edge>> = BTreeMap::new();
+    for (&(gx, gy), &zone) in &cells {
+        let right = cells.get(&(gx + 1, gy)).map(|&z| (z, Edge::Vertical { line: gx + 1, at: gy }));
+        let down = cells.get(&(gx, gy + 1)).map(|&z| (z, Edge::Horizontal { line: gy + 1, at: gx }));
+        for (other, edge) in right.into_iter().chain(down) {
+            if other != zone {
+                edges.entry((zone.min(other), zone.max(other))).or_default().push(edge);
+            }
+        }
+    }
+
+    edges
+        .into_iter()
+        .map(|(pair, mut pair_edges)| {
+            pair_edges.sort();
+            (pair, merge_edges(&pair_edges))
+        })
+        .collect()
+}
+
+/// Merge sorted unit edges into straight segments
+fn merge_edges(edges: &[Edge]) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut run: Option<(Edge, u32)> = None; // first edge, length
+
+    for &edge in edges {
+        run = match run {
+            Some((start, len)) if continues(start, len, edge) => Some((start, len + 1)),
+            Some((start, len)) => {
+                segments.push(to_segment(start, len));
+                Some((edge, 1))
+            }
+            None => Some((edge, 1)),
+        };
+    }
+    if let Some((start, len)) = run {
+        segments.push(to_segment(start, len));
+    }
+    segments
+}
+
+fn continues(start: Edge, len: u32, next: Edge) -> bool {
+    match (start, next) {
+        (Edge::Vertical { line: a, at }, Edge::Vertical { line: b, at: next_at })
+        | (Edge::Horizontal { line: a, at }, Edge::Horizontal { line: b, at: next_at }) => {
+            a == b && next_at == at + len
+        }
+        _ => false,
+    }
+}
+
+/// World coordinates of a run of `len` edges starting at `start`
+fn to_segment(start: Edge, len: u32) -> Segment {
+    let half = TILE_SIZE * (TILES_PER_SIDE / 2) as f32;
+    let chunk_size = TILE_SIZE / CHUNKS_PER_SIDE as f32;
+    let world = |i: u32| half - i as f32 * chunk_size;
+
+    match start {
+        // Column boundaries are lines of constant world Y
+        Edge::Vertical { line, at } => Segment { x1: world(at), y1: world(line), x2: world(at + len), y2: world(line) },
+        // Row boundaries are lines of constant world X
+        Edge::Horizontal { line, at } => Segment { x1: world(line), y1: world(at), x2: world(line), y2: world(at + len) },
+    }
+}
+
+// ============================================================================
+// Hysteresis
+// ============================================================================
+
+impl Segment {
+    pub fn length(&self) -> f32 {
+        ((self.x2 - self.x1).powi(2) + (self.y2 - self.y1).powi(2)).sqrt()
+    }
+}
+
+/// How jagged a border is: 1.0 when every straight run is a single chunk
+/// edge (a staircase), approaching 0 for long straight runs
+pub fn jaggedness(segments: &[Segment]) -> f32 {
+    let chunk_size = TILE_SIZE / CHUNKS_PER_SIDE as f32;
+    let total: f32 = segments.iter().map(|s| s.length()).sum();
+    if segments.is_empty() || total <= 0.0 {
+        return 0.0;
+    }
+    let avg_run = total / segments.len() as f32;
+    (chunk_size / avg_run).min(1.0)
+}
+
+/// Distance in yards a player should move past a border before the zone
+/// change is accepted: half a chunk for straight borders, up to two chunks
+/// for staircase borders where chunk noise causes flip-flopping
+pub fn recommended_hysteresis(segments: &[Segment]) -> f32 {
+    let chunk_size = TILE_SIZE / CHUNKS_PER_SIDE as f32;
+    chunk_size * (0.5 + 1.5 * jaggedness(segments))
+}
@@ -0,0 +1,122 @@
+use crate::grid::WorldRect;
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+// ============================================================================
+// UiMapAssignment
+// ============================================================================
+
+/// One row of UiMapAssignment: a world region drawn into part of a UI map
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UiMapAssignment {
+    pub ui_map_id: u32,
+    pub map_id: u32,
+    pub area_id: u32,
+    pub ui_min: [f32; 2],
+    pub ui_max: [f32; 2],
+    pub region_min: [f32; 3],
+    pub region_max: [f32; 3],
+}
+
+impl UiMapAssignment {
+    /// Normalized (0-1) `{x, y}` of a world point on this map, clamped to the map.
+    /// UI x runs along decreasing world Y and UI y along decreasing world X.
+    pub fn normalize_point(&self, wx: f32, wy: f32) -> [f32; 2] {
+        let tx = (self.region_max[1] - wy) / (self.region_max[1] - self.region_min[1]);
+        let ty = (self.region_max[0] - wx) / (self.region_max[0] - self.region_min[0]);
+        [
+            (self.ui_min[0] + tx * (self.ui_max[0] - self.ui_min[0])).clamp(0.0, 1.0),
+            (self.ui_min[1] + ty * (self.ui_max[1] - self.ui_min[1])).clamp(0.0, 1.0),
+        ]
+    }
+
+    /// Normalized (0-1) `{left, top, right, bottom}` of a world rect on this map
+    pub fn normalize(&self, rect: &WorldRect) -> [f32; 4] {
+        let [left, top] = self.normalize_point(rect.max_x, rect.max_y);
+        let [right, bottom] = self.normalize_point(rect.min_x, rect.min_y);
+        [left, top, right, bottom]
+    }
+
+    /// Whether the world region has a finite, non-zero extent on both axes;
+    /// `normalize_point` divides by it
+    pub fn has_region(&self) -> bool {
+        (0..2).all(|i| {
+            let extent = self.region_max[i] - self.region_min[i];
+            extent.is_finite() && extent != 0.0
+        })
+    }
+
+    /// World area (yards²) this assignment covers
+    fn region_area(&self) -> f32 {
+        (self.region_max[0] - self.region_min[0]) * (self.region_max[1] - self.region_min[1])
+    }
+}
+
+/// The assignment that draws zone `zone_area_id` of map `map_id` on its own zone
+/// map. Zones with several (caves, sub-maps) use the one covering the most ground.
+pub fn zone_assignment(assignments: &[UiMapAssignment], map_id: u32, zone_area_id: u32) -> Option<&UiMapAssignment> {
+    assignments
+        .iter()
+        .filter(|a| a.map_id == map_id && a.area_id == zone_area_id)
+        .max_by(|a, b| a.region_area().total_cmp(&b.region_area()))
+}
+
+/// Parse a UiMapAssignment CSV export (wago.tools column names)
+pub fn parse_ui_map_assignment_csv(csv_path: &Path) -> Result<Vec<UiMapAssignment>, Box<dyn std::error::Error>> {
+    let reader = BufReader::new(File::open(csv_path)?);
+    let mut lines = reader.lines();
+    let header = lines.next().ok_or("Empty CSV")??;
+
+    let columns: Vec<&str> = header.split(',').map(|c| c.trim()).collect();
+    let col = |name: &str| {
+        columns
+            .iter()
+            .position(|&c| c == name)
+            .ok_or_else(|| format!("No {} column", name))
+    };
+    let ui_map_idx = col("UiMapID")?;
+    let map_idx = col("MapID")?;
+    let area_idx = col("AreaID")?;
+    let ui_min_idx = [col("UiMin_0")?, col("UiMin_1")?];
+    let ui_max_idx = [col("UiMax_0")?, col("UiMax_1")?];
+    let region_idx = [
+        col("Region_0")?,
+        col("Region_1")?,
+        col("Region_2")?,
+        col("Region_3")?,
+        col("Region_4")?,
+        col("Region_5")?,
+    ];
+    let max_idx = columns.len() - 1;
+
+    let mut assignments = Vec::new();
+    for line in lines {
+        let line = line?;
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+        if fields.len() <= max_idx {
+            continue;
+        }
+
+        let int = |i: usize| fields[i].parse::<u32>().unwrap_or(0);
+        let float = |i: usize| fields[i].parse::<f32>().unwrap_or(0.0);
+
+        let assignment = UiMapAssignment {
+            ui_map_id: int(ui_map_idx),
+            map_id: int(map_idx),
+            area_id: int(area_idx),
+            ui_min: [float(ui_min_idx[0]), float(ui_min_idx[1])],
+            ui_max: [float(ui_max_idx[0]), float(ui_max_idx[1])],
+            region_min: [float(region_idx[0]), float(region_idx[1]), float(region_idx[2])],
+            region_max: [float(region_idx[3]), float(region_idx[4]), float(region_idx[5])],
+        };
+        // A degenerate region can't be normalized against
+        if assignment.has_region() {
+            assignments.push(assignment);
+        }
+    }
+
+    Ok(assignments)
+}
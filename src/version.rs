@@ -0,0 +1,170 @@
+use crate::diff::ExportDiff;
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+// ============================================================================
+// Data versioning
+// ============================================================================
+//
+// Every export carries a semantic data version, separate from the addon's
+// own version:
+// - major when the output schema changes (the addon must be updated too)
+// - minor when any tile grid changes
+// - patch when only metadata (names, colours, lookups) changes
+//
+// The version is written to Data/DataVersion.lua and stamped on the first
+// line of every other Lua file, so a mixed set of files is easy to spot.
+
+/// Layout of the generated Lua files. Bump when a change would break an addon
+/// reading the previous layout.
+pub const DATA_SCHEMA: u32 = 1;
+
+pub const VERSION_FILE: &str = "DataVersion.lua";
+
+const STAMP_PREFIX: &str = "-- ZoneMap data ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DataVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl DataVersion {
+    /// Version of the first export
+    pub const INITIAL: DataVersion = DataVersion { major: 1, minor: 0, patch: 0 };
+
+    pub fn bump(self, bump: Bump) -> Self {
+        match bump {
+            Bump::None => self,
+            Bump::Patch => Self { patch: self.patch + 1, ..self },
+            Bump::Minor => Self { minor: self.minor + 1, patch: 0, ..self },
+            Bump::Major => Self { major: self.major + 1, minor: 0, patch: 0 },
+        }
+    }
+}
+
+impl FromStr for DataVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<u32> = s
+            .trim()
+            .split('.')
+            .map(|p| p.parse().map_err(|_| format!("bad version `{}`", s)))
+            .collect::<Result<_, _>>()?;
+        match parts.as_slice() {
+            &[major, minor, patch] => Ok(Self { major, minor, patch }),
+            _ => Err(format!("bad version `{}` (expected major.minor.patch)", s)),
+        }
+    }
+}
+
+impl fmt::Display for DataVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Bump {
+    None,
+    Patch,
+    Minor,
+    Major,
+}
+
+impl fmt::Display for Bump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Bump::None => "none",
+            Bump::Patch => "patch",
+            Bump::Minor => "minor",
+            Bump::Major => "major",
+        })
+    }
+}
+
+/// Bump for going from an export with `old_schema` to this one. `files_changed`
+/// covers anything the diff doesn't model (colours, lookups, triggers).
+pub fn required_bump(old_schema: u32, diff: &ExportDiff, files_changed: bool) -> Bump {
+    if old_schema != DATA_SCHEMA {
+        Bump::Major
+    } else if diff.tiles_changed() {
+        Bump::Minor
+    } else if !diff.is_empty() || files_changed {
+        Bump::Patch
+    } else {
+        Bump::None
+    }
+}
+
+/// Version and schema recorded in an export's DataVersion.lua, if it has one
+pub fn read_version(dir: &Path) -> Result<Option<(DataVersion, u32)>, Box<dyn std::error::Error>> {
+    let path = dir.join(VERSION_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let text = fs::read_to_string(&path)?;
+    let field = |name: &str| {
+        text.lines()
+            .find_map(|l| l.trim().strip_prefix(name)?.strip_prefix(" = "))
+            .map(|v| v.trim_end_matches(',').trim_matches('"').to_string())
+            .ok_or_else(|| format!("{}: no {} field", VERSION_FILE, name))
+    };
+    let version = field("version")?.parse()?;
+    let schema = field("schema")?.parse().map_err(|_| format!("{}: bad schema", VERSION_FILE))?;
+    Ok(Some((version, schema)))
+}
+
+/// Lua files in `dir` with version stamps removed, by name
+fn unstamped_files(dir: &Path) -> std::io::Result<Vec<(String, String)>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()).map(str::to_string) else {
+            continue;
+        };
+        if !name.ends_with(".lua") || name == VERSION_FILE {
+            continue;
+        }
+        let text = fs::read_to_string(&path)?;
+        let body = match text.split_once('\n') {
+            Some((first, rest)) if first.starts_with(STAMP_PREFIX) => rest.to_string(),
+            _ => text,
+        };
+        files.push((name, body));
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Whether the Lua files of two exports differ, ignoring version stamps
+pub fn files_changed(old_dir: &Path, new_dir: &Path) -> std::io::Result<bool> {
+    Ok(unstamped_files(old_dir)? != unstamped_files(new_dir)?)
+}
+
+/// Write DataVersion.lua and stamp every other Lua file in `dir`.
+/// Returns the number of files stamped.
+pub fn stamp_export(dir: &Path, version: DataVersion, build: &str) -> std::io::Result<usize> {
+    let stamp = format!("{}{} (schema {}, build {})", STAMP_PREFIX, version, DATA_SCHEMA, build);
+
+    let files = unstamped_files(dir)?;
+    for (name, body) in &files {
+        fs::write(dir.join(name), format!("{}\n{}", stamp, body))?;
+    }
+
+    fs::write(
+        dir.join(VERSION_FILE),
+        format!(
+            "{}\n\nlocal _, addon = ...\n\naddon.DataVersion = {{\n  version = \"{}\",\n  schema = {},\n  build = \"{}\",\n}}\n",
+            stamp, version, DATA_SCHEMA, build
+        ),
+    )?;
+    Ok(files.len())
+}
@@ -0,0 +1,90 @@
+use crate::adt::TileData;
+use crate::error;
+use crate::layers::LayerExtractor;
+
+use wasmi::{Config, Engine, Linker, Module, Store, TypedFunc};
+
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+
+// ============================================================================
+// WASM layer plugins
+// ============================================================================
+
+/// Fuel granted to a single `extract` call; a runaway plugin traps instead of hanging
+const FUEL_PER_CHUNK: u64 = 100_000;
+
+/// Layer extractor backed by a sandboxed WASM module.
+///
+/// The module must export
+/// `extract(area_id: i32, flags: i32, holes: i32, liquid_type: i32) -> i32`,
+/// which is called once per chunk. No host imports are provided, so a plugin
+/// can only compute on the values it is handed.
+pub struct WasmExtractor {
+    name: String,
+    store: RefCell<Store<()>>,
+    extract: TypedFunc<(i32, i32, i32, i32), i32>,
+}
+
+impl WasmExtractor {
+    /// Load a plugin from a `.wasm` (or `.wat`) file, named after its file stem
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .ok_or("plugin path has no file name")?
+            .to_string();
+        Self::from_bytes(&name, &fs::read(path)?)
+    }
+
+    pub fn from_bytes(name: &str, wasm: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+
+        let module = Module::new(&engine, wasm)?;
+        let mut store = Store::new(&engine, ());
+        let linker = Linker::<()>::new(&engine);
+        let instance = linker.instantiate_and_start(&mut store, &module)?;
+        let extract = instance
+            .get_typed_func::<(i32, i32, i32, i32), i32>(&store, "extract")
+            .map_err(|e| format!("plugin {} does not export extract(i32, i32, i32, i32) -> i32: {}", name, e))?;
+
+        Ok(Self { name: name.to_string(), store: RefCell::new(store), extract })
+    }
+}
+
+impl LayerExtractor for WasmExtractor {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn extract(&self, tile: &TileData) -> Vec<u32> {
+        let mut store = self.store.borrow_mut();
+        let mut failed = false;
+
+        tile
+            .chunks
+            .iter()
+            .map(|c| {
+                let liquid_type = c.liquid.map(|l| l.liquid_type as i32).unwrap_or(0);
+                let args = (c.area_id as i32, c.flags as i32, c.holes as i32, liquid_type);
+
+                let result = store
+                    .set_fuel(FUEL_PER_CHUNK)
+                    .and_then(|_| self.extract.call(&mut *store, args));
+                match result {
+                    Ok(v) => v as u32,
+                    Err(e) => {
+                        if !failed {
+                            error!("  ERROR in plugin {}: {}", self.name, e);
+                            failed = true;
+                        }
+                        0
+                    }
+                }
+            })
+            .collect()
+    }
+}
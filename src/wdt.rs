@@ -0,0 +1,154 @@
+use crate::grid::TILES_PER_SIDE;
+
+use std::collections::BTreeSet;
+
+// ============================================================================
+// WDT
+// ============================================================================
+//
+// A map's `<Map>.wdt` lists which of its 64x64 tiles have an ADT:
+//
+//     MVER   version
+//     MPHD   header flags (bit 0: the map is a single global WMO, no ADTs)
+//     MAIN   64*64 entries of (flags u32, u32), row-major by tile_y; flag
+//            bit 0 set means the tile exists
+//
+// Chunk magics are stored reversed (`NIAM` for MAIN), as in ADTs. Tiles the
+// WDT leaves out are empty: open ocean or void with no terrain at all.
+
+const MAIN_ENTRY_SIZE: usize = 8;
+const MPHD_WMO_ONLY: u32 = 0x1;
+const MAIN_HAS_ADT: u32 = 0x1;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Wdt {
+    /// MPHD flags
+    pub flags: u32,
+    /// Keys (`tile_y * 64 + tile_x`) of tiles with an ADT
+    pub tiles: BTreeSet<u32>,
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+impl Wdt {
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        let mut wdt = Wdt::default();
+        let mut seen_main = false;
+        let mut at = 0;
+        while at + 8 <= data.len() {
+            let magic = &data[at..at + 4];
+            let size = read_u32(data, at + 4).ok_or("truncated chunk header")? as usize;
+            let body = data
+                .get(at + 8..(at + 8).saturating_add(size))
+                .ok_or_else(|| format!("{} chunk runs past the end of the file", String::from_utf8_lossy(magic)))?;
+            match magic {
+                b"DHPM" => wdt.flags = read_u32(body, 0).ok_or("MPHD chunk too short")?,
+                b"NIAM" => {
+                    let count = (TILES_PER_SIDE * TILES_PER_SIDE) as usize;
+                    if body.len() < count * MAIN_ENTRY_SIZE {
+                        return Err(format!("MAIN chunk has {} bytes, expected {}", body.len(), count * MAIN_ENTRY_SIZE));
+                    }
+                    for key in 0..count {
+                        if read_u32(body, key * MAIN_ENTRY_SIZE).is_some_and(|f| f & MAIN_HAS_ADT != 0) {
+                            wdt.tiles.insert(key as u32);
+                        }
+                    }
+                    seen_main = true;
+                }
+                _ => {}
+            }
+            at += 8 + size;
+        }
+        if !seen_main {
+            return Err("no MAIN chunk".to_string());
+        }
+        Ok(wdt)
+    }
+
+    /// Whether the map is one global WMO (instances such as Stormwind's
+    /// stockade), with no ADTs at all
+    pub fn is_wmo_only(&self) -> bool {
+        self.flags & MPHD_WMO_ONLY != 0
+    }
+
+    pub fn has_tile(&self, tile_x: u32, tile_y: u32) -> bool {
+        tile_x < TILES_PER_SIDE && tile_y < TILES_PER_SIDE && self.tiles.contains(&(tile_y * TILES_PER_SIDE + tile_x))
+    }
+
+    /// Tiles inside the bounding box of the existing ones that the WDT leaves
+    /// empty: the ocean and gaps within the map's extent
+    pub fn empty_tiles(&self) -> Vec<u32> {
+        let xs = self.tiles.iter().map(|k| k % TILES_PER_SIDE);
+        let ys = self.tiles.iter().map(|k| k / TILES_PER_SIDE);
+        let (Some(min_x), Some(max_x), Some(min_y), Some(max_y)) = (xs.clone().min(), xs.max(), ys.clone().min(), ys.max())
+        else {
+            return Vec::new();
+        };
+        (min_y..=max_y)
+            .flat_map(|y| (min_x..=max_x).map(move |x| y * TILES_PER_SIDE + x))
+            .filter(|key| !self.tiles.contains(key))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(magic: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut out = magic.to_vec();
+        out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        out.extend_from_slice(body);
+        out
+    }
+
+    /// MVER, MPHD with `flags` and a MAIN listing `tiles`
+    fn wdt_file(flags: u32, tiles: &[u32]) -> Vec<u8> {
+        let mut main = vec![0u8; (TILES_PER_SIDE * TILES_PER_SIDE) as usize * MAIN_ENTRY_SIZE];
+        for &key in tiles {
+            main[key as usize * MAIN_ENTRY_SIZE] = MAIN_HAS_ADT as u8;
+        }
+        let mut mphd = flags.to_le_bytes().to_vec();
+        mphd.resize(32, 0);
+        [chunk(b"REVM", &18u32.to_le_bytes()), chunk(b"DHPM", &mphd), chunk(b"NIAM", &main)].concat()
+    }
+
+    #[test]
+    fn tiles_and_flags_are_read() {
+        let wdt = Wdt::parse(&wdt_file(0, &[32 * 64 + 31, 32 * 64 + 33, 34 * 64 + 32])).unwrap();
+        assert!(!wdt.is_wmo_only());
+        assert!(wdt.has_tile(31, 32) && wdt.has_tile(33, 32) && wdt.has_tile(32, 34));
+        assert!(!wdt.has_tile(32, 32) && !wdt.has_tile(64, 0));
+        // The 3x3 box around them, less the three tiles
+        assert_eq!(wdt.empty_tiles().len(), 6);
+        assert!(wdt.empty_tiles().contains(&(32 * 64 + 32)));
+
+        let instance = Wdt::parse(&wdt_file(MPHD_WMO_ONLY, &[])).unwrap();
+        assert!(instance.is_wmo_only());
+        assert!(instance.tiles.is_empty() && instance.empty_tiles().is_empty());
+    }
+
+    #[test]
+    fn truncated_files_are_errors() {
+        let data = wdt_file(0, &[0]);
+        for len in [0, 3, 8, 12, 40, data.len() - 1] {
+            assert!(Wdt::parse(&data[..len]).is_err(), "{} bytes", len);
+        }
+        // A MAIN too short for 64x64 entries
+        let short = [chunk(b"DHPM", &[0; 32]), chunk(b"NIAM", &[1; 64])].concat();
+        assert_eq!(Wdt::parse(&short).unwrap_err(), "MAIN chunk has 64 bytes, expected 32768");
+        assert_eq!(Wdt::parse(&chunk(b"DHPM", &[])).unwrap_err(), "MPHD chunk too short");
+    }
+
+    #[test]
+    fn oversize_chunk_sizes_are_errors() {
+        for size in [u32::MAX, u32::MAX - 7, 1 << 31] {
+            let mut data = wdt_file(0, &[0]);
+            data[4..8].copy_from_slice(&size.to_le_bytes());
+            assert_eq!(Wdt::parse(&data).unwrap_err(), "REVM chunk runs past the end of the file");
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use crate::attributes::{AttrFields, AttributeTable};
+
+use std::path::Path;
+
+// ============================================================================
+// Weather zones
+// ============================================================================
+//
+// Weather is defined per zone, not per chunk: TrinityCore/CMaNGOS keep it in
+// `game_weather` (zone, spring_rain_chance, ..., winter_storm_chance) and a
+// hand-written CSV can use any columns. Subzones without a row use their
+// parent's.
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WeatherTable {
+    zones: AttributeTable,
+}
+
+impl WeatherTable {
+    pub fn from_csv(csv_path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self { zones: AttributeTable::from_csv(csv_path)? })
+    }
+
+    pub fn len(&self) -> usize {
+        self.zones.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.zones.rows.is_empty()
+    }
+
+    /// Weather for `area_id`, walking up `parent_of` until a zone has a row.
+    /// Returns the zone the row belongs to along with it.
+    pub fn resolve(&self, area_id: u32, parent_of: impl Fn(u32) -> Option<u32>) -> Option<(u32, &AttrFields)> {
+        let mut current = area_id;
+        // AreaTable depth is small; the bound only guards against parent cycles
+        for _ in 0..16 {
+            if let Some(fields) = self.zones.rows.get(&current) {
+                return Some((current, fields));
+            }
+            current = parent_of(current).filter(|&p| p != 0)?;
+        }
+        None
+    }
+}
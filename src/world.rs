@@ -0,0 +1,79 @@
+use crate::grid::{world_to_chunk, WorldRect, ZoneGrid};
+
+// ============================================================================
+// World
+// ============================================================================
+//
+// Every continent's grid behind one lookup. Callers that know the map ask by
+// Map.dbc ID; tools that only log x/y (combat logs, old waypoint dumps) can
+// ask `locate` to pick the map for them.
+
+/// One grid plus the world-space extent of its tiles
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct WorldMap {
+    grid: ZoneGrid,
+    bounds: Option<WorldRect>,
+}
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct World {
+    maps: Vec<WorldMap>,
+}
+
+impl World {
+    /// When two grids share a map ID, the first one answers lookups by ID
+    pub fn new(grids: Vec<ZoneGrid>) -> Self {
+        let maps = grids.into_iter().map(|grid| WorldMap { bounds: grid.bounds(), grid }).collect();
+        Self { maps }
+    }
+
+    pub fn add(&mut self, grid: ZoneGrid) {
+        self.maps.push(WorldMap { bounds: grid.bounds(), grid });
+    }
+
+    pub fn grids(&self) -> impl Iterator<Item = &ZoneGrid> {
+        self.maps.iter().map(|m| &m.grid)
+    }
+
+    pub fn grid(&self, map_id: u32) -> Option<&ZoneGrid> {
+        self.maps.iter().map(|m| &m.grid).find(|g| g.map_id == map_id)
+    }
+
+    /// World-space extent of a map's tiles
+    pub fn bounds(&self, map_id: u32) -> Option<WorldRect> {
+        self.maps.iter().find(|m| m.grid.map_id == map_id)?.bounds
+    }
+
+    /// Area ID at world (x, y) on a known map
+    pub fn area_at(&self, map_id: u32, x: f32, y: f32) -> Option<u32> {
+        self.grid(map_id)?.area_at(x, y)
+    }
+
+    /// Maps that could contain world (x, y): those with a tile there, and of
+    /// those, the ones with an area at that chunk first. Every continent
+    /// shares one coordinate space, so more than one map can match.
+    pub fn candidate_maps(&self, x: f32, y: f32) -> Vec<u32> {
+        let Some(pos) = world_to_chunk(x, y) else {
+            return Vec::new();
+        };
+        let mut candidates: Vec<(bool, u32)> = self
+            .maps
+            .iter()
+            .filter(|m| m.bounds.is_some_and(|b| b.contains(x, y)) && m.grid.has_tile(pos.tile_key()))
+            .map(|m| (m.grid.area_at_chunk(pos).is_none(), m.grid.map_id))
+            .collect();
+        // Stable, so grids keep their order within each group
+        candidates.sort_by_key(|&(no_area, _)| no_area);
+        candidates.into_iter().map(|(_, map_id)| map_id).collect()
+    }
+
+    /// `(map ID, area ID)` at world (x, y) for callers that don't know the
+    /// map: the first of `candidate_maps`. Positions where continents
+    /// overlap are ambiguous; check `candidate_maps` when that matters.
+    pub fn locate(&self, x: f32, y: f32) -> Option<(u32, u32)> {
+        let map_id = *self.candidate_maps(x, y).first()?;
+        Some((map_id, self.area_at(map_id, x, y)?))
+    }
+}
@@ -0,0 +1,179 @@
+use crate::png::crc32;
+
+// ============================================================================
+// Zip archives
+// ============================================================================
+//
+// Just enough zip to bundle a few text files (crash reports): each file is a
+// local header and its raw-deflated bytes, followed by a central directory
+// listing them and the end-of-directory record. No zip64, no timestamps
+// (every entry is dated 1980-01-01) and no comments.
+
+const LOCAL_HEADER: u32 = 0x0403_4B50;
+const CENTRAL_HEADER: u32 = 0x0201_4B50;
+const END_OF_DIRECTORY: u32 = 0x0605_4B50;
+/// Version 2.0: deflate
+const VERSION_NEEDED: u16 = 20;
+/// General purpose flag: names are UTF-8
+const UTF8_NAMES: u16 = 0x0800;
+const METHOD_DEFLATE: u16 = 8;
+/// MS-DOS date of 1980-01-01, the earliest a zip can hold
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+fn put16(out: &mut Vec<u8>, v: u16) {
+    out.extend_from_slice(&v.to_le_bytes());
+}
+
+fn put32(out: &mut Vec<u8>, v: u32) {
+    out.extend_from_slice(&v.to_le_bytes());
+}
+
+/// A zip archive of `files`, each a name and its contents
+pub fn write_zip(files: &[(&str, &[u8])]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    let mut directory = Vec::new();
+    for (name, data) in files {
+        let compressed = miniz_oxide::deflate::compress_to_vec(data, 6);
+        let (crc, offset) = (crc32(data), out.len());
+        let (Ok(size), Ok(compressed_size), Ok(offset), Ok(name_len)) =
+            (u32::try_from(data.len()), u32::try_from(compressed.len()), u32::try_from(offset), u16::try_from(name.len()))
+        else {
+            return Err(format!("{} is too large for a zip without zip64", name));
+        };
+
+        // The fields from `version needed` to `name length` are shared by
+        // both headers
+        let mut fields = Vec::with_capacity(26);
+        put16(&mut fields, VERSION_NEEDED);
+        put16(&mut fields, UTF8_NAMES);
+        put16(&mut fields, METHOD_DEFLATE);
+        put16(&mut fields, 0);
+        put16(&mut fields, DOS_DATE);
+        put32(&mut fields, crc);
+        put32(&mut fields, compressed_size);
+        put32(&mut fields, size);
+        put16(&mut fields, name_len);
+        put16(&mut fields, 0);
+
+        put32(&mut out, LOCAL_HEADER);
+        out.extend_from_slice(&fields);
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(&compressed);
+
+        put32(&mut directory, CENTRAL_HEADER);
+        put16(&mut directory, VERSION_NEEDED);
+        directory.extend_from_slice(&fields);
+        // Comment length, disk, internal and external attributes
+        put16(&mut directory, 0);
+        put16(&mut directory, 0);
+        put16(&mut directory, 0);
+        put32(&mut directory, 0);
+        put32(&mut directory, offset);
+        directory.extend_from_slice(name.as_bytes());
+    }
+
+    let (Ok(count), Ok(directory_size), Ok(directory_offset)) =
+        (u16::try_from(files.len()), u32::try_from(directory.len()), u32::try_from(out.len()))
+    else {
+        return Err("too many files for a zip without zip64".to_string());
+    };
+    out.extend_from_slice(&directory);
+    put32(&mut out, END_OF_DIRECTORY);
+    put16(&mut out, 0);
+    put16(&mut out, 0);
+    put16(&mut out, count);
+    put16(&mut out, count);
+    put32(&mut out, directory_size);
+    put32(&mut out, directory_offset);
+    put16(&mut out, 0);
+    Ok(out)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn u16_at(data: &[u8], at: usize) -> u16 {
+        u16::from_le_bytes([data[at], data[at + 1]])
+    }
+
+    fn u32_at(data: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+    }
+
+    /// CRC-32 a bit at a time, independent of the table `png` uses
+    fn slow_crc32(bytes: &[u8]) -> u32 {
+        let mut crc = !0u32;
+        for &b in bytes {
+            crc ^= b as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+            }
+        }
+        !crc
+    }
+
+    /// Read an archive as an unzip tool would, from the end-of-directory
+    /// record through the central directory to each local header, checking
+    /// that they agree and that every entry inflates to its size and CRC
+    pub(crate) fn read_zip(zip: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let end = zip.len() - 22;
+        assert_eq!(u32_at(zip, end), END_OF_DIRECTORY);
+        let count = u16_at(zip, end + 10) as usize;
+        assert_eq!(u16_at(zip, end + 8) as usize, count);
+        let (size, start) = (u32_at(zip, end + 12) as usize, u32_at(zip, end + 16) as usize);
+        assert_eq!(start + size, end, "central directory runs up to the end record");
+
+        let mut files = Vec::new();
+        let mut at = start;
+        for _ in 0..count {
+            assert_eq!(u32_at(zip, at), CENTRAL_HEADER);
+            let shared = &zip[at + 6..at + 32];
+            let name_len = u16_at(zip, at + 28) as usize;
+            let offset = u32_at(zip, at + 42) as usize;
+            let name = String::from_utf8(zip[at + 46..at + 46 + name_len].to_vec()).unwrap();
+            at += 46 + name_len;
+
+            assert_eq!(u32_at(zip, offset), LOCAL_HEADER, "{}", name);
+            assert_eq!(&zip[offset + 4..offset + 30], shared, "{}: local and central headers differ", name);
+            assert_eq!(&zip[offset + 30..offset + 30 + name_len], name.as_bytes());
+            assert_eq!(u16_at(shared, 4), METHOD_DEFLATE);
+            let (crc, compressed, size) = (u32_at(shared, 10), u32_at(shared, 14) as usize, u32_at(shared, 18) as usize);
+            let data_at = offset + 30 + name_len;
+            let data = miniz_oxide::inflate::decompress_to_vec(&zip[data_at..data_at + compressed]).unwrap();
+            assert_eq!(data.len(), size, "{}", name);
+            assert_eq!(crc, slow_crc32(&data), "{}", name);
+            files.push((name, data));
+        }
+        assert_eq!(at, end);
+        files
+    }
+
+    #[test]
+    fn archives_read_back() {
+        let big: Vec<u8> = (0..100_000u32).map(|i| (i * 7 % 251) as u8).collect();
+        let files: [(&str, &[u8]); 4] = [("a.txt", b"hello"), ("empty.txt", b""), ("dir/big.bin", &big), ("café.txt", "ünïcode".as_bytes())];
+        let zip = write_zip(&files).unwrap();
+        let read = read_zip(&zip);
+        assert_eq!(read.len(), files.len());
+        for ((name, data), (read_name, read_data)) in files.iter().zip(&read) {
+            assert_eq!(name, read_name);
+            assert_eq!(*data, read_data.as_slice());
+        }
+        // Local headers ask for UTF-8 names
+        assert_eq!(u16_at(&zip, 6), UTF8_NAMES);
+    }
+
+    #[test]
+    fn crc_matches_the_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn empty_archives_are_just_the_end_record() {
+        let zip = write_zip(&[]).unwrap();
+        assert_eq!(zip.len(), 22);
+        assert!(read_zip(&zip).is_empty());
+    }
+}
@@ -0,0 +1,70 @@
+//! A `generate` run that fails a quality gate stops once the maps are
+//! written: nothing is versioned, signed or stored.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// An empty scratch directory under the system temp dir
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("zonemap-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// One tile of chunk rows with every chunk in `area_id`
+fn write_chunk_csv(path: &Path, area_id: u32) {
+    let mut csv = String::from("tile_x,tile_y,chunk_x,chunk_y,area_id\n");
+    for chunk_y in 0..16 {
+        for chunk_x in 0..16 {
+            csv += &format!("32,32,{},{},{}\n", chunk_x, chunk_y, area_id);
+        }
+    }
+    fs::write(path, csv).unwrap();
+}
+
+/// `zone-map generate` on `Test_chunks.csv` in `dir`, ignoring any
+/// `ZONEMAP_` variables of the environment running the tests
+fn generate(dir: &Path, extra: &[&str]) -> Output {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_zone-map"));
+    cmd.current_dir(dir)
+        .args(["generate", "--input", "Test_chunks.csv", "--continent", "Test", "--map-id", "0", "--out", "Data"])
+        .args(extra);
+    for (var, _) in std::env::vars_os() {
+        if var.to_string_lossy().starts_with("ZONEMAP_") {
+            cmd.env_remove(var);
+        }
+    }
+    cmd.output().unwrap()
+}
+
+fn stored(dir: &Path) -> usize {
+    fs::read_dir(dir.join("store")).unwrap().count()
+}
+
+#[test]
+fn failing_gate_leaves_no_version_signature_or_snapshot() {
+    let dir = scratch("gates");
+    write_chunk_csv(&dir.join("Test_chunks.csv"), 12);
+    fs::create_dir(dir.join("store")).unwrap();
+    // A 32-byte seed, used when the build has `signing`
+    fs::write(dir.join("zonemap.key"), format!("{}=\n", "A".repeat(43))).unwrap();
+
+    // There's no AreaTable, so area 12 is unknown
+    let out = generate(&dir, &["--fail-on", "unknown-areas=0"]);
+    assert_eq!(out.status.code(), Some(2), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(dir.join("Data/Test_tiles.lua").exists());
+    assert!(!dir.join("Data/DataVersion.lua").exists());
+    assert!(!dir.join("Data/ZoneMap.sig").exists());
+    assert_eq!(stored(&dir), 0);
+
+    // Without the gate the same run publishes
+    let out = generate(&dir, &[]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(dir.join("Data/DataVersion.lua").exists());
+    assert_eq!(dir.join("Data/ZoneMap.sig").exists(), cfg!(feature = "signing"));
+    assert_ne!(stored(&dir), 0);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,356 @@
+//! Binary container for ZoneMap area grids, without the generator.
+//!
+//! Readers borrow the bytes they are given, so a memory-mapped file can be
+//! queried in place. The crate is `no_std`; the `alloc` feature adds
+//! `to_vec` and `std` adds `std::error::Error` for `FormatError`.
+//!
+//! Layout, all integers little-endian:
+//!
+//! ```text
+//! header (16 bytes)
+//!   0   [u8; 4]  magic "ZMAP"
+//!   4   u16      format version (1)
+//!   6   u16      reserved, 0
+//!   8   u32      map ID (Map.dbc)
+//!   12  u32      tile count
+//! tile records (1028 bytes each, sorted by tile key)
+//!   0   u8       tile x (0-63)
+//!   1   u8       tile y (0-63)
+//!   2   u16      reserved, 0
+//!   4   [u32; 256] area ID per chunk, row-major (chunk_y * 16 + chunk_x)
+//! ```
+
+#![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+use core::fmt;
+
+pub const MAGIC: [u8; 4] = *b"ZMAP";
+pub const VERSION: u16 = 1;
+pub const HEADER_LEN: usize = 16;
+
+/// Chunks along one side of a tile
+pub const CHUNKS_PER_SIDE: usize = 16;
+pub const CHUNKS_PER_TILE: usize = CHUNKS_PER_SIDE * CHUNKS_PER_SIDE;
+pub const RECORD_LEN: usize = 4 + CHUNKS_PER_TILE * 4;
+
+/// Tiles along one side of a map
+pub const TILES_PER_SIDE: u32 = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FormatError {
+    BadMagic,
+    UnsupportedVersion(u16),
+    /// Input shorter than the header and tile count promise
+    Truncated { expected: usize, actual: usize },
+    /// Tile coordinates outside the 64x64 map
+    BadTile { x: u8, y: u8 },
+    /// Output buffer too small for `write_container`
+    BufferTooSmall { needed: usize },
+    /// Tiles passed to `write_container` out of key order or repeated
+    Unsorted { key: u32 },
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::BadMagic => f.write_str("not a ZoneMap container (bad magic)"),
+            FormatError::UnsupportedVersion(v) => write!(f, "unsupported format version {}", v),
+            FormatError::Truncated { expected, actual } => {
+                write!(f, "truncated: expected {} bytes, got {}", expected, actual)
+            }
+            FormatError::BadTile { x, y } => write!(f, "tile ({}, {}) is outside the map", x, y),
+            FormatError::BufferTooSmall { needed } => write!(f, "output buffer needs {} bytes", needed),
+            FormatError::Unsorted { key } => write!(f, "tile key {} is out of order or repeated", key),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FormatError {}
+
+fn u16_at(data: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([data[at], data[at + 1]])
+}
+
+fn u32_at(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+}
+
+// ============================================================================
+// Reader
+// ============================================================================
+
+/// A validated container borrowing its bytes
+#[derive(Debug, Clone, Copy)]
+pub struct Container<'a> {
+    map_id: u32,
+    records: &'a [u8],
+}
+
+impl<'a> Container<'a> {
+    /// Check the header and length; tiles are read lazily
+    pub fn parse(data: &'a [u8]) -> Result<Self, FormatError> {
+        if data.len() < HEADER_LEN {
+            return Err(FormatError::Truncated { expected: HEADER_LEN, actual: data.len() });
+        }
+        if data[0..4] != MAGIC {
+            return Err(FormatError::BadMagic);
+        }
+        let version = u16_at(data, 4);
+        if version != VERSION {
+            return Err(FormatError::UnsupportedVersion(version));
+        }
+
+        let tile_count = u32_at(data, 12) as usize;
+        let expected = tile_count.saturating_mul(RECORD_LEN).saturating_add(HEADER_LEN);
+        if data.len() < expected {
+            return Err(FormatError::Truncated { expected, actual: data.len() });
+        }
+
+        let records = &data[HEADER_LEN..expected];
+        for record in records.chunks_exact(RECORD_LEN) {
+            if u32::from(record[0]) >= TILES_PER_SIDE || u32::from(record[1]) >= TILES_PER_SIDE {
+                return Err(FormatError::BadTile { x: record[0], y: record[1] });
+            }
+        }
+
+        Ok(Self { map_id: u32_at(data, 8), records })
+    }
+
+    pub fn map_id(&self) -> u32 {
+        self.map_id
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.records.len() / RECORD_LEN
+    }
+
+    pub fn tiles(&self) -> impl Iterator<Item = TileRecord<'a>> + 'a {
+        self.records.chunks_exact(RECORD_LEN).map(|data| TileRecord { data })
+    }
+
+    /// Tile at (x, y), by binary search over the sorted records
+    pub fn tile(&self, x: u32, y: u32) -> Option<TileRecord<'a>> {
+        let key = y * TILES_PER_SIDE + x;
+        let (mut lo, mut hi) = (0, self.tile_count());
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            let record = TileRecord { data: &self.records[mid * RECORD_LEN..(mid + 1) * RECORD_LEN] };
+            match record.key().cmp(&key) {
+                core::cmp::Ordering::Equal => return Some(record),
+                core::cmp::Ordering::Less => lo = mid + 1,
+                core::cmp::Ordering::Greater => hi = mid,
+            }
+        }
+        None
+    }
+
+    /// Area ID at a chunk, `None` when the tile isn't in the container
+    pub fn area_at(&self, tile_x: u32, tile_y: u32, chunk_x: usize, chunk_y: usize) -> Option<u32> {
+        Some(self.tile(tile_x, tile_y)?.area_id(chunk_x, chunk_y))
+    }
+}
+
+/// One tile's record, read in place
+#[derive(Debug, Clone, Copy)]
+pub struct TileRecord<'a> {
+    data: &'a [u8],
+}
+
+impl TileRecord<'_> {
+    pub fn tile_x(&self) -> u32 {
+        u32::from(self.data[0])
+    }
+
+    pub fn tile_y(&self) -> u32 {
+        u32::from(self.data[1])
+    }
+
+    /// `tile_y * 64 + tile_x`, the key the Lua export uses
+    pub fn key(&self) -> u32 {
+        self.tile_y() * TILES_PER_SIDE + self.tile_x()
+    }
+
+    /// Area ID of chunk (x, y); panics if either is 16 or more
+    pub fn area_id(&self, chunk_x: usize, chunk_y: usize) -> u32 {
+        assert!(chunk_x < CHUNKS_PER_SIDE && chunk_y < CHUNKS_PER_SIDE, "chunk out of range");
+        u32_at(self.data, 4 + (chunk_y * CHUNKS_PER_SIDE + chunk_x) * 4)
+    }
+
+    /// All 256 area IDs, row-major
+    pub fn area_ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.data[4..].chunks_exact(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+}
+
+// ============================================================================
+// Writer
+// ============================================================================
+
+/// Bytes `write_container` needs for `tile_count` tiles
+pub fn encoded_len(tile_count: usize) -> usize {
+    HEADER_LEN + tile_count * RECORD_LEN
+}
+
+/// Write a container into `out`, returning the bytes used. `tiles` are
+/// `(tile_x, tile_y, area IDs)`, sorted by tile key (`y * 64 + x`) with no
+/// repeats so `Container::tile` can binary search them.
+pub fn write_container(
+    map_id: u32,
+    tiles: &[(u32, u32, &[u32; CHUNKS_PER_TILE])],
+    out: &mut [u8],
+) -> Result<usize, FormatError> {
+    let needed = encoded_len(tiles.len());
+    if out.len() < needed {
+        return Err(FormatError::BufferTooSmall { needed });
+    }
+
+    out[0..4].copy_from_slice(&MAGIC);
+    out[4..6].copy_from_slice(&VERSION.to_le_bytes());
+    out[6..8].copy_from_slice(&0u16.to_le_bytes());
+    out[8..12].copy_from_slice(&map_id.to_le_bytes());
+    out[12..16].copy_from_slice(&(tiles.len() as u32).to_le_bytes());
+
+    let mut last_key = None;
+    for (i, &(x, y, area_ids)) in tiles.iter().enumerate() {
+        if x >= TILES_PER_SIDE || y >= TILES_PER_SIDE {
+            return Err(FormatError::BadTile { x: x.min(255) as u8, y: y.min(255) as u8 });
+        }
+        let key = y * TILES_PER_SIDE + x;
+        if last_key.is_some_and(|last| key <= last) {
+            return Err(FormatError::Unsorted { key });
+        }
+        last_key = Some(key);
+
+        let record = &mut out[HEADER_LEN + i * RECORD_LEN..HEADER_LEN + (i + 1) * RECORD_LEN];
+        record[0] = x as u8;
+        record[1] = y as u8;
+        record[2..4].copy_from_slice(&0u16.to_le_bytes());
+        for (chunk, area_id) in record[4..].chunks_exact_mut(4).zip(area_ids) {
+            chunk.copy_from_slice(&area_id.to_le_bytes());
+        }
+    }
+
+    Ok(needed)
+}
+
+/// `write_container` into a new buffer
+#[cfg(feature = "alloc")]
+pub fn to_vec(map_id: u32, tiles: &[(u32, u32, &[u32; CHUNKS_PER_TILE])]) -> Result<alloc::vec::Vec<u8>, FormatError> {
+    let mut out = alloc::vec![0u8; encoded_len(tiles.len())];
+    write_container(map_id, tiles, &mut out)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Three tiles, sorted by key, each chunk's area ID derived from its tile
+    fn sample() -> [u8; HEADER_LEN + 3 * RECORD_LEN] {
+        let ids = |base: u32| core::array::from_fn::<u32, CHUNKS_PER_TILE, _>(|i| base + i as u32);
+        let (a, b, c) = (ids(1000), ids(2000), ids(3000));
+        let mut out = [0u8; HEADER_LEN + 3 * RECORD_LEN];
+        let used = write_container(0, &[(32, 30, &a), (31, 48, &b), (32, 48, &c)], &mut out).unwrap();
+        assert_eq!(used, out.len());
+        out
+    }
+
+    #[test]
+    fn written_containers_parse_back() {
+        let data = sample();
+        let container = Container::parse(&data).unwrap();
+        assert_eq!(container.map_id(), 0);
+        assert_eq!(container.tile_count(), 3);
+        let keys: [u32; 3] = core::array::from_fn(|i| container.tiles().nth(i).unwrap().key());
+        assert_eq!(keys, [30 * 64 + 32, 48 * 64 + 31, 48 * 64 + 32]);
+        let tile = container.tile(31, 48).unwrap();
+        assert_eq!((tile.tile_x(), tile.tile_y()), (31, 48));
+        assert_eq!(tile.area_id(0, 0), 2000);
+        assert_eq!(tile.area_id(5, 3), 2000 + 3 * 16 + 5);
+        assert!(tile.area_ids().eq(2000..2256));
+        assert_eq!(container.area_at(32, 48, 15, 15), Some(3255));
+    }
+
+    #[test]
+    fn tile_lookup_misses_absent_tiles() {
+        let data = sample();
+        let container = Container::parse(&data).unwrap();
+        for (x, y, base) in [(32, 30, 1000), (31, 48, 2000), (32, 48, 3000)] {
+            assert_eq!(container.tile(x, y).map(|t| t.area_id(0, 0)), Some(base));
+        }
+        // Before the first key, between keys and past the last
+        for (x, y) in [(0, 0), (31, 30), (33, 30), (30, 48), (33, 48), (63, 63)] {
+            assert!(container.tile(x, y).is_none(), "{},{}", x, y);
+        }
+        assert_eq!(container.area_at(0, 0, 0, 0), None);
+
+        let mut empty = [0u8; HEADER_LEN];
+        assert_eq!(write_container(7, &[], &mut empty), Ok(HEADER_LEN));
+        let container = Container::parse(&empty).unwrap();
+        assert_eq!((container.map_id(), container.tile_count()), (7, 0));
+        assert!(container.tile(0, 0).is_none());
+    }
+
+    #[test]
+    fn short_input_is_truncated() {
+        let data = sample();
+        assert_eq!(Container::parse(&data[..10]).unwrap_err(), FormatError::Truncated { expected: HEADER_LEN, actual: 10 });
+        let short = data.len() - 1;
+        assert_eq!(Container::parse(&data[..short]).unwrap_err(), FormatError::Truncated { expected: data.len(), actual: short });
+
+        // A tile count far past the input doesn't overflow
+        let mut huge = [0u8; HEADER_LEN];
+        huge[..HEADER_LEN].copy_from_slice(&data[..HEADER_LEN]);
+        huge[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(Container::parse(&huge), Err(FormatError::Truncated { actual: HEADER_LEN, .. })));
+    }
+
+    #[test]
+    fn bad_headers_and_tiles_are_rejected() {
+        let mut data = sample();
+        data[0] = b'X';
+        assert_eq!(Container::parse(&data).unwrap_err(), FormatError::BadMagic);
+
+        let mut data = sample();
+        data[4..6].copy_from_slice(&2u16.to_le_bytes());
+        assert_eq!(Container::parse(&data).unwrap_err(), FormatError::UnsupportedVersion(2));
+
+        let mut data = sample();
+        data[HEADER_LEN + RECORD_LEN] = 64;
+        assert_eq!(Container::parse(&data).unwrap_err(), FormatError::BadTile { x: 64, y: 48 });
+
+        let ids = [0u32; CHUNKS_PER_TILE];
+        let mut out = [0u8; HEADER_LEN + RECORD_LEN];
+        assert_eq!(write_container(0, &[(64, 0, &ids)], &mut out).unwrap_err(), FormatError::BadTile { x: 64, y: 0 });
+        assert_eq!(write_container(0, &[(3, 300, &ids)], &mut out).unwrap_err(), FormatError::BadTile { x: 3, y: 255 });
+    }
+
+    #[test]
+    fn writer_rejects_unsorted_tiles_and_small_buffers() {
+        let ids = [0u32; CHUNKS_PER_TILE];
+        let mut out = [0u8; HEADER_LEN + 2 * RECORD_LEN];
+        assert_eq!(write_container(0, &[(1, 1, &ids), (0, 1, &ids)], &mut out).unwrap_err(), FormatError::Unsorted { key: 64 });
+        assert_eq!(write_container(0, &[(1, 1, &ids), (1, 1, &ids)], &mut out).unwrap_err(), FormatError::Unsorted { key: 65 });
+        assert_eq!(
+            write_container(0, &[(0, 0, &ids), (1, 0, &ids)], &mut out[..HEADER_LEN + RECORD_LEN]).unwrap_err(),
+            FormatError::BufferTooSmall { needed: HEADER_LEN + 2 * RECORD_LEN }
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn to_vec_matches_the_buffer_writer() {
+        let ids = [9u32; CHUNKS_PER_TILE];
+        let tiles = [(32, 30, &ids), (31, 48, &ids)];
+        let mut out = [0u8; HEADER_LEN + 2 * RECORD_LEN];
+        write_container(530, &tiles, &mut out).unwrap();
+        assert_eq!(to_vec(530, &tiles).unwrap(), out);
+    }
+}